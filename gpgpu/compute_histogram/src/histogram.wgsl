@@ -0,0 +1,22 @@
+@group(0) @binding(0) var input_tex: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> bins: array<atomic<u32>, 256>;
+
+struct CsInput {
+  @builtin(global_invocation_id) global_id: vec3u,
+}
+
+// 1スレッドが1ピクセルを担当する
+@compute @workgroup_size(16, 16)
+fn cs_main(in: CsInput) {
+  let dims = textureDimensions(input_tex, 0);
+  if (any(in.global_id.xy >= dims)) {
+    return;
+  }
+
+  let color = textureLoad(input_tex, in.global_id.xy, 0).rgb;
+  let luminance = dot(color, vec3f(0.2126, 0.7152, 0.0722));
+  let bin = u32(round(clamp(luminance, 0.0, 1.0) * 255.0));
+
+  // 複数のスレッドが同じビンに書き込むので、アトミックに加算する
+  atomicAdd(&bins[bin], 1u);
+}
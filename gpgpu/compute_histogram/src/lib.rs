@@ -0,0 +1,196 @@
+use std::{error::Error, iter};
+
+use image::GenericImageView;
+use wgpu::util::DeviceExt;
+use wgsim::ppl::ComputePipelineBuilder;
+use wgsim::util;
+
+const BIN_COUNT: usize = 256;
+
+// シェーダーの @workgroup_size と合わせる
+const WORKGROUP_SIZE: u32 = 16;
+
+// 表示するときに、いくつのビンを1行にまとめるか
+const BINS_PER_ROW: usize = 8;
+const BAR_WIDTH: usize = 60;
+
+pub async fn run() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let img_bytes = include_bytes!("../../../assets/img/stained-glass_w600.png");
+  let image = image::load_from_memory(img_bytes)?;
+  let (width, height) = image.dimensions();
+
+  //
+  // init wgpu
+  //
+
+  let instance = wgpu::Instance::default();
+
+  let adapter = instance
+    .request_adapter(&wgpu::RequestAdapterOptions::default())
+    .await
+    .unwrap();
+
+  let (device, queue) = adapter
+    .request_device(&wgpu::DeviceDescriptor::default(), None)
+    .await
+    .unwrap();
+
+  //
+  // compile shader
+  //
+
+  let compute_shader =
+    device.create_shader_module(wgpu::include_wgsl!("./histogram.wgsl"));
+
+  //
+  // texture
+  //
+
+  let size = wgpu::Extent3d {
+    width,
+    height,
+    depth_or_array_layers: 1,
+  };
+
+  // sRGBのまま（画素値そのもの）で数えたいので、Srgbではない形式にする
+  let texture = device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("image texture"),
+    size,
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format: wgpu::TextureFormat::Rgba8Unorm,
+    usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+    view_formats: &[],
+  });
+  queue.write_texture(
+    texture.as_image_copy(),
+    &image.to_rgba8(),
+    wgpu::ImageDataLayout {
+      offset: 0,
+      bytes_per_row: Some(4 * width),
+      rows_per_image: Some(height),
+    },
+    size,
+  );
+
+  //
+  // create a buffer to store data
+  //
+
+  let bins_buffer =
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Storage Buffer for histogram bins"),
+      contents: bytemuck::cast_slice(&[0u32; BIN_COUNT]),
+      usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+    });
+
+  //
+  // create bind_group
+  //
+
+  let bind_group_layout = util::create_bind_group_layout(
+    &device,
+    &[
+      wgpu::BindingType::Texture {
+        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+        view_dimension: wgpu::TextureViewDimension::D2,
+        multisampled: false,
+      },
+      wgpu::BindingType::Buffer {
+        ty: wgpu::BufferBindingType::Storage { read_only: false },
+        has_dynamic_offset: false,
+        min_binding_size: None,
+      },
+    ],
+    &[wgpu::ShaderStages::COMPUTE, wgpu::ShaderStages::COMPUTE],
+  );
+
+  let bind_group = util::create_bind_group(
+    &device,
+    &bind_group_layout,
+    &[
+      wgpu::BindingResource::TextureView(
+        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+      ),
+      bins_buffer.as_entire_binding(),
+    ],
+  );
+
+  //
+  // create compute_pipeline
+  //
+
+  let pipeline_layout =
+    device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: Some("Pipeline Layout"),
+      bind_group_layouts: &[&bind_group_layout],
+      push_constant_ranges: &[],
+    });
+
+  let compute_pipeline = ComputePipelineBuilder::new(&device)
+    .cs_shader(&compute_shader, "cs_main")
+    .pipeline_layout(&pipeline_layout)
+    .build();
+
+  //
+  // encode compute pass
+  //
+
+  let mut command_encoder = device
+    .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+  let mut compute_pass_encoder =
+    command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+      label: Some("Compute Pass"),
+      timestamp_writes: None,
+    });
+
+  compute_pass_encoder.set_pipeline(&compute_pipeline);
+  compute_pass_encoder.set_bind_group(0, &bind_group, &[]);
+  compute_pass_encoder.dispatch_workgroups(
+    width.div_ceil(WORKGROUP_SIZE),
+    height.div_ceil(WORKGROUP_SIZE),
+    1,
+  );
+
+  drop(compute_pass_encoder);
+
+  queue.submit(iter::once(command_encoder.finish()));
+
+  //
+  // result
+  //
+
+  let bins: Vec<u32> = util::read_buffer(&device, &queue, &bins_buffer).await;
+
+  print_histogram(&bins);
+
+  // アトミックに加算していれば、すべてのピクセルがどれかのビンに数えられている
+  let total: u32 = bins.iter().sum();
+  println!("{} of {} pixels counted", total, width * height);
+
+  Ok(())
+}
+
+fn print_histogram(bins: &[u32]) {
+  let rows = bins
+    .chunks(BINS_PER_ROW)
+    .map(|chunk| chunk.iter().sum::<u32>())
+    .collect::<Vec<_>>();
+  let max = rows.iter().copied().max().unwrap_or(0).max(1);
+
+  for (i, count) in rows.iter().enumerate() {
+    let start = i * BINS_PER_ROW;
+    let bar = "#".repeat(*count as usize * BAR_WIDTH / max as usize);
+    println!(
+      "{:>3}-{:>3} {:>7} {}",
+      start,
+      start + BINS_PER_ROW - 1,
+      count,
+      bar
+    );
+  }
+}
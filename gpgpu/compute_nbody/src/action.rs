@@ -0,0 +1,26 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  ToggleTrails,
+  IncreaseFadeFactor,
+  DecreaseFadeFactor,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::ToggleTrails => "Toggle trails",
+      Action::IncreaseFadeFactor => "Increase fade factor (longer trails)",
+      Action::DecreaseFadeFactor => "Decrease fade factor (shorter trails)",
+    }
+  }
+}
+
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new()
+    .bind(KeyCode::KeyT, Action::ToggleTrails)
+    .bind(KeyCode::KeyL, Action::IncreaseFadeFactor)
+    .bind(KeyCode::KeyJ, Action::DecreaseFadeFactor)
+}
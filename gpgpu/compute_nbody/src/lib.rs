@@ -0,0 +1,537 @@
+mod action;
+#[cfg(test)]
+mod reference;
+mod simulation;
+
+use std::{error::Error, f32::consts::PI};
+
+use action::Action;
+use cgmath::{Matrix4, Point3, Vector3};
+use simulation::{Body, NBodySimulation, SimParams};
+use wgpu::util::DeviceExt;
+use wgsim::app::App;
+use wgsim::camera::OrbitCamera;
+use wgsim::ctx::{DrawingContext, Size};
+use wgsim::input::KeyBindings;
+use wgsim::matrix;
+use wgsim::ppl::RenderPipelineBuilder;
+use wgsim::render::{Render, RenderTarget};
+use wgsim::trail::{FadeBindGroups, FadePass, PersistentTarget};
+use wgsim::util;
+use winit::event::{MouseButton, WindowEvent};
+
+const BODY_COUNT: usize = 4096;
+const STEPS_PER_FRAME: u32 = 2;
+
+// 画面上の点の半径（ピクセル）
+const POINT_RADIUS: f32 = 2.;
+// この速さ以上は最も速い色で描く
+const MAX_SPEED: f32 = 15.;
+
+// 軌跡：前のフレームに毎フレーム掛ける係数（1に近いほど長く残る）
+const DEFAULT_FADE_FACTOR: f32 = 0.95;
+const MIN_FADE_FACTOR: f32 = 0.8;
+const MAX_FADE_FACTOR: f32 = 0.99;
+const FADE_FACTOR_STEP: f32 = 0.01;
+
+pub fn run() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = Initial {
+    bodies: create_disk(BODY_COUNT, 0x2545_f491),
+    params: SimParams {
+      dt: 0.0025,
+      softening: 0.05,
+      gravity: 1.,
+    },
+    camera_position: Point3::new(0., 4., 8.),
+    trails: false,
+    key_bindings: action::default_key_bindings(),
+  };
+  initial.key_bindings.print_help();
+
+  let mut app: App<State> = App::new("compute_nbody", initial);
+  app.run()?;
+
+  Ok(())
+}
+
+/// 重い中心天体のまわりを円軌道で回る円盤（xorshift なので実行ごとに同じ配置）
+fn create_disk(count: usize, seed: u32) -> Vec<Body> {
+  const CENTER_MASS: f32 = 100.;
+  const DISK_MASS: f32 = 50.;
+  const INNER_RADIUS: f32 = 0.5;
+  const OUTER_RADIUS: f32 = 4.;
+  const THICKNESS: f32 = 0.05;
+
+  let mut state = seed;
+  let mut random = move || {
+    state ^= state << 13;
+    state ^= state >> 17;
+    state ^= state << 5;
+    state as f32 / u32::MAX as f32
+  };
+
+  let mass = DISK_MASS / (count - 1) as f32;
+  let mut bodies = vec![Body::new([0., 0., 0.], [0., 0., 0.], CENTER_MASS)];
+
+  for _ in 1..count {
+    // 面積あたりで一様になるよう、半径は平方根で分布させる
+    let u = random();
+    let r2 =
+      INNER_RADIUS.powi(2) + (OUTER_RADIUS.powi(2) - INNER_RADIUS.powi(2)) * u;
+    let r = r2.sqrt();
+    let angle = 2. * PI * random();
+    let y = (random() - 0.5) * THICKNESS;
+
+    // 内側の円盤の質量も中心にあるとみなして、円軌道の速さを決める
+    let enclosed = CENTER_MASS + DISK_MASS * u;
+    let speed = (enclosed / r).sqrt();
+
+    let (sin, cos) = angle.sin_cos();
+    bodies.push(Body::new(
+      [r * cos, y, r * sin],
+      [-speed * sin, 0., speed * cos],
+      mass,
+    ));
+  }
+
+  bodies
+}
+
+struct Initial {
+  bodies: Vec<Body>,
+  params: SimParams,
+  camera_position: Point3<f32>,
+  trails: bool,
+  key_bindings: KeyBindings<Action>,
+}
+
+struct State {
+  simulation: NBodySimulation,
+  pipeline: wgpu::RenderPipeline,
+  // シミュレーションのバッファごとに1つ（[i] は body_buffers[i] を読む）
+  bind_groups: [wgpu::BindGroup; 2],
+  camera_buffer: wgpu::Buffer,
+
+  // 軌跡を残すときは、点を加算合成で persistent target に重ねる
+  trail_pipeline: wgpu::RenderPipeline,
+  trail_target: PersistentTarget,
+  fade_pass: FadePass,
+  fade_bind_groups: FadeBindGroups,
+  fade_factor: f32,
+  fade_factor_updated: bool,
+  trails: bool,
+  clear_trails: bool,
+
+  camera: OrbitCamera,
+  project_mat: Matrix4<f32>,
+
+  key_bindings: KeyBindings<Action>,
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    let simulation =
+      NBodySimulation::new(&ctx.device, &initial.bodies, initial.params);
+
+    let shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./render.wgsl"));
+
+    //
+    // uniform
+    //
+
+    // view-projection 行列、点の大きさ、最大速度（とパディング）
+    let camera_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Camera Buffer"),
+        contents: bytemuck::cast_slice(&[0f32; 20]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+
+    //
+    // bind group
+    //
+
+    let bind_group_layout = util::create_bind_group_layout_for_buffer(
+      &ctx.device,
+      &[
+        wgpu::BufferBindingType::Uniform,
+        wgpu::BufferBindingType::Storage { read_only: true },
+      ],
+      &[wgpu::ShaderStages::VERTEX, wgpu::ShaderStages::VERTEX],
+    );
+
+    // 現在のバッファは1ステップごとに入れ替わるので、両方の分を作っておく
+    let bind_groups = simulation.body_buffers().each_ref().map(|buffer| {
+      util::create_bind_group(
+        &ctx.device,
+        &bind_group_layout,
+        &[
+          camera_buffer.as_entire_binding(),
+          buffer.as_entire_binding(),
+        ],
+      )
+    });
+
+    //
+    // pipeline
+    //
+
+    let pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Bodies Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let pipeline = RenderPipelineBuilder::new(ctx)
+      .vs_shader(&shader, "vs_main")
+      .fs_shader(&shader, "fs_main")
+      .pipeline_layout(&pipeline_layout)
+      .blend(wgpu::BlendState::ALPHA_BLENDING)
+      .label("Bodies Pipeline")
+      .build();
+
+    // 重なるほど明るくなり、光の筋のように見える
+    let trail_pipeline = RenderPipelineBuilder::new(ctx)
+      .vs_shader(&shader, "vs_main")
+      .fs_shader(&shader, "fs_main")
+      .pipeline_layout(&pipeline_layout)
+      .blend(wgpu::BlendState {
+        color: wgpu::BlendComponent {
+          src_factor: wgpu::BlendFactor::SrcAlpha,
+          dst_factor: wgpu::BlendFactor::One,
+          operation: wgpu::BlendOperation::Add,
+        },
+        alpha: wgpu::BlendComponent::OVER,
+      })
+      .label("Bodies Trail Pipeline")
+      .build();
+
+    //
+    // trail
+    //
+
+    let trail_target = PersistentTarget::new(ctx, ctx.format());
+    let fade_pass = FadePass::new(ctx, ctx.format(), DEFAULT_FADE_FACTOR);
+    let fade_bind_groups = fade_pass.prepare(&ctx.device, &trail_target);
+
+    Self {
+      simulation,
+      pipeline,
+      bind_groups,
+      camera_buffer,
+      trail_pipeline,
+      trail_target,
+      fade_pass,
+      fade_bind_groups,
+      fade_factor: DEFAULT_FADE_FACTOR,
+      fade_factor_updated: false,
+      trails: initial.trails,
+      clear_trails: false,
+      camera: OrbitCamera::new(
+        initial.camera_position,
+        Point3::new(0., 0., 0.),
+        Vector3::unit_y(),
+      ),
+      project_mat: matrix::create_projection_mat(ctx.aspect_ratio(), true),
+      key_bindings: initial.key_bindings.clone(),
+    }
+  }
+
+  fn resize(&mut self, ctx: &mut DrawingContext<'_>, size: Size) {
+    if size.width > 0 && size.height > 0 {
+      ctx.resize(size);
+
+      if self.trail_target.resize(ctx) {
+        self.fade_bind_groups =
+          self.fade_pass.prepare(&ctx.device, &self.trail_target);
+      }
+
+      self.project_mat = matrix::create_projection_mat(
+        size.width as f32 / size.height as f32,
+        true,
+      );
+    }
+  }
+
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    let action = match self.key_bindings.process_event(event) {
+      Some(action) => action,
+      None => return false,
+    };
+
+    match action {
+      Action::ToggleTrails => {
+        self.trails = !self.trails;
+        // 前に残っていた軌跡から再開しないよう、消してから描き始める
+        self.clear_trails = self.trails;
+        log::info!("trails: {}", self.trails);
+      }
+      Action::IncreaseFadeFactor => {
+        self.fade_factor =
+          MAX_FADE_FACTOR.min(self.fade_factor + FADE_FACTOR_STEP);
+        log::info!("fade factor: {:.2}", self.fade_factor);
+        self.fade_factor_updated = true;
+      }
+      Action::DecreaseFadeFactor => {
+        self.fade_factor =
+          MIN_FADE_FACTOR.max(self.fade_factor - FADE_FACTOR_STEP);
+        log::info!("fade factor: {:.2}", self.fade_factor);
+        self.fade_factor_updated = true;
+      }
+    }
+
+    true
+  }
+
+  fn on_scroll(&mut self, delta: f32) {
+    self.camera.scroll(delta);
+  }
+
+  fn on_mouse_drag(&mut self, dx: f32, dy: f32, button: MouseButton) {
+    if button == MouseButton::Left {
+      self.camera.drag(dx, dy);
+    }
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, _dt: std::time::Duration) {
+    let vp_mat = self.project_mat * self.camera.view_mat();
+    let vp_ref: &[f32; 16] = vp_mat.as_ref();
+
+    let size = ctx.size();
+    let point_size = [
+      2. * POINT_RADIUS / size.width as f32,
+      2. * POINT_RADIUS / size.height as f32,
+    ];
+
+    let mut data: Vec<f32> = vec![];
+    data.extend(vp_ref);
+    data.extend(point_size);
+    data.extend([MAX_SPEED, 0.]);
+
+    ctx.queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&data));
+
+    if self.fade_factor_updated {
+      self.fade_pass.set_factor(&ctx.queue, self.fade_factor);
+      self.fade_factor_updated = false;
+    }
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: RenderTarget,
+    _sample_count: u32,
+  ) -> Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+    let (view, frame) = match target {
+      RenderTarget::Surface(surface) => {
+        let frame = surface.get_current_texture()?;
+        let view =
+          frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, Some(frame))
+      }
+      RenderTarget::Texture(texture) => {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, None)
+      }
+    };
+
+    // 描画の前に、同じエンコーダーでシミュレーションを進める
+    self.simulation.step(encoder, STEPS_PER_FRAME);
+
+    if self.trails {
+      self.draw_trails(encoder, &view);
+      return Ok(frame);
+    }
+
+    let color_attachment = util::create_color_attachment(&view);
+
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render Pass"),
+        color_attachments: &[Some(color_attachment)],
+        ..Default::default()
+      });
+
+    self.draw_bodies(&mut render_pass, &self.pipeline);
+
+    drop(render_pass);
+
+    Ok(frame)
+  }
+}
+
+impl State {
+  fn draw_bodies<'p>(
+    &'p self,
+    render_pass: &mut wgpu::RenderPass<'p>,
+    pipeline: &'p wgpu::RenderPipeline,
+  ) {
+    render_pass.set_pipeline(pipeline);
+    render_pass.set_bind_group(
+      0,
+      &self.bind_groups[self.simulation.current_index()],
+      &[],
+    );
+    render_pass.draw(0..6, 0..self.simulation.count());
+  }
+
+  /// 前のフレームを薄めた上に点を加算合成で重ね、その結果を `view` に写す
+  fn draw_trails(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    view: &wgpu::TextureView,
+  ) {
+    if self.clear_trails {
+      self.trail_target.clear(encoder);
+      self.clear_trails = false;
+    }
+
+    self.fade_pass.draw(encoder, &self.trail_target, &self.fade_bind_groups);
+
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Trail Render Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view: self.trail_target.current_view(),
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Load,
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        ..Default::default()
+      });
+
+    self.draw_bodies(&mut render_pass, &self.trail_pipeline);
+
+    drop(render_pass);
+
+    self.fade_pass.present(
+      encoder,
+      &self.trail_target,
+      &self.fade_bind_groups,
+      view,
+    );
+    self.trail_target.swap();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::iter;
+
+  use wgsim::render::TestHarness;
+
+  use super::*;
+
+  /// 4体を GPU と CPU で数ステップ進め、結果が許容誤差内で一致するか確かめる
+  #[test]
+  fn gpu_step_matches_cpu_reference() {
+    const STEPS: u32 = 8;
+    const TOLERANCE: f32 = 1e-4;
+
+    let bodies = [
+      Body::new([0., 0., 0.], [0., 0., 0.], 10.),
+      Body::new([1., 0., 0.], [0., 3., 0.], 1.),
+      Body::new([0., -1.5, 0.5], [2., 0., 0.], 2.),
+      Body::new([-0.5, 0.5, -1.], [0., 0., 1.], 0.5),
+    ];
+    let params = SimParams {
+      dt: 0.01,
+      softening: 0.01,
+      gravity: 1.,
+    };
+
+    let mut expected = bodies;
+    for _ in 0..STEPS {
+      reference::step(&mut expected, &params);
+    }
+
+    let result: Vec<Body> = pollster::block_on(async {
+      let instance = wgpu::Instance::default();
+      let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions::default())
+        .await
+        .unwrap();
+      let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor::default(), None)
+        .await
+        .unwrap();
+
+      let mut simulation = NBodySimulation::new(&device, &bodies, params);
+
+      let mut command_encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+          label: None,
+        });
+      simulation.step(&mut command_encoder, STEPS);
+      queue.submit(iter::once(command_encoder.finish()));
+
+      util::read_buffer(&device, &queue, simulation.current_buffer()).await
+    });
+
+    assert_eq!(result.len(), expected.len());
+    for (i, (gpu, cpu)) in result.iter().zip(&expected).enumerate() {
+      let values = gpu.position.iter().zip(&cpu.position);
+      let values = values.chain(gpu.velocity.iter().zip(&cpu.velocity));
+      for (g, c) in values {
+        // 値の大きさに比例した誤差まで許す
+        assert!(
+          (g - c).abs() <= TOLERANCE * c.abs().max(1.),
+          "GPU differs from CPU for body {} after {} steps: {:?} != {:?}",
+          i,
+          STEPS,
+          gpu,
+          cpu
+        );
+      }
+    }
+  }
+
+  /// 数フレーム描いたあと、何か描かれている画素の数
+  fn lit_pixels_after_frames(trails: bool) -> usize {
+    let initial = Initial {
+      bodies: create_disk(256, 0x2545_f491),
+      params: SimParams {
+        dt: 0.01,
+        softening: 0.05,
+        gravity: 1.,
+      },
+      camera_position: Point3::new(0., 4., 8.),
+      trails,
+      key_bindings: action::default_key_bindings(),
+    };
+    let mut harness: TestHarness<State> = TestHarness::new(&initial, 64, 64)
+      .expect("no GPU adapter for the headless harness");
+
+    let mut frame = vec![];
+    for _ in 0..8 {
+      harness.step(std::time::Duration::from_millis(16));
+      frame = harness.draw_to_vec().unwrap();
+    }
+    frame
+      .chunks_exact(4)
+      .filter(|pixel| pixel[..3].iter().any(|&c| c > 0))
+      .count()
+  }
+
+  #[test]
+  fn trails_keep_earlier_positions_lit() {
+    let without_trails = lit_pixels_after_frames(false);
+    let with_trails = lit_pixels_after_frames(true);
+
+    assert!(without_trails > 0);
+    assert!(
+      with_trails > without_trails,
+      "{} lit pixels with trails, {} without",
+      with_trails,
+      without_trails
+    );
+  }
+}
@@ -0,0 +1,83 @@
+// Rust 側の TILE_SIZE と合わせる
+const tile_size = 64u;
+
+struct Body {
+  // xyz: 位置, w: 質量
+  position: vec4f,
+  // xyz: 速度（半ステップずれた時刻の値）
+  velocity: vec4f,
+}
+
+struct Params {
+  dt: f32,
+  softening: f32,
+  gravity: f32,
+  count: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> bodies_in: array<Body>;
+@group(0) @binding(2) var<storage, read_write> bodies_out: array<Body>;
+
+// ワークグループ全員で読み込んだ1タイル分の位置と質量
+var<workgroup> tile: array<vec4f, tile_size>;
+
+struct Input {
+  @builtin(global_invocation_id) global_id: vec3u,
+  @builtin(local_invocation_id) local_id: vec3u,
+}
+
+@compute @workgroup_size(tile_size)
+fn cs_main(in: Input) {
+  let i = in.global_id.x;
+  let in_range = i < params.count;
+  
+  var position = vec3f(0.0);
+  if (in_range) {
+    position = bodies_in[i].position.xyz;
+  }
+  
+  //
+  // 全天体からの加速度をタイルごとに足し合わせる
+  //
+  
+  let eps2 = params.softening * params.softening;
+  let tile_count = (params.count + tile_size - 1u) / tile_size;
+  var acc = vec3f(0.0);
+  
+  // 範囲外のスレッドもタイルの読み込みとバリアには参加する
+  for (var t = 0u; t < tile_count; t = t + 1u) {
+    let j = t * tile_size + in.local_id.x;
+    if (j < params.count) {
+      tile[in.local_id.x] = bodies_in[j].position;
+    } else {
+      // 質量0の天体は力を及ぼさない
+      tile[in.local_id.x] = vec4f(0.0);
+    }
+    workgroupBarrier();
+    
+    for (var k = 0u; k < tile_size; k = k + 1u) {
+      let other = tile[k];
+      let d = other.xyz - position;
+      // 自分自身は d = 0 なので、softening > 0 なら寄与しない
+      let inv_dist = inverseSqrt(dot(d, d) + eps2);
+      acc += d * (other.w * inv_dist * inv_dist * inv_dist);
+    }
+    workgroupBarrier();
+  }
+  
+  if (!in_range) {
+    return;
+  }
+  
+  //
+  // Leapfrog (kick-drift)
+  //
+  
+  let body = bodies_in[i];
+  let velocity = body.velocity.xyz + params.gravity * acc * params.dt;
+  let next_position = position + velocity * params.dt;
+  
+  bodies_out[i].position = vec4f(next_position, body.position.w);
+  bodies_out[i].velocity = vec4f(velocity, 0.0);
+}
@@ -0,0 +1,33 @@
+use crate::simulation::{Body, SimParams};
+
+/// CPU で1ステップ進める（nbody.wgsl と同じ式）
+pub fn step(bodies: &mut [Body], params: &SimParams) {
+  let eps2 = params.softening * params.softening;
+
+  let accelerations = bodies
+    .iter()
+    .map(|body| {
+      let mut acc = [0f32; 3];
+      for other in bodies.iter() {
+        let d = [0, 1, 2].map(|k| other.position[k] - body.position[k]);
+        let dist2 = d[0] * d[0] + d[1] * d[1] + d[2] * d[2] + eps2;
+        let inv_dist = 1. / dist2.sqrt();
+        let s = other.position[3] * inv_dist * inv_dist * inv_dist;
+        for (a, d) in acc.iter_mut().zip(d) {
+          *a += d * s;
+        }
+      }
+      acc
+    })
+    .collect::<Vec<_>>();
+
+  for (body, acc) in bodies.iter_mut().zip(accelerations) {
+    // w（質量と未使用）は acc が3要素なので触らない
+    let velocity = body.velocity.iter_mut();
+    let position = body.position.iter_mut();
+    for ((v, p), a) in velocity.zip(position).zip(acc) {
+      *v += params.gravity * a * params.dt;
+      *p += *v * params.dt;
+    }
+  }
+}
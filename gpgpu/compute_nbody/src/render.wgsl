@@ -0,0 +1,62 @@
+struct Body {
+  position: vec4f,
+  velocity: vec4f,
+}
+
+struct Camera {
+  view_project_mat: mat4x4f,
+  // 点の半径（NDC）
+  point_size: vec2f,
+  // この速さ以上は同じ色になる
+  max_speed: f32,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+@group(0) @binding(1) var<storage, read> bodies: array<Body>;
+
+struct VertexOutput {
+  @builtin(position) position: vec4f,
+  @location(0) corner: vec2f,
+  @location(1) color: vec3f,
+}
+
+@vertex
+fn vs_main(
+  @builtin(vertex_index) vertex_index: u32,
+  @builtin(instance_index) instance_index: u32,
+) -> VertexOutput {
+  var corners = array<vec2f, 6>(
+    vec2f(-1.0, -1.0),
+    vec2f( 1.0, -1.0),
+    vec2f( 1.0,  1.0),
+    vec2f(-1.0, -1.0),
+    vec2f( 1.0,  1.0),
+    vec2f(-1.0,  1.0),
+  );
+  let corner = corners[vertex_index];
+  let body = bodies[instance_index];
+  
+  // 画面上で同じ大きさになるよう、クリップ空間で広げる
+  var position = camera.view_project_mat * vec4f(body.position.xyz, 1.0);
+  position += vec4f(corner * camera.point_size * position.w, 0.0, 0.0);
+  
+  // 遅いものは青、速いものは橙
+  let t = saturate(length(body.velocity.xyz) / camera.max_speed);
+  let color = mix(vec3f(0.2, 0.4, 1.0), vec3f(1.0, 0.6, 0.2), t);
+  
+  var output: VertexOutput;
+  output.position = position;
+  output.corner = corner;
+  output.color = color;
+  return output;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+  let r = length(in.corner);
+  if (r > 1.0) {
+    discard;
+  }
+  
+  return vec4f(in.color, 1.0 - r * r);
+}
@@ -0,0 +1,194 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+use wgsim::ppl::ComputePipelineBuilder;
+use wgsim::util;
+
+// シェーダーの tile_size と合わせる
+pub const TILE_SIZE: u32 = 64;
+
+/// 位置と速度。w は位置が質量、速度が未使用
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct Body {
+  pub position: [f32; 4],
+  pub velocity: [f32; 4],
+}
+
+impl Body {
+  pub fn new(position: [f32; 3], velocity: [f32; 3], mass: f32) -> Self {
+    let [px, py, pz] = position;
+    let [vx, vy, vz] = velocity;
+    Self {
+      position: [px, py, pz, mass],
+      velocity: [vx, vy, vz, 0.],
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SimParams {
+  pub dt: f32,
+  /// 近づきすぎたときに力が発散しないよう、距離に足す長さ（0より大きくする）
+  pub softening: f32,
+  pub gravity: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Params {
+  dt: f32,
+  softening: f32,
+  gravity: f32,
+  count: u32,
+}
+
+/// Pairwise gravity between all bodies on the GPU, O(N²) per step.
+///
+/// Each workgroup loads the bodies tile by tile into workgroup memory, so
+/// every position is read from the storage buffer once per workgroup
+/// instead of once per thread. Velocities are integrated with leapfrog
+/// (kick-drift): the velocities given to `new` are taken as those half a
+/// step before the positions.
+///
+/// The bodies are ping-ponged between two storage buffers; `current_buffer`
+/// holds the latest state.
+pub struct NBodySimulation {
+  pipeline: wgpu::ComputePipeline,
+  bind_groups: [wgpu::BindGroup; 2],
+  body_buffers: [wgpu::Buffer; 2],
+  count: u32,
+  current: usize,
+}
+
+impl NBodySimulation {
+  pub fn new(
+    device: &wgpu::Device,
+    bodies: &[Body],
+    params: SimParams,
+  ) -> Self {
+    let shader =
+      device.create_shader_module(wgpu::include_wgsl!("./nbody.wgsl"));
+
+    //
+    // buffers
+    //
+
+    let count = bodies.len() as u32;
+
+    let params_buffer =
+      device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("NBody Params Buffer"),
+        contents: bytemuck::bytes_of(&Params {
+          dt: params.dt,
+          softening: params.softening,
+          gravity: params.gravity,
+          count,
+        }),
+        usage: wgpu::BufferUsages::UNIFORM,
+      });
+
+    // 描画（頂点シェーダーからの読み込み）と、検証時の読み戻しにも使う
+    let create_body_buffer = |label| {
+      device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(label),
+        contents: bytemuck::cast_slice(bodies),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+      })
+    };
+    let body_buffers = [
+      create_body_buffer("Body Buffer 0"),
+      create_body_buffer("Body Buffer 1"),
+    ];
+
+    //
+    // bind groups
+    //
+
+    let bind_group_layout = util::create_bind_group_layout_for_buffer(
+      device,
+      &[
+        wgpu::BufferBindingType::Uniform,
+        wgpu::BufferBindingType::Storage { read_only: true },
+        wgpu::BufferBindingType::Storage { read_only: false },
+      ],
+      &[
+        wgpu::ShaderStages::COMPUTE,
+        wgpu::ShaderStages::COMPUTE,
+        wgpu::ShaderStages::COMPUTE,
+      ],
+    );
+
+    // [0] は 0 -> 1、[1] は 1 -> 0 へ1ステップ進める
+    let bind_groups = [(0, 1), (1, 0)].map(|(src, dst)| {
+      util::create_bind_group(
+        device,
+        &bind_group_layout,
+        &[
+          params_buffer.as_entire_binding(),
+          body_buffers[src].as_entire_binding(),
+          body_buffers[dst].as_entire_binding(),
+        ],
+      )
+    });
+
+    //
+    // pipeline
+    //
+
+    let pipeline_layout =
+      device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("NBody Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let pipeline = ComputePipelineBuilder::new(device)
+      .cs_shader(&shader, "cs_main")
+      .pipeline_layout(&pipeline_layout)
+      .build();
+
+    Self {
+      pipeline,
+      bind_groups,
+      body_buffers,
+      count,
+      current: 0,
+    }
+  }
+
+  pub fn count(&self) -> u32 {
+    self.count
+  }
+
+  /// Records `steps` steps into one compute pass.
+  pub fn step(&mut self, encoder: &mut wgpu::CommandEncoder, steps: u32) {
+    let mut compute_pass =
+      encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("NBody Compute Pass"),
+        timestamp_writes: None,
+      });
+
+    compute_pass.set_pipeline(&self.pipeline);
+
+    for _ in 0..steps {
+      compute_pass.set_bind_group(0, &self.bind_groups[self.current], &[]);
+      compute_pass.dispatch_workgroups(self.count.div_ceil(TILE_SIZE), 1, 1);
+      self.current = 1 - self.current;
+    }
+  }
+
+  #[cfg(test)]
+  pub fn current_buffer(&self) -> &wgpu::Buffer {
+    &self.body_buffers[self.current]
+  }
+
+  /// Both buffers, for creating a bind group per buffer up front; pick the
+  /// one at `current_index` when drawing.
+  pub fn body_buffers(&self) -> &[wgpu::Buffer; 2] {
+    &self.body_buffers
+  }
+
+  pub fn current_index(&self) -> usize {
+    self.current
+  }
+}
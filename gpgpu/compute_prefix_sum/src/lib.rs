@@ -0,0 +1,251 @@
+use std::{error::Error, iter};
+
+use wgpu::util::DeviceExt;
+use wgsim::ppl::ComputePipelineBuilder;
+use wgsim::util;
+
+// シェーダーの block_size と合わせる（1ワークグループ = 256スレッド x 2要素）
+const BLOCK_SIZE: usize = 512;
+
+// ブロックごとの合計も1つのワークグループでスキャンするため、ブロック数の上限は BLOCK_SIZE
+const MAX_LEN: usize = BLOCK_SIZE * BLOCK_SIZE;
+
+const INPUT_LEN: usize = 1000;
+
+pub async fn run() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let input = random_input(INPUT_LEN, 0x2545_f491);
+
+  //
+  // init wgpu
+  //
+
+  let instance = wgpu::Instance::default();
+
+  let adapter = instance
+    .request_adapter(&wgpu::RequestAdapterOptions::default())
+    .await
+    .unwrap();
+
+  let (device, queue) = adapter
+    .request_device(&wgpu::DeviceDescriptor::default(), None)
+    .await
+    .unwrap();
+
+  let result = prefix_sum(&device, &queue, &input).await;
+
+  println!("input  (first 10): {:?}", &input[..10]);
+  println!("output (first 10): {:?}", &result[..10]);
+
+  Ok(())
+}
+
+/// Inclusive scan on the GPU.
+///
+/// The input is padded with zeros to the next power of two (and to at least
+/// one block), so every workgroup scans a full block and padding does not
+/// change the sums of the real elements.
+async fn prefix_sum(
+  device: &wgpu::Device,
+  queue: &wgpu::Queue,
+  input: &[i32],
+) -> Vec<i32> {
+  assert!(
+    input.len() <= MAX_LEN,
+    "prefix_sum supports at most {} elements, got {}",
+    MAX_LEN,
+    input.len()
+  );
+
+  let padded_len = input.len().next_power_of_two().max(BLOCK_SIZE);
+  let block_count = padded_len / BLOCK_SIZE;
+
+  let mut padded = input.to_vec();
+  padded.resize(padded_len, 0);
+
+  //
+  // compile shader
+  //
+
+  let shader = device.create_shader_module(wgpu::include_wgsl!("./scan.wgsl"));
+
+  //
+  // create a buffer to store data
+  //
+
+  let data_buffer =
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Storage Buffer for data"),
+      contents: bytemuck::cast_slice(&padded),
+      usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+    });
+
+  // ブロックごとの合計もスキャンするので、1ブロック分の長さを確保しておく
+  let block_sums_buffer =
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Storage Buffer for block sums"),
+      contents: bytemuck::cast_slice(&[0i32; BLOCK_SIZE]),
+      usage: wgpu::BufferUsages::STORAGE,
+    });
+
+  // block_sums をスキャンするときの合計の書き込み先（使わない）
+  let unused_sum_buffer =
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Storage Buffer for the unused total"),
+      contents: bytemuck::cast_slice(&[0i32]),
+      usage: wgpu::BufferUsages::STORAGE,
+    });
+
+  //
+  // create bind_group
+  //
+
+  let bind_group_layout = util::create_bind_group_layout_for_buffer(
+    device,
+    &[
+      wgpu::BufferBindingType::Storage { read_only: false }, // data
+      wgpu::BufferBindingType::Storage { read_only: false }, // block sums
+    ],
+    &[wgpu::ShaderStages::COMPUTE, wgpu::ShaderStages::COMPUTE],
+  );
+
+  let data_bind_group = util::create_bind_group(
+    device,
+    &bind_group_layout,
+    &[
+      data_buffer.as_entire_binding(),
+      block_sums_buffer.as_entire_binding(),
+    ],
+  );
+
+  let block_sums_bind_group = util::create_bind_group(
+    device,
+    &bind_group_layout,
+    &[
+      block_sums_buffer.as_entire_binding(),
+      unused_sum_buffer.as_entire_binding(),
+    ],
+  );
+
+  //
+  // create compute_pipeline
+  //
+
+  let pipeline_layout =
+    device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: Some("Pipeline Layout"),
+      bind_group_layouts: &[&bind_group_layout],
+      push_constant_ranges: &[],
+    });
+
+  let scan_pipeline = ComputePipelineBuilder::new(device)
+    .cs_shader(&shader, "scan_blocks")
+    .pipeline_layout(&pipeline_layout)
+    .build();
+
+  let add_offsets_pipeline = ComputePipelineBuilder::new(device)
+    .cs_shader(&shader, "add_block_offsets")
+    .pipeline_layout(&pipeline_layout)
+    .build();
+
+  //
+  // encode compute pass
+  //
+
+  let mut command_encoder = device
+    .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+  let mut compute_pass_encoder =
+    command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+      label: Some("Compute Pass"),
+      timestamp_writes: None,
+    });
+
+  // 1. ブロックごとにスキャンし、各ブロックの合計を block_sums に書き出す
+  compute_pass_encoder.set_pipeline(&scan_pipeline);
+  compute_pass_encoder.set_bind_group(0, &data_bind_group, &[]);
+  compute_pass_encoder.dispatch_workgroups(block_count as u32, 1, 1);
+
+  if block_count > 1 {
+    // 2. block_sums 自体をスキャンして、各ブロックまでの累計にする
+    compute_pass_encoder.set_bind_group(0, &block_sums_bind_group, &[]);
+    compute_pass_encoder.dispatch_workgroups(1, 1, 1);
+
+    // 3. 前のブロックまでの累計を足す
+    compute_pass_encoder.set_pipeline(&add_offsets_pipeline);
+    compute_pass_encoder.set_bind_group(0, &data_bind_group, &[]);
+    compute_pass_encoder.dispatch_workgroups(block_count as u32, 1, 1);
+  }
+
+  drop(compute_pass_encoder);
+
+  queue.submit(iter::once(command_encoder.finish()));
+
+  //
+  // result
+  //
+
+  // パディング部分は捨てる
+  let mut result: Vec<i32> =
+    util::read_buffer(device, queue, &data_buffer).await;
+  result.truncate(input.len());
+  result
+}
+
+/// -100..100 の値を xorshift で生成する（実行ごとに同じ列になる）
+fn random_input(len: usize, seed: u32) -> Vec<i32> {
+  let mut state = seed;
+  (0..len)
+    .map(|_| {
+      state ^= state << 13;
+      state ^= state >> 17;
+      state ^= state << 5;
+      (state % 200) as i32 - 100
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn scan(values: &[i32]) -> Vec<i32> {
+    values
+      .iter()
+      .scan(0, |acc, v| {
+        *acc += v;
+        Some(*acc)
+      })
+      .collect()
+  }
+
+  fn gpu_prefix_sum(input: &[i32]) -> Vec<i32> {
+    pollster::block_on(async {
+      let instance = wgpu::Instance::default();
+      let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions::default())
+        .await
+        .unwrap();
+      let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor::default(), None)
+        .await
+        .unwrap();
+
+      prefix_sum(&device, &queue, input).await
+    })
+  }
+
+  #[test]
+  fn gpu_scan_matches_cpu_scan_within_one_block() {
+    let input = random_input(INPUT_LEN.min(BLOCK_SIZE), 0x2545_f491);
+    assert_eq!(gpu_prefix_sum(&input), scan(&input));
+  }
+
+  #[test]
+  fn gpu_scan_matches_cpu_scan_across_blocks() {
+    // ブロックの合計をスキャンする2段目と、オフセットを足す3段目も通す
+    let input = random_input(INPUT_LEN, 0x2545_f491);
+    assert_eq!(gpu_prefix_sum(&input), scan(&input));
+  }
+}
@@ -0,0 +1,78 @@
+// 1つのワークグループが扱う要素数（スレッド数の2倍。1スレッドが2要素を受け持つ）
+const block_size = 512u;
+
+@group(0) @binding(0) var<storage, read_write> data: array<i32>;
+// ブロックごとの合計。add_block_offsets の時点では、ブロック単位で inclusive scan 済み
+@group(0) @binding(1) var<storage, read_write> block_sums: array<i32>;
+
+var<workgroup> temp: array<i32, block_size>;
+
+struct CsInput {
+  @builtin(workgroup_id) workgroup_id: vec3u,
+  @builtin(local_invocation_id) local_id: vec3u,
+}
+
+// Blelloch の work-efficient scan で、ブロック内の inclusive scan を求める
+@compute @workgroup_size(256)
+fn scan_blocks(in: CsInput) {
+  let t = in.local_id.x;
+  let base = in.workgroup_id.x * block_size;
+
+  let a = data[base + 2u * t];
+  let b = data[base + 2u * t + 1u];
+  temp[2u * t] = a;
+  temp[2u * t + 1u] = b;
+
+  // up-sweep: 部分和を木の根に向かって積み上げる
+  var offset = 1u;
+  for (var d = block_size >> 1u; d > 0u; d >>= 1u) {
+    workgroupBarrier();
+    if (t < d) {
+      let ai = offset * (2u * t + 1u) - 1u;
+      let bi = offset * (2u * t + 2u) - 1u;
+      temp[bi] += temp[ai];
+    }
+    offset <<= 1u;
+  }
+
+  // 根にはブロック全体の合計が入っている
+  workgroupBarrier();
+  if (t == 0u) {
+    block_sums[in.workgroup_id.x] = temp[block_size - 1u];
+    temp[block_size - 1u] = 0;
+  }
+
+  // down-sweep: 根から葉に向かって、左側の合計を配っていく（exclusive scan になる）
+  for (var d = 1u; d < block_size; d <<= 1u) {
+    offset >>= 1u;
+    workgroupBarrier();
+    if (t < d) {
+      let ai = offset * (2u * t + 1u) - 1u;
+      let bi = offset * (2u * t + 2u) - 1u;
+      let left = temp[ai];
+      temp[ai] = temp[bi];
+      temp[bi] += left;
+    }
+  }
+
+  workgroupBarrier();
+
+  // 元の値を足して inclusive scan にする
+  data[base + 2u * t] = temp[2u * t] + a;
+  data[base + 2u * t + 1u] = temp[2u * t + 1u] + b;
+}
+
+// 前のブロックまでの合計を、各ブロックの要素に足す
+@compute @workgroup_size(256)
+fn add_block_offsets(in: CsInput) {
+  if (in.workgroup_id.x == 0u) {
+    return;
+  }
+
+  let t = in.local_id.x;
+  let base = in.workgroup_id.x * block_size;
+  let offset = block_sums[in.workgroup_id.x - 1u];
+
+  data[base + 2u * t] += offset;
+  data[base + 2u * t + 1u] += offset;
+}
@@ -0,0 +1,29 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  IncreaseKernelSize,
+  DecreaseKernelSize,
+  IncreaseIterations,
+  DecreaseIterations,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::IncreaseKernelSize => "Increase kernel size",
+      Action::DecreaseKernelSize => "Decrease kernel size",
+      Action::IncreaseIterations => "Increase iterations",
+      Action::DecreaseIterations => "Decrease iterations",
+    }
+  }
+}
+
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new()
+    .bind(KeyCode::KeyL, Action::IncreaseKernelSize)
+    .bind(KeyCode::KeyJ, Action::DecreaseKernelSize)
+    .bind(KeyCode::KeyO, Action::IncreaseIterations)
+    .bind(KeyCode::KeyU, Action::DecreaseIterations)
+}
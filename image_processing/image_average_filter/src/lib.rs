@@ -1,15 +1,18 @@
+mod action;
+
 use std::error::Error;
 
+use action::Action;
 use bytemuck::cast_slice;
 use image::GenericImageView;
 use wgpu::util::DeviceExt;
 use wgsim::app::App;
 use wgsim::ctx::{DrawingContext, Size};
+use wgsim::input::KeyBindings;
 use wgsim::ppl::{ComputePipelineBuilder, RenderPipelineBuilder};
 use wgsim::render::{Render, RenderTarget};
-use wgsim::util;
-use winit::event::{ElementState, KeyEvent, WindowEvent};
-use winit::keyboard::{KeyCode, PhysicalKey};
+use wgsim::util::{self, SamplerOptions};
+use winit::event::WindowEvent;
 
 const TILE_SIZE: u32 = 4;
 const WORKGROUP_SIZE: u32 = 32;
@@ -37,6 +40,7 @@ fn setup() -> Initial {
     image_size,
     kernel_size: 3,
     iterations: 1,
+    key_bindings: action::default_key_bindings(),
   }
 }
 
@@ -44,6 +48,7 @@ pub fn run() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
   let initial = setup();
+  initial.key_bindings.print_help();
 
   let mut app: App<State> = App::new("image_average_filter", initial);
   app.run()?;
@@ -56,6 +61,7 @@ struct Initial {
   image_size: (u32, u32),
   kernel_size: u32,
   iterations: u32,
+  key_bindings: KeyBindings<Action>,
 }
 
 struct State {
@@ -79,6 +85,8 @@ struct State {
   kernel_size_updated: bool,
 
   resolution_updated: bool,
+
+  key_bindings: KeyBindings<Action>,
 }
 
 impl<'a> Render<'a> for State {
@@ -99,12 +107,7 @@ impl<'a> Render<'a> for State {
     // texture & sampler
     //
 
-    let sampler = ctx.device.create_sampler(&wgpu::SamplerDescriptor {
-      label: Some("sampler"),
-      mag_filter: wgpu::FilterMode::Linear,
-      min_filter: wgpu::FilterMode::Linear,
-      ..Default::default()
-    });
+    let sampler = util::create_sampler(&ctx.device, SamplerOptions::default());
 
     let image_texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
       label: Some("image texture"),
@@ -334,7 +337,9 @@ impl<'a> Render<'a> for State {
       .vs_shader(&fullscreen_quad_shader, "vs_main")
       .fs_shader(&fullscreen_quad_shader, "fs_main")
       .pipeline_layout(&fullscreen_quad_pipeline_layout)
-      .build();
+      .label("Fullscreen Quad Pipeline")
+      .build_checked()
+      .unwrap_or_else(|e| panic!("{}", e));
 
     let blur_pipeline_layout =
       ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -371,6 +376,8 @@ impl<'a> Render<'a> for State {
       kernel_size_updated: false,
 
       resolution_updated: false,
+
+      key_bindings: initial.key_bindings.clone(),
     }
   }
 
@@ -382,46 +389,35 @@ impl<'a> Render<'a> for State {
   }
 
   fn process_event(&mut self, event: &WindowEvent) -> bool {
-    match event {
-      WindowEvent::KeyboardInput {
-        event:
-          KeyEvent {
-            physical_key,
-            state: ElementState::Pressed,
-            ..
-          },
-        ..
-      } => match physical_key {
-        PhysicalKey::Code(KeyCode::KeyL) => {
-          self.kernel_size =
-            MAX_KERNEL_SIZE.min(self.kernel_size + KERNEL_SIZE_STEP);
-          println!("kernel size: {}", self.kernel_size);
-          self.kernel_size_updated = true;
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyJ) => {
-          self.kernel_size =
-            MIN_KERNEL_SIZE.max(self.kernel_size - KERNEL_SIZE_STEP);
-          println!("kernel size: {}", self.kernel_size);
-          self.kernel_size_updated = true;
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyO) => {
-          self.iterations =
-            MAX_ITERATIONS.min(self.iterations + ITERATIONS_STEP);
-          println!("iterations: {}", self.iterations);
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyU) => {
-          self.iterations =
-            MIN_ITERATIONS.max(self.iterations - ITERATIONS_STEP);
-          println!("iterations: {}", self.iterations);
-          true
-        }
-        _ => false,
-      },
-      _ => false,
+    let action = match self.key_bindings.process_event(event) {
+      Some(action) => action,
+      None => return false,
+    };
+
+    match action {
+      Action::IncreaseKernelSize => {
+        self.kernel_size =
+          MAX_KERNEL_SIZE.min(self.kernel_size + KERNEL_SIZE_STEP);
+        log::info!("kernel size: {}", self.kernel_size);
+        self.kernel_size_updated = true;
+      }
+      Action::DecreaseKernelSize => {
+        self.kernel_size =
+          MIN_KERNEL_SIZE.max(self.kernel_size - KERNEL_SIZE_STEP);
+        log::info!("kernel size: {}", self.kernel_size);
+        self.kernel_size_updated = true;
+      }
+      Action::IncreaseIterations => {
+        self.iterations = MAX_ITERATIONS.min(self.iterations + ITERATIONS_STEP);
+        log::info!("iterations: {}", self.iterations);
+      }
+      Action::DecreaseIterations => {
+        self.iterations = MIN_ITERATIONS.max(self.iterations - ITERATIONS_STEP);
+        log::info!("iterations: {}", self.iterations);
+      }
     }
+
+    true
   }
 
   fn update(&mut self, ctx: &DrawingContext, _dt: std::time::Duration) {
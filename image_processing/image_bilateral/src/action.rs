@@ -0,0 +1,29 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  IncreaseSigmaSpatial,
+  DecreaseSigmaSpatial,
+  IncreaseSigmaRange,
+  DecreaseSigmaRange,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::IncreaseSigmaSpatial => "Increase spatial sigma",
+      Action::DecreaseSigmaSpatial => "Decrease spatial sigma",
+      Action::IncreaseSigmaRange => "Increase range sigma",
+      Action::DecreaseSigmaRange => "Decrease range sigma",
+    }
+  }
+}
+
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new()
+    .bind(KeyCode::KeyD, Action::IncreaseSigmaSpatial)
+    .bind(KeyCode::KeyA, Action::DecreaseSigmaSpatial)
+    .bind(KeyCode::KeyL, Action::IncreaseSigmaRange)
+    .bind(KeyCode::KeyJ, Action::DecreaseSigmaRange)
+}
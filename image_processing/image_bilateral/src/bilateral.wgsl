@@ -0,0 +1,85 @@
+struct BilateralParams {
+  sigma_spatial: f32,
+  sigma_range: f32,
+}
+
+@group(0) @binding(0) var samp: sampler;
+@group(0) @binding(1) var<uniform> params: BilateralParams;
+
+@group(1) @binding(0) var input_tex: texture_2d<f32>;
+@group(1) @binding(1) var output_tex: texture_storage_2d<rgba8unorm, write>;
+
+// 1つのワークグループは 8x8 ピクセルを出力する
+const workgroup_size = 8u;
+
+// カーネル半径の上限（sigma_spatial の最大 5.0 の2倍）
+const max_radius = 10u;
+
+// 重みが色の差にも依存するため、横と縦に分離できない。
+// 2Dの近傍をまとめてキャッシュするので、上下左右に max_radius ずつ境界を含める
+const cache_size = workgroup_size + 2u * max_radius; // 28
+
+var<workgroup> cache: array<array<vec3f, 28>, 28>;
+
+struct CsInput {
+  @builtin(workgroup_id) workgroup_id: vec3u,
+  @builtin(local_invocation_id) local_id: vec3u,
+  @builtin(global_invocation_id) global_id: vec3u
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn cs_main(in: CsInput) {
+  let dims = vec2i(textureDimensions(input_tex, 0));
+
+  // キャッシュの左上に対応するピクセル
+  let cache_origin = vec2i(in.workgroup_id.xy * workgroup_size) - vec2i(i32(max_radius));
+
+  // 28x28 のキャッシュを 8x8 のスレッドで埋めるので、1スレッドが複数回読み込む
+  let local_index = in.local_id.y * workgroup_size + in.local_id.x;
+  for (var i = local_index; i < cache_size * cache_size; i += workgroup_size * workgroup_size) {
+    let cache_index = vec2u(i % cache_size, i / cache_size);
+
+    // ピクセルの中心をサンプリングする（画像の外はサンプラーが端の色にクランプする）
+    let uv = (vec2f(cache_origin + vec2i(cache_index)) + 0.5) / vec2f(dims);
+    cache[cache_index.y][cache_index.x] = textureSampleLevel(input_tex, samp, uv, 0.0).rgb;
+  }
+
+  workgroupBarrier();
+
+  let pixel = vec2i(in.global_id.xy);
+  if (any(pixel >= dims)) {
+    return;
+  }
+
+  // 2σより外側の重みはほぼ0なので打ち切る
+  let radius = min(i32(ceil(2.0 * params.sigma_spatial)), i32(max_radius));
+  let center = vec2i(in.local_id.xy + vec2u(max_radius));
+  let center_color = cache[center.y][center.x];
+
+  let spatial_denom = 2.0 * params.sigma_spatial * params.sigma_spatial;
+  let range_denom = 2.0 * params.sigma_range * params.sigma_range;
+
+  var acc = vec3(0.0);
+  var sum_w = 0.0;
+  for (var dy = -radius; dy <= radius; dy++) {
+    for (var dx = -radius; dx <= radius; dx++) {
+      let c = center + vec2i(dx, dy);
+      let color = cache[c.y][c.x];
+
+      // 距離による重み
+      let d2 = f32(dx * dx + dy * dy);
+      let spatial_weight = exp(-d2 / spatial_denom);
+
+      // 中心との色の差による重み（エッジをまたぐと小さくなる）
+      let diff = color - center_color;
+      let range_weight = exp(-dot(diff, diff) / range_denom);
+
+      let weight = spatial_weight * range_weight;
+      acc += color * weight;
+      sum_w += weight;
+    }
+  }
+
+  // 中心自身の重みは1なので、sum_w は0にならない
+  textureStore(output_tex, pixel, vec4(acc / sum_w, 1.0));
+}
@@ -0,0 +1,29 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  IncreaseFilterSize,
+  DecreaseFilterSize,
+  IncreaseIterations,
+  DecreaseIterations,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::IncreaseFilterSize => "Increase filter size",
+      Action::DecreaseFilterSize => "Decrease filter size",
+      Action::IncreaseIterations => "Increase iterations",
+      Action::DecreaseIterations => "Decrease iterations",
+    }
+  }
+}
+
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new()
+    .bind(KeyCode::KeyG, Action::IncreaseFilterSize)
+    .bind(KeyCode::KeyD, Action::DecreaseFilterSize)
+    .bind(KeyCode::KeyO, Action::IncreaseIterations)
+    .bind(KeyCode::KeyU, Action::DecreaseIterations)
+}
@@ -1,15 +1,18 @@
+mod action;
+
 use std::error::Error;
 
+use action::Action;
 use bytemuck::cast_slice;
 use image::GenericImageView;
 use wgpu::util::DeviceExt;
 use wgsim::app::App;
 use wgsim::ctx::{DrawingContext, Size};
+use wgsim::input::KeyBindings;
 use wgsim::ppl::{ComputePipelineBuilder, RenderPipelineBuilder};
 use wgsim::render::{Render, RenderTarget};
-use wgsim::util;
-use winit::event::{ElementState, KeyEvent, WindowEvent};
-use winit::keyboard::{KeyCode, PhysicalKey};
+use wgsim::util::{self, PingPong, PingPongBinding, SamplerOptions};
+use winit::event::WindowEvent;
 
 const TILE_DIM: u32 = 128;
 const BATCH: [u32; 2] = [4, 4];
@@ -36,6 +39,7 @@ fn setup() -> Initial {
     image_size,
     filter_size: 8,
     iterations: 2,
+    key_bindings: action::default_key_bindings(),
   }
 }
 
@@ -43,6 +47,7 @@ pub fn run() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
   let initial = setup();
+  initial.key_bindings.print_help();
 
   let mut app: App<State> =
     App::new("image_blur", initial).with_window_size(600, 400);
@@ -56,6 +61,7 @@ struct Initial {
   image_size: (u32, u32),
   filter_size: u32,
   iterations: u32,
+  key_bindings: KeyBindings<Action>,
 }
 
 struct State {
@@ -63,9 +69,8 @@ struct State {
   fullscreen_quad_pipeline: wgpu::RenderPipeline,
 
   compute_constants_bind_group: wgpu::BindGroup,
-  compute_bind_group_0: wgpu::BindGroup,
-  compute_bind_group_1: wgpu::BindGroup,
-  compute_bind_group_2: wgpu::BindGroup,
+  compute_bind_group_init: wgpu::BindGroup,
+  compute_ping_pong: PingPong<wgpu::TextureView>,
   show_result_bind_group: wgpu::BindGroup,
 
   blur_params_uniform_buffer: wgpu::Buffer,
@@ -79,6 +84,8 @@ struct State {
   block_dim_updated: bool,
 
   resolution_updated: bool,
+
+  key_bindings: KeyBindings<Action>,
 }
 
 impl<'a> Render<'a> for State {
@@ -99,12 +106,7 @@ impl<'a> Render<'a> for State {
     // texture & sampler
     //
 
-    let sampler = ctx.device.create_sampler(&wgpu::SamplerDescriptor {
-      label: Some("sampler"),
-      mag_filter: wgpu::FilterMode::Linear,
-      min_filter: wgpu::FilterMode::Linear,
-      ..Default::default()
-    });
+    let sampler = util::create_sampler(&ctx.device, SamplerOptions::default());
 
     let image_texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
       label: Some("image texture"),
@@ -250,45 +252,38 @@ impl<'a> Render<'a> for State {
       ],
     );
 
-    let compute_bind_group_0 = util::create_bind_group(
+    let texture_views = textures
+      .iter()
+      .map(|texture| {
+        texture.create_view(&wgpu::TextureViewDescriptor::default())
+      })
+      .collect::<Vec<_>>();
+
+    // 元画像 -> textures[0]（横方向）
+    let compute_bind_group_init = util::create_bind_group(
       &ctx.device,
       &compute_bind_group_layout,
       &[
         wgpu::BindingResource::TextureView(
           &image_texture.create_view(&wgpu::TextureViewDescriptor::default()),
         ),
-        wgpu::BindingResource::TextureView(
-          &textures[0].create_view(&wgpu::TextureViewDescriptor::default()),
-        ),
+        wgpu::BindingResource::TextureView(&texture_views[0]),
         flip_0_uniform_buffer.as_entire_binding(),
       ],
     );
 
-    let compute_bind_group_1 = util::create_bind_group(
-      &ctx.device,
-      &compute_bind_group_layout,
-      &[
-        wgpu::BindingResource::TextureView(
-          &textures[0].create_view(&wgpu::TextureViewDescriptor::default()),
-        ),
-        wgpu::BindingResource::TextureView(
-          &textures[1].create_view(&wgpu::TextureViewDescriptor::default()),
-        ),
-        flip_1_uniform_buffer.as_entire_binding(),
-      ],
-    );
-
-    let compute_bind_group_2 = util::create_bind_group(
+    // textures[0] -> textures[1]（縦方向）, textures[1] -> textures[0]（横方向）
+    let compute_ping_pong = PingPong::new(
       &ctx.device,
       &compute_bind_group_layout,
+      texture_views,
       &[
-        wgpu::BindingResource::TextureView(
-          &textures[1].create_view(&wgpu::TextureViewDescriptor::default()),
-        ),
-        wgpu::BindingResource::TextureView(
-          &textures[0].create_view(&wgpu::TextureViewDescriptor::default()),
-        ),
-        flip_0_uniform_buffer.as_entire_binding(),
+        PingPongBinding::Current,
+        PingPongBinding::Next,
+        PingPongBinding::PerStep(vec![
+          flip_1_uniform_buffer.as_entire_binding(),
+          flip_0_uniform_buffer.as_entire_binding(),
+        ]),
       ],
     );
 
@@ -310,9 +305,7 @@ impl<'a> Render<'a> for State {
       &show_result_bind_group_layout,
       &[
         wgpu::BindingResource::Sampler(&sampler),
-        wgpu::BindingResource::TextureView(
-          &textures[1].create_view(&wgpu::TextureViewDescriptor::default()),
-        ),
+        wgpu::BindingResource::TextureView(compute_ping_pong.next_resource()),
         resolution_uniform_buffer.as_entire_binding(),
       ],
     );
@@ -331,7 +324,9 @@ impl<'a> Render<'a> for State {
       .vs_shader(&fullscreen_quad_shader, "vs_main")
       .fs_shader(&fullscreen_quad_shader, "fs_main")
       .pipeline_layout(&fullscreen_quad_pipeline_layout)
-      .build();
+      .label("Fullscreen Quad Pipeline")
+      .build_checked()
+      .unwrap_or_else(|e| panic!("{}", e));
 
     let blur_pipeline_layout =
       ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -352,9 +347,8 @@ impl<'a> Render<'a> for State {
       fullscreen_quad_pipeline,
 
       compute_constants_bind_group,
-      compute_bind_group_0,
-      compute_bind_group_1,
-      compute_bind_group_2,
+      compute_bind_group_init,
+      compute_ping_pong,
       show_result_bind_group,
 
       blur_params_uniform_buffer,
@@ -368,6 +362,8 @@ impl<'a> Render<'a> for State {
       block_dim_updated: false,
 
       resolution_updated: false,
+
+      key_bindings: initial.key_bindings.clone(),
     }
   }
 
@@ -379,46 +375,35 @@ impl<'a> Render<'a> for State {
   }
 
   fn process_event(&mut self, event: &WindowEvent) -> bool {
-    match event {
-      WindowEvent::KeyboardInput {
-        event:
-          KeyEvent {
-            physical_key,
-            state: ElementState::Pressed,
-            ..
-          },
-        ..
-      } => match physical_key {
-        PhysicalKey::Code(KeyCode::KeyG) => {
-          self.filter_size =
-            MAX_FILTER_SIZE.min(self.filter_size + FILTER_SIZE_STEP);
-          println!("filter size: {}", self.filter_size);
-          self.block_dim_updated = true;
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyD) => {
-          self.filter_size =
-            MIN_FILTER_SIZE.max(self.filter_size - FILTER_SIZE_STEP);
-          println!("filter size: {}", self.filter_size);
-          self.block_dim_updated = true;
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyO) => {
-          self.iterations =
-            MAX_ITERATIONS.min(self.iterations + ITERATIONS_STEP);
-          println!("iterations: {}", self.iterations);
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyU) => {
-          self.iterations =
-            MIN_ITERATIONS.max(self.iterations - ITERATIONS_STEP);
-          println!("iterations: {}", self.iterations);
-          true
-        }
-        _ => false,
-      },
-      _ => false,
+    let action = match self.key_bindings.process_event(event) {
+      Some(action) => action,
+      None => return false,
+    };
+
+    match action {
+      Action::IncreaseFilterSize => {
+        self.filter_size =
+          MAX_FILTER_SIZE.min(self.filter_size + FILTER_SIZE_STEP);
+        log::info!("filter size: {}", self.filter_size);
+        self.block_dim_updated = true;
+      }
+      Action::DecreaseFilterSize => {
+        self.filter_size =
+          MIN_FILTER_SIZE.max(self.filter_size - FILTER_SIZE_STEP);
+        log::info!("filter size: {}", self.filter_size);
+        self.block_dim_updated = true;
+      }
+      Action::IncreaseIterations => {
+        self.iterations = MAX_ITERATIONS.min(self.iterations + ITERATIONS_STEP);
+        log::info!("iterations: {}", self.iterations);
+      }
+      Action::DecreaseIterations => {
+        self.iterations = MIN_ITERATIONS.max(self.iterations - ITERATIONS_STEP);
+        log::info!("iterations: {}", self.iterations);
+      }
     }
+
+    true
   }
 
   fn update(&mut self, ctx: &DrawingContext, _dt: std::time::Duration) {
@@ -471,34 +456,30 @@ impl<'a> Render<'a> for State {
     compute_pass.set_pipeline(&self.blur_pipeline);
     compute_pass.set_bind_group(0, &self.compute_constants_bind_group, &[]);
 
-    compute_pass.set_bind_group(1, &self.compute_bind_group_0, &[]);
+    compute_pass.set_bind_group(1, &self.compute_bind_group_init, &[]);
     compute_pass.dispatch_workgroups(
       self.image_size.0.div_ceil(self.block_dim),
       self.image_size.1.div_ceil(BATCH[1]),
       1,
     );
 
-    compute_pass.set_bind_group(1, &self.compute_bind_group_1, &[]);
-    compute_pass.dispatch_workgroups(
-      self.image_size.1.div_ceil(self.block_dim),
-      self.image_size.0.div_ceil(BATCH[1]),
-      1,
-    );
+    // 縦方向と横方向を交互に繰り返し、最後は縦方向の出力（textures[1]）で終わる
+    self.compute_ping_pong.reset();
+    for _ in 0..self.iterations * 2 - 1 {
+      let (width, height) = if self.compute_ping_pong.index() == 0 {
+        (self.image_size.1, self.image_size.0)
+      } else {
+        (self.image_size.0, self.image_size.1)
+      };
 
-    for _ in 0..self.iterations - 1 {
-      compute_pass.set_bind_group(1, &self.compute_bind_group_2, &[]);
+      compute_pass.set_bind_group(1, self.compute_ping_pong.current(), &[]);
       compute_pass.dispatch_workgroups(
-        self.image_size.0.div_ceil(self.block_dim),
-        self.image_size.1.div_ceil(BATCH[1]),
+        width.div_ceil(self.block_dim),
+        height.div_ceil(BATCH[1]),
         1,
       );
 
-      compute_pass.set_bind_group(1, &self.compute_bind_group_1, &[]);
-      compute_pass.dispatch_workgroups(
-        self.image_size.1.div_ceil(self.block_dim),
-        self.image_size.0.div_ceil(BATCH[1]),
-        1,
-      );
+      self.compute_ping_pong.advance();
     }
 
     drop(compute_pass);
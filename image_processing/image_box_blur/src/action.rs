@@ -0,0 +1,35 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  IncreaseRadius,
+  DecreaseRadius,
+  IncreaseRadiusFast,
+  DecreaseRadiusFast,
+  IncreaseIterations,
+  DecreaseIterations,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::IncreaseRadius => "Increase radius by 1",
+      Action::DecreaseRadius => "Decrease radius by 1",
+      Action::IncreaseRadiusFast => "Increase radius by 16",
+      Action::DecreaseRadiusFast => "Decrease radius by 16",
+      Action::IncreaseIterations => "Increase iterations",
+      Action::DecreaseIterations => "Decrease iterations",
+    }
+  }
+}
+
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new()
+    .bind(KeyCode::ArrowUp, Action::IncreaseRadius)
+    .bind(KeyCode::ArrowDown, Action::DecreaseRadius)
+    .bind(KeyCode::ArrowRight, Action::IncreaseRadiusFast)
+    .bind(KeyCode::ArrowLeft, Action::DecreaseRadiusFast)
+    .bind(KeyCode::KeyO, Action::IncreaseIterations)
+    .bind(KeyCode::KeyU, Action::DecreaseIterations)
+}
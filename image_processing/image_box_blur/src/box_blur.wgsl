@@ -0,0 +1,86 @@
+struct Flip {
+  value: u32,
+}
+
+// カーネルのうち、1回のディスパッチで足し合わせる区間
+struct Segment {
+  // 中心からみた区間の先頭のオフセット
+  start: i32,
+  len: u32,
+  block_dim: u32,
+  // 前の区間までの部分和を足すかどうか
+  accumulate: u32,
+  // 最後の区間なら、平均をとって output_tex に書き込む
+  finish: u32,
+  inv_filter_dim: f32,
+}
+
+@group(0) @binding(0) var input_tex: texture_2d<f32>;
+@group(0) @binding(1) var output_tex: texture_storage_2d<rgba16float, write>;
+@group(0) @binding(2) var<uniform> flip: Flip;
+
+@group(1) @binding(0) var acc_in_tex: texture_2d<f32>;
+@group(1) @binding(1) var acc_out_tex: texture_storage_2d<rgba32float, write>;
+@group(1) @binding(2) var<uniform> segment: Segment;
+
+var<workgroup> tile: array<array<vec3f, 128>, 4>;
+
+struct CsInput {
+  @builtin(workgroup_id) workgroup_id: vec3u,
+  @builtin(local_invocation_id) local_id: vec3u
+}
+
+// flip が立っているときは縦方向に処理するため、xとyを入れ替えて扱う
+fn to_texel(index: vec2i) -> vec2i {
+  if (flip.value != 0u) {
+    return index.yx;
+  }
+  return index;
+}
+
+@compute @workgroup_size(32, 1, 1)
+fn cs_main(in: CsInput) {
+  var dims = vec2i(textureDimensions(input_tex, 0));
+  if (flip.value != 0u) {
+    dims = dims.yx;
+  }
+
+  let block_start = vec2i(in.workgroup_id.xy * vec2(segment.block_dim, 4));
+  let tile_start = block_start + vec2(segment.start, 0);
+
+  for (var r = 0; r < 4; r++) {
+    for (var c = 0; c < 4; c++) {
+      let i = 4 * i32(in.local_id.x) + c;
+      let load_index = clamp(tile_start + vec2(i, r), vec2(0), dims - 1);
+      tile[r][i] = textureLoad(input_tex, to_texel(load_index), 0).rgb;
+    }
+  }
+
+  workgroupBarrier();
+
+  for (var r = 0; r < 4; r++) {
+    for (var c = 0; c < 4; c++) {
+      let i = 4 * in.local_id.x + u32(c);
+      let write_index = block_start + vec2(i32(i), r);
+      if (i >= segment.block_dim || any(write_index >= dims)) {
+        continue;
+      }
+
+      var acc = vec3(0.0);
+      for (var f = 0u; f < segment.len; f++) {
+        acc += tile[r][i + f];
+      }
+
+      let texel = to_texel(write_index);
+      if (segment.accumulate != 0u) {
+        acc += textureLoad(acc_in_tex, texel, 0).rgb;
+      }
+
+      if (segment.finish != 0u) {
+        textureStore(output_tex, texel, vec4(acc * segment.inv_filter_dim, 1.0));
+      } else {
+        textureStore(acc_out_tex, texel, vec4(acc, 1.0));
+      }
+    }
+  }
+}
@@ -0,0 +1,604 @@
+mod action;
+
+use std::error::Error;
+
+use action::Action;
+use bytemuck::cast_slice;
+use image::GenericImageView;
+use wgpu::util::DeviceExt;
+use wgsim::app::App;
+use wgsim::ctx::{DrawingContext, Size};
+use wgsim::input::KeyBindings;
+use wgsim::ppl::{ComputePipelineBuilder, RenderPipelineBuilder};
+use wgsim::render::{Render, RenderTarget};
+use wgsim::util::{self, PingPong, PingPongBinding, SamplerOptions};
+use winit::event::WindowEvent;
+
+const TILE_DIM: u32 = 128;
+const BATCH: [u32; 2] = [4, 4];
+
+/// 1回のディスパッチで足し合わせるタップ数の上限。
+/// これを超えるカーネルは区間に分けて、部分和を積み上げる。
+const MAX_SEGMENT_LEN: u32 = TILE_DIM / 2 + 1;
+
+const MIN_RADIUS: u32 = 1;
+const MAX_RADIUS: u32 = 128;
+const RADIUS_STEP: u32 = 1;
+const RADIUS_FAST_STEP: u32 = 16;
+
+const MIN_ITERATIONS: u32 = 1;
+const MAX_ITERATIONS: u32 = 10;
+const ITERATIONS_STEP: u32 = 1;
+
+/// 中間テクスチャの形式（シェーダーの output_tex に合わせる）
+const IMAGE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+/// 部分和の形式（シェーダーの acc_out_tex に合わせる）
+const ACC_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+
+/// One dispatch worth of taps, `start` being relative to the output pixel.
+struct Segment {
+  start: i32,
+  len: u32,
+}
+
+impl Segment {
+  /// Each workgroup loads `TILE_DIM` texels, so it can only finish the
+  /// outputs whose taps all lie inside the tile.
+  fn block_dim(&self) -> u32 {
+    TILE_DIM - (self.len - 1)
+  }
+}
+
+/// Splits the `2 * radius + 1` taps into runs of at most `MAX_SEGMENT_LEN`.
+fn split_kernel(radius: u32) -> Vec<Segment> {
+  let filter_dim = 2 * radius + 1;
+
+  (0..filter_dim)
+    .step_by(MAX_SEGMENT_LEN as usize)
+    .map(|offset| Segment {
+      start: offset as i32 - radius as i32,
+      len: MAX_SEGMENT_LEN.min(filter_dim - offset),
+    })
+    .collect()
+}
+
+fn setup() -> Initial {
+  let img_bytes = include_bytes!("../../../assets/img/stained-glass_w600.png");
+  let image = image::load_from_memory(img_bytes).unwrap();
+  let image_size = image.dimensions();
+
+  Initial {
+    image,
+    image_size,
+    radius: 16,
+    iterations: 1,
+    key_bindings: action::default_key_bindings(),
+  }
+}
+
+pub fn run() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup();
+  initial.key_bindings.print_help();
+
+  let mut app: App<State> =
+    App::new("image_box_blur", initial).with_window_size(600, 400);
+  app.run()?;
+
+  Ok(())
+}
+
+struct Initial {
+  image: image::DynamicImage,
+  image_size: (u32, u32),
+  radius: u32,
+  iterations: u32,
+  key_bindings: KeyBindings<Action>,
+}
+
+struct State {
+  blur_pipeline: wgpu::ComputePipeline,
+  fullscreen_quad_pipeline: wgpu::RenderPipeline,
+
+  compute_bind_group_init: wgpu::BindGroup,
+  compute_ping_pong: PingPong<wgpu::TextureView>,
+  segment_bind_group_layout: wgpu::BindGroupLayout,
+  segment_bind_groups: Vec<(wgpu::BindGroup, u32)>,
+  show_result_bind_group: wgpu::BindGroup,
+
+  acc_texture_views: Vec<wgpu::TextureView>,
+  resolution_uniform_buffer: wgpu::Buffer,
+
+  image_size: (u32, u32),
+  radius: u32,
+  iterations: u32,
+
+  radius_updated: bool,
+  resolution_updated: bool,
+
+  key_bindings: KeyBindings<Action>,
+}
+
+/// Bind groups for every segment of the kernel, paired with the segment's
+/// block dimension. The partial sums alternate between the two `acc` views
+/// so no dispatch reads the texture it writes.
+fn create_segment_bind_groups(
+  device: &wgpu::Device,
+  layout: &wgpu::BindGroupLayout,
+  acc_texture_views: &[wgpu::TextureView],
+  radius: u32,
+) -> Vec<(wgpu::BindGroup, u32)> {
+  let segments = split_kernel(radius);
+  let inv_filter_dim = 1. / (2 * radius + 1) as f32;
+
+  segments
+    .iter()
+    .enumerate()
+    .map(|(i, segment)| {
+      let is_last = i == segments.len() - 1;
+      let params = [
+        segment.start as u32,
+        segment.len,
+        segment.block_dim(),
+        (i > 0) as u32,
+        is_last as u32,
+        inv_filter_dim.to_bits(),
+        0,
+        0,
+      ];
+      let params_buffer =
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+          label: Some("segment uniform buffer"),
+          contents: cast_slice(&params),
+          usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+      let bind_group = util::create_bind_group(
+        device,
+        layout,
+        &[
+          wgpu::BindingResource::TextureView(&acc_texture_views[(i + 1) % 2]),
+          wgpu::BindingResource::TextureView(&acc_texture_views[i % 2]),
+          params_buffer.as_entire_binding(),
+        ],
+      );
+
+      (bind_group, segment.block_dim())
+    })
+    .collect()
+}
+
+/// 区間ごとにディスパッチし、最後の区間で平均をとって書き出す
+fn dispatch_segments(
+  pass: &mut wgpu::ComputePass,
+  segment_bind_groups: &[(wgpu::BindGroup, u32)],
+  width: u32,
+  height: u32,
+) {
+  for (bind_group, block_dim) in segment_bind_groups {
+    pass.set_bind_group(1, bind_group, &[]);
+    pass.dispatch_workgroups(
+      width.div_ceil(*block_dim),
+      height.div_ceil(BATCH[1]),
+      1,
+    );
+  }
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    //
+    // shader
+    //
+
+    let fullscreen_quad_shader = ctx.device.create_shader_module(
+      wgpu::include_wgsl!("./fullscreen-textured-quad.wgsl"),
+    );
+    let blur_shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./box_blur.wgsl"));
+
+    //
+    // texture & sampler
+    //
+
+    let sampler = util::create_sampler(&ctx.device, SamplerOptions::default());
+
+    let image_extent = wgpu::Extent3d {
+      width: initial.image_size.0,
+      height: initial.image_size.1,
+      depth_or_array_layers: 1,
+    };
+
+    let image_texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("image texture"),
+      size: image_extent,
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: wgpu::TextureFormat::Rgba8UnormSrgb,
+      usage: wgpu::TextureUsages::COPY_DST
+        | wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    });
+    ctx.queue.write_texture(
+      image_texture.as_image_copy(),
+      &initial.image.to_rgba8(),
+      wgpu::ImageDataLayout {
+        offset: 0,
+        bytes_per_row: Some(4 * initial.image_size.0),
+        rows_per_image: Some(initial.image_size.1),
+      },
+      image_extent,
+    );
+
+    let create_storage_texture = |label, format| {
+      ctx.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: image_extent,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::STORAGE_BINDING
+          | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+      })
+    };
+
+    let texture_views = (0..=1)
+      .map(|_| {
+        create_storage_texture("texture", IMAGE_FORMAT)
+          .create_view(&wgpu::TextureViewDescriptor::default())
+      })
+      .collect::<Vec<_>>();
+
+    let acc_texture_views = (0..=1)
+      .map(|_| {
+        create_storage_texture("partial sum texture", ACC_FORMAT)
+          .create_view(&wgpu::TextureViewDescriptor::default())
+      })
+      .collect::<Vec<_>>();
+
+    //
+    // uniform
+    //
+
+    let flip_0_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("flip uniform buffer with 0"),
+        contents: cast_slice(&[0u32]),
+        usage: wgpu::BufferUsages::UNIFORM,
+      });
+
+    let flip_1_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("flip uniform buffer with 1"),
+        contents: cast_slice(&[1u32]),
+        usage: wgpu::BufferUsages::UNIFORM,
+      });
+
+    let resolution = ctx.resolution();
+
+    let resolution_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("resolution uniform buffer"),
+        contents: cast_slice(&[
+          resolution.width as f32,
+          resolution.height as f32,
+        ]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+
+    //
+    // bind group
+    //
+
+    let sampler_binding_type =
+      wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering);
+
+    let uniform_binding_type = wgpu::BindingType::Buffer {
+      ty: wgpu::BufferBindingType::Uniform,
+      has_dynamic_offset: false,
+      min_binding_size: None,
+    };
+
+    let texture_binding_type = wgpu::BindingType::Texture {
+      sample_type: wgpu::TextureSampleType::Float { filterable: true },
+      view_dimension: wgpu::TextureViewDimension::D2,
+      multisampled: false,
+    };
+
+    // Rgba32Float はフィルタリングできないので、textureLoad でのみ読む
+    let acc_texture_binding_type = wgpu::BindingType::Texture {
+      sample_type: wgpu::TextureSampleType::Float { filterable: false },
+      view_dimension: wgpu::TextureViewDimension::D2,
+      multisampled: false,
+    };
+
+    let storage_binding_type = |format| wgpu::BindingType::StorageTexture {
+      access: wgpu::StorageTextureAccess::WriteOnly,
+      format,
+      view_dimension: wgpu::TextureViewDimension::D2,
+    };
+
+    let compute_stages = [
+      wgpu::ShaderStages::COMPUTE,
+      wgpu::ShaderStages::COMPUTE,
+      wgpu::ShaderStages::COMPUTE,
+    ];
+
+    let compute_bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[
+        texture_binding_type,
+        storage_binding_type(IMAGE_FORMAT),
+        uniform_binding_type,
+      ],
+      &compute_stages,
+    );
+
+    // 元画像 -> textures[0]（横方向）
+    let compute_bind_group_init = util::create_bind_group(
+      &ctx.device,
+      &compute_bind_group_layout,
+      &[
+        wgpu::BindingResource::TextureView(
+          &image_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        ),
+        wgpu::BindingResource::TextureView(&texture_views[0]),
+        flip_0_uniform_buffer.as_entire_binding(),
+      ],
+    );
+
+    // textures[0] -> textures[1]（縦方向）, textures[1] -> textures[0]（横方向）
+    let compute_ping_pong = PingPong::new(
+      &ctx.device,
+      &compute_bind_group_layout,
+      texture_views,
+      &[
+        PingPongBinding::Current,
+        PingPongBinding::Next,
+        PingPongBinding::PerStep(vec![
+          flip_1_uniform_buffer.as_entire_binding(),
+          flip_0_uniform_buffer.as_entire_binding(),
+        ]),
+      ],
+    );
+
+    let segment_bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[
+        acc_texture_binding_type,
+        storage_binding_type(ACC_FORMAT),
+        uniform_binding_type,
+      ],
+      &compute_stages,
+    );
+    let segment_bind_groups = create_segment_bind_groups(
+      &ctx.device,
+      &segment_bind_group_layout,
+      &acc_texture_views,
+      initial.radius,
+    );
+
+    let show_result_bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[
+        sampler_binding_type,
+        texture_binding_type,
+        uniform_binding_type,
+      ],
+      &[
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::ShaderStages::VERTEX_FRAGMENT,
+        wgpu::ShaderStages::VERTEX,
+      ],
+    );
+    let show_result_bind_group = util::create_bind_group(
+      &ctx.device,
+      &show_result_bind_group_layout,
+      &[
+        wgpu::BindingResource::Sampler(&sampler),
+        wgpu::BindingResource::TextureView(compute_ping_pong.next_resource()),
+        resolution_uniform_buffer.as_entire_binding(),
+      ],
+    );
+
+    //
+    // pipeline
+    //
+
+    let fullscreen_quad_pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Fullscreen Quad Pipeline Layout"),
+        bind_group_layouts: &[&show_result_bind_group_layout],
+        push_constant_ranges: &[],
+      });
+    let fullscreen_quad_pipeline = RenderPipelineBuilder::new(&ctx)
+      .vs_shader(&fullscreen_quad_shader, "vs_main")
+      .fs_shader(&fullscreen_quad_shader, "fs_main")
+      .pipeline_layout(&fullscreen_quad_pipeline_layout)
+      .label("Fullscreen Quad Pipeline")
+      .build_checked()
+      .unwrap_or_else(|e| panic!("{}", e));
+
+    let blur_pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Box Blur Pipeline Layout"),
+        bind_group_layouts: &[
+          &compute_bind_group_layout,
+          &segment_bind_group_layout,
+        ],
+        push_constant_ranges: &[],
+      });
+    let blur_pipeline = ComputePipelineBuilder::new(&ctx.device)
+      .cs_shader(&blur_shader, "cs_main")
+      .pipeline_layout(&blur_pipeline_layout)
+      .build();
+
+    Self {
+      blur_pipeline,
+      fullscreen_quad_pipeline,
+
+      compute_bind_group_init,
+      compute_ping_pong,
+      segment_bind_group_layout,
+      segment_bind_groups,
+      show_result_bind_group,
+
+      acc_texture_views,
+      resolution_uniform_buffer,
+
+      image_size: initial.image_size,
+      radius: initial.radius,
+      iterations: initial.iterations,
+
+      radius_updated: false,
+      resolution_updated: false,
+
+      key_bindings: initial.key_bindings.clone(),
+    }
+  }
+
+  fn resize(&mut self, ctx: &mut DrawingContext<'_>, size: Size) {
+    if size.width > 0 && size.height > 0 {
+      ctx.resize(size.into());
+      self.resolution_updated = true;
+    }
+  }
+
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    let action = match self.key_bindings.process_event(event) {
+      Some(action) => action,
+      None => return false,
+    };
+
+    let radius = match action {
+      Action::IncreaseRadius => self.radius + RADIUS_STEP,
+      Action::DecreaseRadius => self.radius.saturating_sub(RADIUS_STEP),
+      Action::IncreaseRadiusFast => self.radius + RADIUS_FAST_STEP,
+      Action::DecreaseRadiusFast => {
+        self.radius.saturating_sub(RADIUS_FAST_STEP)
+      }
+      Action::IncreaseIterations => {
+        self.iterations = MAX_ITERATIONS.min(self.iterations + ITERATIONS_STEP);
+        log::info!("iterations: {}", self.iterations);
+        return true;
+      }
+      Action::DecreaseIterations => {
+        self.iterations = MIN_ITERATIONS.max(self.iterations - ITERATIONS_STEP);
+        log::info!("iterations: {}", self.iterations);
+        return true;
+      }
+    };
+
+    self.radius = radius.clamp(MIN_RADIUS, MAX_RADIUS);
+    log::info!(
+      "radius: {} ({} dispatches per pass)",
+      self.radius,
+      split_kernel(self.radius).len()
+    );
+    self.radius_updated = true;
+
+    true
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, _dt: std::time::Duration) {
+    if self.radius_updated {
+      self.segment_bind_groups = create_segment_bind_groups(
+        &ctx.device,
+        &self.segment_bind_group_layout,
+        &self.acc_texture_views,
+        self.radius,
+      );
+      self.radius_updated = false;
+    }
+
+    if self.resolution_updated {
+      let resolution = ctx.resolution();
+      ctx.queue.write_buffer(
+        &self.resolution_uniform_buffer,
+        0,
+        cast_slice(&[resolution.width as f32, resolution.height as f32]),
+      );
+      self.resolution_updated = false;
+    }
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: RenderTarget,
+    _sample_count: u32,
+  ) -> Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+    let (view, frame) = match target {
+      RenderTarget::Surface(surface) => {
+        let frame = surface.get_current_texture()?;
+        let view =
+          frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, Some(frame))
+      }
+      RenderTarget::Texture(texture) => {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, None)
+      }
+    };
+
+    let mut compute_pass =
+      encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("compute pass"),
+        ..Default::default()
+      });
+
+    compute_pass.set_pipeline(&self.blur_pipeline);
+
+    compute_pass.set_bind_group(0, &self.compute_bind_group_init, &[]);
+    dispatch_segments(
+      &mut compute_pass,
+      &self.segment_bind_groups,
+      self.image_size.0,
+      self.image_size.1,
+    );
+
+    // 縦方向と横方向を交互に繰り返し、最後は縦方向の出力（textures[1]）で終わる
+    self.compute_ping_pong.reset();
+    for _ in 0..self.iterations * 2 - 1 {
+      let (width, height) = if self.compute_ping_pong.index() == 0 {
+        (self.image_size.1, self.image_size.0)
+      } else {
+        (self.image_size.0, self.image_size.1)
+      };
+
+      compute_pass.set_bind_group(0, self.compute_ping_pong.current(), &[]);
+      dispatch_segments(
+        &mut compute_pass,
+        &self.segment_bind_groups,
+        width,
+        height,
+      );
+
+      self.compute_ping_pong.advance();
+    }
+
+    drop(compute_pass);
+
+    let color_attachment = util::create_color_attachment(&view);
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render Pass"),
+        color_attachments: &[Some(color_attachment)],
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.fullscreen_quad_pipeline);
+    render_pass.set_bind_group(0, &self.show_result_bind_group, &[]);
+    render_pass.draw(0..6, 0..1);
+
+    drop(render_pass);
+
+    Ok(frame)
+  }
+}
@@ -0,0 +1,35 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  IncreaseSigma,
+  DecreaseSigma,
+  IncreaseKernelSize,
+  DecreaseKernelSize,
+  IncreaseIterations,
+  DecreaseIterations,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::IncreaseSigma => "Increase sigma",
+      Action::DecreaseSigma => "Decrease sigma",
+      Action::IncreaseKernelSize => "Increase kernel size",
+      Action::DecreaseKernelSize => "Decrease kernel size",
+      Action::IncreaseIterations => "Increase iterations",
+      Action::DecreaseIterations => "Decrease iterations",
+    }
+  }
+}
+
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new()
+    .bind(KeyCode::KeyD, Action::IncreaseSigma)
+    .bind(KeyCode::KeyA, Action::DecreaseSigma)
+    .bind(KeyCode::KeyL, Action::IncreaseKernelSize)
+    .bind(KeyCode::KeyJ, Action::DecreaseKernelSize)
+    .bind(KeyCode::KeyO, Action::IncreaseIterations)
+    .bind(KeyCode::KeyU, Action::DecreaseIterations)
+}
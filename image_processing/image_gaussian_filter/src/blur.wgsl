@@ -11,18 +11,18 @@ fn gaussian(d: f32, sigma: u32) -> f32 {
   return a * exp(-d * d / (2.0 * s * s));
 }
 
-// スレッド数
-const workgroup_size = 32u;
+// スレッド数 workgroup_size は、Rust 側でこのファイルの先頭に const として付け足す
+// （@workgroup_size と共有メモリの配列の長さに使うので、override ではなく const にする）
 
 // 各スレッドは、1つのタイル（複数のピクセル）を処理する
 const tile_size = 4u;
 
 // 1つのワークグループに必要なすべてのピクセルを保持する
-const cache_size = tile_size * workgroup_size; // 128
+const cache_size = tile_size * workgroup_size;
 
 // テクスチャルックアップ用のキャッシュ
 // 各スレッドは、ピクセルのタイルをワークグループの共有メモリに追加する
-var<workgroup> cache: array<array<vec3f, 128>, 4>;
+var<workgroup> cache: array<array<vec3f, cache_size>, tile_size>;
 
 @group(0) @binding(0) var samp: sampler;
 @group(0) @binding(1) var<uniform> blur_params: BlurParams;
@@ -37,7 +37,7 @@ struct CsInput {
   @builtin(global_invocation_id) global_id: vec3u
 }
 
-@compute @workgroup_size(32, 1, 1)
+@compute @workgroup_size(workgroup_size, 1, 1)
 fn cs_main(in: CsInput) {
   let workgroup_id = in.workgroup_id.xy;
   let local_id = in.local_id.xy;
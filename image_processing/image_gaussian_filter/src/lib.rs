@@ -1,19 +1,27 @@
+mod action;
+
 use std::error::Error;
 
+use action::Action;
 use bytemuck::cast_slice;
 use image::GenericImageView;
 use wgpu::util::DeviceExt;
 use wgsim::app::App;
 use wgsim::ctx::{DrawingContext, Size};
+use wgsim::input::KeyBindings;
+use wgsim::mipmap::{MipChain, MipmapGenerator};
+use wgsim::overlay::HelpOverlay;
 use wgsim::ppl::{ComputePipelineBuilder, RenderPipelineBuilder};
 use wgsim::render::{Render, RenderTarget};
-use wgsim::util;
-use winit::event::{ElementState, KeyEvent, WindowEvent};
-use winit::keyboard::{KeyCode, PhysicalKey};
+use wgsim::util::{self, SamplerOptions};
+use winit::event::WindowEvent;
 
 const TILE_SIZE: u32 = 4;
-const WORKGROUP_SIZE: u32 = 32;
-const CACHE_SIZE: u32 = TILE_SIZE * WORKGROUP_SIZE;
+const DEFAULT_WORKGROUP_SIZE: u32 = 32;
+// 最大のカーネルでも、1つのワークグループが1ピクセル以上を書き出せる大きさ
+const MIN_WORKGROUP_SIZE: u32 = MAX_KERNEL_SIZE.div_ceil(TILE_SIZE);
+// 共有メモリのキャッシュ1ピクセル分（vec3f の配列の間隔は 16 バイト）
+const CACHE_BYTES_PER_PIXEL: u32 = 16;
 
 const MIN_KERNEL_SIZE: u32 = 3;
 const MAX_KERNEL_SIZE: u32 = 33;
@@ -27,11 +35,32 @@ const MIN_SIGMA: u32 = 1;
 const MAX_SIGMA: u32 = 5;
 const SIGMA_STEP: u32 = 1;
 
-fn calc_dispatch_size(kernel_size: u32) -> u32 {
-  CACHE_SIZE - (kernel_size - 1)
+const HELP_FONT: &[u8] =
+  include_bytes!("../../../font/Lusitana/Lusitana-Regular.ttf");
+
+fn calc_dispatch_size(workgroup_size: u32, kernel_size: u32) -> u32 {
+  TILE_SIZE * workgroup_size - (kernel_size - 1)
+}
+
+/// Largest workgroup size whose threads and cache fit the device limits.
+fn max_workgroup_size(limits: &wgpu::Limits) -> u32 {
+  let cache_bytes_per_thread = TILE_SIZE * TILE_SIZE * CACHE_BYTES_PER_PIXEL;
+
+  (limits.max_compute_workgroup_storage_size / cache_bytes_per_thread)
+    .min(limits.max_compute_invocations_per_workgroup)
+    .min(limits.max_compute_workgroup_size_x)
 }
 
-fn setup() -> Initial {
+// スレッド数をシェーダーの先頭に const として付け足す
+fn create_blur_shader_source(workgroup_size: u32) -> String {
+  format!(
+    "const workgroup_size = {}u;\n{}",
+    workgroup_size,
+    include_str!("./blur.wgsl")
+  )
+}
+
+fn setup(workgroup_size: u32) -> Initial {
   let img_bytes = include_bytes!("../../../assets/img/stained-glass_w600.png");
   let image = image::load_from_memory(img_bytes).unwrap();
   let image_size = image.dimensions();
@@ -42,13 +71,27 @@ fn setup() -> Initial {
     kernel_size: 3,
     sigma: 2,
     iterations: 1,
+    workgroup_size,
+    key_bindings: action::default_key_bindings(),
   }
 }
 
 pub fn run() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
-  let initial = setup();
+  // 1つ目の引数でワークグループのスレッド数を変えられる（例: 64）
+  let args = std::env::args().collect::<Vec<String>>();
+  let workgroup_size = args
+    .iter()
+    .skip(2)
+    .find(|arg| !arg.starts_with("--"))
+    .map(|s| s.parse::<u32>())
+    .transpose()?
+    .unwrap_or(DEFAULT_WORKGROUP_SIZE);
+
+  let initial = setup(workgroup_size);
+  initial.key_bindings.print_help();
+  log::info!("Press F1 to show the key bindings on screen");
 
   let mut app: App<State> = App::new("image_gaussian_filter", initial);
   app.run()?;
@@ -62,6 +105,9 @@ struct Initial {
   kernel_size: u32,
   sigma: u32,
   iterations: u32,
+  /// threads per workgroup of the blur pass
+  workgroup_size: u32,
+  key_bindings: KeyBindings<Action>,
 }
 
 struct State {
@@ -74,18 +120,25 @@ struct State {
   compute_bind_group_for_swap_2: wgpu::BindGroup,
   render_result_bind_group: wgpu::BindGroup,
 
+  mipmap_generator: MipmapGenerator,
+  mip_chain: MipChain,
+
   blur_params_uniform_buffer: wgpu::Buffer,
   resolution_uniform_buffer: wgpu::Buffer,
 
   image_size: (u32, u32),
   iterations: u32,
 
+  workgroup_size: u32,
   dispatch_size: u32,
   kernel_size: u32,
   sigma: u32,
   blur_params_updated: bool,
 
   resolution_updated: bool,
+
+  key_bindings: KeyBindings<Action>,
+  help_overlay: HelpOverlay,
 }
 
 impl<'a> Render<'a> for State {
@@ -99,51 +152,56 @@ impl<'a> Render<'a> for State {
     let fullscreen_quad_shader = ctx.device.create_shader_module(
       wgpu::include_wgsl!("./fullscreen-textured-quad.wgsl"),
     );
+    let max_workgroup_size = max_workgroup_size(&ctx.device.limits());
+    let workgroup_size =
+      initial.workgroup_size.clamp(MIN_WORKGROUP_SIZE, max_workgroup_size);
+    if workgroup_size != initial.workgroup_size {
+      log::warn!(
+        "workgroup size {} is out of range {}..={}, using {}",
+        initial.workgroup_size,
+        MIN_WORKGROUP_SIZE,
+        max_workgroup_size,
+        workgroup_size
+      );
+    }
+    log::info!("workgroup size: {}", workgroup_size);
+
     let blur_shader =
-      ctx.device.create_shader_module(wgpu::include_wgsl!("./blur.wgsl"));
+      ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("blur.wgsl"),
+        source: wgpu::ShaderSource::Wgsl(
+          create_blur_shader_source(workgroup_size).into(),
+        ),
+      });
 
     //
     // texture & sampler
     //
 
-    let sampler = ctx.device.create_sampler(&wgpu::SamplerDescriptor {
-      label: Some("sampler"),
-      mag_filter: wgpu::FilterMode::Linear,
-      min_filter: wgpu::FilterMode::Linear,
-      ..Default::default()
-    });
-
-    let image_texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
-      label: Some("image texture"),
-      size: wgpu::Extent3d {
-        width: initial.image_size.0,
-        height: initial.image_size.1,
-        depth_or_array_layers: 1,
+    let sampler = util::create_sampler(&ctx.device, SamplerOptions::default());
+
+    // 縮小表示でちらつかないよう、表示用のサンプラーはミップマップ間も補間する（トライリニア）
+    let display_sampler = util::create_sampler(
+      &ctx.device,
+      SamplerOptions {
+        label: Some("display sampler"),
+        mipmap_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
       },
-      mip_level_count: 1,
-      sample_count: 1,
-      dimension: wgpu::TextureDimension::D2,
-      format: wgpu::TextureFormat::Rgba8UnormSrgb, // 元画像の色味を保つため、ここだけsRGB
-      usage: wgpu::TextureUsages::COPY_DST
-        | wgpu::TextureUsages::RENDER_ATTACHMENT
-        | wgpu::TextureUsages::TEXTURE_BINDING,
-      view_formats: &[],
-    });
-    ctx.queue.write_texture(
-      image_texture.as_image_copy(),
+    );
+
+    // ブラーの入力にしか使わないので、ミップマップは要らない
+    let image_texture = util::create_image_texture(
+      &ctx.device,
+      &ctx.queue,
       &initial.image.to_rgba8(),
-      wgpu::ImageDataLayout {
-        offset: 0,
-        bytes_per_row: Some(4 * initial.image_size.0),
-        rows_per_image: Some(initial.image_size.1),
-      },
-      wgpu::Extent3d {
-        width: initial.image_size.0,
-        height: initial.image_size.1,
-        depth_or_array_layers: 1,
-      },
+      wgpu::TextureFormat::Rgba8UnormSrgb, // 元画像の色味を保つため、ここだけsRGB
+      1,
     );
 
+    let mip_levels =
+      util::mip_level_count(initial.image_size.0, initial.image_size.1);
+
     // for Ping-Pong pattern
     let textures = (0..=1)
       .map(|_| {
@@ -154,18 +212,37 @@ impl<'a> Render<'a> for State {
             height: initial.image_size.1,
             depth_or_array_layers: 1,
           },
-          mip_level_count: 1,
+          mip_level_count: mip_levels,
           sample_count: 1,
           dimension: wgpu::TextureDimension::D2,
           format: wgpu::TextureFormat::Rgba8Unorm, // ここではsRGBは指定できないので注意（STORAGE_BINDINGとの併用不可）
+          // RENDER_ATTACHMENT はミップマップの生成に使う
           usage: wgpu::TextureUsages::COPY_DST
             | wgpu::TextureUsages::STORAGE_BINDING
-            | wgpu::TextureUsages::TEXTURE_BINDING,
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::RENDER_ATTACHMENT,
           view_formats: &[],
         })
       })
       .collect::<Vec<_>>();
 
+    // ブラーが読み書きするのはレベル0だけ（ストレージテクスチャのビューは1レベルに限られる）
+    let base_views = textures
+      .iter()
+      .map(|texture| {
+        texture.create_view(&wgpu::TextureViewDescriptor {
+          mip_level_count: Some(1),
+          ..Default::default()
+        })
+      })
+      .collect::<Vec<_>>();
+
+    // 最終結果はtextures[1]なので、毎フレームそのミップマップを作り直す
+    let mipmap_generator =
+      MipmapGenerator::new(&ctx.device, wgpu::TextureFormat::Rgba8Unorm);
+    let mip_chain =
+      mipmap_generator.prepare(&ctx.device, &textures[1], mip_levels);
+
     //
     // uniform
     //
@@ -266,9 +343,7 @@ impl<'a> Render<'a> for State {
         wgpu::BindingResource::TextureView(
           &image_texture.create_view(&wgpu::TextureViewDescriptor::default()),
         ),
-        wgpu::BindingResource::TextureView(
-          &textures[0].create_view(&wgpu::TextureViewDescriptor::default()),
-        ),
+        wgpu::BindingResource::TextureView(&base_views[0]),
         flip_blur_dir_0_uniform_buffer.as_entire_binding(),
       ],
     );
@@ -278,12 +353,8 @@ impl<'a> Render<'a> for State {
       &ctx.device,
       &compute_bind_group_layout,
       &[
-        wgpu::BindingResource::TextureView(
-          &textures[0].create_view(&wgpu::TextureViewDescriptor::default()),
-        ),
-        wgpu::BindingResource::TextureView(
-          &textures[1].create_view(&wgpu::TextureViewDescriptor::default()),
-        ),
+        wgpu::BindingResource::TextureView(&base_views[0]),
+        wgpu::BindingResource::TextureView(&base_views[1]),
         flip_blur_dir_1_uniform_buffer.as_entire_binding(),
       ],
     );
@@ -291,12 +362,8 @@ impl<'a> Render<'a> for State {
       &ctx.device,
       &compute_bind_group_layout,
       &[
-        wgpu::BindingResource::TextureView(
-          &textures[1].create_view(&wgpu::TextureViewDescriptor::default()),
-        ),
-        wgpu::BindingResource::TextureView(
-          &textures[0].create_view(&wgpu::TextureViewDescriptor::default()),
-        ),
+        wgpu::BindingResource::TextureView(&base_views[1]),
+        wgpu::BindingResource::TextureView(&base_views[0]),
         flip_blur_dir_0_uniform_buffer.as_entire_binding(),
       ],
     );
@@ -319,9 +386,9 @@ impl<'a> Render<'a> for State {
       &ctx.device,
       &render_result_bind_group_layout,
       &[
-        wgpu::BindingResource::Sampler(&sampler),
+        wgpu::BindingResource::Sampler(&display_sampler),
         wgpu::BindingResource::TextureView(
-          &textures[1].create_view(&wgpu::TextureViewDescriptor::default()), // 最終結果はtextures[1]
+          &textures[1].create_view(&wgpu::TextureViewDescriptor::default()), // 最終結果はtextures[1]（全レベル）
         ),
         resolution_uniform_buffer.as_entire_binding(),
       ],
@@ -341,7 +408,9 @@ impl<'a> Render<'a> for State {
       .vs_shader(&fullscreen_quad_shader, "vs_main")
       .fs_shader(&fullscreen_quad_shader, "fs_main")
       .pipeline_layout(&fullscreen_quad_pipeline_layout)
-      .build();
+      .label("Fullscreen Quad Pipeline")
+      .build_checked()
+      .unwrap_or_else(|e| panic!("{}", e));
 
     let blur_pipeline_layout =
       ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -367,86 +436,86 @@ impl<'a> Render<'a> for State {
       compute_bind_group_for_swap_2,
       render_result_bind_group,
 
+      mipmap_generator,
+      mip_chain,
+
       blur_params_uniform_buffer,
       resolution_uniform_buffer,
 
       image_size: initial.image_size,
       iterations: initial.iterations,
 
-      dispatch_size: calc_dispatch_size(initial.kernel_size),
+      workgroup_size,
+      dispatch_size: calc_dispatch_size(workgroup_size, initial.kernel_size),
       kernel_size: initial.kernel_size,
       sigma: initial.sigma,
       blur_params_updated: false,
 
       resolution_updated: false,
+
+      key_bindings: initial.key_bindings.clone(),
+      help_overlay: HelpOverlay::new(ctx, HELP_FONT, &initial.key_bindings),
     }
   }
 
   fn resize(&mut self, ctx: &mut DrawingContext<'_>, size: Size) {
     if size.width > 0 && size.height > 0 {
       ctx.resize(size.into());
+      self.help_overlay.resize(ctx);
       self.resolution_updated = true;
     }
   }
 
   fn process_event(&mut self, event: &WindowEvent) -> bool {
-    match event {
-      WindowEvent::KeyboardInput {
-        event:
-          KeyEvent {
-            physical_key,
-            state: ElementState::Pressed,
-            ..
-          },
-        ..
-      } => match physical_key {
-        PhysicalKey::Code(KeyCode::KeyD) => {
-          self.sigma = MAX_SIGMA.min(self.sigma + SIGMA_STEP);
-          println!("sigma: {}", self.sigma);
-          self.blur_params_updated = true;
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyA) => {
-          self.sigma = MIN_SIGMA.max(self.sigma - SIGMA_STEP);
-          println!("sigma: {}", self.sigma);
-          self.blur_params_updated = true;
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyL) => {
-          self.kernel_size =
-            MAX_KERNEL_SIZE.min(self.kernel_size + KERNEL_SIZE_STEP);
-          println!("kernel size: {}", self.kernel_size);
-          self.blur_params_updated = true;
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyJ) => {
-          self.kernel_size =
-            MIN_KERNEL_SIZE.max(self.kernel_size - KERNEL_SIZE_STEP);
-          println!("kernel size: {}", self.kernel_size);
-          self.blur_params_updated = true;
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyO) => {
-          self.iterations =
-            MAX_ITERATIONS.min(self.iterations + ITERATIONS_STEP);
-          println!("iterations: {}", self.iterations);
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyU) => {
-          self.iterations =
-            MIN_ITERATIONS.max(self.iterations - ITERATIONS_STEP);
-          println!("iterations: {}", self.iterations);
-          true
-        }
-        _ => false,
-      },
-      _ => false,
+    if self.help_overlay.process_event(event) {
+      return true;
+    }
+
+    let action = match self.key_bindings.process_event(event) {
+      Some(action) => action,
+      None => return false,
+    };
+
+    match action {
+      Action::IncreaseSigma => {
+        self.sigma = MAX_SIGMA.min(self.sigma + SIGMA_STEP);
+        log::info!("sigma: {}", self.sigma);
+        self.blur_params_updated = true;
+      }
+      Action::DecreaseSigma => {
+        self.sigma = MIN_SIGMA.max(self.sigma - SIGMA_STEP);
+        log::info!("sigma: {}", self.sigma);
+        self.blur_params_updated = true;
+      }
+      Action::IncreaseKernelSize => {
+        self.kernel_size =
+          MAX_KERNEL_SIZE.min(self.kernel_size + KERNEL_SIZE_STEP);
+        log::info!("kernel size: {}", self.kernel_size);
+        self.blur_params_updated = true;
+      }
+      Action::DecreaseKernelSize => {
+        self.kernel_size =
+          MIN_KERNEL_SIZE.max(self.kernel_size - KERNEL_SIZE_STEP);
+        log::info!("kernel size: {}", self.kernel_size);
+        self.blur_params_updated = true;
+      }
+      Action::IncreaseIterations => {
+        self.iterations = MAX_ITERATIONS.min(self.iterations + ITERATIONS_STEP);
+        log::info!("iterations: {}", self.iterations);
+      }
+      Action::DecreaseIterations => {
+        self.iterations = MIN_ITERATIONS.max(self.iterations - ITERATIONS_STEP);
+        log::info!("iterations: {}", self.iterations);
+      }
     }
+
+    true
   }
 
   fn update(&mut self, ctx: &DrawingContext, _dt: std::time::Duration) {
     if self.blur_params_updated {
-      self.dispatch_size = calc_dispatch_size(self.kernel_size);
+      self.dispatch_size =
+        calc_dispatch_size(self.workgroup_size, self.kernel_size);
       ctx.queue.write_buffer(
         &self.blur_params_uniform_buffer,
         0,
@@ -527,6 +596,8 @@ impl<'a> Render<'a> for State {
 
     drop(compute_pass);
 
+    self.mipmap_generator.generate(encoder, &self.mip_chain);
+
     let color_attachment = util::create_color_attachment(&view);
     let mut render_pass =
       encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -541,6 +612,37 @@ impl<'a> Render<'a> for State {
 
     drop(render_pass);
 
+    self.help_overlay.draw(encoder, &view);
+
     Ok(frame)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use wgsim::render::TestHarness;
+
+  use super::*;
+
+  fn render_with_workgroup_size(workgroup_size: u32) -> Vec<u8> {
+    // 大きめのカーネルで、ワークグループの境界をまたぐ画素を増やす
+    let initial = Initial {
+      kernel_size: 15,
+      ..setup(workgroup_size)
+    };
+    let mut harness: TestHarness<State> = TestHarness::new(&initial, 64, 64)
+      .expect("no GPU adapter for the headless harness");
+    assert_eq!(harness.renderer().workgroup_size, workgroup_size);
+
+    harness.draw_to_vec().unwrap()
+  }
+
+  #[test]
+  fn workgroup_size_does_not_change_the_blur() {
+    let small = render_with_workgroup_size(16);
+    let large = render_with_workgroup_size(64);
+
+    let diff = util::compare_images(&large, &small, 64, 64, 0.);
+    assert!(diff.is_match(), "{:?}", diff);
+  }
+}
@@ -0,0 +1,26 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  IncreaseBlend,
+  DecreaseBlend,
+  ToggleEqualized,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::IncreaseBlend => "Blend toward the equalized image",
+      Action::DecreaseBlend => "Blend toward the original image",
+      Action::ToggleEqualized => "Toggle original / equalized",
+    }
+  }
+}
+
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new()
+    .bind(KeyCode::KeyD, Action::IncreaseBlend)
+    .bind(KeyCode::KeyA, Action::DecreaseBlend)
+    .bind(KeyCode::Space, Action::ToggleEqualized)
+}
@@ -0,0 +1,112 @@
+// Rust 側の BIN_COUNT と合わせる
+const bin_count = 256u;
+
+struct Params {
+  // 0 で元画像、1 で平坦化した画像
+  blend: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+
+@group(1) @binding(0) var input_tex: texture_2d<f32>;
+@group(1) @binding(1) var output_tex: texture_storage_2d<rgba8unorm, write>;
+@group(1) @binding(2) var<storage, read_write> bins: array<atomic<u32>, bin_count>;
+@group(1) @binding(3) var<storage, read_write> lut: array<f32, bin_count>;
+
+// テクスチャは sRGB なので線形の値が読める
+// 明るさの分布は見た目に近いよう、ガンマをかけた輝度で数える
+fn perceptual_luminance(color: vec3f) -> f32 {
+  let luminance = dot(color, vec3f(0.2126, 0.7152, 0.0722));
+  return pow(clamp(luminance, 0.0, 1.0), 1.0 / 2.2);
+}
+
+fn bin_of(value: f32) -> u32 {
+  return u32(round(value * f32(bin_count - 1u)));
+}
+
+//
+// 1. 輝度のヒストグラムを数える
+//
+
+@compute @workgroup_size(16, 16)
+fn histogram(@builtin(global_invocation_id) global_id: vec3u) {
+  let dims = textureDimensions(input_tex, 0);
+  if (any(global_id.xy >= dims)) {
+    return;
+  }
+
+  let color = textureLoad(input_tex, global_id.xy, 0).rgb;
+  let bin = bin_of(perceptual_luminance(color));
+
+  // 複数のスレッドが同じビンに書き込むので、アトミックに加算する
+  atomicAdd(&bins[bin], 1u);
+}
+
+//
+// 2. ヒストグラムを累積分布にスキャンし、ルックアップテーブルにする
+//
+
+var<workgroup> cdf: array<u32, bin_count>;
+var<workgroup> cdf_min: atomic<u32>;
+
+// 1つのワークグループで、1スレッドが1ビンを担当する
+@compute @workgroup_size(bin_count)
+fn build_lut(@builtin(local_invocation_index) i: u32) {
+  cdf[i] = atomicLoad(&bins[i]);
+  if (i == 0u) {
+    atomicStore(&cdf_min, 0xffffffffu);
+  }
+  workgroupBarrier();
+
+  // Hillis-Steele の inclusive scan
+  for (var offset = 1u; offset < bin_count; offset = offset * 2u) {
+    var sum = cdf[i];
+    if (i >= offset) {
+      sum += cdf[i - offset];
+    }
+    workgroupBarrier();
+    cdf[i] = sum;
+    workgroupBarrier();
+  }
+
+  // 最も暗いピクセルが 0 になるよう、0 でない最小の累積値を引く
+  if (cdf[i] > 0u) {
+    atomicMin(&cdf_min, cdf[i]);
+  }
+  workgroupBarrier();
+
+  let total = cdf[bin_count - 1u];
+  let min_value = atomicLoad(&cdf_min);
+  if (total > min_value) {
+    lut[i] = f32(cdf[i] - min(cdf[i], min_value)) / f32(total - min_value);
+  } else {
+    // 1色だけの画像は変えない
+    lut[i] = f32(i) / f32(bin_count - 1u);
+  }
+}
+
+//
+// 3. ルックアップテーブルで輝度を置き換える
+//
+
+@compute @workgroup_size(16, 16)
+fn apply(@builtin(global_invocation_id) global_id: vec3u) {
+  let dims = textureDimensions(input_tex, 0);
+  if (any(global_id.xy >= dims)) {
+    return;
+  }
+
+  let color = textureLoad(input_tex, global_id.xy, 0);
+  let luminance = perceptual_luminance(color.rgb);
+  let equalized_luminance = lut[bin_of(luminance)];
+
+  // 色相を保つため、RGB をまとめて同じ比率で拡大・縮小する（線形の値で）
+  var scale = 0.0;
+  if (luminance > 0.0) {
+    scale = pow(equalized_luminance / luminance, 2.2);
+  }
+  let equalized = clamp(color.rgb * scale, vec3f(0.0), vec3f(1.0));
+
+  let result = mix(color.rgb, equalized, params.blend);
+  textureStore(output_tex, global_id.xy, vec4f(result, color.a));
+}
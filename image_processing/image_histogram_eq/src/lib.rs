@@ -0,0 +1,469 @@
+mod action;
+
+use std::error::Error;
+
+use action::Action;
+use bytemuck::cast_slice;
+use image::GenericImageView;
+use wgpu::util::DeviceExt;
+use wgsim::app::App;
+use wgsim::ctx::{DrawingContext, Size};
+use wgsim::input::KeyBindings;
+use wgsim::overlay::HelpOverlay;
+use wgsim::ppl::{ComputePipelineBuilder, RenderPipelineBuilder};
+use wgsim::render::{Render, RenderTarget};
+use wgsim::util::{self, SamplerOptions};
+use winit::event::WindowEvent;
+
+// シェーダーの @workgroup_size(16, 16) と合わせる
+const WORKGROUP_SIZE: u32 = 16;
+// シェーダーの bin_count と合わせる
+const BIN_COUNT: u64 = 256;
+
+const BLEND_STEP: f32 = 0.25;
+
+const HELP_FONT: &[u8] =
+  include_bytes!("../../../font/Lusitana/Lusitana-Regular.ttf");
+
+fn setup() -> Initial {
+  let img_bytes = include_bytes!("../../../assets/img/stained-glass_w600.png");
+  let image = image::load_from_memory(img_bytes).unwrap();
+  let image_size = image.dimensions();
+
+  Initial {
+    image,
+    image_size,
+    blend: 1.,
+    key_bindings: action::default_key_bindings(),
+  }
+}
+
+pub fn run() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup();
+  initial.key_bindings.print_help();
+  log::info!("Press F1 to show the key bindings on screen");
+
+  let mut app: App<State> = App::new("image_histogram_eq", initial);
+  app.run()?;
+
+  Ok(())
+}
+
+struct Initial {
+  image: image::DynamicImage,
+  image_size: (u32, u32),
+  blend: f32,
+  key_bindings: KeyBindings<Action>,
+}
+
+struct State {
+  histogram_pipeline: wgpu::ComputePipeline,
+  build_lut_pipeline: wgpu::ComputePipeline,
+  apply_pipeline: wgpu::ComputePipeline,
+  fullscreen_quad_pipeline: wgpu::RenderPipeline,
+
+  compute_constants_bind_group: wgpu::BindGroup,
+  compute_bind_group: wgpu::BindGroup,
+  render_result_bind_group: wgpu::BindGroup,
+
+  bins_buffer: wgpu::Buffer,
+  params_uniform_buffer: wgpu::Buffer,
+  resolution_uniform_buffer: wgpu::Buffer,
+
+  image_size: (u32, u32),
+
+  blend: f32,
+  params_updated: bool,
+
+  resolution_updated: bool,
+
+  key_bindings: KeyBindings<Action>,
+  help_overlay: HelpOverlay,
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    //
+    // shader
+    //
+
+    let fullscreen_quad_shader = ctx.device.create_shader_module(
+      wgpu::include_wgsl!("./fullscreen-textured-quad.wgsl"),
+    );
+    let equalize_shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./equalize.wgsl"));
+
+    //
+    // texture & sampler
+    //
+
+    let sampler = util::create_sampler(&ctx.device, SamplerOptions::default());
+
+    let image_extent = wgpu::Extent3d {
+      width: initial.image_size.0,
+      height: initial.image_size.1,
+      depth_or_array_layers: 1,
+    };
+
+    let image_texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("image texture"),
+      size: image_extent,
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: wgpu::TextureFormat::Rgba8UnormSrgb,
+      usage: wgpu::TextureUsages::COPY_DST
+        | wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    });
+    ctx.queue.write_texture(
+      image_texture.as_image_copy(),
+      &initial.image.to_rgba8(),
+      wgpu::ImageDataLayout {
+        offset: 0,
+        bytes_per_row: Some(4 * initial.image_size.0),
+        rows_per_image: Some(initial.image_size.1),
+      },
+      image_extent,
+    );
+
+    // 平坦化の結果を書き込む先（STORAGE_BINDINGとの併用のため、sRGBは指定できない）
+    let result_texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("result texture"),
+      size: image_extent,
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: wgpu::TextureFormat::Rgba8Unorm,
+      usage: wgpu::TextureUsages::STORAGE_BINDING
+        | wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    });
+    let result_texture_view =
+      result_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    //
+    // storage
+    //
+
+    // 毎フレーム数え直すので、描画の前に0にクリアする
+    let bins_buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("histogram bins buffer"),
+      size: BIN_COUNT * std::mem::size_of::<u32>() as u64,
+      usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+    let lut_buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("lookup table buffer"),
+      size: BIN_COUNT * std::mem::size_of::<f32>() as u64,
+      usage: wgpu::BufferUsages::STORAGE,
+      mapped_at_creation: false,
+    });
+
+    //
+    // uniform
+    //
+
+    // 特定のキー入力イベントで更新する必要がある
+    let params_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("equalize params uniform buffer"),
+        contents: cast_slice(&[initial.blend]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+
+    // リサイズのたびに更新する必要がある
+    let resolution = ctx.resolution();
+    let resolution_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("resolution uniform buffer"),
+        contents: cast_slice(&[
+          resolution.width as f32,
+          resolution.height as f32,
+        ]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+
+    //
+    // bind group
+    //
+
+    let sampler_binding_type =
+      wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering);
+    let uniform_binding_type = wgpu::BindingType::Buffer {
+      ty: wgpu::BufferBindingType::Uniform,
+      has_dynamic_offset: false,
+      min_binding_size: None,
+    };
+    let storage_binding_type = wgpu::BindingType::Buffer {
+      ty: wgpu::BufferBindingType::Storage { read_only: false },
+      has_dynamic_offset: false,
+      min_binding_size: None,
+    };
+    let texture_binding_type = wgpu::BindingType::Texture {
+      sample_type: wgpu::TextureSampleType::Float { filterable: true },
+      view_dimension: wgpu::TextureViewDimension::D2,
+      multisampled: false,
+    };
+    let texture_storage_binding_type = wgpu::BindingType::StorageTexture {
+      access: wgpu::StorageTextureAccess::WriteOnly,
+      format: wgpu::TextureFormat::Rgba8Unorm,
+      view_dimension: wgpu::TextureViewDimension::D2,
+    };
+
+    let compute_constants_bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[uniform_binding_type],
+      &[wgpu::ShaderStages::COMPUTE],
+    );
+    let compute_constants_bind_group = util::create_bind_group(
+      &ctx.device,
+      &compute_constants_bind_group_layout,
+      &[params_uniform_buffer.as_entire_binding()],
+    );
+
+    // 3つのパスで共有する（それぞれが使うのは一部だけ）
+    let compute_bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[
+        texture_binding_type,
+        texture_storage_binding_type,
+        storage_binding_type,
+        storage_binding_type,
+      ],
+      &[
+        wgpu::ShaderStages::COMPUTE,
+        wgpu::ShaderStages::COMPUTE,
+        wgpu::ShaderStages::COMPUTE,
+        wgpu::ShaderStages::COMPUTE,
+      ],
+    );
+    let compute_bind_group = util::create_bind_group(
+      &ctx.device,
+      &compute_bind_group_layout,
+      &[
+        wgpu::BindingResource::TextureView(
+          &image_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        ),
+        wgpu::BindingResource::TextureView(&result_texture_view),
+        bins_buffer.as_entire_binding(),
+        lut_buffer.as_entire_binding(),
+      ],
+    );
+
+    // 結果をスクリーンに描画するRenderPipeline用
+    let render_result_bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[
+        sampler_binding_type,
+        texture_binding_type,
+        uniform_binding_type,
+      ],
+      &[
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::ShaderStages::VERTEX_FRAGMENT,
+        wgpu::ShaderStages::VERTEX,
+      ],
+    );
+    let render_result_bind_group = util::create_bind_group(
+      &ctx.device,
+      &render_result_bind_group_layout,
+      &[
+        wgpu::BindingResource::Sampler(&sampler),
+        wgpu::BindingResource::TextureView(&result_texture_view),
+        resolution_uniform_buffer.as_entire_binding(),
+      ],
+    );
+
+    //
+    // pipeline
+    //
+
+    let fullscreen_quad_pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Fullscreen Quad Pipeline Layout"),
+        bind_group_layouts: &[&render_result_bind_group_layout],
+        push_constant_ranges: &[],
+      });
+    let fullscreen_quad_pipeline = RenderPipelineBuilder::new(&ctx)
+      .vs_shader(&fullscreen_quad_shader, "vs_main")
+      .fs_shader(&fullscreen_quad_shader, "fs_main")
+      .pipeline_layout(&fullscreen_quad_pipeline_layout)
+      .label("Fullscreen Quad Pipeline")
+      .build_checked()
+      .unwrap_or_else(|e| panic!("{}", e));
+
+    let equalize_pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Equalize Pipeline Layout"),
+        bind_group_layouts: &[
+          &compute_constants_bind_group_layout,
+          &compute_bind_group_layout,
+        ],
+        push_constant_ranges: &[],
+      });
+    let create_equalize_pipeline = |entry_point| {
+      ComputePipelineBuilder::new(&ctx.device)
+        .cs_shader(&equalize_shader, entry_point)
+        .pipeline_layout(&equalize_pipeline_layout)
+        .build()
+    };
+    let histogram_pipeline = create_equalize_pipeline("histogram");
+    let build_lut_pipeline = create_equalize_pipeline("build_lut");
+    let apply_pipeline = create_equalize_pipeline("apply");
+
+    Self {
+      histogram_pipeline,
+      build_lut_pipeline,
+      apply_pipeline,
+      fullscreen_quad_pipeline,
+
+      compute_constants_bind_group,
+      compute_bind_group,
+      render_result_bind_group,
+
+      bins_buffer,
+      params_uniform_buffer,
+      resolution_uniform_buffer,
+
+      image_size: initial.image_size,
+
+      blend: initial.blend,
+      params_updated: false,
+
+      resolution_updated: false,
+
+      key_bindings: initial.key_bindings.clone(),
+      help_overlay: HelpOverlay::new(ctx, HELP_FONT, &initial.key_bindings),
+    }
+  }
+
+  fn resize(&mut self, ctx: &mut DrawingContext<'_>, size: Size) {
+    if size.width > 0 && size.height > 0 {
+      ctx.resize(size.into());
+      self.help_overlay.resize(ctx);
+      self.resolution_updated = true;
+    }
+  }
+
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    if self.help_overlay.process_event(event) {
+      return true;
+    }
+
+    let action = match self.key_bindings.process_event(event) {
+      Some(action) => action,
+      None => return false,
+    };
+
+    match action {
+      Action::IncreaseBlend => {
+        self.blend = (self.blend + BLEND_STEP).min(1.);
+      }
+      Action::DecreaseBlend => {
+        self.blend = (self.blend - BLEND_STEP).max(0.);
+      }
+      Action::ToggleEqualized => {
+        // 途中の値からは、近くない方の端へ切り替える
+        self.blend = if self.blend < 0.5 { 1. } else { 0. };
+      }
+    }
+    log::info!("blend: {}", self.blend);
+    self.params_updated = true;
+
+    true
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, _dt: std::time::Duration) {
+    if self.params_updated {
+      ctx.queue.write_buffer(
+        &self.params_uniform_buffer,
+        0,
+        cast_slice(&[self.blend]),
+      );
+      self.params_updated = false;
+    }
+
+    if self.resolution_updated {
+      let resolution = ctx.resolution();
+      ctx.queue.write_buffer(
+        &self.resolution_uniform_buffer,
+        0,
+        cast_slice(&[resolution.width as f32, resolution.height as f32]),
+      );
+      self.resolution_updated = false;
+    }
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: RenderTarget,
+    _sample_count: u32,
+  ) -> Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+    let (view, frame) = match target {
+      RenderTarget::Surface(surface) => {
+        let frame = surface.get_current_texture()?;
+        let view =
+          frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, Some(frame))
+      }
+      RenderTarget::Texture(texture) => {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, None)
+      }
+    };
+
+    encoder.clear_buffer(&self.bins_buffer, 0, None);
+
+    let mut compute_pass =
+      encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("compute pass"),
+        ..Default::default()
+      });
+
+    compute_pass.set_bind_group(0, &self.compute_constants_bind_group, &[]);
+    compute_pass.set_bind_group(1, &self.compute_bind_group, &[]);
+
+    let workgroup_count = (
+      self.image_size.0.div_ceil(WORKGROUP_SIZE),
+      self.image_size.1.div_ceil(WORKGROUP_SIZE),
+    );
+
+    // 1. 輝度のヒストグラム
+    compute_pass.set_pipeline(&self.histogram_pipeline);
+    compute_pass.dispatch_workgroups(workgroup_count.0, workgroup_count.1, 1);
+
+    // 2. 累積分布からルックアップテーブルを作る（256ビンを1つのワークグループで）
+    compute_pass.set_pipeline(&self.build_lut_pipeline);
+    compute_pass.dispatch_workgroups(1, 1, 1);
+
+    // 3. ルックアップテーブルを適用する
+    compute_pass.set_pipeline(&self.apply_pipeline);
+    compute_pass.dispatch_workgroups(workgroup_count.0, workgroup_count.1, 1);
+
+    drop(compute_pass);
+
+    let color_attachment = util::create_color_attachment(&view);
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render Pass"),
+        color_attachments: &[Some(color_attachment)],
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.fullscreen_quad_pipeline);
+    render_pass.set_bind_group(0, &self.render_result_bind_group, &[]);
+    render_pass.draw(0..6, 0..1);
+
+    drop(render_pass);
+
+    self.help_overlay.draw(encoder, &view);
+
+    Ok(frame)
+  }
+}
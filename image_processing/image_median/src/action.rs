@@ -0,0 +1,23 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  ToggleKernelSize,
+  ToggleNoise,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::ToggleKernelSize => "Toggle kernel size 3x3 / 5x5",
+      Action::ToggleNoise => "Toggle salt-and-pepper noise",
+    }
+  }
+}
+
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new()
+    .bind(KeyCode::Space, Action::ToggleKernelSize)
+    .bind(KeyCode::KeyN, Action::ToggleNoise)
+}
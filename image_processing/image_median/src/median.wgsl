@@ -0,0 +1,105 @@
+struct MedianParams {
+  // 1: 3x3, 2: 5x5
+  radius: u32,
+  // 0 以外なら、読み込み時にごま塩ノイズを加える
+  salt_and_pepper: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: MedianParams;
+
+@group(1) @binding(0) var input_tex: texture_2d<f32>;
+@group(1) @binding(1) var output_tex: texture_storage_2d<rgba8unorm, write>;
+
+const workgroup_size = 8u;
+
+// 5x5 に必要な上下左右2ピクセルずつの境界を含めたキャッシュ
+const max_radius = 2u;
+const cache_size = workgroup_size + 2u * max_radius; // 12
+
+var<workgroup> cache: array<array<vec3f, 12>, 12>;
+
+// ノイズに置き換えるピクセルの割合
+const noise_ratio = 0.1;
+
+struct CsInput {
+  @builtin(workgroup_id) workgroup_id: vec3u,
+  @builtin(local_invocation_id) local_id: vec3u,
+  @builtin(global_invocation_id) global_id: vec3u
+}
+
+fn hash(p: vec2u) -> f32 {
+  var h = p.x * 374761393u + p.y * 668265263u;
+  h = (h ^ (h >> 13u)) * 1274126177u;
+  h = h ^ (h >> 16u);
+  return f32(h) / 4294967295.0;
+}
+
+fn load(index: vec2i, dims: vec2i) -> vec3f {
+  let clamped = clamp(index, vec2i(0), dims - 1);
+  let color = textureLoad(input_tex, clamped, 0).rgb;
+
+  if (params.salt_and_pepper == 0u) {
+    return color;
+  }
+
+  // ピクセルごとに固定の乱数で、白か黒に置き換える
+  let h = hash(vec2u(clamped));
+  if (h < noise_ratio * 0.5) {
+    return vec3f(0.0);
+  }
+  if (h < noise_ratio) {
+    return vec3f(1.0);
+  }
+  return color;
+}
+
+// 奇偶転置ソート（n ラウンドで n 個が必ず整列するソーティングネットワーク）
+// min/max を vec3f のまま使うので、RGB それぞれが独立に整列される
+fn median(values: ptr<function, array<vec3f, 25>>, n: u32) -> vec3f {
+  for (var pass_index = 0u; pass_index < n; pass_index++) {
+    for (var i = pass_index % 2u; i + 1u < n; i += 2u) {
+      let a = (*values)[i];
+      let b = (*values)[i + 1u];
+      (*values)[i] = min(a, b);
+      (*values)[i + 1u] = max(a, b);
+    }
+  }
+  return (*values)[n / 2u];
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn cs_main(in: CsInput) {
+  let dims = vec2i(textureDimensions(input_tex, 0));
+
+  // キャッシュの左上に対応するピクセル
+  let cache_origin = vec2i(in.workgroup_id.xy * workgroup_size) - vec2i(i32(max_radius));
+
+  // 12x12 のキャッシュを 8x8 のスレッドで埋めるので、1スレッドが複数回読み込む
+  let local_index = in.local_id.y * workgroup_size + in.local_id.x;
+  for (var i = local_index; i < cache_size * cache_size; i += workgroup_size * workgroup_size) {
+    let cache_index = vec2u(i % cache_size, i / cache_size);
+    cache[cache_index.y][cache_index.x] = load(cache_origin + vec2i(cache_index), dims);
+  }
+
+  workgroupBarrier();
+
+  let pixel = vec2i(in.global_id.xy);
+  if (any(pixel >= dims)) {
+    return;
+  }
+
+  let radius = i32(min(params.radius, max_radius));
+  let center = vec2i(in.local_id.xy + vec2u(max_radius));
+
+  var values: array<vec3f, 25>;
+  var n = 0u;
+  for (var dy = -radius; dy <= radius; dy++) {
+    for (var dx = -radius; dx <= radius; dx++) {
+      let c = center + vec2i(dx, dy);
+      values[n] = cache[c.y][c.x];
+      n++;
+    }
+  }
+
+  textureStore(output_tex, pixel, vec4(median(&values, n), 1.0));
+}
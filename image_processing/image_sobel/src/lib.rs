@@ -0,0 +1,420 @@
+mod action;
+
+use std::error::Error;
+
+use action::Action;
+use bytemuck::cast_slice;
+use image::GenericImageView;
+use wgpu::util::DeviceExt;
+use wgsim::app::App;
+use wgsim::ctx::{DrawingContext, Size};
+use wgsim::input::KeyBindings;
+use wgsim::overlay::HelpOverlay;
+use wgsim::ppl::{ComputePipelineBuilder, RenderPipelineBuilder};
+use wgsim::render::{Render, RenderTarget};
+use wgsim::util::{self, SamplerOptions};
+use winit::event::WindowEvent;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+const HELP_FONT: &[u8] =
+  include_bytes!("../../../font/Lusitana/Lusitana-Regular.ttf");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ViewMode {
+  Magnitude,
+  Gradients,
+}
+
+impl ViewMode {
+  fn toggled(self) -> Self {
+    match self {
+      ViewMode::Magnitude => ViewMode::Gradients,
+      ViewMode::Gradients => ViewMode::Magnitude,
+    }
+  }
+
+  /// シェーダーの view_mode に渡す値
+  fn as_uniform(self) -> u32 {
+    match self {
+      ViewMode::Magnitude => 0,
+      ViewMode::Gradients => 1,
+    }
+  }
+}
+
+fn setup() -> Initial {
+  let img_bytes = include_bytes!("../../../assets/img/stained-glass_w600.png");
+  let image = image::load_from_memory(img_bytes).unwrap();
+  let image_size = image.dimensions();
+
+  Initial {
+    image,
+    image_size,
+    view_mode: ViewMode::Magnitude,
+    key_bindings: action::default_key_bindings(),
+  }
+}
+
+pub fn run() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup();
+  initial.key_bindings.print_help();
+  log::info!("Press F1 to show the key bindings on screen");
+
+  let mut app: App<State> = App::new("image_sobel", initial);
+  app.run()?;
+
+  Ok(())
+}
+
+struct Initial {
+  image: image::DynamicImage,
+  image_size: (u32, u32),
+  view_mode: ViewMode,
+  key_bindings: KeyBindings<Action>,
+}
+
+struct State {
+  sobel_pipeline: wgpu::ComputePipeline,
+  fullscreen_quad_pipeline: wgpu::RenderPipeline,
+
+  compute_constants_bind_group: wgpu::BindGroup,
+  compute_bind_group: wgpu::BindGroup,
+  render_result_bind_group: wgpu::BindGroup,
+
+  view_mode_uniform_buffer: wgpu::Buffer,
+  resolution_uniform_buffer: wgpu::Buffer,
+
+  image_size: (u32, u32),
+
+  view_mode: ViewMode,
+  view_mode_updated: bool,
+
+  resolution_updated: bool,
+
+  key_bindings: KeyBindings<Action>,
+  help_overlay: HelpOverlay,
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    //
+    // shader
+    //
+
+    let fullscreen_quad_shader = ctx.device.create_shader_module(
+      wgpu::include_wgsl!("./fullscreen-textured-quad.wgsl"),
+    );
+    let sobel_shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./sobel.wgsl"));
+
+    //
+    // texture & sampler
+    //
+
+    let sampler = util::create_sampler(&ctx.device, SamplerOptions::default());
+
+    let image_extent = wgpu::Extent3d {
+      width: initial.image_size.0,
+      height: initial.image_size.1,
+      depth_or_array_layers: 1,
+    };
+
+    let image_texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("image texture"),
+      size: image_extent,
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: wgpu::TextureFormat::Rgba8UnormSrgb,
+      usage: wgpu::TextureUsages::COPY_DST
+        | wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    });
+    ctx.queue.write_texture(
+      image_texture.as_image_copy(),
+      &initial.image.to_rgba8(),
+      wgpu::ImageDataLayout {
+        offset: 0,
+        bytes_per_row: Some(4 * initial.image_size.0),
+        rows_per_image: Some(initial.image_size.1),
+      },
+      image_extent,
+    );
+
+    // エッジの強さを書き込む先（STORAGE_BINDINGとの併用のため、sRGBは指定できない）
+    let edge_texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("edge texture"),
+      size: image_extent,
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: wgpu::TextureFormat::Rgba8Unorm,
+      usage: wgpu::TextureUsages::STORAGE_BINDING
+        | wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    });
+    let edge_texture_view =
+      edge_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    //
+    // uniform
+    //
+
+    // 特定のキー入力イベントで更新する必要がある
+    let view_mode_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("view mode uniform buffer"),
+        contents: cast_slice(&[initial.view_mode.as_uniform()]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+
+    // リサイズのたびに更新する必要がある
+    let resolution = ctx.resolution();
+    let resolution_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("resolution uniform buffer"),
+        contents: cast_slice(&[
+          resolution.width as f32,
+          resolution.height as f32,
+        ]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+
+    //
+    // bind group
+    //
+
+    let sampler_binding_type =
+      wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering);
+    let uniform_binding_type = wgpu::BindingType::Buffer {
+      ty: wgpu::BufferBindingType::Uniform,
+      has_dynamic_offset: false,
+      min_binding_size: None,
+    };
+    let texture_binding_type = wgpu::BindingType::Texture {
+      sample_type: wgpu::TextureSampleType::Float { filterable: true },
+      view_dimension: wgpu::TextureViewDimension::D2,
+      multisampled: false,
+    };
+    let texture_storage_binding_type = wgpu::BindingType::StorageTexture {
+      access: wgpu::StorageTextureAccess::WriteOnly,
+      format: wgpu::TextureFormat::Rgba8Unorm,
+      view_dimension: wgpu::TextureViewDimension::D2,
+    };
+
+    let compute_constants_bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[uniform_binding_type],
+      &[wgpu::ShaderStages::COMPUTE],
+    );
+    let compute_constants_bind_group = util::create_bind_group(
+      &ctx.device,
+      &compute_constants_bind_group_layout,
+      &[view_mode_uniform_buffer.as_entire_binding()],
+    );
+
+    let compute_bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[texture_binding_type, texture_storage_binding_type],
+      &[wgpu::ShaderStages::COMPUTE, wgpu::ShaderStages::COMPUTE],
+    );
+    let compute_bind_group = util::create_bind_group(
+      &ctx.device,
+      &compute_bind_group_layout,
+      &[
+        wgpu::BindingResource::TextureView(
+          &image_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        ),
+        wgpu::BindingResource::TextureView(&edge_texture_view),
+      ],
+    );
+
+    // 結果をスクリーンに描画するRenderPipeline用
+    let render_result_bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[
+        sampler_binding_type,
+        texture_binding_type,
+        uniform_binding_type,
+      ],
+      &[
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::ShaderStages::VERTEX_FRAGMENT,
+        wgpu::ShaderStages::VERTEX,
+      ],
+    );
+    let render_result_bind_group = util::create_bind_group(
+      &ctx.device,
+      &render_result_bind_group_layout,
+      &[
+        wgpu::BindingResource::Sampler(&sampler),
+        wgpu::BindingResource::TextureView(&edge_texture_view),
+        resolution_uniform_buffer.as_entire_binding(),
+      ],
+    );
+
+    //
+    // pipeline
+    //
+
+    let fullscreen_quad_pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Fullscreen Quad Pipeline Layout"),
+        bind_group_layouts: &[&render_result_bind_group_layout],
+        push_constant_ranges: &[],
+      });
+    let fullscreen_quad_pipeline = RenderPipelineBuilder::new(&ctx)
+      .vs_shader(&fullscreen_quad_shader, "vs_main")
+      .fs_shader(&fullscreen_quad_shader, "fs_main")
+      .pipeline_layout(&fullscreen_quad_pipeline_layout)
+      .label("Fullscreen Quad Pipeline")
+      .build_checked()
+      .unwrap_or_else(|e| panic!("{}", e));
+
+    let sobel_pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Sobel Pipeline Layout"),
+        bind_group_layouts: &[
+          &compute_constants_bind_group_layout,
+          &compute_bind_group_layout,
+        ],
+        push_constant_ranges: &[],
+      });
+    let sobel_pipeline = ComputePipelineBuilder::new(&ctx.device)
+      .cs_shader(&sobel_shader, "cs_main")
+      .pipeline_layout(&sobel_pipeline_layout)
+      .build();
+
+    Self {
+      sobel_pipeline,
+      fullscreen_quad_pipeline,
+
+      compute_constants_bind_group,
+      compute_bind_group,
+      render_result_bind_group,
+
+      view_mode_uniform_buffer,
+      resolution_uniform_buffer,
+
+      image_size: initial.image_size,
+
+      view_mode: initial.view_mode,
+      view_mode_updated: false,
+
+      resolution_updated: false,
+
+      key_bindings: initial.key_bindings.clone(),
+      help_overlay: HelpOverlay::new(ctx, HELP_FONT, &initial.key_bindings),
+    }
+  }
+
+  fn resize(&mut self, ctx: &mut DrawingContext<'_>, size: Size) {
+    if size.width > 0 && size.height > 0 {
+      ctx.resize(size.into());
+      self.help_overlay.resize(ctx);
+      self.resolution_updated = true;
+    }
+  }
+
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    if self.help_overlay.process_event(event) {
+      return true;
+    }
+
+    let action = match self.key_bindings.process_event(event) {
+      Some(action) => action,
+      None => return false,
+    };
+
+    match action {
+      Action::ToggleView => {
+        self.view_mode = self.view_mode.toggled();
+        log::info!("view: {:?}", self.view_mode);
+        self.view_mode_updated = true;
+      }
+    }
+
+    true
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, _dt: std::time::Duration) {
+    if self.view_mode_updated {
+      ctx.queue.write_buffer(
+        &self.view_mode_uniform_buffer,
+        0,
+        cast_slice(&[self.view_mode.as_uniform()]),
+      );
+      self.view_mode_updated = false;
+    }
+
+    if self.resolution_updated {
+      let resolution = ctx.resolution();
+      ctx.queue.write_buffer(
+        &self.resolution_uniform_buffer,
+        0,
+        cast_slice(&[resolution.width as f32, resolution.height as f32]),
+      );
+      self.resolution_updated = false;
+    }
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: RenderTarget,
+    _sample_count: u32,
+  ) -> Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+    let (view, frame) = match target {
+      RenderTarget::Surface(surface) => {
+        let frame = surface.get_current_texture()?;
+        let view =
+          frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, Some(frame))
+      }
+      RenderTarget::Texture(texture) => {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, None)
+      }
+    };
+
+    let mut compute_pass =
+      encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+        label: Some("compute pass"),
+        ..Default::default()
+      });
+
+    compute_pass.set_pipeline(&self.sobel_pipeline);
+    compute_pass.set_bind_group(0, &self.compute_constants_bind_group, &[]);
+    compute_pass.set_bind_group(1, &self.compute_bind_group, &[]);
+    compute_pass.dispatch_workgroups(
+      self.image_size.0.div_ceil(WORKGROUP_SIZE),
+      self.image_size.1.div_ceil(WORKGROUP_SIZE),
+      1,
+    );
+
+    drop(compute_pass);
+
+    let color_attachment = util::create_color_attachment(&view);
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render Pass"),
+        color_attachments: &[Some(color_attachment)],
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.fullscreen_quad_pipeline);
+    render_pass.set_bind_group(0, &self.render_result_bind_group, &[]);
+    render_pass.draw(0..6, 0..1);
+
+    drop(render_pass);
+
+    self.help_overlay.draw(encoder, &view);
+
+    Ok(frame)
+  }
+}
@@ -0,0 +1,72 @@
+// 0: 勾配の大きさ, 1: Gx を赤、Gy を緑に割り当てて表示
+@group(0) @binding(0) var<uniform> view_mode: u32;
+
+@group(1) @binding(0) var input_tex: texture_2d<f32>;
+@group(1) @binding(1) var output_tex: texture_storage_2d<rgba8unorm, write>;
+
+const workgroup_size = 8u;
+
+// ワークグループが担当する領域に、上下左右1ピクセルずつの境界を加えたもの
+const cache_size = workgroup_size + 2u; // 10
+
+var<workgroup> cache: array<array<f32, 10>, 10>;
+
+struct CsInput {
+  @builtin(workgroup_id) workgroup_id: vec3u,
+  @builtin(local_invocation_id) local_id: vec3u,
+  @builtin(global_invocation_id) global_id: vec3u
+}
+
+fn luminance(color: vec3f) -> f32 {
+  return dot(color, vec3f(0.2126, 0.7152, 0.0722));
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn cs_main(in: CsInput) {
+  let dims = vec2i(textureDimensions(input_tex, 0));
+
+  // キャッシュの左上に対応するピクセル（境界の1ピクセル分ずらす）
+  let cache_origin = vec2i(in.workgroup_id.xy * workgroup_size) - vec2i(1);
+
+  // 10x10 のキャッシュを 8x8 のスレッドで埋めるので、1スレッドが複数回読み込む
+  let local_index = in.local_id.y * workgroup_size + in.local_id.x;
+  for (var i = local_index; i < cache_size * cache_size; i += workgroup_size * workgroup_size) {
+    let cache_index = vec2u(i % cache_size, i / cache_size);
+    // 画像の外は端のピクセルで代用する
+    let load_index = clamp(cache_origin + vec2i(cache_index), vec2i(0), dims - 1);
+    let color = textureLoad(input_tex, load_index, 0).rgb;
+    cache[cache_index.y][cache_index.x] = luminance(color);
+  }
+
+  workgroupBarrier();
+
+  let pixel = vec2i(in.global_id.xy);
+  if (any(pixel >= dims)) {
+    return;
+  }
+
+  // キャッシュ上での中心
+  let c = in.local_id.xy + vec2u(1u);
+
+  let tl = cache[c.y - 1u][c.x - 1u];
+  let t = cache[c.y - 1u][c.x];
+  let tr = cache[c.y - 1u][c.x + 1u];
+  let l = cache[c.y][c.x - 1u];
+  let r = cache[c.y][c.x + 1u];
+  let bl = cache[c.y + 1u][c.x - 1u];
+  let b = cache[c.y + 1u][c.x];
+  let br = cache[c.y + 1u][c.x + 1u];
+
+  let gx = (tr + 2.0 * r + br) - (tl + 2.0 * l + bl);
+  let gy = (bl + 2.0 * b + br) - (tl + 2.0 * t + tr);
+
+  var color: vec3f;
+  if (view_mode == 0u) {
+    color = vec3f(length(vec2f(gx, gy)));
+  } else {
+    // Gx, Gy は -4..4 なので 0..1 に収める（勾配がないところは 0.5）
+    color = vec3f(gx, gy, 0.0) * 0.125 + vec3f(0.5, 0.5, 0.0);
+  }
+
+  textureStore(output_tex, pixel, vec4(color, 1.0));
+}
@@ -4,7 +4,7 @@ use cgmath::{Matrix, SquareMatrix};
 use rand::Rng;
 use wgpu::util::DeviceExt;
 use wgsim::geometry::generator as ge;
-use wgsim::geometry::{Cube, Sphere, Torus};
+use wgsim::geometry::{Cube, Icosphere, Torus};
 use wgsim::matrix;
 
 #[repr(C)]
@@ -42,12 +42,13 @@ fn cube_vertices() -> Geometry {
 }
 
 fn sphere_vertices() -> Geometry {
-  let Sphere {
+  // 極に三角形が集まらないよう、正二十面体を細分割した球を使う
+  let Icosphere {
     positions,
     normals,
     indices,
     ..
-  } = ge::create_sphere_data(2.2, 20, 30);
+  } = ge::create_icosphere_data(2.2, 3);
 
   let mut data: Vec<Vertex> = Vec::with_capacity(positions.len());
   for i in 0..positions.len() {
@@ -7,28 +7,32 @@ use cgmath::{Matrix4, Point3, Vector3};
 use instance_defs::{Matrices, Shapes, Vertex};
 use wgpu::util::DeviceExt;
 use wgsim::app::App;
+use wgsim::camera::OrbitCamera;
 use wgsim::ctx::{DrawingContext, Size};
 use wgsim::matrix;
 use wgsim::ppl::RenderPipelineBuilder;
 use wgsim::render::{Render, RenderTarget};
 use wgsim::util;
+use winit::event::WindowEvent;
 
 const NUM_CUBES: u32 = 50;
 const NUM_SPHERES: u32 = 50;
 const NUM_TORI: u32 = 50;
 
-fn setup() -> Initial {
+fn setup(wireframe: bool, indirect: bool) -> Initial {
   Initial {
     camera_position: Point3::new(8., 8., 16.),
     look_direction: Point3::new(0., 0., 0.),
     up_direction: Vector3::unit_y(),
+    wireframe,
+    indirect,
   }
 }
 
 pub fn run() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
-  let initial = setup();
+  let initial = setup(false, false);
 
   let mut app: App<State> =
     App::new("instanced_cube_sphere_torus - base", initial).with_msaa();
@@ -37,23 +41,58 @@ pub fn run() -> Result<(), Box<dyn Error>> {
   Ok(())
 }
 
+pub fn run_wireframe() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup(true, false);
+
+  let mut app: App<State> =
+    App::new("instanced_cube_sphere_torus - base (wireframe)", initial)
+      .with_msaa();
+  app.run()?;
+
+  Ok(())
+}
+
+pub fn run_indirect() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup(false, true);
+
+  let mut app: App<State> =
+    App::new("instanced_cube_sphere_torus - base (indirect)", initial)
+      .with_msaa();
+  app.run()?;
+
+  Ok(())
+}
+
 struct Initial {
   pub camera_position: Point3<f32>,
   pub look_direction: Point3<f32>,
   pub up_direction: Vector3<f32>,
+  pub wireframe: bool,
+  pub indirect: bool,
 }
 
 struct State {
   pipeline: wgpu::RenderPipeline,
+  wireframe_pipeline: Option<wgpu::RenderPipeline>,
 
   shapes: Shapes,
+  // 立方体・球・トーラスの順に、1形状につき1つの描画引数
+  indirect_buffer: Option<wgpu::Buffer>,
 
   vert_bind_group: wgpu::BindGroup,
 
   msaa_texture_view: wgpu::TextureView,
   depth_texture_view: wgpu::TextureView,
 
+  vp_uniform_buffer: wgpu::Buffer,
+
   project_mat: Matrix4<f32>,
+
+  camera: OrbitCamera,
 }
 
 impl<'a> Render<'a> for State {
@@ -181,6 +220,19 @@ impl<'a> Render<'a> for State {
 
     let pipeline = pipeline_builder.build();
 
+    // 同じ頂点・インデックスバッファを辺だけで描き直す
+    // （LineList 用のインデックスバッファは要らない）
+    let wireframe_pipeline = initial.wireframe.then(|| {
+      RenderPipelineBuilder::new(&ctx)
+        .vs_shader(&vs_shader, "vs_main")
+        .fs_shader(&fs_shader, "fs_wireframe")
+        .pipeline_layout(&pipeline_layout)
+        .vertex_buffer_layout(&vertex_buffer_layout)
+        .enable_depth_stencil(None)
+        .polygon_mode(wgpu::PolygonMode::Line)
+        .build()
+    });
+
     //
     // texture views
     //
@@ -194,13 +246,51 @@ impl<'a> Render<'a> for State {
 
     let shapes = instance_defs::create_object_buffers(&ctx.device);
 
+    //
+    // indirect draw
+    //
+
+    // first_instance が0でない間接描画は、この機能がないと描画されない
+    let supports_indirect =
+      ctx.device.features().contains(wgpu::Features::INDIRECT_FIRST_INSTANCE);
+    if initial.indirect && !supports_indirect {
+      eprintln!("INDIRECT_FIRST_INSTANCE is not supported, drawing directly");
+    }
+    let indirect_buffer = (initial.indirect && supports_indirect).then(|| {
+      let args = |index_count, first_instance, instance_count| {
+        wgpu::util::DrawIndexedIndirectArgs {
+          index_count,
+          instance_count,
+          first_index: 0,
+          base_vertex: 0,
+          first_instance,
+        }
+      };
+      util::create_indirect_buffer(
+        &ctx.device,
+        &[
+          args(shapes.cube.index_count, 0, NUM_CUBES),
+          args(shapes.sphere.index_count, NUM_CUBES, NUM_SPHERES),
+          args(shapes.torus.index_count, NUM_CUBES + NUM_SPHERES, NUM_TORI),
+        ],
+      )
+    });
+
     Self {
       pipeline,
+      wireframe_pipeline,
       shapes,
+      indirect_buffer,
       vert_bind_group,
       msaa_texture_view,
       depth_texture_view,
+      vp_uniform_buffer,
       project_mat,
+      camera: OrbitCamera::new(
+        initial.camera_position,
+        initial.look_direction,
+        initial.up_direction,
+      ),
     }
   }
 
@@ -221,6 +311,20 @@ impl<'a> Render<'a> for State {
     }
   }
 
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    self.camera.process_event(event)
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, _dt: std::time::Duration) {
+    let view_project_mat = self.project_mat * self.camera.view_mat();
+    let view_projection_ref: &[f32; 16] = view_project_mat.as_ref();
+    ctx.queue.write_buffer(
+      &self.vp_uniform_buffer,
+      0,
+      cast_slice(view_projection_ref),
+    );
+  }
+
   fn draw(
     &mut self,
     encoder: &mut wgpu::CommandEncoder,
@@ -259,9 +363,33 @@ impl<'a> Render<'a> for State {
         ..Default::default()
       });
 
-    render_pass.set_pipeline(&self.pipeline);
     render_pass.set_bind_group(0, &self.vert_bind_group, &[]);
 
+    render_pass.set_pipeline(&self.pipeline);
+    self.draw_shapes(&mut render_pass);
+
+    if let Some(wireframe_pipeline) = &self.wireframe_pipeline {
+      render_pass.set_pipeline(wireframe_pipeline);
+      self.draw_shapes(&mut render_pass);
+    }
+
+    drop(render_pass);
+
+    Ok(frame)
+  }
+}
+
+const INDIRECT_ARGS_SIZE: wgpu::BufferAddress = std::mem::size_of::<
+  wgpu::util::DrawIndexedIndirectArgs,
+>() as wgpu::BufferAddress;
+
+impl State {
+  fn draw_shapes(&self, render_pass: &mut wgpu::RenderPass) {
+    if let Some(indirect_buffer) = &self.indirect_buffer {
+      self.draw_shapes_indirect(render_pass, indirect_buffer);
+      return;
+    }
+
     //
     // draw cubes
     //
@@ -300,9 +428,25 @@ impl<'a> Render<'a> for State {
       0,
       NUM_CUBES + NUM_SPHERES..NUM_CUBES + NUM_SPHERES + NUM_TORI,
     );
+  }
 
-    drop(render_pass);
-
-    Ok(frame)
+  // 個数は間接描画の引数から読まれる（コンピュートシェーダーで書き換えられる）
+  fn draw_shapes_indirect(
+    &self,
+    render_pass: &mut wgpu::RenderPass,
+    indirect_buffer: &wgpu::Buffer,
+  ) {
+    let meshes = [&self.shapes.cube, &self.shapes.sphere, &self.shapes.torus];
+    for (i, mesh) in meshes.into_iter().enumerate() {
+      render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+      render_pass.set_index_buffer(
+        mesh.index_buffer.slice(..),
+        wgpu::IndexFormat::Uint16,
+      );
+      render_pass.draw_indexed_indirect(
+        indirect_buffer,
+        INDIRECT_ARGS_SIZE * i as wgpu::BufferAddress,
+      );
+    }
   }
 }
@@ -9,4 +9,10 @@ fn fs_main(in: Input) -> @location(0) vec4f {
   let final_color = in.v_color;
 
   return vec4<f32>(final_color.rgb, 1.0);
-}
\ No newline at end of file
+}
+
+// ワイヤーフレームは元の色を暗くして、面の上に重ねる
+@fragment
+fn fs_wireframe(in: Input) -> @location(0) vec4f {
+  return vec4<f32>(in.v_color.rgb * 0.3, 1.0);
+}
@@ -0,0 +1,32 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  FocusFarther,
+  FocusNearer,
+  IncreaseAperture,
+  DecreaseAperture,
+  ToggleFocusRack,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::FocusFarther => "Move focus farther",
+      Action::FocusNearer => "Move focus nearer",
+      Action::IncreaseAperture => "Increase aperture",
+      Action::DecreaseAperture => "Decrease aperture",
+      Action::ToggleFocusRack => "Toggle focus racking",
+    }
+  }
+}
+
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new()
+    .bind(KeyCode::ArrowUp, Action::FocusFarther)
+    .bind(KeyCode::ArrowDown, Action::FocusNearer)
+    .bind(KeyCode::ArrowRight, Action::IncreaseAperture)
+    .bind(KeyCode::ArrowLeft, Action::DecreaseAperture)
+    .bind(KeyCode::Space, Action::ToggleFocusRack)
+}
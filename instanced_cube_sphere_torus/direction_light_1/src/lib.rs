@@ -1,26 +1,49 @@
+mod action;
 mod instance_defs;
 mod light_defs;
 
 use std::error::Error;
 
+use action::Action;
 use bytemuck::cast_slice;
 use cgmath::{Matrix4, Point3, Vector3};
-use instance_defs::{Matrices, Shapes, Vertex};
+use instance_defs::{Matrices, Shapes};
 use light_defs::DirectionLight;
 use wgpu::util::DeviceExt;
 use wgsim::app::App;
+use wgsim::camera::OrbitCamera;
 use wgsim::ctx::{DrawingContext, Size};
+use wgsim::depth::DepthResolvePass;
 use wgsim::export::Gif;
+use wgsim::geometry::IndexedMesh;
+#[cfg(feature = "gamepad")]
+use wgsim::input::GamepadState;
+use wgsim::input::KeyBindings;
 use wgsim::matrix;
+use wgsim::post::{BloomPass, DepthOfField, ToneMap, ToneMapOperator};
 use wgsim::ppl::RenderPipelineBuilder;
 use wgsim::render::{Render, RenderTarget};
 use wgsim::util;
+use winit::event::WindowEvent;
 
 const NUM_CUBES: u32 = 50;
 const NUM_SPHERES: u32 = 50;
 const NUM_TORI: u32 = 50;
 
-fn setup(animation_speed: f32) -> Initial {
+const FOCUS_NEAR: f32 = 35.;
+const FOCUS_FAR: f32 = 95.;
+const MIN_FOCUS_DISTANCE: f32 = 5.;
+const MAX_FOCUS_DISTANCE: f32 = 150.;
+const FOCUS_STEP: f32 = 5.;
+const APERTURE: f32 = 16.;
+const MAX_APERTURE: f32 = 40.;
+const APERTURE_STEP: f32 = 2.;
+
+const ADAPTIVE_TARGET_MS: f32 = 12.;
+const FPS_FONT: &[u8] =
+  include_bytes!("../../../font/Lusitana/Lusitana-Regular.ttf");
+
+fn setup(animation_speed: f32, depth_of_field: bool) -> Initial {
   Initial {
     camera_position: Point3::new(8., 8., 16.),
     look_direction: Point3::new(0., 0., 0.),
@@ -32,17 +55,24 @@ fn setup(animation_speed: f32) -> Initial {
     },
 
     animation_speed,
+
+    depth_of_field,
+    key_bindings: action::default_key_bindings(),
   }
 }
 
 pub fn run() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
-  let initial = setup(1.);
+  let initial = setup(1., false);
 
   let mut app: App<State> =
     App::new("instanced_cube_sphere_torus - direction_light_1", initial)
       .with_msaa();
+  #[cfg(feature = "gamepad")]
+  {
+    app = app.with_gamepad();
+  }
   app.run()?;
 
   Ok(())
@@ -51,9 +81,9 @@ pub fn run() -> Result<(), Box<dyn Error>> {
 pub async fn export_gif() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
-  let initial = setup(3.);
+  let initial = setup(3., false);
 
-  let mut gif = Gif::<State>::new(1024, initial, true).await;
+  let mut gif = Gif::<State>::new(1024, initial, true).await?;
   gif
     .export(
       "export/instanced-cube-sphere-torus_direction-light-diffuse-4.gif",
@@ -65,6 +95,101 @@ pub async fn export_gif() -> Result<(), Box<dyn Error>> {
   Ok(())
 }
 
+/// 明るい面の光をにじませる
+pub fn run_bloom() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup(1., false);
+
+  let mut app: App<State> = App::new(
+    "instanced_cube_sphere_torus - direction_light_1 (bloom)",
+    initial,
+  )
+  .with_msaa()
+  .with_post_effect(Box::new(
+    BloomPass::new().with_tone_map(ToneMapOperator::Aces, 1.),
+  ));
+  #[cfg(feature = "gamepad")]
+  {
+    app = app.with_gamepad();
+  }
+  app.run()?;
+
+  Ok(())
+}
+
+/// Rgba16Float に描画して、露出を上げてからトーンマッピングする
+pub fn run_tone_map() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup(1., false);
+
+  let mut app: App<State> = App::new(
+    "instanced_cube_sphere_torus - direction_light_1 (tone map)",
+    initial,
+  )
+  .with_msaa()
+  .with_post_effect(Box::new(
+    ToneMap::new(ToneMapOperator::Reinhard).with_exposure(2.),
+  ));
+  #[cfg(feature = "gamepad")]
+  {
+    app = app.with_gamepad();
+  }
+  app.run()?;
+
+  Ok(())
+}
+
+pub fn run_dof() -> Result<(), Box<dyn Error>> {
+  run_dof_with(None)
+}
+
+/// 被写界深度の負荷に応じて描画解像度を自動で下げる
+pub fn run_dof_adaptive() -> Result<(), Box<dyn Error>> {
+  run_dof_with(Some(ADAPTIVE_TARGET_MS))
+}
+
+fn run_dof_with(adaptive_target_ms: Option<f32>) -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup(1., true);
+  initial.key_bindings.print_help();
+
+  let mut app: App<State> = App::new(
+    "instanced_cube_sphere_torus - direction_light_1 (depth of field)",
+    initial,
+  )
+  .with_msaa();
+  if let Some(target_ms) = adaptive_target_ms {
+    app = app.with_adaptive_resolution(target_ms).with_fps_overlay(FPS_FONT);
+  }
+  #[cfg(feature = "gamepad")]
+  {
+    app = app.with_gamepad();
+  }
+  app.run()?;
+
+  Ok(())
+}
+
+pub async fn export_gif_dof() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup(3., true);
+
+  let mut gif = Gif::<State>::new_with_size(1024, 576, initial, true).await?;
+  gif
+    .export(
+      "export/instanced-cube-sphere-torus_depth-of-field.gif",
+      120,
+      30,
+    )
+    .await?;
+
+  Ok(())
+}
+
 struct Initial {
   pub camera_position: Point3<f32>,
   pub look_direction: Point3<f32>,
@@ -73,6 +198,85 @@ struct Initial {
   pub light: DirectionLight,
 
   pub animation_speed: f32,
+
+  pub depth_of_field: bool,
+  pub key_bindings: KeyBindings<Action>,
+}
+
+struct DofPass {
+  dof: DepthOfField,
+  depth_resolve: Option<DepthResolvePass>,
+  scene_view: wgpu::TextureView,
+
+  focus_distance: f32,
+  aperture: f32,
+  focus_rack: bool,
+}
+
+impl DofPass {
+  fn new(ctx: &DrawingContext, depth_view: &wgpu::TextureView) -> Self {
+    let mut pass = Self {
+      dof: DepthOfField::new(ctx),
+      depth_resolve: (ctx.sample_count > 1)
+        .then(|| DepthResolvePass::new(ctx, depth_view)),
+      scene_view: util::create_sampleable_color_view(ctx),
+      focus_distance: FOCUS_NEAR,
+      aperture: APERTURE,
+      focus_rack: true,
+    };
+    pass.bind_inputs(ctx, depth_view);
+
+    pass
+  }
+
+  fn resize(&mut self, ctx: &DrawingContext, depth_view: &wgpu::TextureView) {
+    self.scene_view = util::create_sampleable_color_view(ctx);
+    if let Some(depth_resolve) = self.depth_resolve.as_mut() {
+      depth_resolve.resize(ctx, depth_view);
+    }
+    self.bind_inputs(ctx, depth_view);
+  }
+
+  fn bind_inputs(
+    &mut self,
+    ctx: &DrawingContext,
+    depth_view: &wgpu::TextureView,
+  ) {
+    let depth_view = match &self.depth_resolve {
+      Some(depth_resolve) => depth_resolve.resolved_view(),
+      None => depth_view,
+    };
+    self.dof.set_inputs(ctx, &self.scene_view, depth_view);
+  }
+
+  fn process_action(&mut self, action: Action) {
+    match action {
+      Action::FocusFarther => {
+        self.focus_rack = false;
+        self.focus_distance =
+          MAX_FOCUS_DISTANCE.min(self.focus_distance + FOCUS_STEP);
+        log::info!("focus distance: {}", self.focus_distance);
+      }
+      Action::FocusNearer => {
+        self.focus_rack = false;
+        self.focus_distance =
+          MIN_FOCUS_DISTANCE.max(self.focus_distance - FOCUS_STEP);
+        log::info!("focus distance: {}", self.focus_distance);
+      }
+      Action::IncreaseAperture => {
+        self.aperture = MAX_APERTURE.min(self.aperture + APERTURE_STEP);
+        log::info!("aperture: {}", self.aperture);
+      }
+      Action::DecreaseAperture => {
+        self.aperture = (self.aperture - APERTURE_STEP).max(0.);
+        log::info!("aperture: {}", self.aperture);
+      }
+      Action::ToggleFocusRack => {
+        self.focus_rack = !self.focus_rack;
+        log::info!("focus racking: {}", self.focus_rack);
+      }
+    }
+  }
 }
 
 struct State {
@@ -92,7 +296,16 @@ struct State {
   view_mat: Matrix4<f32>,
   project_mat: Matrix4<f32>,
 
+  camera: OrbitCamera,
+  #[cfg(feature = "gamepad")]
+  gamepad: GamepadState,
+  #[cfg(feature = "gamepad")]
+  prev_time: f32,
+
   animation_speed: f32,
+
+  dof: Option<DofPass>,
+  key_bindings: KeyBindings<Action>,
 }
 
 impl<'a> Render<'a> for State {
@@ -232,11 +445,7 @@ impl<'a> Render<'a> for State {
     // pipeline
     //
 
-    let vertex_buffer_layout = [wgpu::VertexBufferLayout {
-      array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
-      step_mode: wgpu::VertexStepMode::Vertex,
-      attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3],
-    }];
+    let vertex_buffer_layout = [IndexedMesh::vertex_buffer_layout()];
 
     let pipeline_layout =
       ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -259,7 +468,18 @@ impl<'a> Render<'a> for State {
     //
 
     let msaa_texture_view = util::create_msaa_texture_view(&ctx);
-    let depth_texture_view = util::create_depth_view(&ctx);
+    let depth_texture_view = if initial.depth_of_field {
+      util::create_sampleable_depth_view(&ctx)
+    } else {
+      util::create_depth_view(&ctx)
+    };
+
+    //
+    // depth of field
+    //
+
+    let dof =
+      initial.depth_of_field.then(|| DofPass::new(&ctx, &depth_texture_view));
 
     //
     // vertex and index buffers for objects
@@ -278,7 +498,18 @@ impl<'a> Render<'a> for State {
       vp_uniform_buffer,
       view_mat,
       project_mat,
+      camera: OrbitCamera::new(
+        initial.camera_position,
+        initial.look_direction,
+        initial.up_direction,
+      ),
+      #[cfg(feature = "gamepad")]
+      gamepad: GamepadState::default(),
+      #[cfg(feature = "gamepad")]
+      prev_time: 0.,
       animation_speed: initial.animation_speed,
+      dof,
+      key_bindings: initial.key_bindings.clone(),
     }
   }
 
@@ -291,19 +522,74 @@ impl<'a> Render<'a> for State {
         true,
       );
 
-      self.depth_texture_view = util::create_depth_view(ctx);
-
       if ctx.sample_count > 1 {
         self.msaa_texture_view = util::create_msaa_texture_view(&ctx);
       }
+
+      match self.dof.as_mut() {
+        Some(dof) => {
+          self.depth_texture_view = util::create_sampleable_depth_view(ctx);
+          dof.resize(ctx, &self.depth_texture_view);
+        }
+        None => {
+          self.depth_texture_view = util::create_depth_view(ctx);
+        }
+      }
     }
   }
 
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    if let Some(dof) = self.dof.as_mut() {
+      if let Some(action) = self.key_bindings.process_event(event) {
+        dof.process_action(action);
+        return true;
+      }
+    }
+
+    self.camera.process_event(event)
+  }
+
+  #[cfg(feature = "gamepad")]
+  fn process_gamepad(&mut self, state: &GamepadState) {
+    self.gamepad = *state;
+  }
+
   fn update(&mut self, ctx: &DrawingContext, dt: std::time::Duration) {
+    //
+    // ゲームパッド：左スティックで注視点のまわりを回転、トリガーでズーム
+    //
+
+    #[cfg(feature = "gamepad")]
+    {
+      let time = dt.as_secs_f32();
+      let frame_dt = time - self.prev_time;
+      self.prev_time = time;
+
+      self.camera.apply_gamepad(
+        &self.gamepad,
+        std::time::Duration::from_secs_f32(frame_dt.max(0.)),
+      );
+    }
+
+    self.view_mat = self.camera.view_mat();
+
     let dt = self.animation_speed * dt.as_secs_f32();
     let sin = 10.0 * (0.5 + dt.sin());
     let cos = 10.0 * (0.5 + dt.cos());
 
+    //
+    // 被写界深度：ピントを手前と奥のあいだで往復させる
+    //
+
+    if let Some(dof) = self.dof.as_mut() {
+      if dof.focus_rack {
+        let t = 0.5 - 0.5 * (0.5 * dt).cos();
+        dof.focus_distance = FOCUS_NEAR + (FOCUS_FAR - FOCUS_NEAR) * t;
+      }
+      dof.dof.set_focus_distance(&ctx.queue, dof.focus_distance);
+      dof.dof.set_aperture(&ctx.queue, dof.aperture);
+    }
+
     ctx.queue.write_buffer(
       &self.light_uniform_buffer,
       0,
@@ -338,16 +624,25 @@ impl<'a> Render<'a> for State {
       }
     };
 
-    let color_attach = util::create_color_attachment(&view);
+    // 被写界深度を使うときはいったんオフスクリーンに描画する
+    let scene_view = match &self.dof {
+      Some(dof) => &dof.scene_view,
+      None => &view,
+    };
+
+    let color_attach = util::create_color_attachment(scene_view);
     let msaa_attach =
-      util::create_msaa_color_attachment(&view, &self.msaa_texture_view);
+      util::create_msaa_color_attachment(scene_view, &self.msaa_texture_view);
     let color_attachment = if sample_count == 1 {
       color_attach
     } else {
       msaa_attach
     };
-    let depth_attachment =
-      util::create_depth_stencil_attachment(&self.depth_texture_view);
+    let depth_attachment = if self.dof.is_some() {
+      util::create_stored_depth_stencil_attachment(&self.depth_texture_view)
+    } else {
+      util::create_depth_stencil_attachment(&self.depth_texture_view)
+    };
 
     let mut render_pass =
       encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -362,45 +657,30 @@ impl<'a> Render<'a> for State {
     render_pass.set_bind_group(1, &self.frag_bind_group, &[]);
 
     //
-    // draw cubes
+    // draw cubes, spheres and tori
     //
-    render_pass.set_vertex_buffer(0, self.shapes.cube.vertex_buffer.slice(..));
-    render_pass.set_index_buffer(
-      self.shapes.cube.index_buffer.slice(..),
-      wgpu::IndexFormat::Uint16,
+    self.shapes.cube.draw(&mut render_pass, 0..NUM_CUBES);
+    self
+      .shapes
+      .sphere
+      .draw(&mut render_pass, NUM_CUBES..NUM_CUBES + NUM_SPHERES);
+    self.shapes.torus.draw(
+      &mut render_pass,
+      NUM_CUBES + NUM_SPHERES..NUM_CUBES + NUM_SPHERES + NUM_TORI,
     );
-    render_pass.draw_indexed(0..self.shapes.cube.index_count, 0, 0..NUM_CUBES);
 
-    //
-    // draw spheres
-    //
-    render_pass
-      .set_vertex_buffer(0, self.shapes.sphere.vertex_buffer.slice(..));
-    render_pass.set_index_buffer(
-      self.shapes.sphere.index_buffer.slice(..),
-      wgpu::IndexFormat::Uint16,
-    );
-    render_pass.draw_indexed(
-      0..self.shapes.sphere.index_count,
-      0,
-      NUM_CUBES..NUM_CUBES + NUM_SPHERES,
-    );
+    drop(render_pass);
 
     //
-    // draw tori
+    // depth of field
     //
-    render_pass.set_vertex_buffer(0, self.shapes.torus.vertex_buffer.slice(..));
-    render_pass.set_index_buffer(
-      self.shapes.torus.index_buffer.slice(..),
-      wgpu::IndexFormat::Uint16,
-    );
-    render_pass.draw_indexed(
-      0..self.shapes.torus.index_count,
-      0,
-      NUM_CUBES + NUM_SPHERES..NUM_CUBES + NUM_SPHERES + NUM_TORI,
-    );
 
-    drop(render_pass);
+    if let Some(dof) = &self.dof {
+      if let Some(depth_resolve) = &dof.depth_resolve {
+        depth_resolve.resolve(encoder);
+      }
+      dof.dof.draw(encoder, &view);
+    }
 
     Ok(frame)
   }
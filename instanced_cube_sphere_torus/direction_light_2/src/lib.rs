@@ -9,18 +9,20 @@ use instance_defs::{Matrices, Shapes, Vertex};
 use light_defs::DirectionLight;
 use wgpu::util::DeviceExt;
 use wgsim::app::App;
+use wgsim::camera::{FlyCamera, OrbitCamera};
 use wgsim::ctx::{DrawingContext, Size};
 use wgsim::export::Gif;
 use wgsim::matrix;
 use wgsim::ppl::RenderPipelineBuilder;
 use wgsim::render::{Render, RenderTarget};
 use wgsim::util;
+use winit::event::{DeviceEvent, WindowEvent};
 
 const NUM_CUBES: u32 = 50;
 const NUM_SPHERES: u32 = 50;
 const NUM_TORI: u32 = 50;
 
-fn setup(animation_speed: f32) -> Initial {
+fn setup(animation_speed: f32, fly: bool) -> Initial {
   Initial {
     camera_position: Point3::new(8., 8., 16.),
     look_direction: Point3::new(0., 0., 0.),
@@ -33,13 +35,14 @@ fn setup(animation_speed: f32) -> Initial {
     ambient: 0.2,
 
     animation_speed,
+    fly,
   }
 }
 
 pub fn run() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
-  let initial = setup(1.);
+  let initial = setup(1., false);
 
   let mut app: App<State> =
     App::new("instanced_cube_sphere_torus - direction_light_2", initial)
@@ -49,12 +52,28 @@ pub fn run() -> Result<(), Box<dyn Error>> {
   Ok(())
 }
 
+pub fn run_fly() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup(1., true);
+
+  let mut app: App<State> = App::new(
+    "instanced_cube_sphere_torus - direction_light_2 (fly camera)",
+    initial,
+  )
+  .with_msaa()
+  .with_cursor_grab();
+  app.run()?;
+
+  Ok(())
+}
+
 pub async fn export_gif() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
-  let initial = setup(3.);
+  let initial = setup(3., false);
 
-  let mut gif = Gif::<State>::new(1024, initial, true).await;
+  let mut gif = Gif::<State>::new(1024, initial, true).await?;
   gif
     .export(
       "export/instanced-cube-sphere-torus_direction-light-diffuse-ambient.gif",
@@ -75,6 +94,21 @@ struct Initial {
   pub ambient: f32,
 
   pub animation_speed: f32,
+  pub fly: bool,
+}
+
+enum Camera {
+  Orbit(OrbitCamera),
+  Fly(FlyCamera),
+}
+
+impl Camera {
+  fn view_mat(&self) -> Matrix4<f32> {
+    match self {
+      Camera::Orbit(camera) => camera.view_mat(),
+      Camera::Fly(camera) => camera.view_mat(),
+    }
+  }
 }
 
 struct State {
@@ -94,6 +128,9 @@ struct State {
   view_mat: Matrix4<f32>,
   project_mat: Matrix4<f32>,
 
+  camera: Camera,
+  prev_time: f32,
+
   animation_speed: f32,
 }
 
@@ -293,6 +330,20 @@ impl<'a> Render<'a> for State {
       vp_uniform_buffer,
       view_mat,
       project_mat,
+      camera: if initial.fly {
+        Camera::Fly(FlyCamera::new(
+          initial.camera_position,
+          initial.look_direction,
+          initial.up_direction,
+        ))
+      } else {
+        Camera::Orbit(OrbitCamera::new(
+          initial.camera_position,
+          initial.look_direction,
+          initial.up_direction,
+        ))
+      },
+      prev_time: 0.,
       animation_speed: initial.animation_speed,
     }
   }
@@ -314,7 +365,33 @@ impl<'a> Render<'a> for State {
     }
   }
 
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    match &mut self.camera {
+      Camera::Orbit(camera) => camera.process_event(event),
+      Camera::Fly(camera) => camera.process_event(event),
+    }
+  }
+
+  fn process_device_event(&mut self, event: &DeviceEvent) {
+    if let Camera::Fly(camera) = &mut self.camera {
+      camera.process_device_event(event);
+    }
+  }
+
   fn update(&mut self, ctx: &DrawingContext, dt: std::time::Duration) {
+    //
+    // 視点：フライカメラは前フレームからの経過時間ぶん移動する
+    //
+
+    let time = dt.as_secs_f32();
+    let frame_dt = time - self.prev_time;
+    self.prev_time = time;
+
+    if let Camera::Fly(camera) = &mut self.camera {
+      camera.update(std::time::Duration::from_secs_f32(frame_dt.max(0.)));
+    }
+    self.view_mat = self.camera.view_mat();
+
     let dt = self.animation_speed * dt.as_secs_f32();
     let sin = 10.0 * (0.5 + dt.sin());
     let cos = 10.0 * (0.5 + dt.cos());
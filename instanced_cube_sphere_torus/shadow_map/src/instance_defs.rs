@@ -0,0 +1,201 @@
+use bytemuck::{cast_slice, Pod, Zeroable};
+
+use cgmath::{Matrix, SquareMatrix};
+use rand::Rng;
+use wgpu::util::DeviceExt;
+use wgsim::geometry::generator as ge;
+use wgsim::geometry::{Cube, Icosphere, Plane, Torus};
+use wgsim::matrix;
+
+pub const GROUND_SIZE: f32 = 100.;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct Vertex {
+  pub position: [f32; 3],
+  pub normal: [f32; 3],
+}
+
+struct Geometry {
+  pub vertices: Vec<Vertex>,
+  pub indices: Vec<u16>,
+}
+
+fn cube_vertices() -> Geometry {
+  let Cube {
+    positions,
+    normals,
+    indices,
+    ..
+  } = ge::create_cube_data(2.0);
+
+  let mut data: Vec<Vertex> = Vec::with_capacity(positions.len());
+  for i in 0..positions.len() {
+    data.push(Vertex {
+      position: positions[i],
+      normal: normals[i],
+    });
+  }
+
+  Geometry {
+    vertices: data,
+    indices,
+  }
+}
+
+fn sphere_vertices() -> Geometry {
+  // 極に三角形が集まらないよう、正二十面体を細分割した球を使う
+  let Icosphere {
+    positions,
+    normals,
+    indices,
+    ..
+  } = ge::create_icosphere_data(2.2, 3);
+
+  let mut data: Vec<Vertex> = Vec::with_capacity(positions.len());
+  for i in 0..positions.len() {
+    data.push(Vertex {
+      position: positions[i],
+      normal: normals[i],
+    });
+  }
+
+  Geometry {
+    vertices: data,
+    indices,
+  }
+}
+
+fn torus_vertices() -> Geometry {
+  let Torus {
+    positions,
+    normals,
+    indices,
+    ..
+  } = ge::create_torus_data(1.8, 0.4, 60, 20);
+
+  let mut data: Vec<Vertex> = Vec::with_capacity(positions.len());
+  for i in 0..positions.len() {
+    data.push(Vertex {
+      position: positions[i],
+      normal: normals[i],
+    });
+  }
+
+  Geometry {
+    vertices: data,
+    indices,
+  }
+}
+
+fn ground_vertices() -> Geometry {
+  let Plane {
+    positions,
+    normals,
+    indices,
+    ..
+  } = ge::create_plane_data(GROUND_SIZE, GROUND_SIZE, 1, 1);
+
+  let mut data: Vec<Vertex> = Vec::with_capacity(positions.len());
+  for i in 0..positions.len() {
+    data.push(Vertex {
+      position: positions[i],
+      normal: normals[i],
+    });
+  }
+
+  Geometry {
+    vertices: data,
+    indices,
+  }
+}
+
+pub struct Model {
+  pub vertex_buffer: wgpu::Buffer,
+  pub index_buffer: wgpu::Buffer,
+  pub index_count: u32,
+}
+
+pub struct Shapes {
+  pub cube: Model,
+  pub sphere: Model,
+  pub torus: Model,
+  pub ground: Model,
+}
+
+fn create_model(
+  device: &wgpu::Device,
+  name: &str,
+  geometry: Geometry,
+) -> Model {
+  let vertex_buffer =
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some(&format!("{} Vertex Buffer", name)),
+      contents: cast_slice(&geometry.vertices),
+      usage: wgpu::BufferUsages::VERTEX,
+    });
+
+  let index_buffer =
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some(&format!("{} Index Buffer", name)),
+      contents: cast_slice(&geometry.indices),
+      usage: wgpu::BufferUsages::INDEX,
+    });
+
+  Model {
+    vertex_buffer,
+    index_buffer,
+    index_count: geometry.indices.len() as u32,
+  }
+}
+
+pub fn create_object_buffers(device: &wgpu::Device) -> Shapes {
+  Shapes {
+    cube: create_model(device, "Cube", cube_vertices()),
+    sphere: create_model(device, "Sphere", sphere_vertices()),
+    torus: create_model(device, "Torus", torus_vertices()),
+    ground: create_model(device, "Ground", ground_vertices()),
+  }
+}
+
+pub struct Matrices {
+  pub model_mat: Vec<[f32; 16]>,
+  pub normal_mat: Vec<[f32; 16]>,
+  pub color_vec: Vec<[f32; 4]>,
+}
+
+/// 地面の上に浮かべた `objects_count` 個の物体と、最後に地面1枚ぶんの
+/// 行列と色を作る（地面のインスタンス番号は `objects_count`）
+pub fn create_transform_mat_color(objects_count: u32) -> Matrices {
+  let mut model_mat: Vec<[f32; 16]> = vec![];
+  let mut normal_mat: Vec<[f32; 16]> = vec![];
+  let mut color_vec: Vec<[f32; 4]> = vec![];
+
+  let mut rng = rand::thread_rng();
+  for _i in 0..objects_count {
+    let translation = [
+      rng.gen::<f32>() * 60.0 - 30.0,
+      rng.gen::<f32>() * 15.0 + 3.0,
+      rng.gen::<f32>() * 60.0 - 30.0,
+    ];
+    let rotation = [rng.gen::<f32>(), rng.gen::<f32>(), rng.gen::<f32>()];
+    let scale = [1.0, 1.0, 1.0];
+    let m = matrix::create_model_mat(translation, rotation, scale);
+    let n = (m.invert().unwrap()).transpose();
+    let color = [rng.gen::<f32>(), rng.gen::<f32>(), rng.gen::<f32>(), 1.0];
+    model_mat.push(*(m.as_ref()));
+    normal_mat.push(*(n.as_ref()));
+    color_vec.push(color);
+  }
+
+  let ground = matrix::create_initial_model_mat();
+  model_mat.push(*(ground.as_ref()));
+  normal_mat.push(*(ground.as_ref()));
+  color_vec.push([0.8, 0.8, 0.8, 1.0]);
+
+  Matrices {
+    model_mat,
+    normal_mat,
+    color_vec,
+  }
+}
@@ -0,0 +1,511 @@
+mod instance_defs;
+mod light_defs;
+
+use std::error::Error;
+
+use bytemuck::cast_slice;
+use cgmath::{InnerSpace, Matrix4, Point3, Vector3};
+use instance_defs::{Matrices, Model, Shapes, Vertex};
+use light_defs::DirectionLight;
+use wgpu::util::DeviceExt;
+use wgsim::app::App;
+use wgsim::camera::OrbitCamera;
+use wgsim::ctx::{DrawingContext, Size};
+use wgsim::matrix;
+use wgsim::ppl::RenderPipelineBuilder;
+use wgsim::render::{Render, RenderTarget};
+use wgsim::shadow::ShadowMap;
+use wgsim::util;
+use winit::event::WindowEvent;
+
+const NUM_CUBES: u32 = 50;
+const NUM_SPHERES: u32 = 50;
+const NUM_TORI: u32 = 50;
+
+const SHADOW_MAP_SIZE: u32 = 2048;
+// 物体と地面がすっぽり収まる球
+const SCENE_CENTER: [f32; 3] = [0., 8., 0.];
+const SCENE_RADIUS: f32 = 50.;
+
+// Light Uniform Buffer 内のオフセット（vec4 2つ分）
+const LIGHT_DIRECTION_OFFSET: wgpu::BufferAddress = 0;
+const LIGHT_COLOR_OFFSET: wgpu::BufferAddress = 16;
+
+pub fn run() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = Initial {
+    camera_position: Point3::new(40., 45., 60.),
+    look_direction: Point3::new(0., 0., 0.),
+    up_direction: Vector3::unit_y(),
+
+    light: DirectionLight {
+      direction: Point3::new(0.3, -1., 0.2).into(),
+      color: Point3::new(1., 1., 1.).into(),
+    },
+    ambient: 0.2,
+
+    animation_speed: 0.3,
+  };
+
+  let mut app: App<State> =
+    App::new("instanced_cube_sphere_torus - shadow_map", initial).with_msaa();
+  app.run()?;
+
+  Ok(())
+}
+
+struct Initial {
+  pub camera_position: Point3<f32>,
+  pub look_direction: Point3<f32>,
+  pub up_direction: Vector3<f32>,
+
+  pub light: DirectionLight,
+  pub ambient: f32,
+
+  pub animation_speed: f32,
+}
+
+struct State {
+  pipeline: wgpu::RenderPipeline,
+  shadow_pipeline: wgpu::RenderPipeline,
+
+  shapes: Shapes,
+  shadow_map: ShadowMap,
+
+  vert_bind_group: wgpu::BindGroup,
+  frag_bind_group: wgpu::BindGroup,
+  shadow_bind_group: wgpu::BindGroup,
+
+  msaa_texture_view: wgpu::TextureView,
+  depth_texture_view: wgpu::TextureView,
+
+  light_uniform_buffer: wgpu::Buffer,
+  vp_uniform_buffer: wgpu::Buffer,
+
+  project_mat: Matrix4<f32>,
+  camera: OrbitCamera,
+
+  light_direction: Vector3<f32>,
+  animation_speed: f32,
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    //
+    // shader
+    //
+
+    let vs_shader = ctx
+      .device
+      .create_shader_module(wgpu::include_wgsl!("./shader-vert.wgsl"));
+    let fs_shader = ctx
+      .device
+      .create_shader_module(wgpu::include_wgsl!("./shader-frag.wgsl"));
+    let shadow_shader = ctx
+      .device
+      .create_shader_module(wgpu::include_wgsl!("./shadow-depth.wgsl"));
+
+    //
+    // matrix
+    //
+
+    let objects_count = NUM_CUBES + NUM_SPHERES + NUM_TORI;
+    let aspect = ctx.aspect_ratio();
+
+    let Matrices {
+      model_mat,
+      normal_mat,
+      color_vec,
+    } = instance_defs::create_transform_mat_color(objects_count);
+
+    let view_mat = matrix::create_view_mat(
+      initial.camera_position,
+      initial.look_direction,
+      initial.up_direction,
+    );
+    let project_mat = matrix::create_projection_mat(aspect, true);
+    let vp_mat = project_mat * view_mat;
+
+    //
+    // uniform
+    //
+
+    let vp_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("View-Projection Buffer"),
+        contents: cast_slice(vp_mat.as_ref() as &[f32; 16]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+
+    let model_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Model Uniform Buffer"),
+        contents: cast_slice(model_mat.as_slice()),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+      });
+
+    let normal_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Normal Uniform Buffer"),
+        contents: cast_slice(normal_mat.as_slice()),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+      });
+
+    let color_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("color Uniform Buffer"),
+        contents: cast_slice(color_vec.as_slice()),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+      });
+
+    let light_uniform_buffer =
+      ctx.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Light Uniform Buffer"),
+        size: (std::mem::size_of::<[f32; 4]>() * 4) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+      });
+    ctx.queue.write_buffer(
+      &light_uniform_buffer,
+      LIGHT_DIRECTION_OFFSET,
+      cast_slice(&initial.light.direction),
+    );
+    ctx.queue.write_buffer(
+      &light_uniform_buffer,
+      LIGHT_COLOR_OFFSET,
+      cast_slice(&initial.light.color),
+    );
+
+    let ambient_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Ambient Uniform Buffer"),
+        contents: cast_slice(&[initial.ambient]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+
+    //
+    // shadow map
+    //
+
+    let light_direction = Vector3::from(initial.light.direction);
+
+    let mut shadow_map = ShadowMap::new(&ctx.device, SHADOW_MAP_SIZE);
+    shadow_map.set_light(
+      &ctx.queue,
+      light_direction,
+      SCENE_CENTER.into(),
+      SCENE_RADIUS,
+    );
+
+    //
+    // uniform bind group for vertex shader
+    //
+
+    let vert_bind_group_layout = util::create_bind_group_layout_for_buffer(
+      &ctx.device,
+      &[
+        wgpu::BufferBindingType::Uniform,
+        wgpu::BufferBindingType::Storage { read_only: true },
+        wgpu::BufferBindingType::Storage { read_only: true },
+        wgpu::BufferBindingType::Storage { read_only: true },
+      ],
+      &[
+        wgpu::ShaderStages::VERTEX,
+        wgpu::ShaderStages::VERTEX,
+        wgpu::ShaderStages::VERTEX,
+        wgpu::ShaderStages::VERTEX,
+      ],
+    );
+
+    let vert_bind_group = util::create_bind_group(
+      &ctx.device,
+      &vert_bind_group_layout,
+      &[
+        vp_uniform_buffer.as_entire_binding(),
+        model_uniform_buffer.as_entire_binding(),
+        normal_uniform_buffer.as_entire_binding(),
+        color_uniform_buffer.as_entire_binding(),
+      ],
+    );
+
+    //
+    // uniform bind group for fragment shader
+    //
+
+    let frag_bind_group_layout = util::create_bind_group_layout_for_buffer(
+      &ctx.device,
+      &[
+        wgpu::BufferBindingType::Uniform,
+        wgpu::BufferBindingType::Uniform,
+      ],
+      &[wgpu::ShaderStages::FRAGMENT, wgpu::ShaderStages::FRAGMENT],
+    );
+
+    let frag_bind_group = util::create_bind_group(
+      &ctx.device,
+      &frag_bind_group_layout,
+      &[
+        light_uniform_buffer.as_entire_binding(),
+        ambient_uniform_buffer.as_entire_binding(),
+      ],
+    );
+
+    //
+    // uniform bind group for shadow depth pass
+    //
+
+    // シャドウマップ自体は描画先なので、深度パスでは行列だけをバインドする
+    let shadow_bind_group_layout = util::create_bind_group_layout_for_buffer(
+      &ctx.device,
+      &[
+        wgpu::BufferBindingType::Uniform,
+        wgpu::BufferBindingType::Storage { read_only: true },
+      ],
+      &[wgpu::ShaderStages::VERTEX, wgpu::ShaderStages::VERTEX],
+    );
+
+    let shadow_bind_group = util::create_bind_group(
+      &ctx.device,
+      &shadow_bind_group_layout,
+      &[
+        shadow_map.light_vp_buffer().as_entire_binding(),
+        model_uniform_buffer.as_entire_binding(),
+      ],
+    );
+
+    //
+    // pipeline
+    //
+
+    let vertex_buffer_layout = [wgpu::VertexBufferLayout {
+      array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+      step_mode: wgpu::VertexStepMode::Vertex,
+      attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3],
+    }];
+
+    let pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Render Pipeline Layout"),
+        bind_group_layouts: &[
+          &vert_bind_group_layout,
+          &frag_bind_group_layout,
+          shadow_map.bind_group_layout(),
+        ],
+        push_constant_ranges: &[],
+      });
+
+    let pipeline = RenderPipelineBuilder::new(ctx)
+      .vs_shader(&vs_shader, "vs_main")
+      .fs_shader(&fs_shader, "fs_main")
+      .pipeline_layout(&pipeline_layout)
+      .vertex_buffer_layout(&vertex_buffer_layout)
+      .enable_depth_stencil(None)
+      .build();
+
+    let shadow_pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Shadow Pipeline Layout"),
+        bind_group_layouts: &[&shadow_bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let shadow_pipeline = RenderPipelineBuilder::new(ctx)
+      .vs_shader(&shadow_shader, "vs_main")
+      .pipeline_layout(&shadow_pipeline_layout)
+      .vertex_buffer_layout(&vertex_buffer_layout)
+      .enable_depth_stencil(Some(ShadowMap::depth_stencil_state()))
+      .sample_count(1)
+      .build();
+
+    //
+    // texture views
+    //
+
+    let msaa_texture_view = util::create_msaa_texture_view(ctx);
+    let depth_texture_view = util::create_depth_view(ctx);
+
+    //
+    // vertex and index buffers for objects
+    //
+
+    let shapes = instance_defs::create_object_buffers(&ctx.device);
+
+    Self {
+      pipeline,
+      shadow_pipeline,
+      shapes,
+      shadow_map,
+      vert_bind_group,
+      frag_bind_group,
+      shadow_bind_group,
+      msaa_texture_view,
+      depth_texture_view,
+      light_uniform_buffer,
+      vp_uniform_buffer,
+      project_mat,
+      camera: OrbitCamera::new(
+        initial.camera_position,
+        initial.look_direction,
+        initial.up_direction,
+      ),
+      light_direction,
+      animation_speed: initial.animation_speed,
+    }
+  }
+
+  fn resize(&mut self, ctx: &mut DrawingContext<'_>, size: Size) {
+    if size.width > 0 && size.height > 0 {
+      ctx.resize(size);
+
+      self.project_mat = matrix::create_projection_mat(
+        size.width as f32 / size.height as f32,
+        true,
+      );
+
+      self.depth_texture_view = util::create_depth_view(ctx);
+
+      if ctx.sample_count > 1 {
+        self.msaa_texture_view = util::create_msaa_texture_view(ctx);
+      }
+    }
+  }
+
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    self.camera.process_event(event)
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, dt: std::time::Duration) {
+    //
+    // 光源：斜め上から、ゆっくりと向きを変えながら照らす
+    //
+
+    let t = self.animation_speed * dt.as_secs_f32();
+    self.light_direction =
+      Vector3::new(0.5 * t.sin(), -1., 0.5 * t.cos()).normalize();
+
+    let light_direction: [f32; 3] = self.light_direction.into();
+    ctx.queue.write_buffer(
+      &self.light_uniform_buffer,
+      LIGHT_DIRECTION_OFFSET,
+      cast_slice(&light_direction),
+    );
+    self.shadow_map.set_light(
+      &ctx.queue,
+      self.light_direction,
+      SCENE_CENTER.into(),
+      SCENE_RADIUS,
+    );
+
+    let view_project_mat = self.project_mat * self.camera.view_mat();
+    let view_projection_ref: &[f32; 16] = view_project_mat.as_ref();
+    ctx.queue.write_buffer(
+      &self.vp_uniform_buffer,
+      0,
+      cast_slice(view_projection_ref),
+    );
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: RenderTarget,
+    sample_count: u32,
+  ) -> Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+    //
+    // 1. 光源から見た深度をシャドウマップに描く（地面は影を落とさない）
+    //
+
+    let mut shadow_pass = self.shadow_map.begin_depth_pass(encoder);
+    shadow_pass.set_pipeline(&self.shadow_pipeline);
+    shadow_pass.set_bind_group(0, &self.shadow_bind_group, &[]);
+    self.draw_shapes(&mut shadow_pass);
+    drop(shadow_pass);
+
+    //
+    // 2. シャドウマップを参照しながら、カメラから見た絵を描く
+    //
+
+    let (view, frame) = match target {
+      RenderTarget::Surface(surface) => {
+        let frame = surface.get_current_texture()?;
+        let view =
+          frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, Some(frame))
+      }
+      RenderTarget::Texture(texture) => {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, None)
+      }
+    };
+
+    let color_attach = util::create_color_attachment(&view);
+    let msaa_attach =
+      util::create_msaa_color_attachment(&view, &self.msaa_texture_view);
+    let color_attachment = if sample_count == 1 {
+      color_attach
+    } else {
+      msaa_attach
+    };
+    let depth_attachment =
+      util::create_depth_stencil_attachment(&self.depth_texture_view);
+
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render Pass"),
+        color_attachments: &[Some(color_attachment)],
+        depth_stencil_attachment: Some(depth_attachment),
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, &self.vert_bind_group, &[]);
+    render_pass.set_bind_group(1, &self.frag_bind_group, &[]);
+    render_pass.set_bind_group(2, self.shadow_map.bind_group(), &[]);
+
+    self.draw_shapes(&mut render_pass);
+
+    //
+    // draw ground
+    //
+    let ground_instance = NUM_CUBES + NUM_SPHERES + NUM_TORI;
+    draw_model(
+      &mut render_pass,
+      &self.shapes.ground,
+      ground_instance..ground_instance + 1,
+    );
+
+    drop(render_pass);
+
+    Ok(frame)
+  }
+}
+
+impl State {
+  fn draw_shapes<'p>(&'p self, render_pass: &mut wgpu::RenderPass<'p>) {
+    draw_model(render_pass, &self.shapes.cube, 0..NUM_CUBES);
+    draw_model(
+      render_pass,
+      &self.shapes.sphere,
+      NUM_CUBES..NUM_CUBES + NUM_SPHERES,
+    );
+    draw_model(
+      render_pass,
+      &self.shapes.torus,
+      NUM_CUBES + NUM_SPHERES..NUM_CUBES + NUM_SPHERES + NUM_TORI,
+    );
+  }
+}
+
+fn draw_model<'p>(
+  render_pass: &mut wgpu::RenderPass<'p>,
+  model: &'p Model,
+  instances: std::ops::Range<u32>,
+) {
+  render_pass.set_vertex_buffer(0, model.vertex_buffer.slice(..));
+  render_pass
+    .set_index_buffer(model.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+  render_pass.draw_indexed(0..model.index_count, 0, instances);
+}
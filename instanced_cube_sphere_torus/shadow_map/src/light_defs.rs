@@ -0,0 +1,4 @@
+pub struct DirectionLight {
+  pub direction: [f32; 3],
+  pub color: [f32; 3],
+}
@@ -0,0 +1,60 @@
+struct DirectionLight {
+  direction: vec3f,
+  color: vec3f,
+}
+
+@group(1) @binding(0) var<uniform> light: DirectionLight;
+@group(1) @binding(1) var<uniform> ambient: f32;
+
+@group(2) @binding(1) var shadow_map: texture_depth_2d;
+@group(2) @binding(2) var shadow_sampler: sampler_comparison;
+
+struct Input {
+  @location(0) v_position:vec4f,
+  @location(1) v_normal:vec4f,
+  @location(2) v_color: vec4f,
+  @location(3) v_light_position: vec4f,
+}
+
+//
+// 影：光源から見た深度と比べ、周囲3x3テクセルの平均で縁をぼかす（PCF）
+//
+fn shadow_visibility(light_position: vec4f) -> f32 {
+  let ndc = light_position.xyz / light_position.w;
+  // NDCは+Yが上、テクスチャ座標は+Yが下
+  let uv = vec2(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+  let texel = 1.0 / vec2f(textureDimensions(shadow_map));
+
+  var visibility = 0.0;
+  for (var y = -1; y <= 1; y++) {
+    for (var x = -1; x <= 1; x++) {
+      let offset = vec2f(f32(x), f32(y)) * texel;
+      visibility += textureSampleCompareLevel(shadow_map, shadow_sampler, uv + offset, ndc.z);
+    }
+  }
+  visibility /= 9.0;
+
+  // シャドウマップの外は影にしない
+  let outside = any(uv < vec2(0.0)) || any(uv > vec2(1.0)) || ndc.z > 1.0;
+  return select(visibility, 1.0, outside);
+}
+
+@fragment
+fn fs_main(in: Input) -> @location(0) vec4f {
+  let N = normalize(in.v_normal.xyz);
+  let L = normalize(-light.direction.xyz);
+
+  //
+  // 拡散反射光：Lambert拡散反射モデル
+  //
+  let diffuse = light.color * max(dot(N, L), 0.0);
+  
+  //
+  // 最終的な光：影になった部分には環境光だけが届く
+  //
+  let lig = diffuse * shadow_visibility(in.v_light_position) + ambient;
+
+  let final_color = in.v_color.rgb * lig;
+
+  return vec4<f32>(final_color.rgb, 1.0);
+}
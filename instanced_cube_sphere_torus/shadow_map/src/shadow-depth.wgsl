@@ -0,0 +1,13 @@
+@group(0) @binding(0) var<uniform> light_vp_mat: mat4x4f;
+@group(0) @binding(1) var<storage> model_mat: array<mat4x4f>;
+
+struct Input {
+  @builtin(instance_index) idx: u32,
+  @location(0) position: vec3f,
+}
+
+// 光源から見た深度だけを書くので、フラグメントシェーダーは不要
+@vertex
+fn vs_main(in: Input) -> @builtin(position) vec4f {
+  return light_vp_mat * model_mat[in.idx] * vec4(in.position, 1.0);
+}
@@ -1,5 +1,6 @@
 use std::{marker::PhantomData, ops::Deref};
 
+use anyhow::{anyhow, Result};
 use wgpu::util::DeviceExt;
 
 pub struct BufferBuilder<'a, C: bytemuck::Pod> {
@@ -40,6 +41,16 @@ impl<'a, C: bytemuck::Pod> BufferBuilder<'a, C> {
     self
   }
 
+  ///
+  /// Set the STORAGE and COPY_DST usages for per-instance data (model
+  /// matrices, colors, ...) read with `instance_index` and updated from the
+  /// CPU.
+  ///
+  pub fn instance(mut self) -> Self {
+    self.usages |= wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST;
+    self
+  }
+
   ///
   /// Set the UNIFORM usage.
   ///
@@ -107,6 +118,70 @@ impl<'a, C: bytemuck::Pod> BufferBuilder<'a, C> {
   pub fn build_empty(&self, device: &wgpu::Device, len: usize) -> Buffer<C> {
     Buffer::<C>::new_empty(device, self.usages, self.label, len)
   }
+
+  ///
+  /// Build a buffer with length that GPU results can be copied into and
+  /// read back. Adds the MAP_READ and COPY_DST usages; combining them with
+  /// anything else needs `Features::MAPPABLE_PRIMARY_BUFFERS`.
+  ///
+  pub fn build_readable(
+    &self,
+    device: &wgpu::Device,
+    len: usize,
+  ) -> ReadableBuffer<C> {
+    let usages =
+      self.usages | wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST;
+
+    ReadableBuffer {
+      buffer: Buffer::<C>::new_empty(device, usages, self.label, len),
+    }
+  }
+}
+
+///
+/// A buffer created by `BufferBuilder::build_readable`.
+///
+pub struct ReadableBuffer<C: bytemuck::Pod> {
+  buffer: Buffer<C>,
+}
+
+impl<C: bytemuck::Pod> ReadableBuffer<C> {
+  ///
+  /// Map the buffer and copy its content out as `T`. Submit the commands
+  /// writing to the buffer before calling this.
+  ///
+  pub async fn read<T: bytemuck::Pod>(
+    &self,
+    device: &wgpu::Device,
+  ) -> Result<Vec<T>> {
+    let buffer_slice = self.buffer.slice(..);
+    let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+      tx.send(result).unwrap();
+    });
+    device.poll(wgpu::Maintain::Wait);
+
+    match rx.receive().await {
+      Some(Ok(())) => {}
+      Some(Err(e)) => return Err(e.into()),
+      None => return Err(anyhow!("buffer was never mapped")),
+    }
+
+    let data = buffer_slice.get_mapped_range();
+    let result = bytemuck::pod_collect_to_vec(&data);
+    drop(data);
+    self.buffer.unmap();
+
+    Ok(result)
+  }
+}
+
+impl<C: bytemuck::Pod> Deref for ReadableBuffer<C> {
+  type Target = wgpu::Buffer;
+
+  fn deref(&self) -> &Self::Target {
+    &self.buffer
+  }
 }
 
 ///
@@ -122,6 +122,9 @@ pub struct RenderSet<'a> {
   pub strip_index_format: Option<wgpu::IndexFormat>,
   pub cull_mode: Option<wgpu::Face>,
   pub is_depth_stencil: bool,
+  pub depth_format: wgpu::TextureFormat,
+  pub depth_write_enabled: bool,
+  pub depth_compare: wgpu::CompareFunction,
   pub vs_entry: &'a str,
   pub fs_entry: &'a str,
 }
@@ -138,6 +141,9 @@ impl<'a> Default for RenderSet<'a> {
       strip_index_format: None,
       cull_mode: None,
       is_depth_stencil: true,
+      depth_format: wgpu::TextureFormat::Depth24Plus,
+      depth_write_enabled: true,
+      depth_compare: wgpu::CompareFunction::LessEqual,
       vs_entry: "vs_main",
       fs_entry: "fs_main",
     }
@@ -153,9 +159,9 @@ impl RenderSet<'_> {
 
     let depth_stencil = if self.is_depth_stencil {
       Some(wgpu::DepthStencilState {
-        format: wgpu::TextureFormat::Depth24Plus,
-        depth_write_enabled: true,
-        depth_compare: wgpu::CompareFunction::LessEqual,
+        format: self.depth_format,
+        depth_write_enabled: self.depth_write_enabled,
+        depth_compare: self.depth_compare,
         stencil: wgpu::StencilState::default(),
         bias: wgpu::DepthBiasState::default(),
       })
@@ -332,6 +338,13 @@ pub fn create_msaa_color_attachment<'a>(
 }
 
 pub fn create_depth_view(init: &WgpuContext) -> wgpu::TextureView {
+  create_depth_view_with_format(init, wgpu::TextureFormat::Depth24Plus)
+}
+
+pub fn create_depth_view_with_format(
+  init: &WgpuContext,
+  format: wgpu::TextureFormat,
+) -> wgpu::TextureView {
   let depth_texture = init.device.create_texture(&wgpu::TextureDescriptor {
     label: None,
     size: wgpu::Extent3d {
@@ -342,7 +355,7 @@ pub fn create_depth_view(init: &WgpuContext) -> wgpu::TextureView {
     mip_level_count: 1,
     sample_count: init.sample_count,
     dimension: wgpu::TextureDimension::D2,
-    format: wgpu::TextureFormat::Depth24Plus,
+    format,
     usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
     view_formats: &[],
   });
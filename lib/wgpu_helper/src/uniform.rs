@@ -184,6 +184,113 @@ impl<C: bytemuck::Pod> binding::BindGroupContent for Uniform<C> {
   }
 }
 
+///
+/// A uniform buffer holding exactly one `C`, written without a CPU-side copy.
+///
+/// `C` has to match the WGSL uniform layout (e.g. pad a `vec3` to 16 bytes),
+/// so its size is checked to be a multiple of 16 in debug builds.
+///
+pub struct TypedUniform<C: bytemuck::Pod> {
+  buffer: Buffer<C>,
+}
+
+impl<C: bytemuck::Pod> TypedUniform<C> {
+  const SIZE: u64 = std::mem::size_of::<C>() as u64;
+
+  pub fn new(src: &C, device: &wgpu::Device) -> Self {
+    Self::check_layout();
+
+    let buffer = BufferBuilder::new()
+      .uniform()
+      .copy_dst()
+      .set_label(&format!("UniformBuffer: {}", UniformVec::<C>::name()))
+      .build(device, std::slice::from_ref(src));
+
+    Self { buffer }
+  }
+
+  pub fn new_empty(device: &wgpu::Device) -> Self {
+    Self::check_layout();
+
+    let buffer = BufferBuilder::new()
+      .uniform()
+      .copy_dst()
+      .set_label(&format!("UniformBuffer: {}", UniformVec::<C>::name()))
+      .build_empty(device, 1);
+
+    Self { buffer }
+  }
+
+  pub fn buffer(&self) -> &wgpu::Buffer {
+    &self.buffer
+  }
+
+  pub fn write(&self, queue: &wgpu::Queue, value: &C) {
+    queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(value));
+  }
+
+  ///
+  /// Writes a single field at `offset` bytes, e.g.
+  /// `std::mem::offset_of!(C, field)`.
+  ///
+  /// A field of up to 16 bytes must not straddle a 16-byte boundary, and
+  /// larger ones (matrices, arrays) must start on one.
+  ///
+  pub fn write_field(&self, queue: &wgpu::Queue, offset: u64, bytes: &[u8]) {
+    let len = bytes.len() as u64;
+    debug_assert!(
+      offset + len <= Self::SIZE,
+      "field at {}..{} is outside the {}-byte uniform",
+      offset,
+      offset + len,
+      Self::SIZE
+    );
+    debug_assert!(
+      offset.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)
+        && len.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+      "field at {} with {} bytes is not 4-byte aligned",
+      offset,
+      len
+    );
+    debug_assert!(
+      if len <= 16 {
+        len == 0 || offset / 16 == (offset + len - 1) / 16
+      } else {
+        offset.is_multiple_of(16)
+      },
+      "field at {} with {} bytes breaks the 16-byte uniform layout",
+      offset,
+      len
+    );
+
+    queue.write_buffer(&self.buffer, offset, bytes);
+  }
+
+  fn check_layout() {
+    debug_assert!(
+      Self::SIZE.is_multiple_of(16),
+      "{} is {} bytes, uniforms must be padded to a multiple of 16",
+      UniformVec::<C>::name(),
+      Self::SIZE
+    );
+  }
+}
+
+impl<C: bytemuck::Pod> binding::BindGroupContent for TypedUniform<C> {
+  fn entries(
+    visibility: wgpu::ShaderStages,
+  ) -> Vec<binding::BindGroupLayoutEntry> {
+    vec![binding::BindGroupLayoutEntry::new(
+      visibility,
+      binding::wgsl::uniform(),
+    )]
+  }
+
+  fn resources<'br>(&'br self) -> Vec<wgpu::BindingResource<'br>> {
+    vec![self.buffer.as_entire_binding()]
+  }
+}
+
 ///
 /// A uniform inside a BindGroup
 ///
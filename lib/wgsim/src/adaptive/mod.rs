@@ -0,0 +1,3 @@
+mod resolution;
+
+pub use resolution::AdaptiveResolution;
@@ -0,0 +1,263 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{ctx::DrawingContext, util};
+
+const DEFAULT_MIN_SCALE: f32 = 0.25;
+const MAX_SCALE: f32 = 1.0;
+const SCALE_STEP: f32 = 0.1;
+
+/// Weight of the newest frame in the moving average of the frame time.
+const SMOOTHING: f32 = 0.1;
+/// Frames to wait after a scale change before judging the new scale.
+const COOLDOWN_FRAMES: u32 = 30;
+/// Scale down above `target * OVER_BUDGET`, up below `target * UNDER_BUDGET`.
+const OVER_BUDGET: f32 = 1.05;
+const UNDER_BUDGET: f32 = 0.7;
+
+/// Renders the scene into an offscreen target smaller than the surface and
+/// upscales it, adjusting the render scale to keep the frame time near a
+/// target.
+///
+/// The frame time is the time from `queue.submit` until the GPU reports the
+/// work done (`Queue::on_submitted_work_done`). It includes time spent
+/// waiting behind earlier frames, so it rises as soon as the GPU falls
+/// behind, but it is only a proxy for the GPU cost of the frame.
+///
+/// Cost roughly follows the pixel count, i.e. `scale²`. To avoid
+/// oscillating between two scales, the scale only changes
+///
+/// - by 0.1 at a time, clamped to `[min_scale, 1]`
+/// - when the smoothed frame time leaves the band between 70% and 105% of
+///   the target (hysteresis)
+/// - after 30 frames at the current scale
+///
+/// Only work that scales with `DrawingContext::size()` gets cheaper; passes
+/// sized by something else (e.g. an image) cost the same at any scale.
+pub struct AdaptiveResolution {
+  target_ms: f32,
+  min_scale: f32,
+  scale: f32,
+  smoothed_ms: Option<f32>,
+  frames_since_change: u32,
+  frame_time: Arc<Mutex<Option<Duration>>>,
+
+  pipeline: wgpu::RenderPipeline,
+  bind_group_layout: wgpu::BindGroupLayout,
+  sampler: wgpu::Sampler,
+  bind_group: wgpu::BindGroup,
+  texture: wgpu::Texture,
+}
+
+impl AdaptiveResolution {
+  pub fn new(ctx: &DrawingContext, target_ms: f32) -> Self {
+    let shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./upscale.wgsl"));
+
+    let sampler = ctx.device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some("upscale sampler"),
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      ..Default::default()
+    });
+
+    let bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        wgpu::BindingType::Texture {
+          sample_type: wgpu::TextureSampleType::Float { filterable: true },
+          view_dimension: wgpu::TextureViewDimension::D2,
+          multisampled: false,
+        },
+      ],
+      &[wgpu::ShaderStages::FRAGMENT, wgpu::ShaderStages::FRAGMENT],
+    );
+
+    let pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Upscale Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let pipeline =
+      ctx.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Upscale Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &[Some(ctx.surface_format().into())],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+      });
+
+    let texture = create_target_texture(ctx);
+    let bind_group =
+      create_bind_group(ctx, &bind_group_layout, &sampler, &texture);
+
+    Self {
+      target_ms,
+      min_scale: DEFAULT_MIN_SCALE,
+      scale: ctx.render_scale(),
+      smoothed_ms: None,
+      frames_since_change: 0,
+      frame_time: Arc::new(Mutex::new(None)),
+      pipeline,
+      bind_group_layout,
+      sampler,
+      bind_group,
+      texture,
+    }
+  }
+
+  /// Lowest render scale, 0.25 by default.
+  pub fn with_min_scale(mut self, min_scale: f32) -> Self {
+    self.min_scale = min_scale.clamp(SCALE_STEP, MAX_SCALE);
+    self
+  }
+
+  pub fn scale(&self) -> f32 {
+    self.scale
+  }
+
+  /// Offscreen target the renderer draws into, `DrawingContext::size()` in
+  /// the surface format (a post effect writes into it in that case).
+  pub fn texture(&self) -> &wgpu::Texture {
+    &self.texture
+  }
+
+  /// Recreates the offscreen target after the surface or scale changed.
+  pub fn resize(&mut self, ctx: &DrawingContext) {
+    self.texture = create_target_texture(ctx);
+    self.bind_group = create_bind_group(
+      ctx,
+      &self.bind_group_layout,
+      &self.sampler,
+      &self.texture,
+    );
+  }
+
+  /// Acquires the next surface texture and stretches the offscreen target
+  /// over it.
+  pub fn upscale(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    surface: &wgpu::Surface,
+  ) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
+    let frame = surface.get_current_texture()?;
+    let view =
+      frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Upscale Pass"),
+        color_attachments: &[Some(util::create_color_attachment(&view))],
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, &self.bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+    drop(render_pass);
+
+    Ok(frame)
+  }
+
+  /// Call right after the frame was submitted at `submitted_at`.
+  pub fn track_submission(&self, queue: &wgpu::Queue, submitted_at: Instant) {
+    let frame_time = self.frame_time.clone();
+    queue.on_submitted_work_done(move || {
+      *frame_time.lock().unwrap() = Some(submitted_at.elapsed());
+    });
+  }
+
+  /// Folds the latest measurement into the average and returns the new
+  /// scale when it should change.
+  pub fn next_scale(&mut self) -> Option<f32> {
+    let measured = self.frame_time.lock().unwrap().take()?;
+    let measured_ms = measured.as_secs_f32() * 1000.;
+
+    let smoothed_ms = match self.smoothed_ms {
+      Some(ms) => ms + (measured_ms - ms) * SMOOTHING,
+      None => measured_ms,
+    };
+    self.smoothed_ms = Some(smoothed_ms);
+
+    self.frames_since_change += 1;
+    if self.frames_since_change < COOLDOWN_FRAMES {
+      return None;
+    }
+
+    let scale = if smoothed_ms > self.target_ms * OVER_BUDGET {
+      self.scale - SCALE_STEP
+    } else if smoothed_ms < self.target_ms * UNDER_BUDGET {
+      self.scale + SCALE_STEP
+    } else {
+      return None;
+    };
+    let scale = scale.clamp(self.min_scale, MAX_SCALE);
+    if (scale - self.scale).abs() < f32::EPSILON {
+      return None;
+    }
+
+    log::info!("render scale: {:.2} ({:.1} ms)", scale, smoothed_ms);
+
+    // 新しいスケールでの計測をやり直す
+    self.scale = scale;
+    self.smoothed_ms = None;
+    self.frames_since_change = 0;
+
+    Some(scale)
+  }
+}
+
+fn create_target_texture(ctx: &DrawingContext) -> wgpu::Texture {
+  let size = ctx.size();
+
+  ctx.device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("Adaptive Resolution Target"),
+    size: wgpu::Extent3d {
+      width: size.width,
+      height: size.height,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format: ctx.surface_format(),
+    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+      | wgpu::TextureUsages::TEXTURE_BINDING,
+    view_formats: &[],
+  })
+}
+
+fn create_bind_group(
+  ctx: &DrawingContext,
+  layout: &wgpu::BindGroupLayout,
+  sampler: &wgpu::Sampler,
+  texture: &wgpu::Texture,
+) -> wgpu::BindGroup {
+  let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+  util::create_bind_group(
+    &ctx.device,
+    layout,
+    &[
+      wgpu::BindingResource::Sampler(sampler),
+      wgpu::BindingResource::TextureView(&view),
+    ],
+  )
+}
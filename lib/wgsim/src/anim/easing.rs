@@ -0,0 +1,67 @@
+use std::f32::consts::PI;
+
+/// Shape of the interpolation between two keyframes. Every easing maps 0 to
+/// 0, 0.5 to 0.5 and 1 to 1; they only differ in speed along the way.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+  /// Constant speed.
+  #[default]
+  Linear,
+  /// Sine ease-in-out: starts and stops smoothly.
+  EaseInOut,
+  /// Cubic ease-in-out: like `EaseInOut`, with a sharper middle.
+  Cubic,
+}
+
+impl Easing {
+  /// Eased progress for `t` in `[0, 1]`; `t` outside is clamped.
+  pub fn apply(self, t: f32) -> f32 {
+    let t = t.clamp(0., 1.);
+
+    match self {
+      Easing::Linear => t,
+      Easing::EaseInOut => 0.5 - 0.5 * (PI * t).cos(),
+      Easing::Cubic => {
+        if t < 0.5 {
+          4. * t * t * t
+        } else {
+          1. - (-2. * t + 2.).powi(3) / 2.
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EASINGS: [Easing; 3] =
+    [Easing::Linear, Easing::EaseInOut, Easing::Cubic];
+
+  #[test]
+  fn every_easing_fixes_start_middle_and_end() {
+    for easing in EASINGS {
+      for t in [0., 0.5, 1.] {
+        let eased = easing.apply(t);
+        assert!((eased - t).abs() < 1e-6, "{:?}({}) = {}", easing, t, eased);
+      }
+    }
+  }
+
+  #[test]
+  fn ease_in_out_starts_slower_than_linear() {
+    for easing in [Easing::EaseInOut, Easing::Cubic] {
+      assert!(easing.apply(0.25) < 0.25, "{:?}", easing);
+      assert!(easing.apply(0.75) > 0.75, "{:?}", easing);
+    }
+  }
+
+  #[test]
+  fn clamps_outside_the_unit_range() {
+    for easing in EASINGS {
+      assert_eq!(easing.apply(-1.), 0.);
+      assert_eq!(easing.apply(2.), 1.);
+    }
+  }
+}
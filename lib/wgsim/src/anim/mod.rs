@@ -0,0 +1,5 @@
+mod easing;
+mod timeline;
+
+pub use easing::Easing;
+pub use timeline::{Interpolate, Timeline};
@@ -0,0 +1,146 @@
+use cgmath::*;
+
+use super::Easing;
+
+/// Values a `Timeline` can blend: `t` is 0 at `a` and 1 at `b`.
+pub trait Interpolate: Copy {
+  fn interpolate(a: Self, b: Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+  fn interpolate(a: Self, b: Self, t: f32) -> Self {
+    a + (b - a) * t
+  }
+}
+
+impl Interpolate for Vector3<f32> {
+  fn interpolate(a: Self, b: Self, t: f32) -> Self {
+    a.lerp(b, t)
+  }
+}
+
+impl Interpolate for Quaternion<f32> {
+  /// Spherical, along the shorter arc.
+  fn interpolate(a: Self, b: Self, t: f32) -> Self {
+    // q と -q は同じ回転なので、内積が負なら反転して近い方の弧をたどる
+    let b = if a.dot(b) < 0. { -b } else { b };
+    a.slerp(b, t)
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Keyframe<T> {
+  time: f32,
+  value: T,
+  easing: Easing,
+}
+
+/// Keyframed value over time in seconds, e.g. a camera position, a light
+/// color or a rotation.
+///
+/// Build it once (in `Render::new`) and `sample` it with the elapsed time
+/// in `update`. The result depends on the time only, so exported GIFs come
+/// out the same on every run.
+///
+/// Before the first keyframe the first value holds, after the last one the
+/// last value, unless the timeline `looped`.
+#[derive(Debug, Clone)]
+pub struct Timeline<T: Interpolate> {
+  keyframes: Vec<Keyframe<T>>,
+  looped: bool,
+}
+
+impl<T: Interpolate> Timeline<T> {
+  /// A timeline holding `value` from time 0.
+  pub fn new(value: T) -> Self {
+    Self {
+      keyframes: vec![Keyframe {
+        time: 0.,
+        value,
+        easing: Easing::Linear,
+      }],
+      looped: false,
+    }
+  }
+
+  /// Adds a keyframe at `time`; `easing` shapes the segment arriving at it.
+  /// Keyframes may be added in any order. One at the time of an existing
+  /// keyframe replaces it.
+  pub fn key(mut self, time: f32, value: T, easing: Easing) -> Self {
+    let time = time.max(0.);
+    let keyframe = Keyframe {
+      time,
+      value,
+      easing,
+    };
+
+    match self.keyframes.binary_search_by(|k| k.time.total_cmp(&time)) {
+      Ok(i) => self.keyframes[i] = keyframe,
+      Err(i) => self.keyframes.insert(i, keyframe),
+    }
+    self
+  }
+
+  /// Repeats the timeline from time 0 after the last keyframe. Make the last
+  /// value match the first for a seamless loop.
+  pub fn looped(mut self) -> Self {
+    self.looped = true;
+    self
+  }
+
+  /// Time of the last keyframe.
+  pub fn duration(&self) -> f32 {
+    self.keyframes.last().map_or(0., |k| k.time)
+  }
+
+  pub fn sample(&self, time: f32) -> T {
+    let duration = self.duration();
+    let time = if self.looped && duration > 0. {
+      time.rem_euclid(duration)
+    } else {
+      time
+    };
+
+    // time より後の最初のキーフレーム
+    let next = self.keyframes.partition_point(|k| k.time <= time);
+    if next == 0 {
+      return self.keyframes[0].value;
+    }
+    if next == self.keyframes.len() {
+      return self.keyframes[next - 1].value;
+    }
+
+    let a = &self.keyframes[next - 1];
+    let b = &self.keyframes[next];
+    let t = (time - a.time) / (b.time - a.time);
+    T::interpolate(a.value, b.value, b.easing.apply(t))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn samples_keyframes_and_the_eased_middle() {
+    let timeline = Timeline::new(0.).key(2., 10., Easing::EaseInOut);
+
+    assert_eq!(timeline.sample(0.), 0.);
+    assert!((timeline.sample(1.) - 5.).abs() < 1e-5);
+    assert_eq!(timeline.sample(2.), 10.);
+    // 範囲外では端の値を保つ
+    assert_eq!(timeline.sample(-1.), 0.);
+    assert_eq!(timeline.sample(3.), 10.);
+  }
+
+  #[test]
+  fn looped_timeline_wraps_around() {
+    let timeline = Timeline::new(0.)
+      .key(1., 4., Easing::Linear)
+      .key(2., 0., Easing::Linear)
+      .looped();
+
+    assert_eq!(timeline.sample(2.5), timeline.sample(0.5));
+    assert_eq!(timeline.sample(0.5), 2.);
+  }
+}
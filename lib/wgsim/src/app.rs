@@ -1,20 +1,36 @@
-use std::{error::Error, sync::Arc};
+use std::{error::Error, path::Path, sync::Arc};
 
 use winit::{
   application::ApplicationHandler,
   dpi::LogicalSize,
-  event::{ElementState, KeyEvent, StartCause, WindowEvent},
+  event::{
+    DeviceEvent, DeviceId, ElementState, KeyEvent, StartCause, WindowEvent,
+  },
   event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
   keyboard::{KeyCode, PhysicalKey},
-  window::{Window, WindowId},
+  window::{CursorGrabMode, Window, WindowId},
 };
 
+#[cfg(feature = "gamepad")]
+use crate::input::Gamepad;
+use crate::input::{PointerEvent, PointerTracker};
+#[cfg(feature = "ui")]
+use crate::ui::EguiLayer;
 use crate::{
-  ctx::DrawingContext,
+  adaptive::AdaptiveResolution,
+  ctx::{ContextError, ContextOptions, DrawingContext, Size},
+  export,
+  fps::FpsOverlay,
+  post::{PostEffect, PostProcess},
   render::{Render, RenderTarget},
+  shader::ShaderWatcher,
   surface_cfg::SurfaceConfigBuilder,
 };
 
+/// Draws the egui windows each frame, see `App::with_ui`.
+#[cfg(feature = "ui")]
+type UiCallback<'a, R> = Box<dyn FnMut(&egui::Context, &mut R) + 'a>;
+
 pub struct App<'a, R>
 where
   R: Render<'a>,
@@ -24,11 +40,38 @@ where
   window_size: Option<LogicalSize<u32>>,
   initial: R::Initial,
   ctx: Option<DrawingContext<'a>>,
+  context_error: Option<ContextError>,
   surface_cfg_builder: Option<&'a SurfaceConfigBuilder<'a>>,
+  context_options: ContextOptions,
   sample_count: u32,
+  clear_color: wgpu::Color,
+  present_mode: Option<wgpu::PresentMode>,
+  surface_format: Option<wgpu::TextureFormat>,
+  prefer_linear: bool,
+  transparent: bool,
+  cursor_grab: bool,
+  simulate_device_loss_key: bool,
+  adaptive_target_ms: Option<f32>,
+  adaptive: Option<AdaptiveResolution>,
+  fps_overlay_font: Option<&'static [u8]>,
+  fps_overlay: Option<FpsOverlay>,
+  /// `init` に渡すまでのエフェクト
+  post_effect: Option<Box<dyn PostEffect>>,
+  post: Option<PostProcess>,
+  shader_watcher: Option<ShaderWatcher>,
+  #[cfg(feature = "gamepad")]
+  gamepad: Option<Gamepad>,
+  pointer: PointerTracker,
+  #[cfg(feature = "ui")]
+  ui: Option<UiCallback<'a, R>>,
+  #[cfg(feature = "ui")]
+  egui_layer: Option<EguiLayer>,
   renderer: Option<R>,
   render_start_time: Option<std::time::Instant>,
   update_interval: Option<std::time::Duration>,
+  resize_debounce: Option<std::time::Duration>,
+  /// 最後の `Resized` の大きさと時刻
+  pending_resize: Option<(Size, std::time::Instant)>,
   need_redraw: bool,
 }
 
@@ -43,11 +86,36 @@ where
       window_size: None,
       initial,
       sample_count: 1,
+      clear_color: wgpu::Color::BLACK,
+      present_mode: None,
+      surface_format: None,
+      prefer_linear: false,
+      transparent: false,
+      cursor_grab: false,
+      simulate_device_loss_key: false,
+      adaptive_target_ms: None,
+      adaptive: None,
+      fps_overlay_font: None,
+      fps_overlay: None,
+      post_effect: None,
+      post: None,
+      shader_watcher: None,
+      #[cfg(feature = "gamepad")]
+      gamepad: None,
+      pointer: PointerTracker::default(),
+      #[cfg(feature = "ui")]
+      ui: None,
+      #[cfg(feature = "ui")]
+      egui_layer: None,
       ctx: None,
+      context_error: None,
       surface_cfg_builder: None,
+      context_options: ContextOptions::default(),
       renderer: None,
       render_start_time: None,
       update_interval: None,
+      resize_debounce: None,
+      pending_resize: None,
       need_redraw: true,
     }
   }
@@ -62,11 +130,152 @@ where
     self
   }
 
+  /// Waits until no `Resized` event has arrived for `debounce` before
+  /// calling `Render::resize`, so dragging the window edge does not rebuild
+  /// the size-dependent textures at every intermediate size. Until then the
+  /// previous frame size keeps being drawn.
+  pub fn with_resize_debounce(mut self, debounce: std::time::Duration) -> Self {
+    self.resize_debounce = Some(debounce);
+    self
+  }
+
   pub fn with_msaa(mut self) -> Self {
     self.sample_count = 4;
     self
   }
 
+  /// Stored as `DrawingContext::clear_color` for the renderer to clear to.
+  pub fn with_clear_color(mut self, color: wgpu::Color) -> Self {
+    self.clear_color = color;
+    self
+  }
+
+  /// `PresentMode::Immediate` (or `Mailbox`) runs uncapped for
+  /// benchmarking. Falls back to a supported mode, see `PresentModePref`.
+  pub fn with_present_mode(mut self, mode: wgpu::PresentMode) -> Self {
+    self.present_mode = Some(mode);
+    self
+  }
+
+  /// Overrides the surface format, which is otherwise the first sRGB format
+  /// the surface offers. An unsupported format is reported with a warning
+  /// and replaced by that default.
+  ///
+  /// Everything drawn straight to the surface has to use the chosen
+  /// format: the pipeline builder, `util::create_msaa_texture_view` and the
+  /// other helpers read it from `DrawingContext::format()`, and so should
+  /// color targets created by hand. Depth textures keep their own format.
+  pub fn with_surface_format(mut self, format: wgpu::TextureFormat) -> Self {
+    self.surface_format = Some(format);
+    self
+  }
+
+  /// Picks a linear surface format instead of an sRGB one, for shaders that
+  /// output already encoded colors. Ignored with `with_surface_format`.
+  pub fn prefer_linear(mut self) -> Self {
+    self.prefer_linear = true;
+    self
+  }
+
+  /// Makes the window background see-through, so only what the renderer
+  /// draws is shown over the desktop. The renderer should clear with an alpha
+  /// of 0 and output premultiplied colors.
+  ///
+  /// Whether this works depends on the platform and backend: X11 needs a
+  /// running compositor, and surfaces that only support
+  /// `CompositeAlphaMode::Opaque` (e.g. DX12 swapchains) stay opaque.
+  pub fn with_transparent_window(mut self) -> Self {
+    self.transparent = true;
+    self
+  }
+
+  /// Hides the cursor and keeps it inside the window, for mouse-look
+  /// cameras that read `Render::process_device_event`. Esc still quits.
+  pub fn with_cursor_grab(mut self) -> Self {
+    self.cursor_grab = true;
+    self
+  }
+
+  /// Polls a gamepad every frame and passes its state to
+  /// `Render::process_gamepad`. Controllers connected later are picked up.
+  #[cfg(feature = "gamepad")]
+  pub fn with_gamepad(mut self) -> Self {
+    match Gamepad::new() {
+      Ok(gamepad) => self.gamepad = Some(gamepad),
+      Err(e) => eprintln!("Gamepad input is not available: {}", e),
+    }
+    self
+  }
+
+  /// Draws an egui UI over every frame, built by `ui` with mutable access
+  /// to the renderer, e.g. `ui::material_sliders` in an `egui::Window`.
+  ///
+  /// Window events go to egui first; those it uses (a drag on a slider,
+  /// typing into a field) do not reach `Render::process_event`.
+  #[cfg(feature = "ui")]
+  pub fn with_ui(
+    mut self,
+    ui: impl FnMut(&egui::Context, &mut R) + 'a,
+  ) -> Self {
+    self.ui = Some(Box::new(ui));
+    self
+  }
+
+  /// Watches the WGSL files in `paths` and calls `Render::reload_shaders`
+  /// before the next frame whenever one of them is saved.
+  pub fn with_shader_watch(mut self, paths: &[&Path]) -> Self {
+    match ShaderWatcher::new(paths) {
+      Ok(watcher) => self.shader_watcher = Some(watcher),
+      Err(e) => eprintln!("Shaders cannot be watched: {}", e),
+    }
+    self
+  }
+
+  /// Binds F10 to destroy the device, to check that the renderer survives
+  /// a device loss.
+  pub fn with_device_loss_simulation(mut self) -> Self {
+    self.simulate_device_loss_key = true;
+    self
+  }
+
+  /// Renders into an offscreen target smaller than the window and upscales
+  /// it, lowering the resolution while frames take longer than `target_ms`
+  /// and raising it again when there is headroom. See `AdaptiveResolution`
+  /// for how the frame time is measured and the scale is damped.
+  ///
+  /// The renderer sees the reduced size through `DrawingContext::size()`
+  /// and is resized whenever the scale changes.
+  pub fn with_adaptive_resolution(mut self, target_ms: f32) -> Self {
+    self.adaptive_target_ms = Some(target_ms);
+    self
+  }
+
+  /// Shows the frame rate in the top-right corner, drawn with `font` on top
+  /// of whatever the renderer drew, right before the frame is presented.
+  pub fn with_fps_overlay(mut self, font: &'static [u8]) -> Self {
+    self.fps_overlay_font = Some(font);
+    self
+  }
+
+  /// Renders the scene into an offscreen texture and runs `effect` on it
+  /// before presenting, e.g. a `post::BloomPass`.
+  ///
+  /// The renderer draws in `effect.input_format()` (`Rgba16Float` for
+  /// bloom), which `DrawingContext::format()` returns from then on; use
+  /// `DrawingContext::surface_format()` for what is drawn onto the surface.
+  /// Screenshots read the offscreen texture, so they fail for float formats.
+  pub fn with_post_effect(mut self, effect: Box<dyn PostEffect>) -> Self {
+    self.post_effect = Some(effect);
+    self
+  }
+
+  /// Selects the backend and GPU, e.g. to compare backends on the same
+  /// machine or force the discrete GPU of a laptop.
+  pub fn with_context_options(mut self, options: ContextOptions) -> Self {
+    self.context_options = options;
+    self
+  }
+
   pub fn with_surface_cfg_builder(
     mut self,
     builder: &'a SurfaceConfigBuilder<'a>,
@@ -79,7 +288,11 @@ where
     let event_loop = EventLoop::builder().build()?;
     event_loop.run_app(self)?;
 
-    Ok(())
+    // ウィンドウイベントの中ではエラーを返せないので、ループを抜けてから返す
+    match self.context_error.take() {
+      Some(e) => Err(e.into()),
+      None => Ok(()),
+    }
   }
 
   fn window(&self) -> Option<&Window> {
@@ -89,35 +302,134 @@ where
     }
   }
 
-  async fn init(&mut self, window: Arc<Window>) {
-    let surface_cfg_builder = match self.surface_cfg_builder {
-      Some(builder) => builder,
-      None => &SurfaceConfigBuilder::new(),
+  async fn init(&mut self, window: Arc<Window>) -> Result<(), ContextError> {
+    let mut surface_cfg_builder = match self.surface_cfg_builder {
+      Some(builder) => builder.clone(),
+      None => SurfaceConfigBuilder::new(),
     };
+    if let Some(format) = self.surface_format {
+      surface_cfg_builder = surface_cfg_builder.format(format);
+    }
+    if self.prefer_linear {
+      surface_cfg_builder = surface_cfg_builder.prefer_linear();
+    }
+    if let Some(mode) = self.present_mode {
+      surface_cfg_builder = surface_cfg_builder.present_mode(mode);
+    }
+    if self.transparent {
+      surface_cfg_builder = surface_cfg_builder.transparent();
+    }
 
-    let ctx = DrawingContext::new_for_surface(window, &surface_cfg_builder)
-      .await
-      .with_sample_count(self.sample_count);
+    #[cfg(feature = "ui")]
+    let ui_window = window.clone();
+
+    let mut ctx = DrawingContext::new_for_surface_with(
+      window,
+      &surface_cfg_builder,
+      self.context_options,
+    )
+    .await?
+    .with_sample_count(self.sample_count)
+    .with_clear_color(self.clear_color);
+    self.adaptive = self
+      .adaptive_target_ms
+      .map(|target_ms| AdaptiveResolution::new(&ctx, target_ms));
+    self.fps_overlay =
+      self.fps_overlay_font.map(|font| FpsOverlay::new(&ctx, font));
+    #[cfg(feature = "ui")]
+    if self.ui.is_some() {
+      self.egui_layer =
+        Some(EguiLayer::new(&ctx.device, ui_window, ctx.surface_format()));
+    }
+    if let Some(effect) = self.post_effect.take() {
+      // ここから先に作るパイプラインは、サーフェスではなくオフスクリーンの形式で描く
+      ctx.set_render_format(Some(effect.input_format()));
+      self.post = Some(PostProcess::new(&ctx, effect));
+    }
     self.ctx = Some(ctx);
 
     let renderer = R::new(self.ctx.as_ref().unwrap(), &self.initial).await;
     self.renderer = Some(renderer);
+
+    Ok(())
+  }
+
+  fn resize(&mut self, size: Size) {
+    let (Some(renderer), Some(ctx)) = (&mut self.renderer, &mut self.ctx)
+    else {
+      return;
+    };
+
+    renderer.resize(ctx, size);
+    if let Some(adaptive) = &mut self.adaptive {
+      adaptive.resize(ctx);
+    }
+    if let Some(post) = &mut self.post {
+      post.resize(ctx);
+    }
+    if let Some(fps_overlay) = &mut self.fps_overlay {
+      fps_overlay.resize(ctx);
+    }
+  }
+
+  /// A lost device cannot be recovered, so the context is rebuilt from
+  /// scratch and `Render::new` is run again with the stored `Initial`.
+  /// The elapsed time keeps counting from the original start.
+  fn recover_from_device_loss(&mut self) -> Result<(), ContextError> {
+    let window = match &self.window {
+      Some(window) => window.clone(),
+      None => return Ok(()),
+    };
+
+    log::warn!("Recreating the drawing context after device loss");
+
+    // サーフェスは1つのウィンドウに1つまでなので、古いものを先に破棄する
+    self.renderer = None;
+    self.adaptive = None;
+    self.fps_overlay = None;
+    #[cfg(feature = "ui")]
+    {
+      self.egui_layer = None;
+    }
+    // エフェクトの設定は残して、リソースだけ新しいデバイスで作り直す
+    if let Some(post) = self.post.take() {
+      self.post_effect = Some(post.into_effect());
+    }
+    self.ctx = None;
+
+    pollster::block_on(self.init(window))
   }
 }
 
 impl<'a, R: Render<'a>> ApplicationHandler for App<'a, R> {
   fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-    let mut window_attributes =
-      Window::default_attributes().with_title(self.window_title);
+    let mut window_attributes = Window::default_attributes()
+      .with_title(self.window_title)
+      .with_transparent(self.transparent);
 
     if let Some(window_size) = self.window_size {
       window_attributes = window_attributes.with_max_inner_size(window_size);
     }
 
     let window = event_loop.create_window(window_attributes).unwrap();
+    if self.cursor_grab {
+      // Locked はプラットフォームによって未対応なので、Confined で代用する
+      let grabbed = window
+        .set_cursor_grab(CursorGrabMode::Locked)
+        .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined));
+      if let Err(e) = grabbed {
+        eprintln!("Cursor cannot be grabbed: {}", e);
+      }
+      window.set_cursor_visible(false);
+    }
     self.window = Some(Arc::new(window));
 
-    pollster::block_on(self.init(self.window.as_ref().unwrap().clone()));
+    let window = self.window.as_ref().unwrap().clone();
+    if let Err(e) = pollster::block_on(self.init(window)) {
+      self.context_error = Some(e);
+      event_loop.exit();
+      return;
+    }
 
     self.render_start_time = Some(std::time::Instant::now());
     self.need_redraw = true;
@@ -138,6 +450,24 @@ impl<'a, R: Render<'a>> ApplicationHandler for App<'a, R> {
       return;
     }
 
+    if self.ctx.as_ref().is_some_and(|ctx| ctx.is_device_lost()) {
+      if let Err(e) = self.recover_from_device_loss() {
+        self.context_error = Some(e);
+        event_loop.exit();
+        return;
+      }
+    }
+
+    // UI やレンダラーが使ったイベントでも、ボタンの押し離しは追いかけておく
+    let pointer_event = self.pointer.process(&event);
+
+    #[cfg(feature = "ui")]
+    if let Some(egui_layer) = &mut self.egui_layer {
+      if egui_layer.on_window_event(&event) {
+        return;
+      }
+    }
+
     let renderer = match &mut self.renderer {
       Some(renderer) => renderer,
       None => return,
@@ -145,16 +475,24 @@ impl<'a, R: Render<'a>> ApplicationHandler for App<'a, R> {
     if renderer.process_event(&event) {
       return;
     }
+    match pointer_event {
+      Some(PointerEvent::Scroll(delta)) => renderer.on_scroll(delta),
+      Some(PointerEvent::Drag { dx, dy, button }) => {
+        renderer.on_mouse_drag(dx, dy, button)
+      }
+      None => {}
+    }
 
-    let mut ctx = match &mut self.ctx {
+    let ctx = match &mut self.ctx {
       Some(ctx) => ctx,
       None => return,
     };
 
     match event {
-      WindowEvent::Resized(size) => {
-        renderer.resize(&mut ctx, size.into());
+      WindowEvent::Resized(size) if self.resize_debounce.is_some() => {
+        self.pending_resize = Some((size.into(), std::time::Instant::now()));
       }
+      WindowEvent::Resized(size) => self.resize(size.into()),
       WindowEvent::RedrawRequested => {
         let ctx = match &mut self.ctx {
           Some(ctx) => ctx,
@@ -172,6 +510,18 @@ impl<'a, R: Render<'a>> ApplicationHandler for App<'a, R> {
           }
         };
 
+        #[cfg(feature = "gamepad")]
+        if let Some(gamepad) = &mut self.gamepad {
+          renderer.process_gamepad(gamepad.poll());
+        }
+
+        if let Some(watcher) = &self.shader_watcher {
+          if watcher.poll_changed() {
+            log::info!("Reloading shaders");
+            renderer.reload_shaders(ctx);
+          }
+        }
+
         let now = std::time::Instant::now();
         let dt = now - self.render_start_time.unwrap_or(now);
         renderer.update(ctx, dt);
@@ -181,16 +531,91 @@ impl<'a, R: Render<'a>> ApplicationHandler for App<'a, R> {
             label: None,
           });
 
-        let result = renderer.draw(
-          &mut command_encoder,
-          RenderTarget::Surface(&surface),
-          self.sample_count,
-        );
+        let result = match (&self.post, &self.adaptive) {
+          // オフスクリーンに描画してからエフェクトをかける
+          // 縮小しているときは、エフェクトの結果をさらにサーフェスに拡大する
+          (Some(post), adaptive) => renderer
+            .draw(
+              &mut command_encoder,
+              RenderTarget::Texture(post.texture()),
+              self.sample_count,
+            )
+            .and_then(|_| match adaptive {
+              Some(adaptive) => {
+                let view = adaptive
+                  .texture()
+                  .create_view(&wgpu::TextureViewDescriptor::default());
+                post.apply(&mut command_encoder, &view);
+                adaptive.upscale(&mut command_encoder, surface)
+              }
+              None => post.present(&mut command_encoder, surface),
+            })
+            .map(Some),
+          // 縮小したオフスクリーンに描画してから、サーフェスに拡大する
+          (None, Some(adaptive)) => renderer
+            .draw(
+              &mut command_encoder,
+              RenderTarget::Texture(adaptive.texture()),
+              self.sample_count,
+            )
+            .and_then(|_| adaptive.upscale(&mut command_encoder, surface))
+            .map(Some),
+          (None, None) => renderer.draw(
+            &mut command_encoder,
+            RenderTarget::Surface(&surface),
+            self.sample_count,
+          ),
+        };
+
+        // ユーザーの描画が終わったサーフェスの上に UI を重ねる
+        #[cfg(feature = "ui")]
+        if let (Ok(Some(frame)), Some(egui_layer), Some(ui)) =
+          (&result, &mut self.egui_layer, &mut self.ui)
+        {
+          let view =
+            frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+          egui_layer.draw(
+            &ctx.device,
+            &ctx.queue,
+            &mut command_encoder,
+            &view,
+            |egui_ctx| ui(egui_ctx, renderer),
+          );
+        }
+
+        // ユーザーの描画が終わったサーフェスの上に重ねる
+        if let (Ok(Some(frame)), Some(fps_overlay)) =
+          (&result, &mut self.fps_overlay)
+        {
+          fps_overlay.update(ctx);
+          let view =
+            frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+          fps_overlay.draw(&mut command_encoder, &view);
+        }
 
         match result {
-          Ok(frame) => renderer.submit(&ctx.queue, command_encoder, frame),
+          Ok(frame) => {
+            let submitted_at = std::time::Instant::now();
+            renderer.submit(&ctx.queue, command_encoder, frame);
+
+            if let Some(adaptive) = &mut self.adaptive {
+              adaptive.track_submission(&ctx.queue, submitted_at);
+              if let Some(scale) = adaptive.next_scale() {
+                ctx.set_render_scale(scale);
+                renderer.resize(ctx, *ctx.surface_size());
+                adaptive.resize(ctx);
+                if let Some(post) = &mut self.post {
+                  post.resize(ctx);
+                }
+              }
+            }
+          }
+          // 大きさが落ち着いたら about_to_wait で作り直す
+          Err(wgpu::SurfaceError::Outdated)
+            if self.pending_resize.is_some() => {}
           Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
-            renderer.resize(ctx, *ctx.size())
+            let size = *ctx.surface_size();
+            self.resize(size);
           }
           Err(wgpu::SurfaceError::OutOfMemory) => event_loop.exit(),
           Err(e) => eprintln!("{:?}", e),
@@ -199,6 +624,34 @@ impl<'a, R: Render<'a>> ApplicationHandler for App<'a, R> {
       WindowEvent::CloseRequested => {
         event_loop.exit();
       }
+      WindowEvent::KeyboardInput {
+        event:
+          KeyEvent {
+            physical_key: PhysicalKey::Code(KeyCode::F10),
+            state: ElementState::Pressed,
+            ..
+          },
+        ..
+      } if self.simulate_device_loss_key => {
+        ctx.simulate_device_loss();
+      }
+      WindowEvent::KeyboardInput {
+        event:
+          KeyEvent {
+            physical_key: PhysicalKey::Code(KeyCode::F12),
+            state: ElementState::Pressed,
+            repeat: false,
+            ..
+          },
+        ..
+      } => {
+        let path = screenshot_path();
+        let captured =
+          pollster::block_on(export::capture_surface(ctx, renderer, &path));
+        if let Err(e) = captured {
+          eprintln!("Screenshot failed: {}", e);
+        }
+      }
       WindowEvent::KeyboardInput {
         event:
           KeyEvent {
@@ -214,6 +667,17 @@ impl<'a, R: Render<'a>> ApplicationHandler for App<'a, R> {
     }
   }
 
+  fn device_event(
+    &mut self,
+    _event_loop: &ActiveEventLoop,
+    _device_id: DeviceId,
+    event: DeviceEvent,
+  ) {
+    if let Some(renderer) = &mut self.renderer {
+      renderer.process_device_event(&event);
+    }
+  }
+
   fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: StartCause) {
     if let StartCause::ResumeTimeReached { .. } = cause {
       self.need_redraw = true;
@@ -221,6 +685,21 @@ impl<'a, R: Render<'a>> ApplicationHandler for App<'a, R> {
   }
 
   fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+    if let Some((size, resized_at)) = self.pending_resize {
+      let settle_at = resized_at + self.resize_debounce.unwrap_or_default();
+      if std::time::Instant::now() < settle_at {
+        event_loop.set_control_flow(ControlFlow::WaitUntil(settle_at));
+        return;
+      }
+
+      self.pending_resize = None;
+      self.resize(size);
+      self.need_redraw = true;
+      if self.update_interval.is_none() {
+        event_loop.set_control_flow(ControlFlow::Wait);
+      }
+    }
+
     if !self.need_redraw {
       return;
     }
@@ -241,3 +720,13 @@ impl<'a, R: Render<'a>> ApplicationHandler for App<'a, R> {
     }
   }
 }
+
+/// `screenshot_<unix time in ms>.png` in the working directory, so repeated
+/// captures don't overwrite each other.
+fn screenshot_path() -> String {
+  let millis = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|d| d.as_millis())
+    .unwrap_or_default();
+  format!("screenshot_{}.png", millis)
+}
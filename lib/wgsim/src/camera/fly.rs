@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+use cgmath::{InnerSpace, Matrix4, Point3, Vector3};
+use winit::event::{DeviceEvent, ElementState, KeyEvent, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+#[cfg(feature = "gamepad")]
+use crate::input::GamepadState;
+use crate::matrix;
+
+const PITCH_LIMIT: f32 = 1.5;
+// ゲームパッドの右スティックを倒しきったときの回転の速さ（ラジアン毎秒）
+#[cfg(feature = "gamepad")]
+const GAMEPAD_LOOK_SPEED: f32 = 2.;
+
+/// A first-person camera that flies freely through the scene.
+///
+/// - W / S: move forward / backward along the view direction
+/// - A / D: strafe left / right
+/// - mouse motion: look around
+/// - gamepad left stick / right stick: move / look around, see
+///   `apply_gamepad`
+///
+/// Keys are read from `process_event`, mouse motion from
+/// `process_device_event` (raw `DeviceEvent::MouseMotion`, so it keeps coming
+/// while the cursor is grabbed, see `App::with_cursor_grab`). The position
+/// only moves in `update`, which should be given the time since the last
+/// frame. Yaw and pitch are measured around `up`, which is expected to be
+/// the world Y axis.
+pub struct FlyCamera {
+  position: Point3<f32>,
+  up: Vector3<f32>,
+  yaw: f32,
+  pitch: f32,
+  move_speed: f32,
+  look_sensitivity: f32,
+  forward: bool,
+  backward: bool,
+  left: bool,
+  right: bool,
+}
+
+impl FlyCamera {
+  pub fn new(eye: Point3<f32>, target: Point3<f32>, up: Vector3<f32>) -> Self {
+    let direction = (target - eye).normalize();
+
+    Self {
+      position: eye,
+      up,
+      yaw: direction.x.atan2(direction.z),
+      pitch: direction.y.asin(),
+      move_speed: 5.,
+      look_sensitivity: 0.002,
+      forward: false,
+      backward: false,
+      left: false,
+      right: false,
+    }
+  }
+
+  /// Units per second.
+  pub fn with_move_speed(mut self, speed: f32) -> Self {
+    self.move_speed = speed;
+    self
+  }
+
+  /// Radians per unit of mouse motion.
+  pub fn with_look_sensitivity(mut self, sensitivity: f32) -> Self {
+    self.look_sensitivity = sensitivity;
+    self
+  }
+
+  pub fn position(&self) -> Point3<f32> {
+    self.position
+  }
+
+  pub fn direction(&self) -> Vector3<f32> {
+    let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+    let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+
+    Vector3::new(cos_pitch * sin_yaw, sin_pitch, cos_pitch * cos_yaw)
+  }
+
+  pub fn view_mat(&self) -> Matrix4<f32> {
+    matrix::create_view_mat(
+      self.position,
+      self.position + self.direction(),
+      self.up,
+    )
+  }
+
+  pub fn look(&mut self, d_yaw: f32, d_pitch: f32) {
+    self.yaw += d_yaw;
+    self.pitch = (self.pitch + d_pitch).clamp(-PITCH_LIMIT, PITCH_LIMIT);
+  }
+
+  pub fn process_event(&mut self, event: &WindowEvent) -> bool {
+    match event {
+      WindowEvent::KeyboardInput {
+        event:
+          KeyEvent {
+            physical_key: PhysicalKey::Code(code),
+            state,
+            ..
+          },
+        ..
+      } => {
+        let pressed = *state == ElementState::Pressed;
+        match code {
+          KeyCode::KeyW => self.forward = pressed,
+          KeyCode::KeyS => self.backward = pressed,
+          KeyCode::KeyA => self.left = pressed,
+          KeyCode::KeyD => self.right = pressed,
+          _ => return false,
+        }
+        true
+      }
+      // フォーカスを失うとキーを離したイベントが届かないので、止めておく
+      WindowEvent::Focused(false) => {
+        self.forward = false;
+        self.backward = false;
+        self.left = false;
+        self.right = false;
+        false
+      }
+      _ => false,
+    }
+  }
+
+  pub fn process_device_event(&mut self, event: &DeviceEvent) {
+    if let DeviceEvent::MouseMotion { delta: (dx, dy) } = event {
+      self.look(
+        -*dx as f32 * self.look_sensitivity,
+        -*dy as f32 * self.look_sensitivity,
+      );
+    }
+  }
+
+  /// Moves with the left stick (tilt sets the speed, up to the move speed)
+  /// and looks around with the right stick, `dt` being the time since the
+  /// last call. Call it alongside `update`, which handles the keys.
+  #[cfg(feature = "gamepad")]
+  pub fn apply_gamepad(&mut self, state: &GamepadState, dt: Duration) {
+    let dt = dt.as_secs_f32();
+
+    let [look_x, look_y] = state.right_stick;
+    self.look(
+      -look_x * GAMEPAD_LOOK_SPEED * dt,
+      look_y * GAMEPAD_LOOK_SPEED * dt,
+    );
+
+    let forward = self.direction();
+    let right = forward.cross(self.up).normalize();
+    let [x, y] = state.left_stick;
+    self.position += (forward * y + right * x) * self.move_speed * dt;
+  }
+
+  /// Moves by the keys currently held, `dt` being the time since the last
+  /// call.
+  pub fn update(&mut self, dt: Duration) {
+    let forward = self.direction();
+    let right = forward.cross(self.up).normalize();
+
+    let mut velocity = Vector3::new(0., 0., 0.);
+    if self.forward {
+      velocity += forward;
+    }
+    if self.backward {
+      velocity -= forward;
+    }
+    if self.right {
+      velocity += right;
+    }
+    if self.left {
+      velocity -= right;
+    }
+
+    // 斜め移動が速くならないように向きだけ取り出す
+    if velocity.magnitude2() > 0. {
+      self.position +=
+        velocity.normalize() * self.move_speed * dt.as_secs_f32();
+    }
+  }
+}
@@ -0,0 +1,5 @@
+mod fly;
+mod orbit;
+
+pub use fly::FlyCamera;
+pub use orbit::OrbitCamera;
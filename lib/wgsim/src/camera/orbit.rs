@@ -0,0 +1,201 @@
+#[cfg(feature = "gamepad")]
+use std::time::Duration;
+
+use cgmath::{InnerSpace, Matrix4, Point3, Vector3};
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+
+#[cfg(feature = "gamepad")]
+use crate::input::GamepadState;
+use crate::matrix;
+
+const PITCH_LIMIT: f32 = 1.5;
+// ゲームパッドのスティックやトリガーを倒しきったときの速さ（毎秒）
+#[cfg(feature = "gamepad")]
+const GAMEPAD_ROTATE_SPEED: f32 = 1.5;
+#[cfg(feature = "gamepad")]
+const GAMEPAD_ZOOM_SPEED: f32 = 0.8;
+
+/// A camera that circles around a target point.
+///
+/// `process_event` consumes mouse input and touch and trackpad gestures:
+///
+/// - left-button drag: orbit horizontally and vertically
+/// - mouse wheel (line steps): zoom toward / away from the target
+/// - pinch: zoom toward / away from the target
+/// - two-finger pan (`PanGesture` on iOS, trackpad scroll elsewhere): orbit
+///   horizontally and vertically
+/// - rotation: orbit horizontally, following the direction of the fingers
+///
+/// Gamepads go through `apply_gamepad` instead.
+///
+/// winit only delivers `PinchGesture` and `RotationGesture` on macOS and iOS,
+/// and `PanGesture` on iOS.
+pub struct OrbitCamera {
+  target: Point3<f32>,
+  up: Vector3<f32>,
+  yaw: f32,
+  pitch: f32,
+  radius: f32,
+  min_radius: f32,
+  max_radius: f32,
+  rotate_sensitivity: f32,
+  zoom_sensitivity: f32,
+  dragging: bool,
+  cursor: Option<PhysicalPosition<f64>>,
+}
+
+impl OrbitCamera {
+  pub fn new(eye: Point3<f32>, target: Point3<f32>, up: Vector3<f32>) -> Self {
+    let offset = eye - target;
+    let radius = offset.magnitude();
+
+    Self {
+      target,
+      up,
+      yaw: offset.x.atan2(offset.z),
+      pitch: (offset.y / radius).asin(),
+      radius,
+      min_radius: radius * 0.1,
+      max_radius: radius * 4.,
+      rotate_sensitivity: 0.005,
+      zoom_sensitivity: 0.1,
+      dragging: false,
+      cursor: None,
+    }
+  }
+
+  pub fn with_radius_range(mut self, min: f32, max: f32) -> Self {
+    self.min_radius = min;
+    self.max_radius = max;
+    self.radius = self.radius.clamp(min, max);
+    self
+  }
+
+  /// Radians per pixel of pan or drag.
+  pub fn with_rotate_sensitivity(mut self, sensitivity: f32) -> Self {
+    self.rotate_sensitivity = sensitivity;
+    self
+  }
+
+  /// Zoom per line of mouse wheel, in the units of `zoom`.
+  pub fn with_zoom_sensitivity(mut self, sensitivity: f32) -> Self {
+    self.zoom_sensitivity = sensitivity;
+    self
+  }
+
+  pub fn eye(&self) -> Point3<f32> {
+    let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+    let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+
+    self.target
+      + self.radius
+        * Vector3::new(cos_pitch * sin_yaw, sin_pitch, cos_pitch * cos_yaw)
+  }
+
+  pub fn target(&self) -> Point3<f32> {
+    self.target
+  }
+
+  pub fn view_mat(&self) -> Matrix4<f32> {
+    matrix::create_view_mat(self.eye(), self.target, self.up)
+  }
+
+  pub fn rotate(&mut self, d_yaw: f32, d_pitch: f32) {
+    self.yaw += d_yaw;
+    self.pitch = (self.pitch + d_pitch).clamp(-PITCH_LIMIT, PITCH_LIMIT);
+  }
+
+  /// Positive `delta` moves closer; `0.1` shortens the distance by 10%.
+  pub fn zoom(&mut self, delta: f32) {
+    self.radius =
+      (self.radius * (1. - delta)).clamp(self.min_radius, self.max_radius);
+  }
+
+  /// Zooms by `lines` of mouse wheel, for `Render::on_scroll`.
+  pub fn scroll(&mut self, lines: f32) {
+    self.zoom(lines * self.zoom_sensitivity);
+  }
+
+  /// Orbits like a left-button drag of `dx`, `dy` pixels, for
+  /// `Render::on_mouse_drag`.
+  pub fn drag(&mut self, dx: f32, dy: f32) {
+    self.pan(dx, dy);
+  }
+
+  /// Orbits with the left stick and zooms with the triggers (right trigger
+  /// closer, left trigger away), `dt` being the time since the last call.
+  #[cfg(feature = "gamepad")]
+  pub fn apply_gamepad(&mut self, state: &GamepadState, dt: Duration) {
+    let dt = dt.as_secs_f32();
+
+    let [x, y] = state.left_stick;
+    self.rotate(
+      -x * GAMEPAD_ROTATE_SPEED * dt,
+      -y * GAMEPAD_ROTATE_SPEED * dt,
+    );
+    self.zoom(
+      (state.right_trigger - state.left_trigger) * GAMEPAD_ZOOM_SPEED * dt,
+    );
+  }
+
+  pub fn process_event(&mut self, event: &WindowEvent) -> bool {
+    match event {
+      WindowEvent::PinchGesture { delta, .. } => {
+        self.zoom(*delta as f32);
+        true
+      }
+      WindowEvent::PanGesture { delta, .. } => {
+        self.pan(delta.x, delta.y);
+        true
+      }
+      WindowEvent::MouseWheel {
+        delta: MouseScrollDelta::PixelDelta(delta),
+        ..
+      } => {
+        self.pan(delta.x as f32, delta.y as f32);
+        true
+      }
+      WindowEvent::MouseWheel {
+        delta: MouseScrollDelta::LineDelta(_, y),
+        ..
+      } => {
+        self.scroll(*y);
+        true
+      }
+      WindowEvent::MouseInput {
+        button: MouseButton::Left,
+        state,
+        ..
+      } => {
+        self.dragging = *state == ElementState::Pressed;
+        true
+      }
+      WindowEvent::CursorMoved { position, .. } => {
+        let prev = self.cursor.replace(*position);
+        match prev {
+          Some(prev) if self.dragging => {
+            self
+              .pan((position.x - prev.x) as f32, (position.y - prev.y) as f32);
+            true
+          }
+          _ => false,
+        }
+      }
+      WindowEvent::CursorLeft { .. } => {
+        self.dragging = false;
+        self.cursor = None;
+        false
+      }
+      WindowEvent::RotationGesture { delta, .. } => {
+        self.rotate(delta.to_radians(), 0.);
+        true
+      }
+      _ => false,
+    }
+  }
+
+  fn pan(&mut self, dx: f32, dy: f32) {
+    self.rotate(-dx * self.rotate_sensitivity, dy * self.rotate_sensitivity);
+  }
+}
@@ -1 +1,4 @@
 pub mod pixel;
+mod timestamp;
+
+pub use timestamp::TimestampQuery;
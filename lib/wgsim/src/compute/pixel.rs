@@ -1,17 +1,33 @@
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::{error::Error, fs::File, io::BufWriter, path::Path};
 
 use wgpu::BufferView;
 
+use crate::ctx::{ContextError, ContextOptions};
 use crate::util;
 
+use super::TimestampQuery;
+
 pub struct ComputePixel {
-  img_size: u32,
+  width: u32,
+  height: u32,
+  format: wgpu::TextureFormat,
+  bytes_per_pixel: u32,
+  /// `bytes_per_pixel * width` rounded up to `COPY_BYTES_PER_ROW_ALIGNMENT`,
+  /// the row stride of the data returned by `compute`.
+  padded_bytes_per_row: u32,
   texture: wgpu::Texture,
   texture_data_buffer: wgpu::Buffer,
   device: wgpu::Device,
   queue: wgpu::Queue,
   compute_pipeline: wgpu::ComputePipeline,
-  bind_group: wgpu::BindGroup,
+  bind_group_layout: wgpu::BindGroupLayout,
+  /// `@group(0) @binding(1)`, grown by `compute_with_uniform` when a larger
+  /// payload comes in. The bind group is rebuilt along with it.
+  uniform: RefCell<(wgpu::Buffer, wgpu::BindGroup)>,
+  timestamp_query: Option<TimestampQuery>,
+  elapsed_ms: Cell<Option<f64>>,
 }
 
 impl ComputePixel {
@@ -20,20 +36,71 @@ impl ComputePixel {
     entry_point: &str,
     tex_format: wgpu::TextureFormat,
     img_size: u32,
+  ) -> Result<Self, Box<dyn Error>> {
+    Self::new_with_size(module, entry_point, tex_format, img_size, img_size)
+      .await
+  }
+
+  /// For non-square output, e.g. 16:9.
+  pub async fn new_with_size(
+    module: wgpu::ShaderModuleDescriptor<'_>,
+    entry_point: &str,
+    tex_format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+  ) -> Result<Self, Box<dyn Error>> {
+    Self::init(
+      module,
+      entry_point,
+      tex_format,
+      width,
+      height,
+      ContextOptions::default(),
+    )
+    .await
+  }
+
+  pub async fn new_with(
+    module: wgpu::ShaderModuleDescriptor<'_>,
+    entry_point: &str,
+    tex_format: wgpu::TextureFormat,
+    img_size: u32,
+    options: ContextOptions,
+  ) -> Result<Self, Box<dyn Error>> {
+    Self::init(module, entry_point, tex_format, img_size, img_size, options)
+      .await
+  }
+
+  async fn init(
+    module: wgpu::ShaderModuleDescriptor<'_>,
+    entry_point: &str,
+    tex_format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    options: ContextOptions,
   ) -> Result<Self, Box<dyn Error>> {
     //
     // init wgpu
     //
 
-    let instance = wgpu::Instance::default();
+    let instance = options.create_instance();
 
     let adapter = instance
-      .request_adapter(&wgpu::RequestAdapterOptions::default())
+      .request_adapter(&options.adapter_options(None))
       .await
-      .unwrap();
-
-    let (device, queue) =
-      adapter.request_device(&wgpu::DeviceDescriptor::default(), None).await?;
+      .ok_or(ContextError::NoAdapter)?;
+
+    let (device, queue) = adapter
+      .request_device(
+        &wgpu::DeviceDescriptor {
+          required_features: adapter.features()
+            & wgpu::Features::TIMESTAMP_QUERY,
+          ..Default::default()
+        },
+        None,
+      )
+      .await
+      .map_err(ContextError::DeviceRequest)?;
 
     //
     // compile shader
@@ -48,8 +115,8 @@ impl ComputePixel {
     let texture = device.create_texture(&wgpu::TextureDescriptor {
       label: Some("compute output texture"),
       size: wgpu::Extent3d {
-        width: img_size,
-        height: img_size,
+        width,
+        height,
         depth_or_array_layers: 1,
       },
       mip_level_count: 1,
@@ -67,9 +134,15 @@ impl ComputePixel {
     // staging buffer
     //
 
+    let bytes_per_pixel = tex_format
+      .block_copy_size(Some(wgpu::TextureAspect::All))
+      .ok_or("texture format cannot be copied to a buffer")?;
+    let padded_bytes_per_row = (bytes_per_pixel * width)
+      .next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
     let texture_data_buffer = device.create_buffer(&wgpu::BufferDescriptor {
       label: Some("staging buffer for texture data"),
-      size: 4 * img_size as u64 * img_size as u64,
+      size: padded_bytes_per_row as u64 * height as u64,
       usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
       mapped_at_creation: false,
     });
@@ -80,21 +153,24 @@ impl ComputePixel {
 
     let bind_group_layout = util::create_bind_group_layout(
       &device,
-      &[wgpu::BindingType::StorageTexture {
-        access: wgpu::StorageTextureAccess::WriteOnly,
-        format: tex_format,
-        view_dimension: wgpu::TextureViewDimension::D2,
-      }],
-      &[wgpu::ShaderStages::COMPUTE],
+      &[
+        wgpu::BindingType::StorageTexture {
+          access: wgpu::StorageTextureAccess::WriteOnly,
+          format: tex_format,
+          view_dimension: wgpu::TextureViewDimension::D2,
+        },
+        wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+      ],
+      &[wgpu::ShaderStages::COMPUTE, wgpu::ShaderStages::COMPUTE],
     );
 
-    let bind_group = util::create_bind_group(
-      &device,
-      &bind_group_layout,
-      &[wgpu::BindingResource::TextureView(
-        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
-      )],
-    );
+    // シェーダーがuniformを使わなくても、バインドグループは埋めておく
+    let uniform =
+      create_uniform(&device, &texture, &bind_group_layout, MIN_UNIFORM_SIZE);
 
     //
     // create compute_pipeline
@@ -118,16 +194,100 @@ impl ComputePixel {
       });
 
     Ok(Self {
-      img_size,
+      width,
+      height,
+      format: tex_format,
+      bytes_per_pixel,
+      padded_bytes_per_row,
       texture,
       texture_data_buffer,
       device,
       queue,
       compute_pipeline,
-      bind_group,
+      bind_group_layout,
+      uniform: RefCell::new(uniform),
+      timestamp_query: None,
+      elapsed_ms: Cell::new(None),
     })
   }
 
+  /// Measures the GPU time of each `compute` call with timestamp queries.
+  ///
+  /// Has no effect if the adapter does not support `TIMESTAMP_QUERY`.
+  pub fn with_timestamps(mut self) -> Self {
+    self.timestamp_query = TimestampQuery::new(&self.device, &self.queue);
+    self
+  }
+
+  /// GPU time of the last compute pass in milliseconds.
+  ///
+  /// `None` until `compute` has run, or if timestamps are not available.
+  pub fn elapsed_ms(&self) -> Option<f64> {
+    self.elapsed_ms.get()
+  }
+
+  /// Like `compute`, but first uploads `uniform` to the buffer at
+  /// `@group(0) @binding(1)`, e.g. a `time` or `resolution` value for the
+  /// shader. The payload has to match the layout of the WGSL struct
+  /// (`bytemuck::bytes_of` on a `#[repr(C)]` struct).
+  pub async fn compute_with_uniform(
+    &self,
+    uniform: &[u8],
+    workgroup_size_x: u32,
+    workgroup_size_y: u32,
+  ) -> Result<BufferView<'_>, Box<dyn Error>> {
+    self.write_uniform(uniform);
+    self.compute(workgroup_size_x, workgroup_size_y).await
+  }
+
+  /// Runs the shader once per frame with the uniform returned by
+  /// `uniform_per_frame(frame)` and collects the pixels of each frame, e.g.
+  /// to export an animation. Each frame is laid out like `compute`'s result.
+  pub async fn compute_frames(
+    &self,
+    uniform_per_frame: impl Fn(usize) -> Vec<u8>,
+    frames: usize,
+    workgroup_size_x: u32,
+    workgroup_size_y: u32,
+  ) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+    let mut px_data = Vec::with_capacity(frames);
+
+    for frame in 0..frames {
+      let uniform = uniform_per_frame(frame);
+      let data_view = self
+        .compute_with_uniform(&uniform, workgroup_size_x, workgroup_size_y)
+        .await?;
+      px_data.push(data_view.to_vec());
+      self.clean_up(data_view);
+    }
+
+    Ok(px_data)
+  }
+
+  fn write_uniform(&self, data: &[u8]) {
+    let size = (data.len() as u64)
+      .max(MIN_UNIFORM_SIZE)
+      .next_multiple_of(MIN_UNIFORM_SIZE);
+
+    let mut uniform = self.uniform.borrow_mut();
+    if uniform.0.size() < size {
+      *uniform = create_uniform(
+        &self.device,
+        &self.texture,
+        &self.bind_group_layout,
+        size,
+      );
+    }
+
+    // write_bufferのサイズは4の倍数でなければならないので、0で埋めておく
+    let mut padded = data.to_vec();
+    padded.resize(size as usize, 0);
+    self.queue.write_buffer(&uniform.0, 0, &padded);
+  }
+
+  /// Rows of the returned data are padded to `COPY_BYTES_PER_ROW_ALIGNMENT`
+  /// when `width` is not a multiple of it. The `export_*` methods take the
+  /// data as is.
   pub async fn compute(
     &self,
     workgroup_size_x: u32,
@@ -144,19 +304,26 @@ impl ComputePixel {
     let mut compute_pass_encoder =
       command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
         label: Some("Compute Pass"),
-        timestamp_writes: None,
+        timestamp_writes: self
+          .timestamp_query
+          .as_ref()
+          .map(|query| query.compute_pass_writes()),
       });
 
     compute_pass_encoder.set_pipeline(&self.compute_pipeline);
-    compute_pass_encoder.set_bind_group(0, &self.bind_group, &[]);
+    compute_pass_encoder.set_bind_group(0, &self.uniform.borrow().1, &[]);
     compute_pass_encoder.dispatch_workgroups(
-      self.img_size / workgroup_size_x,
-      self.img_size / workgroup_size_y,
+      self.width.div_ceil(workgroup_size_x),
+      self.height.div_ceil(workgroup_size_y),
       1,
     );
 
     drop(compute_pass_encoder);
 
+    if let Some(query) = &self.timestamp_query {
+      query.resolve(&mut command_encoder);
+    }
+
     //
     // copy texture to buffer
     //
@@ -172,13 +339,13 @@ impl ComputePixel {
         buffer: &self.texture_data_buffer,
         layout: wgpu::ImageDataLayout {
           offset: 0,
-          bytes_per_row: Some(4 * self.img_size),
-          rows_per_image: Some(self.img_size),
+          bytes_per_row: Some(self.padded_bytes_per_row),
+          rows_per_image: Some(self.height),
         },
       },
       wgpu::Extent3d {
-        width: self.img_size,
-        height: self.img_size,
+        width: self.width,
+        height: self.height,
         depth_or_array_layers: 1,
       },
     );
@@ -189,6 +356,11 @@ impl ComputePixel {
 
     self.queue.submit(std::iter::once(command_encoder.finish()));
 
+    if let Some(query) = &self.timestamp_query {
+      let elapsed_ms = query.read_ms(&self.device, &self.queue).await;
+      self.elapsed_ms.set(Some(elapsed_ms));
+    }
+
     //
     // read buffer
     //
@@ -215,17 +387,128 @@ impl ComputePixel {
     let file = File::create(path)?;
     let ref mut w = BufWriter::new(file);
 
-    let mut png_encoder = png::Encoder::new(w, self.img_size, self.img_size);
+    let mut png_encoder = png::Encoder::new(w, self.width, self.height);
     png_encoder.set_color(png::ColorType::Rgba);
 
     let mut writer = png_encoder.write_header()?;
-    writer.write_image_data(&px_data)?;
+    writer.write_image_data(&self.unpadded(px_data))?;
+
+    Ok(())
+  }
+
+  /// Writes an 8-bit RGBA result in the format given by the file extension
+  /// (png, jpg, bmp, tga, ...). JPEG has no alpha channel, so it is dropped.
+  pub fn export_image(
+    &self,
+    path: &Path,
+    px_data: &[u8],
+  ) -> Result<(), Box<dyn Error>> {
+    if !matches!(
+      self.format,
+      wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb
+    ) {
+      return Err(
+        format!("export_image expects Rgba8Unorm, got {:?}", self.format)
+          .into(),
+      );
+    }
+
+    let format = image::ImageFormat::from_path(path)?;
+    let img = image::RgbaImage::from_raw(
+      self.width,
+      self.height,
+      self.unpadded(px_data).into_owned(),
+    )
+    .ok_or("pixel data does not match the image size")?;
+
+    if format == image::ImageFormat::Jpeg {
+      image::DynamicImage::ImageRgba8(img)
+        .to_rgb8()
+        .save_with_format(path, format)?;
+    } else {
+      img.save_with_format(path, format)?;
+    }
+
+    Ok(())
+  }
+
+  /// Writes a floating point result (`Rgba16Float` or `Rgba32Float`) as
+  /// OpenEXR, keeping values outside of 0..1.
+  pub fn export_exr(
+    &self,
+    path: &Path,
+    px_data: &[u8],
+  ) -> Result<(), Box<dyn Error>> {
+    let px_data = self.unpadded(px_data);
+    let channels: Vec<f32> = match self.format {
+      wgpu::TextureFormat::Rgba16Float => px_data
+        .chunks_exact(2)
+        .map(|b| half::f16::from_le_bytes([b[0], b[1]]).to_f32())
+        .collect(),
+      wgpu::TextureFormat::Rgba32Float => px_data
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect(),
+      format => {
+        return Err(
+          format!("export_exr expects a float format, got {:?}", format).into(),
+        )
+      }
+    };
+
+    let img = image::Rgba32FImage::from_raw(self.width, self.height, channels)
+      .ok_or("pixel data does not match the image size")?;
+    img.save_with_format(path, image::ImageFormat::OpenExr)?;
 
     Ok(())
   }
 
+  /// Strips the row padding of the data returned by `compute`.
+  fn unpadded<'b>(&self, px_data: &'b [u8]) -> Cow<'b, [u8]> {
+    let bytes_per_row = (self.bytes_per_pixel * self.width) as usize;
+    if bytes_per_row == self.padded_bytes_per_row as usize {
+      return Cow::Borrowed(px_data);
+    }
+
+    px_data
+      .chunks(self.padded_bytes_per_row as usize)
+      .flat_map(|row| &row[..bytes_per_row])
+      .copied()
+      .collect()
+  }
+
   pub fn clean_up(&self, data_view: BufferView) {
     drop(data_view);
     self.texture_data_buffer.unmap();
   }
 }
+
+/// Smallest size of a WGSL uniform struct (one `vec4`).
+const MIN_UNIFORM_SIZE: u64 = 16;
+
+fn create_uniform(
+  device: &wgpu::Device,
+  texture: &wgpu::Texture,
+  layout: &wgpu::BindGroupLayout,
+  size: u64,
+) -> (wgpu::Buffer, wgpu::BindGroup) {
+  let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("compute uniform buffer"),
+    size,
+    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  });
+
+  let bind_group = util::create_bind_group(
+    device,
+    layout,
+    &[
+      wgpu::BindingResource::TextureView(
+        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+      ),
+      buffer.as_entire_binding(),
+    ],
+  );
+
+  (buffer, bind_group)
+}
@@ -0,0 +1,66 @@
+use crate::util;
+
+/// Measures the GPU time of a compute pass with a pair of timestamp queries.
+///
+/// Requires `Features::TIMESTAMP_QUERY` on the device.
+pub struct TimestampQuery {
+  query_set: wgpu::QuerySet,
+  resolve_buffer: wgpu::Buffer,
+  period: f32,
+}
+
+impl TimestampQuery {
+  /// Returns `None` if the device was created without `TIMESTAMP_QUERY`.
+  pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+    if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+      return None;
+    }
+
+    let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+      label: Some("timestamp query set"),
+      ty: wgpu::QueryType::Timestamp,
+      count: 2,
+    });
+
+    let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("timestamp resolve buffer"),
+      size: 2 * wgpu::QUERY_SIZE as u64,
+      usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+      mapped_at_creation: false,
+    });
+
+    Some(Self {
+      query_set,
+      resolve_buffer,
+      period: queue.get_timestamp_period(),
+    })
+  }
+
+  /// Writes the begin/end timestamps at the start and end of the pass.
+  pub fn compute_pass_writes(&self) -> wgpu::ComputePassTimestampWrites<'_> {
+    wgpu::ComputePassTimestampWrites {
+      query_set: &self.query_set,
+      beginning_of_pass_write_index: Some(0),
+      end_of_pass_write_index: Some(1),
+    }
+  }
+
+  /// Records the copy of the query results into the resolve buffer.
+  pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+    encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve_buffer, 0);
+  }
+
+  /// Reads back the resolved timestamps and converts them to milliseconds.
+  pub async fn read_ms(
+    &self,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+  ) -> f64 {
+    let ticks =
+      util::read_buffer::<u64>(device, queue, &self.resolve_buffer).await;
+    let elapsed_ns =
+      ticks[1].wrapping_sub(ticks[0]) as f64 * self.period as f64;
+
+    elapsed_ns / 1_000_000.0
+  }
+}
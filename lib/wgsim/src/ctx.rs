@@ -1,3 +1,5 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use winit::{dpi::PhysicalSize, window::Window};
@@ -25,12 +27,97 @@ impl Into<Size> for PhysicalSize<u32> {
   }
 }
 
+/// Why a context could not be created.
+#[derive(Debug)]
+pub enum ContextError {
+  /// No adapter matches the `ContextOptions`, e.g. no GPU on a headless
+  /// machine without a software fallback.
+  NoAdapter,
+  DeviceRequest(wgpu::RequestDeviceError),
+  SurfaceCreation(wgpu::CreateSurfaceError),
+  /// The surface reports no texture formats for the adapter.
+  SurfaceUnsupported,
+}
+
+impl fmt::Display for ContextError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ContextError::NoAdapter => write!(
+        f,
+        "no suitable GPU adapter found (try other backends or a fallback adapter)"
+      ),
+      ContextError::DeviceRequest(e) => {
+        write!(f, "failed to create the device: {}", e)
+      }
+      ContextError::SurfaceCreation(e) => {
+        write!(f, "failed to create the surface: {}", e)
+      }
+      ContextError::SurfaceUnsupported => {
+        write!(f, "the surface is not supported by the adapter")
+      }
+    }
+  }
+}
+
+impl std::error::Error for ContextError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      ContextError::NoAdapter | ContextError::SurfaceUnsupported => None,
+      ContextError::DeviceRequest(e) => Some(e),
+      ContextError::SurfaceCreation(e) => Some(e),
+    }
+  }
+}
+
+/// Which backend and GPU a context is created on. The defaults are what
+/// `wgpu::Instance::default()` and `RequestAdapterOptions::default()` pick.
+#[derive(Debug, Clone, Copy)]
+pub struct ContextOptions {
+  pub backends: wgpu::Backends,
+  pub power_preference: wgpu::PowerPreference,
+  /// Only accept a software adapter (e.g. WARP or llvmpipe).
+  pub force_fallback: bool,
+}
+
+impl Default for ContextOptions {
+  fn default() -> Self {
+    Self {
+      backends: wgpu::Backends::all(),
+      power_preference: wgpu::PowerPreference::default(),
+      force_fallback: false,
+    }
+  }
+}
+
+impl ContextOptions {
+  pub fn create_instance(&self) -> wgpu::Instance {
+    wgpu::Instance::new(wgpu::InstanceDescriptor {
+      backends: self.backends,
+      ..Default::default()
+    })
+  }
+
+  pub fn adapter_options<'s, 'w>(
+    &self,
+    compatible_surface: Option<&'s wgpu::Surface<'w>>,
+  ) -> wgpu::RequestAdapterOptions<'s, 'w> {
+    wgpu::RequestAdapterOptions {
+      power_preference: self.power_preference,
+      force_fallback_adapter: self.force_fallback,
+      compatible_surface,
+    }
+  }
+}
+
 #[derive(Debug)]
 pub struct SurfaceDrawingContext<'a> {
   pub surface: wgpu::Surface<'a>,
   pub config: wgpu::SurfaceConfiguration,
   pub size: Size,
   pub dpi: u32,
+  render_scale: f32,
+  render_size: Size,
+  render_format: Option<wgpu::TextureFormat>,
 }
 
 #[derive(Debug)]
@@ -41,7 +128,7 @@ pub struct TextureDrawingContext {
 
 #[derive(Debug)]
 pub enum DrawingContextType<'a> {
-  Surface(SurfaceDrawingContext<'a>),
+  Surface(Box<SurfaceDrawingContext<'a>>),
   Texture(TextureDrawingContext),
 }
 
@@ -53,82 +140,119 @@ pub struct DrawingContext<'a> {
   pub device: wgpu::Device,
   pub queue: wgpu::Queue,
   pub sample_count: u32,
+  /// Background the renderer should clear to, black unless the app sets
+  /// one (`App::with_clear_color`). Renderers read it in `Render::new`,
+  /// as `draw` has no access to the context.
+  pub clear_color: wgpu::Color,
+  device_lost: Arc<AtomicBool>,
 }
 
 impl<'a> DrawingContext<'a> {
   pub async fn new_for_texture(
     size: Size,
     format: wgpu::TextureFormat,
-  ) -> Self {
-    let instance = wgpu::Instance::default();
+  ) -> Result<Self, ContextError> {
+    Self::new_for_texture_with(size, format, ContextOptions::default()).await
+  }
+
+  pub async fn new_for_texture_with(
+    size: Size,
+    format: wgpu::TextureFormat,
+    options: ContextOptions,
+  ) -> Result<Self, ContextError> {
+    let instance = options.create_instance();
 
     let adapter = instance
-      .request_adapter(&wgpu::RequestAdapterOptions::default())
+      .request_adapter(&options.adapter_options(None))
       .await
-      .unwrap();
+      .ok_or(ContextError::NoAdapter)?;
 
     let (device, queue) = adapter
-      .request_device(&wgpu::DeviceDescriptor::default(), None)
+      .request_device(
+        &wgpu::DeviceDescriptor {
+          required_features: optional_features(&adapter),
+          ..Default::default()
+        },
+        None,
+      )
       .await
-      .unwrap();
+      .map_err(ContextError::DeviceRequest)?;
+    let device_lost = watch_device_lost(&device);
 
-    Self {
+    Ok(Self {
       instance,
       adapter,
       device,
       queue,
       ty: DrawingContextType::Texture(TextureDrawingContext { format, size }),
       sample_count: 1,
-    }
+      clear_color: wgpu::Color::BLACK,
+      device_lost,
+    })
   }
 
   pub async fn new_for_surface(
     window: Arc<Window>,
     cfg_builder: &SurfaceConfigBuilder<'a>,
-  ) -> Self {
+  ) -> Result<Self, ContextError> {
+    Self::new_for_surface_with(window, cfg_builder, ContextOptions::default())
+      .await
+  }
+
+  pub async fn new_for_surface_with(
+    window: Arc<Window>,
+    cfg_builder: &SurfaceConfigBuilder<'a>,
+    options: ContextOptions,
+  ) -> Result<Self, ContextError> {
     let size = window.inner_size();
     let dpi = window.scale_factor();
 
-    let instance = wgpu::Instance::default();
+    let instance = options.create_instance();
     let surface =
-      instance.create_surface(window).expect("Failed to create surface");
+      instance.create_surface(window).map_err(ContextError::SurfaceCreation)?;
 
     let adapter = instance
-      .request_adapter(&wgpu::RequestAdapterOptions {
-        compatible_surface: Some(&surface),
-        ..Default::default()
-      })
+      .request_adapter(&options.adapter_options(Some(&surface)))
       .await
-      .expect("Failed to find an appropriate adapter");
+      .ok_or(ContextError::NoAdapter)?;
+    log::info!("Adapter: {:?}", adapter.get_info());
 
     let (device, queue) = adapter
       .request_device(
         &wgpu::DeviceDescriptor {
           required_features: wgpu::Features::default()
-            | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+            | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+            | optional_features(&adapter),
           ..Default::default()
         },
         None,
       )
       .await
-      .expect("Failed to create device");
+      .map_err(ContextError::DeviceRequest)?;
+    let device_lost = watch_device_lost(&device);
 
-    let config = cfg_builder.build(&adapter, &surface, size.width, size.height);
+    let config =
+      cfg_builder.build(&adapter, &surface, size.width, size.height)?;
     surface.configure(&device, &config);
 
-    Self {
+    Ok(Self {
       instance,
       adapter,
       device,
       queue,
-      ty: DrawingContextType::Surface(SurfaceDrawingContext {
+      ty: DrawingContextType::Surface(Box::new(SurfaceDrawingContext {
         surface,
         config,
         size: size.into(),
         dpi: dpi as u32,
-      }),
+        render_scale: 1.,
+        render_size: size.into(),
+        render_format: None,
+      })),
       sample_count: 1,
-    }
+      clear_color: wgpu::Color::BLACK,
+      device_lost,
+    })
   }
 
   pub fn with_sample_count(mut self, sample_count: u32) -> Self {
@@ -136,13 +260,39 @@ impl<'a> DrawingContext<'a> {
     self
   }
 
-  pub fn format(&'a self) -> wgpu::TextureFormat {
+  pub fn with_clear_color(mut self, color: wgpu::Color) -> Self {
+    self.clear_color = color;
+    self
+  }
+
+  /// Format renderers draw in: the surface format, unless the app renders
+  /// into an offscreen target first (`App::with_post_effect`).
+  pub fn format(&self) -> wgpu::TextureFormat {
+    match &self.ty {
+      DrawingContextType::Surface(ctx) => {
+        ctx.render_format.unwrap_or(ctx.config.format)
+      }
+      DrawingContextType::Texture(ctx) => ctx.format,
+    }
+  }
+
+  /// Format of the surface (or texture) that is presented.
+  pub fn surface_format(&self) -> wgpu::TextureFormat {
     match &self.ty {
       DrawingContextType::Surface(ctx) => ctx.config.format,
       DrawingContextType::Texture(ctx) => ctx.format,
     }
   }
 
+  /// Changes `format()` without reconfiguring the surface; `None` goes back
+  /// to the surface format. Pipelines built before keep their old target
+  /// format. Texture contexts always render in their own format.
+  pub fn set_render_format(&mut self, format: Option<wgpu::TextureFormat>) {
+    if let DrawingContextType::Surface(ctx) = &mut self.ty {
+      ctx.render_format = format;
+    }
+  }
+
   pub fn surface(&self) -> Option<&wgpu::Surface> {
     match &self.ty {
       DrawingContextType::Surface(ctx) => Some(&ctx.surface),
@@ -150,13 +300,41 @@ impl<'a> DrawingContext<'a> {
     }
   }
 
+  /// Size renderers draw at: the surface size times `render_scale`.
   pub fn size(&self) -> &Size {
+    match &self.ty {
+      DrawingContextType::Surface(ctx) => &ctx.render_size,
+      DrawingContextType::Texture(ctx) => &ctx.size,
+    }
+  }
+
+  /// Size of the surface (or texture) that is presented.
+  pub fn surface_size(&self) -> &Size {
     match &self.ty {
       DrawingContextType::Surface(ctx) => &ctx.size,
       DrawingContextType::Texture(ctx) => &ctx.size,
     }
   }
 
+  /// Fraction of the surface size that is rendered, 1 unless the app
+  /// upscales from a smaller target (`App::with_adaptive_resolution`).
+  pub fn render_scale(&self) -> f32 {
+    match &self.ty {
+      DrawingContextType::Surface(ctx) => ctx.render_scale,
+      DrawingContextType::Texture(_) => 1.,
+    }
+  }
+
+  /// Changes `size()` without reconfiguring the surface. Renderers have to
+  /// be resized afterwards to recreate their size-dependent resources.
+  /// Texture contexts always render at full size.
+  pub fn set_render_scale(&mut self, scale: f32) {
+    if let DrawingContextType::Surface(ctx) = &mut self.ty {
+      ctx.render_scale = scale;
+      ctx.render_size = scale_size(ctx.size, scale);
+    }
+  }
+
   pub fn resolution(&self) -> Size {
     match &self.ty {
       DrawingContextType::Surface(ctx) => {
@@ -175,6 +353,19 @@ impl<'a> DrawingContext<'a> {
     *width as f32 / *height as f32
   }
 
+  /// Whether the device has been lost (GPU reset, driver update, ...).
+  /// A lost device cannot be revived; the whole context has to be recreated.
+  pub fn is_device_lost(&self) -> bool {
+    self.device_lost.load(Ordering::SeqCst)
+  }
+
+  /// Destroys the device to exercise the recovery path.
+  pub fn simulate_device_loss(&self) {
+    log::warn!("Simulating device loss");
+    self.device_lost.store(true, Ordering::SeqCst);
+    self.device.destroy();
+  }
+
   pub fn resize(&mut self, size: Size) {
     match &mut self.ty {
       DrawingContextType::Surface(ctx) => ctx.resize(&self.device, size),
@@ -186,6 +377,7 @@ impl<'a> DrawingContext<'a> {
 impl<'a> SurfaceDrawingContext<'a> {
   pub fn resize(&mut self, device: &wgpu::Device, size: Size) {
     self.size = size;
+    self.render_size = scale_size(size, self.render_scale);
     self.config.width = self.size.width;
     self.config.height = self.size.height;
     self.surface.configure(device, &self.config);
@@ -197,3 +389,47 @@ impl TextureDrawingContext {
     self.size = size;
   }
 }
+
+fn scale_size(size: Size, scale: f32) -> Size {
+  Size {
+    width: ((size.width as f32 * scale).round() as u32).max(1),
+    height: ((size.height as f32 * scale).round() as u32).max(1),
+  }
+}
+
+/// Features that are turned on when the adapter has them, so pipelines can
+/// use them without every sample asking for them (see
+/// `RenderPipelineBuilder::polygon_mode`).
+fn optional_features(adapter: &wgpu::Adapter) -> wgpu::Features {
+  adapter.features()
+    & (wgpu::Features::POLYGON_MODE_LINE
+      | wgpu::Features::POLYGON_MODE_POINT
+      | wgpu::Features::INDIRECT_FIRST_INSTANCE)
+}
+
+fn watch_device_lost(device: &wgpu::Device) -> Arc<AtomicBool> {
+  let device_lost = Arc::new(AtomicBool::new(false));
+  let flag = device_lost.clone();
+
+  device.set_device_lost_callback(move |reason, message| {
+    // 通常の破棄（Drop）は復旧の対象ではない
+    if let wgpu::DeviceLostReason::Dropped = reason {
+      return;
+    }
+    log::error!("Device lost ({:?}): {}", reason, message);
+    flag.store(true, Ordering::SeqCst);
+  });
+
+  // デフォルトのハンドラはpanicするので、復旧するまでのフレームで
+  // 失われたデバイスに対して発生したエラーは握りつぶす
+  let flag = device_lost.clone();
+  device.on_uncaptured_error(Box::new(move |error| {
+    if flag.load(Ordering::SeqCst) {
+      log::debug!("Ignoring error on lost device: {}", error);
+    } else {
+      panic!("wgpu error: {}", error);
+    }
+  }));
+
+  device_lost
+}
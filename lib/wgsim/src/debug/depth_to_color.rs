@@ -0,0 +1,191 @@
+use bytemuck::cast_slice;
+use wgpu::util::DeviceExt;
+
+use crate::{matrix, util};
+
+const DEFAULT_MAX_DISTANCE: f32 = 20.;
+
+/// Draws a depth buffer as grayscale, for debugging z-fighting and depth
+/// test issues.
+///
+/// The depth `d` is turned back into the view distance
+///
+/// `z = near * far / (far - d * (far - near))`
+///
+/// (see `post::DepthOfField`), then mapped linearly from white at `near` to
+/// black at `max_distance`. The raw `d` of a perspective projection is
+/// crowded near 1 and would look almost uniformly white. This assumes a
+/// perspective projection; an orthographic `d` is already linear.
+///
+/// The depth texture needs `TEXTURE_BINDING` (e.g.
+/// `util::create_sampleable_depth_view`) and has to be stored at the end of
+/// the scene pass (`util::create_stored_depth_stencil_attachment`). It has
+/// to be the size of the target. For multisampled depth, sample 0 is shown.
+pub struct DepthToColor {
+  pipeline: wgpu::RenderPipeline,
+  bind_group_layout: wgpu::BindGroupLayout,
+  bind_group: Option<wgpu::BindGroup>,
+  params_uniform_buffer: wgpu::Buffer,
+  near: f32,
+  far: f32,
+  max_distance: f32,
+}
+
+impl DepthToColor {
+  /// `sample_count` is that of the depth texture; the pass itself draws
+  /// single-sampled into a `target_format` view.
+  pub fn new(
+    device: &wgpu::Device,
+    target_format: wgpu::TextureFormat,
+    sample_count: u32,
+  ) -> Self {
+    let multisampled = sample_count > 1;
+
+    let source = include_str!("./depth_to_color.wgsl");
+    let source = if multisampled {
+      source.replace("texture_depth_2d", "texture_depth_multisampled_2d")
+    } else {
+      source.to_string()
+    };
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some("depth_to_color.wgsl"),
+      source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+
+    let near = matrix::PERSPECTIVE_NEAR;
+    let far = matrix::PERSPECTIVE_FAR;
+    let max_distance = DEFAULT_MAX_DISTANCE;
+    let params_uniform_buffer =
+      device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("depth to color params uniform buffer"),
+        contents: cast_slice(&[near, far, max_distance, 0.]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+
+    let bind_group_layout = util::create_bind_group_layout(
+      device,
+      &[
+        wgpu::BindingType::Texture {
+          sample_type: wgpu::TextureSampleType::Depth,
+          view_dimension: wgpu::TextureViewDimension::D2,
+          multisampled,
+        },
+        wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+      ],
+      &[wgpu::ShaderStages::FRAGMENT, wgpu::ShaderStages::FRAGMENT],
+    );
+
+    let pipeline_layout =
+      device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Depth To Color Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let pipeline =
+      device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Depth To Color Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &[Some(target_format.into())],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+      });
+
+    Self {
+      pipeline,
+      bind_group_layout,
+      bind_group: None,
+      params_uniform_buffer,
+      near,
+      far,
+      max_distance,
+    }
+  }
+
+  /// Near/far planes of the projection the depth buffer was rendered with.
+  /// The defaults are `matrix::PERSPECTIVE_NEAR`/`PERSPECTIVE_FAR`.
+  pub fn with_clip_planes(
+    mut self,
+    queue: &wgpu::Queue,
+    near: f32,
+    far: f32,
+  ) -> Self {
+    self.near = near;
+    self.far = far;
+    self.write_params(queue);
+    self
+  }
+
+  pub fn max_distance(&self) -> f32 {
+    self.max_distance
+  }
+
+  /// Distance that is drawn black, 20 by default. Anything farther is
+  /// black as well.
+  pub fn set_max_distance(&mut self, queue: &wgpu::Queue, distance: f32) {
+    self.max_distance = distance.max(self.near + f32::EPSILON);
+    self.write_params(queue);
+  }
+
+  /// Sets the depth texture to show, again after every resize.
+  pub fn set_input(
+    &mut self,
+    device: &wgpu::Device,
+    depth_view: &wgpu::TextureView,
+  ) {
+    self.bind_group = Some(util::create_bind_group(
+      device,
+      &self.bind_group_layout,
+      &[
+        wgpu::BindingResource::TextureView(depth_view),
+        self.params_uniform_buffer.as_entire_binding(),
+      ],
+    ));
+  }
+
+  /// Overwrites `target_view` with the depth visualization.
+  pub fn draw(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    target_view: &wgpu::TextureView,
+  ) {
+    let bind_group = self
+      .bind_group
+      .as_ref()
+      .expect("DepthToColor::set_input must be called before draw");
+
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Depth To Color Pass"),
+        color_attachments: &[Some(util::create_color_attachment(target_view))],
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+  }
+
+  fn write_params(&self, queue: &wgpu::Queue) {
+    let params = [self.near, self.far, self.max_distance, 0.];
+    queue.write_buffer(&self.params_uniform_buffer, 0, cast_slice(&params));
+  }
+}
@@ -0,0 +1,39 @@
+struct Params {
+  near: f32,
+  far: f32,
+  max_distance: f32,
+}
+
+// マルチサンプルのときは texture_depth_multisampled_2d に置き換えてコンパイルする
+@group(0) @binding(0) var depth_texture: texture_depth_2d;
+@group(0) @binding(1) var<uniform> params: Params;
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4f {
+  // 画面全体を覆う大きな三角形
+  var pos = array<vec2f, 3>(
+    vec2f(-1.0, -1.0),
+    vec2f( 3.0, -1.0),
+    vec2f(-1.0,  3.0),
+  );
+  return vec4f(pos[i], 0.0, 1.0);
+}
+
+// 深度バッファの値 [0, 1] からカメラまでの距離に戻す
+fn linearize_depth(d: f32) -> f32 {
+  let n = params.near;
+  let f = params.far;
+  return n * f / (f - d * (f - n));
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4f) -> @location(0) vec4f {
+  // 描画先と深度バッファは同じ大きさなので、ピクセル座標でそのまま読む
+  // （マルチサンプルでは3つ目の引数がサンプル番号になり、0番目のサンプルを読む）
+  let d = textureLoad(depth_texture, vec2i(position.xy), 0);
+  let z = linearize_depth(d);
+
+  // 手前ほど白く、max_distance より奥は黒
+  let t = saturate((z - params.near) / (params.max_distance - params.near));
+  return vec4f(vec3f(1.0 - t), 1.0);
+}
@@ -0,0 +1,196 @@
+use bytemuck::{Pod, Zeroable};
+use cgmath::{Matrix4, Point3, Transform};
+
+use crate::util;
+
+// 最初に確保する頂点数（足りなくなったら2倍ずつ増やす）
+const INITIAL_CAPACITY: usize = 256;
+
+const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+  wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x4];
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct LineVertex {
+  position: [f32; 3],
+  color: [f32; 4],
+}
+
+/// Immediate-mode debug lines (axes, bounding boxes, normals) in world
+/// space, drawn as a `LineList` over the scene.
+///
+/// Lines added with `add_*` are kept until `flush`, which uploads them to a
+/// vertex buffer (grown as needed) and starts the next frame's list empty.
+/// `draw` then draws the uploaded lines inside the scene's render pass.
+///
+/// The scene's matrix bind group is shared: group 0 binding 0 must start
+/// with `view_project_mat`, a `mat4x4f`, as in the examples' vertex shaders.
+///
+/// Depth is tested against `util::DEPTH_FORMAT` but not written, so lines
+/// are hidden behind surfaces without hiding anything themselves.
+pub struct LineRenderer {
+  pipeline: wgpu::RenderPipeline,
+  vertex_buffer: wgpu::Buffer,
+  capacity: usize,
+  vertex_count: u32,
+  vertices: Vec<LineVertex>,
+}
+
+impl LineRenderer {
+  pub fn new(
+    device: &wgpu::Device,
+    target_format: wgpu::TextureFormat,
+    sample_count: u32,
+    matrix_bind_group_layout: &wgpu::BindGroupLayout,
+  ) -> Self {
+    let shader =
+      device.create_shader_module(wgpu::include_wgsl!("./lines.wgsl"));
+
+    let pipeline_layout =
+      device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Line Renderer Pipeline Layout"),
+        bind_group_layouts: &[matrix_bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let pipeline =
+      device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Line Renderer Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<LineVertex>()
+              as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &VERTEX_ATTRIBUTES,
+          }],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &[Some(wgpu::ColorTargetState {
+            format: target_format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::ALL,
+          })],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+          topology: wgpu::PrimitiveTopology::LineList,
+          ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+          format: util::DEPTH_FORMAT,
+          depth_write_enabled: false,
+          depth_compare: wgpu::CompareFunction::LessEqual,
+          stencil: wgpu::StencilState::default(),
+          bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+          count: sample_count,
+          ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+      });
+
+    Self {
+      pipeline,
+      vertex_buffer: create_vertex_buffer(device, INITIAL_CAPACITY),
+      capacity: INITIAL_CAPACITY,
+      vertex_count: 0,
+      vertices: vec![],
+    }
+  }
+
+  pub fn add_line(&mut self, a: [f32; 3], b: [f32; 3], color: [f32; 4]) {
+    self.vertices.extend([
+      LineVertex { position: a, color },
+      LineVertex { position: b, color },
+    ]);
+  }
+
+  /// The 12 edges of the axis-aligned box from `min` to `max`.
+  pub fn add_box(&mut self, min: [f32; 3], max: [f32; 3], color: [f32; 4]) {
+    let corner = |i: usize| {
+      [
+        if i & 1 == 0 { min[0] } else { max[0] },
+        if i & 2 == 0 { min[1] } else { max[1] },
+        if i & 4 == 0 { min[2] } else { max[2] },
+      ]
+    };
+
+    // 角の番号のビットが1つだけ違う角どうしを結ぶ
+    for i in 0..8 {
+      for axis in [1, 2, 4] {
+        if i & axis == 0 {
+          self.add_line(corner(i), corner(i | axis), color);
+        }
+      }
+    }
+  }
+
+  /// Unit X, Y and Z axes (red, green, blue) moved by `transform`; scale it
+  /// to change their length.
+  pub fn add_axes(&mut self, transform: Matrix4<f32>) {
+    let origin = transform.transform_point(Point3::new(0., 0., 0.));
+    let axes = [
+      ([1., 0., 0.], [1., 0., 0., 1.]),
+      ([0., 1., 0.], [0., 1., 0., 1.]),
+      ([0., 0., 1.], [0., 0., 1., 1.]),
+    ];
+
+    for (end, color) in axes {
+      let end = transform.transform_point(Point3::from(end));
+      self.add_line(origin.into(), end.into(), color);
+    }
+  }
+
+  /// Uploads the lines added since the last flush for `draw` and clears the
+  /// list. Call once per frame, before the render pass.
+  pub fn flush(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+    if self.vertices.len() > self.capacity {
+      self.capacity = self.vertices.len().next_power_of_two();
+      self.vertex_buffer = create_vertex_buffer(device, self.capacity);
+    }
+
+    queue.write_buffer(
+      &self.vertex_buffer,
+      0,
+      bytemuck::cast_slice(&self.vertices),
+    );
+    self.vertex_count = self.vertices.len() as u32;
+    self.vertices.clear();
+  }
+
+  /// Draws the flushed lines; `matrix_bind_group` is the scene's group 0.
+  pub fn draw<'a>(
+    &'a self,
+    render_pass: &mut wgpu::RenderPass<'a>,
+    matrix_bind_group: &'a wgpu::BindGroup,
+  ) {
+    if self.vertex_count == 0 {
+      return;
+    }
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, matrix_bind_group, &[]);
+    render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+    render_pass.draw(0..self.vertex_count, 0..1);
+  }
+}
+
+fn create_vertex_buffer(
+  device: &wgpu::Device,
+  capacity: usize,
+) -> wgpu::Buffer {
+  device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("Line Renderer Vertex Buffer"),
+    size: (std::mem::size_of::<LineVertex>() * capacity) as wgpu::BufferAddress,
+    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  })
+}
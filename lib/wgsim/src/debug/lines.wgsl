@@ -0,0 +1,30 @@
+// 例題の頂点シェーダーと同じ行列のユニフォームの先頭だけを使う
+// （線はワールド座標で渡すので、モデル行列は要らない）
+struct Uniforms {
+  view_project_mat: mat4x4f,
+}
+
+@group(0) @binding(0) var<uniform> unif: Uniforms;
+
+struct Input {
+  @location(0) position: vec3f,
+  @location(1) color: vec4f,
+}
+
+struct Output {
+  @builtin(position) position: vec4f,
+  @location(0) color: vec4f,
+}
+
+@vertex
+fn vs_main(in: Input) -> Output {
+  var output: Output;
+  output.position = unif.view_project_mat * vec4f(in.position, 1.0);
+  output.color = in.color;
+  return output;
+}
+
+@fragment
+fn fs_main(in: Output) -> @location(0) vec4f {
+  return in.color;
+}
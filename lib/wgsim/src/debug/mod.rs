@@ -0,0 +1,7 @@
+mod depth_to_color;
+mod line_renderer;
+mod normals_to_color;
+
+pub use depth_to_color::DepthToColor;
+pub use line_renderer::LineRenderer;
+pub use normals_to_color::NormalsToColor;
@@ -0,0 +1,79 @@
+use crate::util;
+
+/// Draws a mesh with its world-space normals as RGB (`n * 0.5 + 0.5`, so
+/// +X is red, +Y green and +Z blue), for checking normals from the geometry
+/// generators: flat normals show one color per face, smooth normals a
+/// gradient. Zero-length normals are drawn magenta.
+///
+/// The pipeline is meant to stand in for the scene pipeline inside the same
+/// render pass, with the same vertex buffer and matrix bind group:
+///
+/// - the vertex buffer has `@location(0)` position and `@location(1)`
+///   normal, both `vec3f`; other attributes in the layout are ignored.
+/// - group 0 binding 0 holds `view_project_mat`, `model_mat` and
+///   `normal_mat`, three `mat4x4f` in this order.
+///
+/// Depth is tested against `util::DEPTH_FORMAT`.
+pub struct NormalsToColor {
+  pipeline: wgpu::RenderPipeline,
+}
+
+impl NormalsToColor {
+  pub fn new(
+    device: &wgpu::Device,
+    target_format: wgpu::TextureFormat,
+    sample_count: u32,
+    vertex_buffer_layout: wgpu::VertexBufferLayout,
+    matrix_bind_group_layout: &wgpu::BindGroupLayout,
+  ) -> Self {
+    let shader = device
+      .create_shader_module(wgpu::include_wgsl!("./normals_to_color.wgsl"));
+
+    let pipeline_layout =
+      device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Normals To Color Pipeline Layout"),
+        bind_group_layouts: &[matrix_bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let pipeline =
+      device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Normals To Color Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[vertex_buffer_layout],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &[Some(target_format.into())],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+          format: util::DEPTH_FORMAT,
+          depth_write_enabled: true,
+          depth_compare: wgpu::CompareFunction::LessEqual,
+          stencil: wgpu::StencilState::default(),
+          bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+          count: sample_count,
+          ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+      });
+
+    Self { pipeline }
+  }
+
+  /// Sets the pipeline; bind the matrices to group 0, set the vertex (and
+  /// index) buffer and draw as with the scene pipeline.
+  pub fn set_pipeline<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+    render_pass.set_pipeline(&self.pipeline);
+  }
+}
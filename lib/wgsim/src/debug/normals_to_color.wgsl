@@ -0,0 +1,39 @@
+// 例題の頂点シェーダーと同じ行列のユニフォーム
+struct Uniforms {
+  view_project_mat: mat4x4f,
+  model_mat: mat4x4f,
+  normal_mat: mat4x4f,
+}
+
+@group(0) @binding(0) var<uniform> unif: Uniforms;
+
+struct Input {
+  @location(0) position: vec3f,
+  @location(1) normal: vec3f,
+}
+
+struct Output {
+  @builtin(position) position: vec4f,
+  @location(0) world_normal: vec3f,
+}
+
+@vertex
+fn vs_main(in: Input) -> Output {
+  var output: Output;
+  output.position = unif.view_project_mat * unif.model_mat * vec4f(in.position, 1.0);
+  // w を 0 にして、平行移動の影響を受けないようにする
+  output.world_normal = (unif.normal_mat * vec4f(in.normal, 0.0)).xyz;
+  return output;
+}
+
+// ワールド座標の法線 [-1, 1] を色 [0, 1] に詰める（+X が赤、+Y が緑、+Z が青）
+@fragment
+fn fs_main(in: Output) -> @location(0) vec4f {
+  let len = length(in.world_normal);
+  // 長さ 0 の法線はマゼンタで目立たせる
+  if (len < 1e-6) {
+    return vec4f(1.0, 0.0, 1.0, 1.0);
+  }
+  let n = in.world_normal / len;
+  return vec4f(n * 0.5 + 0.5, 1.0);
+}
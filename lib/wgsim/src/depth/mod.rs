@@ -0,0 +1,3 @@
+mod resolve;
+
+pub use resolve::{DepthResolvePass, RESOLVED_DEPTH_FORMAT};
@@ -0,0 +1,154 @@
+use crate::{ctx::DrawingContext, util};
+
+/// Format of the resolved depth texture.
+pub const RESOLVED_DEPTH_FORMAT: wgpu::TextureFormat =
+  wgpu::TextureFormat::Depth32Float;
+
+/// Resolves a multisampled depth buffer into a single-sample depth texture
+/// that post-effects (DoF, SSAO, ...) can sample.
+///
+/// Resolve attachments only work for color, so this is done by hand: a
+/// full-screen pass loads every sample with `textureLoad` and writes the
+/// minimum (nearest) depth through `frag_depth`. Taking the minimum keeps
+/// silhouettes on the foreground object, matching a `Less`/`LessEqual` depth
+/// test.
+///
+/// The cost is one extra full-screen pass reading `sample_count` depth values
+/// per pixel, plus a `Depth32Float` texture the size of the target. The MSAA
+/// depth buffer must be created with `util::create_sampleable_depth_view` and
+/// stored at the end of the scene pass
+/// (`util::create_stored_depth_stencil_attachment`) instead of discarded.
+///
+/// Without MSAA no resolve is needed: sample the depth buffer directly.
+pub struct DepthResolvePass {
+  pipeline: wgpu::RenderPipeline,
+  bind_group_layout: wgpu::BindGroupLayout,
+  bind_group: wgpu::BindGroup,
+  resolved_view: wgpu::TextureView,
+}
+
+impl DepthResolvePass {
+  pub fn new(
+    ctx: &DrawingContext,
+    msaa_depth_view: &wgpu::TextureView,
+  ) -> Self {
+    let shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./resolve.wgsl"));
+
+    let bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[wgpu::BindingType::Texture {
+        sample_type: wgpu::TextureSampleType::Depth,
+        view_dimension: wgpu::TextureViewDimension::D2,
+        multisampled: true,
+      }],
+      &[wgpu::ShaderStages::FRAGMENT],
+    );
+
+    let pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Depth Resolve Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let pipeline =
+      ctx.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Depth Resolve Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &[],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+          format: RESOLVED_DEPTH_FORMAT,
+          depth_write_enabled: true,
+          depth_compare: wgpu::CompareFunction::Always,
+          stencil: wgpu::StencilState::default(),
+          bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+      });
+
+    let bind_group = util::create_bind_group(
+      &ctx.device,
+      &bind_group_layout,
+      &[wgpu::BindingResource::TextureView(msaa_depth_view)],
+    );
+
+    Self {
+      pipeline,
+      bind_group_layout,
+      bind_group,
+      resolved_view: create_resolved_view(ctx),
+    }
+  }
+
+  /// Call after the MSAA depth buffer has been recreated.
+  pub fn resize(
+    &mut self,
+    ctx: &DrawingContext,
+    msaa_depth_view: &wgpu::TextureView,
+  ) {
+    self.bind_group = util::create_bind_group(
+      &ctx.device,
+      &self.bind_group_layout,
+      &[wgpu::BindingResource::TextureView(msaa_depth_view)],
+    );
+    self.resolved_view = create_resolved_view(ctx);
+  }
+
+  /// Single-sample depth, valid after `resolve` has been recorded.
+  pub fn resolved_view(&self) -> &wgpu::TextureView {
+    &self.resolved_view
+  }
+
+  pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Depth Resolve Pass"),
+        color_attachments: &[],
+        depth_stencil_attachment: Some(
+          util::create_stored_depth_stencil_attachment(&self.resolved_view),
+        ),
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, &self.bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+  }
+}
+
+fn create_resolved_view(ctx: &DrawingContext) -> wgpu::TextureView {
+  let size = ctx.size();
+
+  let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("Resolved Depth Texture"),
+    size: wgpu::Extent3d {
+      width: size.width,
+      height: size.height,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format: RESOLVED_DEPTH_FORMAT,
+    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+      | wgpu::TextureUsages::TEXTURE_BINDING,
+    view_formats: &[],
+  });
+
+  texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
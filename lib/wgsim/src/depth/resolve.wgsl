@@ -0,0 +1,26 @@
+@group(0) @binding(0) var msaa_depth: texture_depth_multisampled_2d;
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4f {
+  // 画面全体を覆う大きな三角形
+  var pos = array<vec2f, 3>(
+    vec2f(-1.0, -1.0),
+    vec2f( 3.0, -1.0),
+    vec2f(-1.0,  3.0),
+  );
+
+  return vec4f(pos[i], 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4f) -> @builtin(frag_depth) f32 {
+  let coord = vec2i(position.xy);
+  let sample_count = i32(textureNumSamples(msaa_depth));
+
+  // 最も手前のサンプルを採用する
+  var depth = 1.0;
+  for (var i = 0; i < sample_count; i++) {
+    depth = min(depth, textureLoad(msaa_depth, coord, i));
+  }
+  return depth;
+}
@@ -0,0 +1,137 @@
+use std::error::Error;
+use std::path::Path;
+
+use super::readback::{save_png, RowPadding};
+use crate::{
+  ctx::DrawingContext,
+  render::{Render, RenderTarget},
+};
+
+/// Saves what `renderer` currently shows as a PNG at `path`.
+///
+/// The swapchain texture cannot be copied from (it is only a
+/// `RENDER_ATTACHMENT`), so the frame is drawn once more into a `COPY_SRC`
+/// texture with the surface's format and `ctx.size()`, and read back from
+/// there. Call it after `Render::update` so the capture matches the frame
+/// on screen. Only 8-bit RGBA/BGRA formats are supported.
+pub async fn capture_surface<'a, R>(
+  ctx: &DrawingContext<'a>,
+  renderer: &mut R,
+  path: &str,
+) -> Result<(), Box<dyn Error>>
+where
+  R: Render<'a>,
+{
+  let data = read_frame(ctx, renderer).await?;
+
+  let size = ctx.size();
+  save_png(Path::new(path), &data, size.width, size.height)?;
+
+  println!("Screenshot has been saved to {}", path);
+
+  Ok(())
+}
+
+/// Draws one frame into a `COPY_SRC` texture of `ctx.size()` and returns
+/// its pixels as tightly packed RGBA rows.
+pub(crate) async fn read_frame<'a, R>(
+  ctx: &DrawingContext<'a>,
+  renderer: &mut R,
+) -> Result<Vec<u8>, Box<dyn Error>>
+where
+  R: Render<'a>,
+{
+  let format = ctx.format();
+  let is_bgra = match format {
+    wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => {
+      false
+    }
+    wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+      true
+    }
+    _ => return Err(format!("cannot read back a {:?} frame", format).into()),
+  };
+
+  let size = *ctx.size();
+  let extent = wgpu::Extent3d {
+    width: size.width,
+    height: size.height,
+    depth_or_array_layers: 1,
+  };
+
+  let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("Capture Texture"),
+    size: extent,
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format,
+    usage: wgpu::TextureUsages::COPY_SRC
+      | wgpu::TextureUsages::RENDER_ATTACHMENT,
+    view_formats: &[],
+  });
+
+  let row_padding = RowPadding::new(size.width);
+  let output_buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("Capture Buffer"),
+    size: (row_padding.padded_bytes_per_row * size.height)
+      as wgpu::BufferAddress,
+    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+    mapped_at_creation: false,
+  });
+
+  let mut command_encoder = ctx
+    .device
+    .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+  renderer.draw(
+    &mut command_encoder,
+    RenderTarget::Texture(&texture),
+    ctx.sample_count,
+  )?;
+
+  command_encoder.copy_texture_to_buffer(
+    wgpu::ImageCopyTexture {
+      texture: &texture,
+      mip_level: 0,
+      origin: wgpu::Origin3d::ZERO,
+      aspect: wgpu::TextureAspect::All,
+    },
+    wgpu::ImageCopyBuffer {
+      buffer: &output_buffer,
+      layout: wgpu::ImageDataLayout {
+        offset: 0,
+        bytes_per_row: Some(row_padding.padded_bytes_per_row),
+        rows_per_image: Some(size.height),
+      },
+    },
+    extent,
+  );
+
+  renderer.submit(&ctx.queue, command_encoder, None);
+
+  let buffer_slice = output_buffer.slice(..);
+  let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+  buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+    tx.send(result).unwrap();
+  });
+  ctx.device.poll(wgpu::Maintain::Wait);
+
+  match rx.receive().await {
+    Some(Ok(())) => {}
+    Some(Err(e)) => return Err(e.into()),
+    None => return Err("capture buffer was never mapped".into()),
+  }
+
+  let padded_data = buffer_slice.get_mapped_range();
+  let mut data = row_padding.strip(&padded_data);
+  drop(padded_data);
+  output_buffer.unmap();
+
+  // 呼び出し側はRGBAの順で扱うので、BGRAのサーフェスは並べ替える
+  if is_bgra {
+    data.chunks_exact_mut(4).for_each(|pixel| pixel.swap(0, 2));
+  }
+
+  Ok(data)
+}
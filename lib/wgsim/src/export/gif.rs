@@ -1,19 +1,40 @@
 use std::error::Error;
+use std::path::{Path, PathBuf};
 
 use indicatif::{ProgressBar, ProgressStyle};
 
+use super::readback::{save_png, RowPadding};
 use crate::{
-  ctx::{DrawingContext, Size},
-  render::{Render, RenderTarget},
+  ctx::{ContextError, DrawingContext, Size},
+  render::{Render, RenderTarget, EXPORT_FRAME_RATE},
 };
 
+/// Delay (in 1/100 s) used by `export_with_delays` for scenes that have no
+/// entry in `delays`. Matches the pace `Render::update_frame` animates at.
+pub const DEFAULT_FRAME_DELAY: u16 = (100. / EXPORT_FRAME_RATE) as u16;
+
+/// How `Gif::save_gif` encodes the frames.
+struct EncodeOptions<'d> {
+  width: u16,
+  height: u16,
+  /// Quantization speed of `gif::Frame::from_rgba_speed`, 1 (best) to 30.
+  speed: i32,
+  /// Delay of each frame in 1/100 s, by index.
+  delays: &'d [u16],
+  /// Delay of the frames past the end of `delays`.
+  default_delay: u16,
+}
+
 pub struct Gif<'a, R>
 where
   R: Render<'a>,
 {
   renderer: R,
-  size: u32,
+  initial: R::Initial,
+  width: u32,
+  height: u32,
   sample_count: u32,
+  clear_color: wgpu::Color,
   ctx: DrawingContext<'a>,
 }
 
@@ -21,42 +42,131 @@ impl<'a, R> Gif<'a, R>
 where
   R: Render<'a>,
 {
-  pub async fn new(size: u32, initial: R::Initial, msaa: bool) -> Self {
-    let sample_count = if msaa { 4 } else { 1 };
+  /// Square output, `size` x `size`.
+  ///
+  /// With `msaa`, the context gets a sample count of 4 and `Render::draw`
+  /// receives it, so the renderer draws into its own MSAA view (see
+  /// `util::create_msaa_texture_view`) and resolves into the exported
+  /// texture through `util::create_msaa_color_attachment`. Frames are read
+  /// back from that resolved texture, so they keep the antialiasing seen on
+  /// screen.
+  pub async fn new(
+    size: u32,
+    initial: R::Initial,
+    msaa: bool,
+  ) -> Result<Self, ContextError> {
+    Self::new_with_size(size, size, initial, msaa).await
+  }
 
-    let ctx = DrawingContext::new_for_texture(
-      Size::new(size, size),
-      wgpu::TextureFormat::Rgba8UnormSrgb,
-    )
-    .await
-    .with_sample_count(sample_count);
+  /// GIF dimensions are 16-bit, so `width` and `height` must not exceed
+  /// 65535.
+  pub async fn new_with_size(
+    width: u32,
+    height: u32,
+    initial: R::Initial,
+    msaa: bool,
+  ) -> Result<Self, ContextError> {
+    assert!(
+      width <= u16::MAX as u32 && height <= u16::MAX as u32,
+      "GIF size {}x{} exceeds 65535",
+      width,
+      height
+    );
+    let sample_count = if msaa { 4 } else { 1 };
 
+    let clear_color = wgpu::Color::BLACK;
+    let ctx =
+      Self::create_ctx(width, height, sample_count, clear_color).await?;
     let renderer = R::new(&ctx, &initial).await;
 
-    Self {
+    Ok(Self {
       renderer,
-      size,
+      initial,
+      width,
+      height,
       sample_count,
+      clear_color,
       ctx,
-    }
+    })
+  }
+
+  /// Background for the exported frames, stored as
+  /// `DrawingContext::clear_color`. The renderer is rebuilt so it picks the
+  /// color up in `Render::new`.
+  pub async fn with_clear_color(mut self, color: wgpu::Color) -> Self {
+    self.clear_color = color;
+    self.ctx.clear_color = color;
+    self.renderer = R::new(&self.ctx, &self.initial).await;
+    self
+  }
+
+  async fn create_ctx(
+    width: u32,
+    height: u32,
+    sample_count: u32,
+    clear_color: wgpu::Color,
+  ) -> Result<DrawingContext<'a>, ContextError> {
+    let ctx = DrawingContext::new_for_texture(
+      Size::new(width, height),
+      wgpu::TextureFormat::Rgba8UnormSrgb,
+    )
+    .await?;
+
+    Ok(ctx.with_sample_count(sample_count).with_clear_color(clear_color))
+  }
+
+  /// Rebuilds the context and the renderer so a long recording can carry on
+  /// after a device loss. Frames captured so far are kept.
+  async fn recover_from_device_loss(&mut self) -> Result<(), ContextError> {
+    log::warn!("Recreating the drawing context after device loss");
+
+    self.ctx = Self::create_ctx(
+      self.width,
+      self.height,
+      self.sample_count,
+      self.clear_color,
+    )
+    .await?;
+    let renderer = R::new(&self.ctx, &self.initial).await;
+    self.renderer = renderer;
+
+    Ok(())
+  }
+
+  fn create_readback_targets(
+    &self,
+    texture_desc: &wgpu::TextureDescriptor,
+    buffer_desc: &wgpu::BufferDescriptor,
+  ) -> (wgpu::Texture, wgpu::Buffer) {
+    let texture = self.ctx.device.create_texture(texture_desc);
+    let buffer = self.ctx.device.create_buffer(buffer_desc);
+    (texture, buffer)
   }
 
   fn save_gif(
     &self,
     file_path: &str,
-    frames: &mut Vec<Vec<u8>>,
-    speed: i32,
-    size: u16,
+    frames: &mut [Vec<u8>],
+    options: &EncodeOptions,
   ) -> Result<(), Box<dyn Error>> {
     use gif::{Encoder, Frame, Repeat};
 
+    let EncodeOptions {
+      width,
+      height,
+      speed,
+      delays,
+      default_delay,
+    } = *options;
+
     let mut image = std::fs::File::create(file_path)?;
-    let mut encoder = Encoder::new(&mut image, size, size, &[])?;
+    let mut encoder = Encoder::new(&mut image, width, height, &[])?;
     encoder.set_repeat(Repeat::Infinite)?;
 
-    for mut frame in frames {
-      encoder
-        .write_frame(&Frame::from_rgba_speed(size, size, &mut frame, speed))?;
+    for (i, data) in frames.iter_mut().enumerate() {
+      let mut frame = Frame::from_rgba_speed(width, height, data, speed);
+      frame.delay = delays.get(i).copied().unwrap_or(default_delay);
+      encoder.write_frame(&frame)?;
     }
 
     Ok(())
@@ -67,6 +177,108 @@ where
     file_path: &str,
     scene_count: usize,
     speed: i32,
+  ) -> Result<(), Box<dyn Error>> {
+    let mut frames = Vec::new();
+    self
+      .render_frames(scene_count, |_, data| {
+        frames.push(data);
+        Ok(())
+      })
+      .await?;
+
+    self.save_gif(
+      file_path,
+      &mut frames,
+      &EncodeOptions {
+        width: self.width as u16,
+        height: self.height as u16,
+        speed,
+        delays: &[],
+        default_delay: 0,
+      },
+    )?;
+
+    println!("Gif has been saved to {}", file_path);
+
+    Ok(())
+  }
+
+  /// Like `export`, but `delays[i]` sets how long scene `i` stays on screen,
+  /// in 1/100 s. Scenes past the end of `delays` use `DEFAULT_FRAME_DELAY`,
+  /// so e.g. a longer last entry holds the end of a loop. `delays` longer
+  /// than `scene_count` is rejected rather than silently truncated.
+  pub async fn export_with_delays(
+    &mut self,
+    file_path: &str,
+    scene_count: usize,
+    speed: i32,
+    delays: &[u16],
+  ) -> Result<(), Box<dyn Error>> {
+    if delays.len() > scene_count {
+      return Err(
+        format!("{} delays given for {} scenes", delays.len(), scene_count)
+          .into(),
+      );
+    }
+
+    let mut frames = Vec::new();
+    self
+      .render_frames(scene_count, |_, data| {
+        frames.push(data);
+        Ok(())
+      })
+      .await?;
+
+    self.save_gif(
+      file_path,
+      &mut frames,
+      &EncodeOptions {
+        width: self.width as u16,
+        height: self.height as u16,
+        speed,
+        delays,
+        default_delay: DEFAULT_FRAME_DELAY,
+      },
+    )?;
+
+    println!("Gif has been saved to {}", file_path);
+
+    Ok(())
+  }
+
+  /// Renders `scene_count` frames like `export`, but writes each one to
+  /// `dir/{prefix}_{i:04}.png` (e.g. to feed ffmpeg) instead of encoding a
+  /// GIF. `dir` is created if missing. Returns the written paths in order.
+  pub async fn export_png_sequence(
+    &mut self,
+    dir: &str,
+    scene_count: usize,
+    prefix: &str,
+  ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    std::fs::create_dir_all(dir)?;
+
+    let (width, height) = (self.width, self.height);
+    let mut paths = Vec::with_capacity(scene_count);
+    self
+      .render_frames(scene_count, |i, data| {
+        let path = Path::new(dir).join(format!("{}_{:04}.png", prefix, i));
+        save_png(&path, &data, width, height)?;
+        paths.push(path);
+        Ok(())
+      })
+      .await?;
+
+    println!("{} frames have been saved to {}", paths.len(), dir);
+
+    Ok(paths)
+  }
+
+  /// Renders the frames one by one and hands each one, as tightly packed
+  /// RGBA rows, to `on_frame` together with its index.
+  async fn render_frames(
+    &mut self,
+    scene_count: usize,
+    mut on_frame: impl FnMut(usize, Vec<u8>) -> Result<(), Box<dyn Error>>,
   ) -> Result<(), Box<dyn Error>> {
     let progress = ProgressBar::new(scene_count as u64);
     progress.set_style(
@@ -79,8 +291,8 @@ where
 
     let texture_desc = wgpu::TextureDescriptor {
       size: wgpu::Extent3d {
-        width: self.size,
-        height: self.size,
+        width: self.width,
+        height: self.height,
         depth_or_array_layers: 1,
       },
       mip_level_count: 1,
@@ -92,34 +304,32 @@ where
       label: None,
       view_formats: &[],
     };
-    let texture = self.ctx.device.create_texture(&texture_desc);
 
-    let pixel_size = std::mem::size_of::<[u8; 4]>() as u32;
-    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-    let unpadded_bytes_per_row = pixel_size * self.size;
-    let padding = (align - unpadded_bytes_per_row % align) % align;
-    let padded_bytes_per_row = unpadded_bytes_per_row + padding;
+    let row_padding = RowPadding::new(self.width);
 
-    let buffer_size = (padded_bytes_per_row * self.size) as wgpu::BufferAddress;
+    let buffer_size =
+      (row_padding.padded_bytes_per_row * self.height) as wgpu::BufferAddress;
     let buffer_desc = wgpu::BufferDescriptor {
       size: buffer_size,
       usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
       label: Some("Output Buffer"),
       mapped_at_creation: false,
     };
-    let output_buffer = self.ctx.device.create_buffer(&buffer_desc);
+    let (mut texture, mut output_buffer) =
+      self.create_readback_targets(&texture_desc, &buffer_desc);
 
-    let mut frames = Vec::new();
-    let render_start_time = std::time::Instant::now();
+    for frame_index in 0..scene_count {
+      if self.ctx.is_device_lost() {
+        self.recover_from_device_loss().await?;
+        (texture, output_buffer) =
+          self.create_readback_targets(&texture_desc, &buffer_desc);
+      }
 
-    for _ in 0..scene_count {
       let mut command_encoder = self.ctx.device.create_command_encoder(
         &wgpu::CommandEncoderDescriptor { label: None },
       );
 
-      let now = std::time::Instant::now();
-      let dt = now - render_start_time;
-      self.renderer.update(&self.ctx, dt);
+      self.renderer.update_frame(&self.ctx, frame_index, scene_count);
 
       self.renderer.draw(
         &mut command_encoder,
@@ -138,8 +348,8 @@ where
           buffer: &output_buffer,
           layout: wgpu::ImageDataLayout {
             offset: 0,
-            bytes_per_row: Some(padded_bytes_per_row),
-            rows_per_image: Some(self.size),
+            bytes_per_row: Some(row_padding.padded_bytes_per_row),
+            rows_per_image: Some(self.height),
           },
         },
         texture_desc.size,
@@ -157,15 +367,10 @@ where
       match rx.receive().await {
         Some(Ok(())) => {
           let padded_data = buffer_slice.get_mapped_range();
-          let data = padded_data
-            .chunks(padded_bytes_per_row as _)
-            .map(|chunk| &chunk[..unpadded_bytes_per_row as _])
-            .flatten()
-            .map(|x| *x)
-            .collect::<Vec<_>>();
+          let data = row_padding.strip(&padded_data);
           drop(padded_data);
           output_buffer.unmap();
-          frames.push(data);
+          on_frame(frame_index, data)?;
         }
         _ => eprintln!("Something went wrong"),
       }
@@ -175,10 +380,201 @@ where
 
     progress.finish_with_message("All scenes have been rendered 🎉");
 
-    self.save_gif(file_path, &mut frames, speed, self.size as u16)?;
+    Ok(())
+  }
+}
 
-    println!("Gif has been saved to {}", file_path);
+#[cfg(test)]
+mod tests {
+  use super::*;
 
-    Ok(())
+  /// Clears the frame to red with the frame index as intensity, and
+  /// remembers which frames `update_frame` was called for.
+  struct ClearRenderer {
+    frame_index: usize,
+    total_frames: usize,
+    seen: Vec<usize>,
+  }
+
+  impl<'a> Render<'a> for ClearRenderer {
+    type Initial = ();
+
+    async fn new(_ctx: &DrawingContext<'a>, _initial: &()) -> Self {
+      Self {
+        frame_index: 0,
+        total_frames: 1,
+        seen: vec![],
+      }
+    }
+
+    fn update_frame(
+      &mut self,
+      _ctx: &DrawingContext,
+      frame_index: usize,
+      total_frames: usize,
+    ) {
+      self.frame_index = frame_index;
+      self.total_frames = total_frames;
+      self.seen.push(frame_index);
+    }
+
+    fn draw(
+      &mut self,
+      encoder: &mut wgpu::CommandEncoder,
+      target: RenderTarget,
+      _sample_count: u32,
+    ) -> Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+      let RenderTarget::Texture(texture) = target else {
+        unreachable!("GIF export draws into textures");
+      };
+      let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+      let red = self.frame_index as f64 / (self.total_frames - 1).max(1) as f64;
+
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view: &view,
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Clear(wgpu::Color {
+              r: red,
+              g: 0.,
+              b: 0.,
+              a: 1.,
+            }),
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        ..Default::default()
+      });
+
+      Ok(None)
+    }
+  }
+
+  #[test]
+  fn render_frames_renders_every_scene() {
+    let (width, height) = (8, 4);
+    let mut gif: Gif<ClearRenderer> =
+      pollster::block_on(Gif::new_with_size(width, height, (), false))
+        .expect("no GPU adapter for offscreen rendering");
+
+    let mut frames = vec![];
+    pollster::block_on(gif.render_frames(10, |i, data| {
+      frames.push((i, data));
+      Ok(())
+    }))
+    .unwrap();
+
+    assert_eq!(frames.len(), 10);
+    assert_eq!(gif.renderer.seen, (0..10).collect::<Vec<_>>());
+    for (expected, (i, data)) in frames.iter().enumerate() {
+      assert_eq!(*i, expected);
+      assert_eq!(data.len(), (width * height * 4) as usize);
+    }
+
+    // 最初のフレームは黒、最後のフレームは赤で塗られている
+    assert_eq!(&frames[0].1[..4], &[0, 0, 0, 255]);
+    assert_eq!(&frames[9].1[..4], &[255, 0, 0, 255]);
+  }
+  // 左下半分を赤く塗る三角形（辺は画素の格子に沿わない斜め）
+  const EDGE_SHADER: &str = "
+    @vertex
+    fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4f {
+      var pos = array(vec2f(-1., -1.), vec2f(1., -1.), vec2f(-1., 0.7));
+      return vec4f(pos[i], 0., 1.);
+    }
+
+    @fragment
+    fn fs_main() -> @location(0) vec4f {
+      return vec4f(1., 0., 0., 1.);
+    }
+  ";
+
+  /// Draws one red triangle with a diagonal edge, through an MSAA view
+  /// when the context has a sample count above 1.
+  struct EdgeRenderer {
+    pipeline: wgpu::RenderPipeline,
+    msaa_view: Option<wgpu::TextureView>,
+  }
+
+  impl<'a> Render<'a> for EdgeRenderer {
+    type Initial = ();
+
+    async fn new(ctx: &DrawingContext<'a>, _initial: &()) -> Self {
+      let module =
+        ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+          label: None,
+          source: wgpu::ShaderSource::Wgsl(EDGE_SHADER.into()),
+        });
+      let pipeline = crate::ppl::RenderPipelineBuilder::new(ctx)
+        .vs_shader(&module, "vs_main")
+        .fs_shader(&module, "fs_main")
+        .build();
+      let msaa_view = (ctx.sample_count > 1)
+        .then(|| crate::util::create_msaa_texture_view(ctx));
+
+      Self {
+        pipeline,
+        msaa_view,
+      }
+    }
+
+    fn draw(
+      &mut self,
+      encoder: &mut wgpu::CommandEncoder,
+      target: RenderTarget,
+      _sample_count: u32,
+    ) -> Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+      let RenderTarget::Texture(texture) = target else {
+        unreachable!("GIF export draws into textures");
+      };
+      let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+      let color_attachment = match &self.msaa_view {
+        Some(msaa_view) => {
+          crate::util::create_msaa_color_attachment(&view, msaa_view)
+        }
+        None => crate::util::create_color_attachment(&view),
+      };
+
+      let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(color_attachment)],
+        ..Default::default()
+      });
+      pass.set_pipeline(&self.pipeline);
+      pass.draw(0..3, 0..1);
+      drop(pass);
+
+      Ok(None)
+    }
+  }
+
+  /// Red channel of the first frame exported with or without MSAA.
+  fn render_edge(msaa: bool) -> Vec<u8> {
+    let mut gif: Gif<EdgeRenderer> = pollster::block_on(Gif::new(16, (), msaa))
+      .expect("no GPU adapter for offscreen rendering");
+
+    let mut red = vec![];
+    pollster::block_on(gif.render_frames(1, |_, data| {
+      red = data.chunks_exact(4).map(|pixel| pixel[0]).collect();
+      Ok(())
+    }))
+    .unwrap();
+    red
+  }
+
+  #[test]
+  fn msaa_export_keeps_antialiased_edges() {
+    let is_partial = |value: &u8| *value != 0 && *value != 255;
+
+    // MSAA なしでは、どの画素も塗られているかいないかのどちらか
+    let aliased = render_edge(false);
+    assert!(!aliased.iter().any(is_partial));
+    assert!(aliased.contains(&255));
+
+    // MSAA ありでは、辺にかかる画素が中間の色になる
+    let antialiased = render_edge(true);
+    assert!(antialiased.iter().any(is_partial));
   }
 }
@@ -1,3 +1,7 @@
+mod capture;
 mod gif;
+mod readback;
 
-pub use gif::Gif;
+pub use capture::capture_surface;
+pub(crate) use capture::read_frame;
+pub use gif::{Gif, DEFAULT_FRAME_DELAY};
@@ -0,0 +1,53 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Rows copied from a texture into a buffer have to start at multiples of
+/// `COPY_BYTES_PER_ROW_ALIGNMENT`, so each row is followed by some padding.
+pub(super) struct RowPadding {
+  pub unpadded_bytes_per_row: u32,
+  pub padded_bytes_per_row: u32,
+}
+
+impl RowPadding {
+  /// For 4-byte pixels.
+  pub fn new(width: u32) -> Self {
+    let pixel_size = std::mem::size_of::<[u8; 4]>() as u32;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let unpadded_bytes_per_row = pixel_size * width;
+    let padding = (align - unpadded_bytes_per_row % align) % align;
+
+    Self {
+      unpadded_bytes_per_row,
+      padded_bytes_per_row: unpadded_bytes_per_row + padding,
+    }
+  }
+
+  /// Returns the pixels as tightly packed rows.
+  pub fn strip(&self, padded_data: &[u8]) -> Vec<u8> {
+    padded_data
+      .chunks(self.padded_bytes_per_row as _)
+      .flat_map(|chunk| &chunk[..self.unpadded_bytes_per_row as _])
+      .copied()
+      .collect()
+  }
+}
+
+pub(super) fn save_png(
+  path: &Path,
+  data: &[u8],
+  width: u32,
+  height: u32,
+) -> Result<(), Box<dyn Error>> {
+  let file = File::create(path)?;
+  let w = &mut BufWriter::new(file);
+
+  let mut png_encoder = png::Encoder::new(w, width, height);
+  png_encoder.set_color(png::ColorType::Rgba);
+
+  let mut writer = png_encoder.write_header()?;
+  writer.write_image_data(data)?;
+
+  Ok(())
+}
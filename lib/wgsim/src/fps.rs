@@ -3,6 +3,13 @@ use std::{
   time::{Duration, Instant},
 };
 
+use crate::ctx::DrawingContext;
+use crate::overlay::{Anchor, TextPanel};
+
+/// How often `FpsOverlay` rebuilds its text. Regenerating the glyph mesh
+/// every frame would cost more than the frame it measures.
+const OVERLAY_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
 #[derive(Debug)]
 pub struct FpsCounter {
   last_second_frames: VecDeque<Instant>,
@@ -23,7 +30,8 @@ impl FpsCounter {
     }
   }
 
-  pub fn print_fps(&mut self, interval: u64) {
+  /// Records a frame and drops the ones older than a second.
+  pub fn tick(&mut self) {
     let now = Instant::now();
     let a_second_ago = now - Duration::from_secs(1);
 
@@ -32,7 +40,26 @@ impl FpsCounter {
     }
 
     self.last_second_frames.push_back(now);
+  }
+
+  /// Average frame rate over the frames of the last second.
+  pub fn fps(&self) -> f32 {
+    match (
+      self.last_second_frames.front(),
+      self.last_second_frames.back(),
+    ) {
+      (Some(first), Some(last)) if last > first => {
+        let span = (*last - *first).as_secs_f32();
+        (self.last_second_frames.len() - 1) as f32 / span
+      }
+      _ => 0.,
+    }
+  }
+
+  pub fn print_fps(&mut self, interval: u64) {
+    self.tick();
 
+    let now = Instant::now();
     if now - self.last_print_time >= Duration::from_secs(interval) {
       let fps = self.last_second_frames.len();
       println!("FPS: {}", fps);
@@ -40,3 +67,61 @@ impl FpsCounter {
     }
   }
 }
+
+/// The frame rate in the top-right corner of the surface, drawn by `App`
+/// after the renderer's own passes. See `App::with_fps_overlay`.
+pub struct FpsOverlay {
+  counter: FpsCounter,
+  panel: TextPanel,
+  last_refresh_time: Instant,
+}
+
+impl FpsOverlay {
+  pub fn new(ctx: &DrawingContext, font: &'static [u8]) -> Self {
+    let mut panel = TextPanel::new(ctx, font, vec![fps_line(0.)])
+      .with_anchor(ctx, Anchor::TopRight);
+    panel.resize_to(ctx, *ctx.surface_size());
+
+    Self {
+      counter: FpsCounter::new(),
+      panel,
+      last_refresh_time: Instant::now(),
+    }
+  }
+
+  pub fn fps(&self) -> f32 {
+    self.counter.fps()
+  }
+
+  /// Call once per presented frame.
+  pub fn update(&mut self, ctx: &DrawingContext) {
+    self.counter.tick();
+
+    let now = Instant::now();
+    if now - self.last_refresh_time >= OVERLAY_REFRESH_INTERVAL {
+      let line = fps_line(self.counter.fps());
+      if self.panel.lines()[0] != line {
+        self.panel.set_lines(ctx, vec![line]);
+      }
+      self.last_refresh_time = now;
+    }
+  }
+
+  /// The overlay covers the whole surface, even when the renderer draws at
+  /// a reduced resolution.
+  pub fn resize(&mut self, ctx: &DrawingContext) {
+    self.panel.resize_to(ctx, *ctx.surface_size());
+  }
+
+  pub fn draw(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    view: &wgpu::TextureView,
+  ) {
+    self.panel.draw(encoder, view);
+  }
+}
+
+fn fps_line(fps: f32) -> String {
+  format!("{:.0} FPS", fps)
+}
@@ -0,0 +1,72 @@
+use bytemuck::{Pod, Zeroable};
+use cgmath::*;
+
+const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![
+  0 => Float32x3,
+  1 => Float32x3,
+  2 => Float32x3
+];
+
+/// Vertex of `with_barycentric`: the corner's position, the face normal and
+/// its barycentric coordinate within the triangle.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct BarycentricVertex {
+  pub position: [f32; 3],
+  pub normal: [f32; 3],
+  pub barycentric: [f32; 3],
+}
+
+impl BarycentricVertex {
+  /// `@location(0)` position, `@location(1)` normal and `@location(2)`
+  /// barycentric, all `vec3f`.
+  pub fn vertex_buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+      array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+      step_mode: wgpu::VertexStepMode::Vertex,
+      attributes: &VERTEX_ATTRIBUTES,
+    }
+  }
+}
+
+/// Unwelds the triangles and gives their corners the barycentric
+/// coordinates `(1, 0, 0)`, `(0, 1, 0)` and `(0, 0, 1)`, for drawing a
+/// wireframe in the fragment shader of the same pass: a fragment lies on an
+/// edge when the smallest interpolated component is close to 0, measured in
+/// pixels with `fwidth`.
+///
+/// Every triangle edge is drawn, including the diagonals of quads. Like
+/// `compute_flat_normals`, the result has `3 * triangle count` vertices
+/// carrying the face normal.
+pub fn with_barycentric(
+  positions: &[[f32; 3]],
+  indices: &[u32],
+) -> (Vec<BarycentricVertex>, Vec<u32>) {
+  const CORNERS: [[f32; 3]; 3] = [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+
+  let mut vertices = Vec::with_capacity(indices.len());
+
+  for tri in indices.chunks_exact(3) {
+    let corners = [0, 1, 2].map(|k| positions[tri[k] as usize]);
+    let [a, b, c] = corners.map(Vector3::from);
+    let face_normal = (b - a).cross(c - a);
+    let normal = if face_normal.magnitude2() > 0. {
+      face_normal.normalize().into()
+    } else {
+      [0., 0., 0.]
+    };
+
+    for (position, barycentric) in corners.into_iter().zip(CORNERS) {
+      vertices.push(BarycentricVertex {
+        position,
+        normal,
+        barycentric,
+      });
+    }
+  }
+
+  // 頂点を三角形ごとに分けたので、インデックスは連番になる
+  let indices = (0..vertices.len() as u32).collect();
+
+  (vertices, indices)
+}
@@ -0,0 +1,146 @@
+use cgmath::*;
+
+pub struct Capsule {
+  pub positions: Vec<[f32; 3]>,
+  pub normals: Vec<[f32; 3]>,
+  pub indices: Vec<u16>,
+  pub indices_wireframe: Vec<u16>,
+}
+
+/// `theta` is measured from +Y, as in `create_sphere_data`; `center_y` is
+/// the center of the hemisphere the point belongs to.
+fn capsule_vertex(
+  r: f32,
+  center_y: f32,
+  theta: Deg<f32>,
+  phi: Deg<f32>,
+) -> ([f32; 3], [f32; 3]) {
+  let normal = [
+    theta.sin() * phi.cos(),
+    theta.cos(),
+    -theta.sin() * phi.sin(),
+  ];
+  let [nx, ny, nz] = normal;
+  ([r * nx, center_y + r * ny, r * nz], normal)
+}
+
+/// Capsule along the Y axis: a cylinder of `cylinder_height` with a
+/// hemisphere of `radius` on each end, so the total height is
+/// `cylinder_height + 2 * radius`. Each hemisphere has `rings` rings and
+/// every ring `segments` segments.
+///
+/// The cylinder has no rings of its own: it is the band between the
+/// equators of the two hemispheres, whose normals are both horizontal. The
+/// seams therefore share vertices and the normals stay continuous, without
+/// cracks. Like `create_sphere_data`, the poles and the `phi = 0` column
+/// are duplicated, which gives `2 * (rings + 1) * (segments + 1)` vertices.
+///
+/// Unlike the sphere and torus generators, triangles are counter-clockwise
+/// seen from the outside.
+pub fn create_capsule_data(
+  radius: f32,
+  cylinder_height: f32,
+  rings: u16,
+  segments: u16,
+) -> Capsule {
+  let mut positions: Vec<[f32; 3]> = vec![];
+  let mut normals: Vec<[f32; 3]> = vec![];
+
+  let half_height = cylinder_height.max(0.) / 2.;
+
+  // 上の半球は北極から赤道、下の半球は赤道から南極へ
+  let caps = [(half_height, 0.), (-half_height, 90.)];
+
+  for (center_y, theta_start) in caps {
+    for i in 0..=rings {
+      let theta = theta_start + i as f32 * 90. / rings as f32;
+      for j in 0..=segments {
+        let phi = j as f32 * 360. / segments as f32;
+        let (pos, normal) =
+          capsule_vertex(radius, center_y, Deg(theta), Deg(phi));
+
+        positions.push(pos);
+        normals.push(normal);
+      }
+    }
+  }
+
+  let mut indices: Vec<u16> = vec![];
+  let mut indices_wireframe: Vec<u16> = vec![];
+
+  let vertices_per_row = segments + 1;
+  // 上の半球の赤道と下の半球の赤道の間が円柱部分になる
+  let row_count = 2 * (rings + 1);
+
+  for i in 0..row_count - 1 {
+    for j in 0..segments {
+      let idx0 = j + i * vertices_per_row;
+      let idx1 = j + 1 + i * vertices_per_row;
+      let idx2 = j + 1 + (i + 1) * vertices_per_row;
+      let idx3 = j + (i + 1) * vertices_per_row;
+
+      let shape = vec![idx0, idx3, idx2, idx2, idx1, idx0];
+      let wireframe = vec![idx0, idx1, idx0, idx3];
+
+      indices.extend(shape);
+      indices_wireframe.extend(wireframe);
+    }
+  }
+
+  Capsule {
+    positions,
+    normals,
+    indices,
+    indices_wireframe,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EPSILON: f32 = 1e-5;
+
+  fn close(a: [f32; 3], b: [f32; 3]) -> bool {
+    a.iter().zip(b).all(|(a, b)| (a - b).abs() < EPSILON)
+  }
+
+  #[test]
+  fn vertex_count_matches_rings_and_segments() {
+    let (rings, segments) = (6, 12);
+    let capsule = create_capsule_data(0.5, 2., rings, segments);
+
+    let expected = 2 * (rings as usize + 1) * (segments as usize + 1);
+    assert_eq!(capsule.positions.len(), expected);
+    assert_eq!(capsule.normals.len(), expected);
+    assert!(capsule.indices.iter().all(|&i| (i as usize) < expected));
+  }
+
+  #[test]
+  fn equator_rows_line_up_without_cracks() {
+    let (radius, height, rings, segments) = (0.5, 2., 6, 12);
+    let capsule = create_capsule_data(radius, height, rings, segments);
+    let row = |i: u16| {
+      let start = (i * (segments + 1)) as usize;
+      start..start + segments as usize + 1
+    };
+
+    // 上の半球の赤道と下の半球の赤道は、円柱の高さだけずれた同じ円
+    for (top, bottom) in row(rings).zip(row(rings + 1)) {
+      let [tx, ty, tz] = capsule.positions[top];
+      let [bx, by, bz] = capsule.positions[bottom];
+      assert!(close([tx, ty - height, tz], [bx, by, bz]));
+      assert!(close(capsule.normals[top], capsule.normals[bottom]));
+      assert!(capsule.normals[top][1].abs() < EPSILON);
+    }
+
+    // phi = 0 と 360 の列は同じ位置に重なる
+    for i in 0..2 * (rings + 1) {
+      let columns = row(i);
+      assert!(close(
+        capsule.positions[columns.start],
+        capsule.positions[columns.end - 1]
+      ));
+    }
+  }
+}
@@ -0,0 +1,84 @@
+use bytemuck::{Pod, Zeroable};
+use cgmath::*;
+
+use super::Mesh;
+
+const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![
+  0 => Float32x3,
+  1 => Float32x3,
+  2 => Float32x4
+];
+
+/// Vertex with its own RGBA color, for simple colored meshes that don't
+/// need a color storage buffer and its bind group.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct ColoredVertex {
+  pub position: [f32; 3],
+  pub normal: [f32; 3],
+  pub color: [f32; 4],
+}
+
+impl ColoredVertex {
+  /// `@location(0)` position and `@location(1)` normal, both `vec3f`, and
+  /// `@location(2)` color, `vec4f`.
+  pub fn vertex_buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+      array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+      step_mode: wgpu::VertexStepMode::Vertex,
+      attributes: &VERTEX_ATTRIBUTES,
+    }
+  }
+}
+
+/// Every vertex of `mesh` in `color`. Draw with `mesh.indices`.
+pub fn with_color(mesh: &Mesh, color: [f32; 4]) -> Vec<ColoredVertex> {
+  mesh
+    .positions
+    .iter()
+    .zip(&mesh.normals)
+    .map(|(&position, &normal)| ColoredVertex {
+      position,
+      normal,
+      color,
+    })
+    .collect()
+}
+
+/// Vertices of `mesh` colored from `from` to `to` along `axis`: the vertex
+/// furthest toward `-axis` gets `from`, the one furthest toward `axis`
+/// gets `to`, and the rest are interpolated linearly in between. Draw with
+/// `mesh.indices`.
+pub fn with_gradient(
+  mesh: &Mesh,
+  axis: [f32; 3],
+  from: [f32; 4],
+  to: [f32; 4],
+) -> Vec<ColoredVertex> {
+  let axis = Vector3::from(axis).normalize();
+  let distances = mesh
+    .positions
+    .iter()
+    .map(|&p| Vector3::from(p).dot(axis))
+    .collect::<Vec<_>>();
+
+  let min = distances.iter().copied().fold(f32::INFINITY, f32::min);
+  let max = distances.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+  let extent = max - min;
+
+  mesh
+    .positions
+    .iter()
+    .zip(&mesh.normals)
+    .zip(distances)
+    .map(|((&position, &normal), d)| {
+      // 幅が0（点や軸に垂直な平面）なら from の色
+      let t = if extent > 0. { (d - min) / extent } else { 0. };
+      ColoredVertex {
+        position,
+        normal,
+        color: Vector4::from(from).lerp(Vector4::from(to), t).into(),
+      }
+    })
+    .collect()
+}
@@ -0,0 +1,137 @@
+use cgmath::*;
+
+pub struct Cone {
+  pub positions: Vec<[f32; 3]>,
+  pub normals: Vec<[f32; 3]>,
+  pub indices: Vec<u16>,
+  pub indices_wireframe: Vec<u16>,
+}
+
+fn cone_base_position(r: f32, theta: Deg<f32>, y: f32) -> [f32; 3] {
+  let x = r * theta.cos();
+  let z = -r * theta.sin();
+  [x, y, z]
+}
+
+/// Normal of the slanted side at `theta`: tilted up from the radial
+/// direction by the slope, `(h cos, r, -h sin) / sqrt(h² + r²)`.
+fn cone_side_normal(r: f32, h: f32, theta: Deg<f32>) -> [f32; 3] {
+  let slant = (r * r + h * h).sqrt();
+  [h * theta.cos() / slant, r / slant, -h * theta.sin() / slant]
+}
+
+/// Cone along the Y axis, centered like `create_cylinder_data`: the base
+/// (`radius`) at `-height / 2` and the apex at `height / 2`.
+///
+/// The side normals follow the slope, so every one has the +Y component
+/// `radius / sqrt(radius² + height²)`. Each side triangle has its own apex
+/// vertex, with the normal halfway between those of its two base corners,
+/// instead of one shared apex whose normal would have to point straight
+/// up. The base cap faces -Y.
+///
+/// Triangles are counter-clockwise seen from the outside.
+pub fn create_cone_data(radius: f32, height: f32, segments: u16) -> Cone {
+  let mut positions: Vec<[f32; 3]> = vec![];
+  let mut normals: Vec<[f32; 3]> = vec![];
+
+  let top = height / 2.;
+  let bottom = -height / 2.;
+  let step = 360. / segments as f32;
+
+  // 側面の底の輪（segments + 1 個）
+  for i in 0..=segments {
+    let theta = Deg(i as f32 * step);
+    positions.push(cone_base_position(radius, theta, bottom));
+    normals.push(cone_side_normal(radius, height, theta));
+  }
+
+  // 側面の三角形ごとの頂点（segments 個）
+  let apex_start = positions.len() as u16;
+  for i in 0..segments {
+    let theta = Deg((i as f32 + 0.5) * step);
+    positions.push([0., top, 0.]);
+    normals.push(cone_side_normal(radius, height, theta));
+  }
+
+  // 底面の中心と輪（segments + 1 個）
+  let base_center = positions.len() as u16;
+  positions.push([0., bottom, 0.]);
+  normals.push([0., -1., 0.]);
+  for i in 0..=segments {
+    let theta = Deg(i as f32 * step);
+    positions.push(cone_base_position(radius, theta, bottom));
+    normals.push([0., -1., 0.]);
+  }
+
+  let mut indices: Vec<u16> = vec![];
+  let mut indices_wireframe: Vec<u16> = vec![];
+
+  for i in 0..segments {
+    let side0 = i;
+    let side1 = i + 1;
+    let apex = apex_start + i;
+    let base0 = base_center + 1 + i;
+    let base1 = base_center + 2 + i;
+
+    // 側面
+    indices.extend([side0, side1, apex]);
+    indices_wireframe.extend([side0, side1, side0, apex]);
+
+    // 底面
+    indices.extend([base_center, base1, base0]);
+    indices_wireframe.extend([base_center, base0]);
+  }
+
+  Cone {
+    positions,
+    normals,
+    indices,
+    indices_wireframe,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EPSILON: f32 = 1e-5;
+
+  #[test]
+  fn side_normals_follow_the_slope() {
+    let (radius, height, segments) = (1.5, 2., 16);
+    let cone = create_cone_data(radius, height, segments);
+    let expected_y = radius / (radius * radius + height * height).sqrt();
+
+    // 底の輪と三角形ごとの頂点が側面の頂点
+    let side_count = 2 * segments as usize + 1;
+    for (i, &normal) in cone.normals[..side_count].iter().enumerate() {
+      let n = Vector3::from(normal);
+      assert!((n.y - expected_y).abs() < EPSILON, "normal {} {:?}", i, n);
+      assert!((n.magnitude() - 1.).abs() < EPSILON);
+    }
+
+    // 底の輪の法線は母線（底から頂点への線）と直交する
+    let apex = Vector3::new(0., height / 2., 0.);
+    for i in 0..=segments as usize {
+      let slant = apex - Vector3::from(cone.positions[i]);
+      assert!(Vector3::from(cone.normals[i]).dot(slant).abs() < EPSILON);
+    }
+
+    for &normal in &cone.normals[side_count..] {
+      assert_eq!(normal, [0., -1., 0.]);
+    }
+  }
+
+  #[test]
+  fn triangles_face_outward() {
+    let cone = create_cone_data(1., 2., 8);
+
+    for tri in cone.indices.chunks_exact(3) {
+      let [a, b, c] =
+        [0, 1, 2].map(|k| Vector3::from(cone.positions[tri[k] as usize]));
+      let face_normal = (b - a).cross(c - a);
+      let vertex_normal = Vector3::from(cone.normals[tri[0] as usize]);
+      assert!(face_normal.dot(vertex_normal) > 0., "{:?}", tri);
+    }
+  }
+}
@@ -7,6 +7,10 @@ pub struct Cube {
   pub indices_wireframe: Vec<u16>,
 }
 
+/// Each face maps to the whole 0..1 UV square, upright when seen from
+/// outside: u to the right, v down (texture coordinates, see
+/// `matrix::Convention`). The top and bottom faces are oriented with -Z
+/// and +Z up respectively.
 pub fn create_cube_data(side: f32) -> Cube {
   let s2 = side / 2.0;
   let positions = [
@@ -91,8 +95,6 @@ pub fn create_cube_data(side: f32) -> Cube {
   ];
 
   let uvs = [
-    [0., 1.],
-    [1., 1.],
     [0., 0.],
     [1., 0.],
     [0., 1.],
@@ -115,6 +117,8 @@ pub fn create_cube_data(side: f32) -> Cube {
     [1., 1.],
     [0., 0.],
     [1., 0.],
+    [0., 1.],
+    [1., 1.],
   ];
 
   let indices = [
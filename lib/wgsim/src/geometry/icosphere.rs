@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use cgmath::*;
+
+/// Each subdivision multiplies the triangle count by 4; beyond this the
+/// vertices no longer fit in `u16` indices.
+const MAX_SUBDIVISIONS: u32 = 6;
+
+pub struct Icosphere {
+  pub positions: Vec<[f32; 3]>,
+  pub normals: Vec<[f32; 3]>,
+  pub indices: Vec<u16>,
+  pub indices_wireframe: Vec<u16>,
+}
+
+fn icosahedron() -> (Vec<Vector3<f32>>, Vec<[u32; 3]>) {
+  let t = (1. + 5f32.sqrt()) / 2.;
+
+  let positions = [
+    [-1., t, 0.],
+    [1., t, 0.],
+    [-1., -t, 0.],
+    [1., -t, 0.],
+    [0., -1., t],
+    [0., 1., t],
+    [0., -1., -t],
+    [0., 1., -t],
+    [t, 0., -1.],
+    [t, 0., 1.],
+    [-t, 0., -1.],
+    [-t, 0., 1.],
+  ]
+  .map(|p| Vector3::from(p).normalize());
+
+  let faces = [
+    [0, 11, 5],
+    [0, 5, 1],
+    [0, 1, 7],
+    [0, 7, 10],
+    [0, 10, 11],
+    [1, 5, 9],
+    [5, 11, 4],
+    [11, 10, 2],
+    [10, 7, 6],
+    [7, 1, 8],
+    [3, 9, 4],
+    [3, 4, 2],
+    [3, 2, 6],
+    [3, 6, 8],
+    [3, 8, 9],
+    [4, 9, 5],
+    [2, 4, 11],
+    [6, 2, 10],
+    [8, 6, 7],
+    [9, 8, 1],
+  ];
+
+  (positions.to_vec(), faces.to_vec())
+}
+
+/// A sphere made by subdividing an icosahedron, so the triangles are spread
+/// evenly instead of bunching up at the poles like `create_sphere_data`.
+///
+/// It has `20 * 4^subdivisions` triangles; `subdivisions` is limited to 6
+/// so the indices fit in `u16`. Triangles are counter-clockwise seen from
+/// outside.
+pub fn create_icosphere_data(radius: f32, subdivisions: u32) -> Icosphere {
+  assert!(
+    subdivisions <= MAX_SUBDIVISIONS,
+    "icosphere subdivisions {} exceeds {}",
+    subdivisions,
+    MAX_SUBDIVISIONS
+  );
+
+  let (mut positions, mut faces) = icosahedron();
+
+  for _ in 0..subdivisions {
+    // 隣り合う三角形で辺の中点を共有する
+    let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut midpoint = |a: u32, b: u32| {
+      let key = (a.min(b), a.max(b));
+      *midpoints.entry(key).or_insert_with(|| {
+        let p = (positions[a as usize] + positions[b as usize]).normalize();
+        positions.push(p);
+        positions.len() as u32 - 1
+      })
+    };
+
+    faces = faces
+      .iter()
+      .flat_map(|&[a, b, c]| {
+        let ab = midpoint(a, b);
+        let bc = midpoint(b, c);
+        let ca = midpoint(c, a);
+        [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+      })
+      .collect();
+  }
+
+  let normals = positions.iter().map(|&n| n.into()).collect();
+  let positions = positions.iter().map(|&n| (n * radius).into()).collect();
+
+  let indices = faces.iter().flatten().map(|&i| i as u16).collect();
+
+  // 閉じたメッシュでは各辺が向きを変えて2回現れるので、片方だけ拾う
+  let indices_wireframe = faces
+    .iter()
+    .flat_map(|&[a, b, c]| [[a, b], [b, c], [c, a]])
+    .filter(|[a, b]| a < b)
+    .flatten()
+    .map(|i| i as u16)
+    .collect();
+
+  Icosphere {
+    positions,
+    normals,
+    indices,
+    indices_wireframe,
+  }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use super::normals::{compute_flat_normals, compute_smooth_normals};
+use super::{Capsule, Cone, Cube, Icosphere, Plane, Sphere, Torus};
+
+/// Relative tolerance used by `Mesh::weld`, scaled by the mesh extent.
+const WELD_EPSILON: f32 = 1e-5;
+
+/// Indexed triangle list shared by the mesh helpers (subdivision, normal
+/// recompute, upload).
+///
+/// Triangles are counter-clockwise when seen from the outside, matching
+/// wgpu's default `FrontFace::Ccw` (see `matrix::Convention`). Indices are `u32` so that subdivided
+/// meshes are not limited to 65535 vertices.
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+  pub positions: Vec<[f32; 3]>,
+  pub normals: Vec<[f32; 3]>,
+  pub indices: Vec<u32>,
+}
+
+impl Mesh {
+  pub fn vertex_count(&self) -> usize {
+    self.positions.len()
+  }
+
+  pub fn triangle_count(&self) -> usize {
+    self.indices.len() / 3
+  }
+
+  /// Merges vertices that share a position and drops the triangles that
+  /// collapse as a result.
+  ///
+  /// The generators duplicate vertices along UV seams and at the poles;
+  /// welding turns those into shared vertices so topology-based helpers such
+  /// as `loop_subdivide` don't treat them as open boundaries. The first
+  /// normal of each group is kept.
+  pub fn weld(&self) -> Mesh {
+    let extent =
+      self.positions.iter().flatten().fold(0f32, |acc, v| acc.max(v.abs()));
+    let cell = (extent * WELD_EPSILON).max(f32::MIN_POSITIVE);
+
+    let mut lookup: HashMap<[i64; 3], u32> = HashMap::new();
+    let mut remap = Vec::with_capacity(self.positions.len());
+    let mut positions = vec![];
+    let mut normals = vec![];
+
+    for (i, p) in self.positions.iter().enumerate() {
+      let key = p.map(|x| (x / cell).round() as i64);
+      let index = *lookup.entry(key).or_insert_with(|| {
+        positions.push(*p);
+        normals.push(self.normals.get(i).copied().unwrap_or_default());
+        positions.len() as u32 - 1
+      });
+      remap.push(index);
+    }
+
+    let indices = self
+      .indices
+      .chunks_exact(3)
+      .map(|tri| [0, 1, 2].map(|k| remap[tri[k] as usize]))
+      .filter(|[a, b, c]| a != b && b != c && c != a)
+      .flatten()
+      .collect();
+
+    Mesh {
+      positions,
+      normals,
+      indices,
+    }
+  }
+
+  /// Replaces the normals with smooth vertex normals, see
+  /// `compute_smooth_normals`.
+  pub fn recompute_normals(&mut self) {
+    self.normals = compute_smooth_normals(&self.positions, &self.indices);
+  }
+
+  /// A copy with one vertex per triangle corner and face normals, see
+  /// `compute_flat_normals`.
+  pub fn flat_shaded(&self) -> Mesh {
+    compute_flat_normals(&self.positions, &self.indices)
+  }
+
+  fn from_clockwise(
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    indices: &[u16],
+  ) -> Self {
+    let indices = indices
+      .chunks_exact(3)
+      .flat_map(|tri| [tri[0], tri[2], tri[1]])
+      .map(u32::from)
+      .collect();
+
+    Mesh {
+      positions,
+      normals,
+      indices,
+    }
+  }
+}
+
+impl From<Cube> for Mesh {
+  fn from(cube: Cube) -> Self {
+    Mesh {
+      positions: cube.positions,
+      normals: cube.normals,
+      indices: cube.indices.into_iter().map(u32::from).collect(),
+    }
+  }
+}
+
+impl From<Icosphere> for Mesh {
+  fn from(icosphere: Icosphere) -> Self {
+    Mesh {
+      positions: icosphere.positions,
+      normals: icosphere.normals,
+      indices: icosphere.indices.into_iter().map(u32::from).collect(),
+    }
+  }
+}
+
+impl From<Capsule> for Mesh {
+  fn from(capsule: Capsule) -> Self {
+    Mesh {
+      positions: capsule.positions,
+      normals: capsule.normals,
+      indices: capsule.indices.into_iter().map(u32::from).collect(),
+    }
+  }
+}
+
+impl From<Cone> for Mesh {
+  fn from(cone: Cone) -> Self {
+    Mesh {
+      positions: cone.positions,
+      normals: cone.normals,
+      indices: cone.indices.into_iter().map(u32::from).collect(),
+    }
+  }
+}
+
+impl From<Plane> for Mesh {
+  fn from(plane: Plane) -> Self {
+    Mesh {
+      positions: plane.positions,
+      normals: plane.normals,
+      indices: plane.indices.into_iter().map(u32::from).collect(),
+    }
+  }
+}
+
+/// The sphere generator winds its triangles clockwise; they are flipped.
+impl From<Sphere> for Mesh {
+  fn from(sphere: Sphere) -> Self {
+    Mesh::from_clockwise(sphere.positions, sphere.normals, &sphere.indices)
+  }
+}
+
+/// The torus generator winds its triangles clockwise; they are flipped.
+impl From<Torus> for Mesh {
+  fn from(torus: Torus) -> Self {
+    Mesh::from_clockwise(torus.positions, torus.normals, &torus.indices)
+  }
+}
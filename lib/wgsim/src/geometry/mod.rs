@@ -1,16 +1,43 @@
+mod barycentric;
+mod capsule;
+mod colored;
+mod cone;
 mod cube;
 mod cylinder;
+mod icosphere;
+mod mesh;
+mod normals;
+mod obj;
+mod plane;
 mod sphere;
+mod subdivide;
+mod tangents;
 mod torus;
+mod upload;
 
+pub use barycentric::{with_barycentric, BarycentricVertex};
+pub use capsule::Capsule;
+pub use colored::{with_color, with_gradient, ColoredVertex};
+pub use cone::Cone;
 pub use cube::Cube;
 pub use cylinder::Cylinder;
-pub use sphere::Sphere;
+pub use icosphere::Icosphere;
+pub use mesh::Mesh;
+pub use normals::{compute_flat_normals, compute_smooth_normals};
+pub use obj::{load_obj, ObjError};
+pub use plane::Plane;
+pub use sphere::{Sphere, SphereKind};
+pub use tangents::compute_tangents;
 pub use torus::Torus;
+pub use upload::{index_format_for, upload_mesh, IndexedMesh};
 
 pub mod generator {
+  pub use super::capsule::create_capsule_data;
+  pub use super::cone::create_cone_data;
   pub use super::cube::create_cube_data;
   pub use super::cylinder::create_cylinder_data;
-  pub use super::sphere::create_sphere_data;
+  pub use super::icosphere::create_icosphere_data;
+  pub use super::plane::create_plane_data;
+  pub use super::sphere::{create_sphere_data, create_sphere_data_kind};
   pub use super::torus::create_torus_data;
 }
@@ -0,0 +1,63 @@
+use cgmath::*;
+
+use super::Mesh;
+
+/// Smooth vertex normals: each vertex gets the area-weighted average of the
+/// normals of the triangles that share it, oriented by the winding
+/// (counter-clockwise is the front, see `matrix::Convention`).
+///
+/// Vertices duplicated along seams keep separate normals; weld the mesh
+/// first (`Mesh::weld`) to smooth across them. Vertices not used by any
+/// triangle get a zero normal.
+pub fn compute_smooth_normals(
+  positions: &[[f32; 3]],
+  indices: &[u32],
+) -> Vec<[f32; 3]> {
+  let mut normals = vec![Vector3::<f32>::zero(); positions.len()];
+
+  for tri in indices.chunks_exact(3) {
+    let [a, b, c] =
+      [0, 1, 2].map(|k| Vector3::from(positions[tri[k] as usize]));
+    // 外積の長さは三角形の面積の 2 倍なので、そのまま面積による重みになる
+    let face_normal = (b - a).cross(c - a);
+    for &i in tri {
+      normals[i as usize] += face_normal;
+    }
+  }
+
+  normals
+    .into_iter()
+    .map(|n| {
+      if n.magnitude2() > 0. {
+        n.normalize().into()
+      } else {
+        [0., 0., 0.]
+      }
+    })
+    .collect()
+}
+
+/// Flat shading: every triangle gets its own three vertices carrying the
+/// face normal, so the returned mesh has `3 * triangle count` vertices.
+pub fn compute_flat_normals(positions: &[[f32; 3]], indices: &[u32]) -> Mesh {
+  let mut mesh = Mesh::default();
+
+  for tri in indices.chunks_exact(3) {
+    let corners = [0, 1, 2].map(|k| positions[tri[k] as usize]);
+    let [a, b, c] = corners.map(Vector3::from);
+    let face_normal = (b - a).cross(c - a);
+    let normal = if face_normal.magnitude2() > 0. {
+      face_normal.normalize().into()
+    } else {
+      [0., 0., 0.]
+    };
+
+    for position in corners {
+      mesh.indices.push(mesh.positions.len() as u32);
+      mesh.positions.push(position);
+      mesh.normals.push(normal);
+    }
+  }
+
+  mesh
+}
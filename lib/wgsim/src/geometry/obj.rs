@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::Mesh;
+
+#[derive(Debug)]
+pub enum ObjError {
+  InvalidUtf8(std::str::Utf8Error),
+  /// `line` is 1-based.
+  Parse {
+    line: usize,
+    message: String,
+  },
+}
+
+impl fmt::Display for ObjError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ObjError::InvalidUtf8(e) => write!(f, "OBJ is not valid UTF-8: {}", e),
+      ObjError::Parse { line, message } => {
+        write!(f, "OBJ line {}: {}", line, message)
+      }
+    }
+  }
+}
+
+impl std::error::Error for ObjError {}
+
+/// Parses a Wavefront OBJ into a `Mesh`.
+///
+/// Only `v`, `vn` and `f` are read; faces may be written as `v`, `v/vt`,
+/// `v//vn` or `v/vt/vn`, with negative (relative) indices allowed. Polygons
+/// are triangulated as fans, and every distinct position/normal pair becomes
+/// one vertex. If any face corner has no normal, all normals are rebuilt
+/// with `Mesh::recompute_normals`.
+///
+/// OBJ files are expected to follow `matrix::Convention` (Y up,
+/// counter-clockwise faces); other conventions have to be converted
+/// afterwards.
+pub fn load_obj(bytes: &[u8]) -> Result<Mesh, ObjError> {
+  let text = std::str::from_utf8(bytes).map_err(ObjError::InvalidUtf8)?;
+
+  let mut obj_positions: Vec<[f32; 3]> = vec![];
+  let mut obj_normals: Vec<[f32; 3]> = vec![];
+
+  let mut mesh = Mesh::default();
+  let mut lookup: HashMap<(usize, Option<usize>), u32> = HashMap::new();
+  let mut missing_normals = false;
+
+  for (i, line) in text.lines().enumerate() {
+    let line_no = i + 1;
+    let error = |message: String| ObjError::Parse {
+      line: line_no,
+      message,
+    };
+
+    let line = line.split('#').next().unwrap_or("");
+    let mut tokens = line.split_whitespace();
+
+    match tokens.next() {
+      Some("v") => {
+        let position = parse_vec3(&mut tokens).map_err(error)?;
+        obj_positions.push(position);
+      }
+      Some("vn") => {
+        let normal = parse_vec3(&mut tokens).map_err(error)?;
+        obj_normals.push(normal);
+      }
+      Some("f") => {
+        let mut corners = vec![];
+        for token in tokens {
+          let (v, vn) =
+            parse_face_corner(token, obj_positions.len(), obj_normals.len())
+              .map_err(error)?;
+          missing_normals |= vn.is_none();
+
+          let index = *lookup.entry((v, vn)).or_insert_with(|| {
+            mesh.positions.push(obj_positions[v]);
+            let normal = vn.map(|n| obj_normals[n]).unwrap_or_default();
+            mesh.normals.push(normal);
+            mesh.positions.len() as u32 - 1
+          });
+          corners.push(index);
+        }
+
+        if corners.len() < 3 {
+          return Err(error(format!(
+            "face has {} vertices, at least 3 are needed",
+            corners.len()
+          )));
+        }
+
+        for k in 1..corners.len() - 1 {
+          mesh.indices.extend([corners[0], corners[k], corners[k + 1]]);
+        }
+      }
+      // テクスチャ座標・グループ・マテリアルなどは使わない
+      _ => {}
+    }
+  }
+
+  if missing_normals {
+    mesh.recompute_normals();
+  }
+
+  Ok(mesh)
+}
+
+fn parse_vec3<'a>(
+  tokens: &mut impl Iterator<Item = &'a str>,
+) -> Result<[f32; 3], String> {
+  let mut v = [0.; 3];
+  for c in v.iter_mut() {
+    let token = tokens.next().ok_or("expected 3 components")?;
+    *c = token.parse().map_err(|_| format!("invalid number {:?}", token))?;
+  }
+  Ok(v)
+}
+
+/// Returns 0-based position and normal indices.
+fn parse_face_corner(
+  token: &str,
+  position_count: usize,
+  normal_count: usize,
+) -> Result<(usize, Option<usize>), String> {
+  let mut parts = token.split('/');
+
+  let v = parts.next().unwrap_or("");
+  let v = resolve_index(v, position_count)?;
+
+  let _vt = parts.next();
+  let vn = match parts.next() {
+    Some(vn) if !vn.is_empty() => Some(resolve_index(vn, normal_count)?),
+    _ => None,
+  };
+
+  Ok((v, vn))
+}
+
+/// OBJ indices start at 1; negative ones count back from the last element
+/// defined so far.
+fn resolve_index(token: &str, count: usize) -> Result<usize, String> {
+  let index: i64 =
+    token.parse().map_err(|_| format!("invalid index {:?}", token))?;
+
+  let resolved = if index > 0 {
+    index - 1
+  } else {
+    count as i64 + index
+  };
+
+  if index == 0 || resolved < 0 || resolved >= count as i64 {
+    return Err(format!("index {} is out of range (1..={})", index, count));
+  }
+
+  Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const CUBE_OBJ: &str = "\
+# unit cube
+v -1 -1  1
+v  1 -1  1
+v  1  1  1
+v -1  1  1
+v -1 -1 -1
+v  1 -1 -1
+v  1  1 -1
+v -1  1 -1
+f 1 2 3 4
+f 6 5 8 7
+f 5 1 4 8
+f 2 6 7 3
+f 4 3 7 8
+f 5 6 2 1
+";
+
+  #[test]
+  fn cube_keeps_its_eight_positions() {
+    let mesh = load_obj(CUBE_OBJ.as_bytes()).unwrap();
+
+    // 法線がないので、位置が同じ角はすべて1つの頂点にまとまる
+    assert_eq!(mesh.positions.len(), 8);
+    assert_eq!(mesh.normals.len(), 8);
+    assert_eq!(mesh.indices.len(), 6 * 2 * 3);
+
+    for x in [-1., 1.] {
+      for y in [-1., 1.] {
+        for z in [-1., 1.] {
+          assert!(mesh.positions.contains(&[x, y, z]), "{:?}", [x, y, z]);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn reports_the_line_of_a_bad_index() {
+    let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n";
+
+    match load_obj(obj.as_bytes()) {
+      Err(ObjError::Parse { line, .. }) => assert_eq!(line, 4),
+      other => panic!("expected a parse error, got {:?}", other.map(|_| ())),
+    }
+  }
+}
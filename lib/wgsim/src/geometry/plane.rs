@@ -0,0 +1,100 @@
+pub struct Plane {
+  pub positions: Vec<[f32; 3]>,
+  pub normals: Vec<[f32; 3]>,
+  pub uvs: Vec<[f32; 2]>,
+  pub indices: Vec<u16>,
+  pub indices_wireframe: Vec<u16>,
+}
+
+/// A grid on the XZ plane centered at the origin, facing +Y.
+///
+/// Subdivisions are at least 1. It has
+/// `(subdivisions_x + 1) * (subdivisions_z + 1)` vertices, which must
+/// fit in `u16` indices. UVs run from 0 to 1 across the whole plane, with u
+/// along +X and v along +Z. Triangles are counter-clockwise seen from above.
+pub fn create_plane_data(
+  width: f32,
+  depth: f32,
+  subdivisions_x: u32,
+  subdivisions_z: u32,
+) -> Plane {
+  let sx = subdivisions_x.max(1);
+  let sz = subdivisions_z.max(1);
+  assert!(
+    (sx + 1) * (sz + 1) <= u16::MAX as u32 + 1,
+    "plane with {}x{} subdivisions needs more than u16 indices",
+    sx,
+    sz
+  );
+
+  let mut positions: Vec<[f32; 3]> = vec![];
+  let mut normals: Vec<[f32; 3]> = vec![];
+  let mut uvs: Vec<[f32; 2]> = vec![];
+
+  for j in 0..=sz {
+    for i in 0..=sx {
+      let u = i as f32 / sx as f32;
+      let v = j as f32 / sz as f32;
+
+      positions.push([(u - 0.5) * width, 0., (v - 0.5) * depth]);
+      normals.push([0., 1., 0.]);
+      uvs.push([u, v]);
+    }
+  }
+
+  let idx = |i: u32, j: u32| (i + j * (sx + 1)) as u16;
+
+  let mut indices: Vec<u16> = vec![];
+  let mut indices_wireframe: Vec<u16> = vec![];
+
+  for j in 0..sz {
+    for i in 0..sx {
+      let idx0 = idx(i, j);
+      let idx1 = idx(i + 1, j);
+      let idx2 = idx(i + 1, j + 1);
+      let idx3 = idx(i, j + 1);
+
+      indices.extend([idx0, idx3, idx1, idx1, idx3, idx2]);
+    }
+  }
+
+  for j in 0..=sz {
+    for i in 0..sx {
+      indices_wireframe.extend([idx(i, j), idx(i + 1, j)]);
+    }
+  }
+  for i in 0..=sx {
+    for j in 0..sz {
+      indices_wireframe.extend([idx(i, j), idx(i, j + 1)]);
+    }
+  }
+
+  Plane {
+    positions,
+    normals,
+    uvs,
+    indices,
+    indices_wireframe,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn vertex_count_matches_subdivisions() {
+    for (subdivisions_x, subdivisions_z) in [(1, 1), (4, 3), (10, 1)] {
+      let plane = create_plane_data(2., 3., subdivisions_x, subdivisions_z);
+      let expected = ((subdivisions_x + 1) * (subdivisions_z + 1)) as usize;
+
+      assert_eq!(plane.positions.len(), expected);
+      assert_eq!(plane.normals.len(), expected);
+      assert_eq!(plane.uvs.len(), expected);
+      assert_eq!(
+        plane.indices.len(),
+        (6 * subdivisions_x * subdivisions_z) as usize
+      );
+    }
+  }
+}
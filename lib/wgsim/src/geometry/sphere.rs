@@ -1,3 +1,5 @@
+use std::f32::consts::PI;
+
 use cgmath::*;
 
 pub struct Sphere {
@@ -15,6 +17,20 @@ fn sphere_position(r: f32, theta: Deg<f32>, phi: Deg<f32>) -> [f32; 3] {
   [x, y, z]
 }
 
+/// How `create_sphere_data_kind` tessellates the sphere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SphereKind {
+  /// Latitude/longitude grid, see `create_sphere_data`. The triangles
+  /// shrink toward the poles, where textures pinch.
+  #[default]
+  UvSphere,
+  /// Subdivided cube pushed out onto the sphere, with triangles of nearly
+  /// the same size everywhere. The faces are UV-mapped to the cells of a
+  /// 3x2 atlas, e.g. for planet textures: +X, -X, +Y from left to right in
+  /// the top half of the image (v < 0.5), -Y, +Z, -Z in the bottom half.
+  CubeSphere,
+}
+
 /// `u` segments and `v` rings
 pub fn create_sphere_data(r: f32, u: u16, v: u16) -> Sphere {
   let mut positions: Vec<[f32; 3]> = vec![];
@@ -59,3 +75,144 @@ pub fn create_sphere_data(r: f32, u: u16, v: u16) -> Sphere {
     indices_wireframe,
   }
 }
+
+/// Sphere of radius `r` tessellated as `kind`. `subdivisions` is the
+/// number of rings of a `UvSphere` (with twice as many segments) or the
+/// number of cells along each edge of a `CubeSphere` face, which has
+/// `6 * (subdivisions + 1)²` vertices and must stay below `u16::MAX`
+/// (`subdivisions` up to 103).
+///
+/// Both kinds wind their triangles clockwise, like `create_sphere_data`.
+pub fn create_sphere_data_kind(
+  r: f32,
+  subdivisions: u16,
+  kind: SphereKind,
+) -> Sphere {
+  match kind {
+    SphereKind::UvSphere => {
+      create_sphere_data(r, subdivisions, 2 * subdivisions)
+    }
+    SphereKind::CubeSphere => create_cube_sphere_data(r, subdivisions),
+  }
+}
+
+/// Each face as (normal, u axis, v axis), with `u × v = normal`, in the
+/// order of the atlas cells.
+const CUBE_FACES: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+  ([1., 0., 0.], [0., 0., -1.], [0., 1., 0.]),
+  ([-1., 0., 0.], [0., 0., 1.], [0., 1., 0.]),
+  ([0., 1., 0.], [1., 0., 0.], [0., 0., -1.]),
+  ([0., -1., 0.], [1., 0., 0.], [0., 0., 1.]),
+  ([0., 0., 1.], [1., 0., 0.], [0., 1., 0.]),
+  ([0., 0., -1.], [-1., 0., 0.], [0., 1., 0.]),
+];
+
+fn create_cube_sphere_data(r: f32, n: u16) -> Sphere {
+  let mut positions: Vec<[f32; 3]> = vec![];
+  let mut normals: Vec<[f32; 3]> = vec![];
+  let mut uvs: Vec<[f32; 2]> = vec![];
+
+  // 等間隔の格子をそのまま正規化すると面の中央のセルが大きくなるので、
+  // 角度が等間隔になるよう tan で歪めておく
+  let grid = |i: u16| (PI / 4. * (2. * i as f32 / n as f32 - 1.)).tan();
+
+  for (k, (normal, u_axis, v_axis)) in CUBE_FACES.iter().enumerate() {
+    let [normal, u_axis, v_axis] =
+      [normal, u_axis, v_axis].map(|v| Vector3::from(*v));
+    let cell = [(k % 3) as f32, (k / 3) as f32];
+
+    for j in 0..=n {
+      for i in 0..=n {
+        let dir = (normal + u_axis * grid(i) + v_axis * grid(j)).normalize();
+
+        positions.push((dir * r).into());
+        normals.push(dir.into());
+        uvs.push([
+          (cell[0] + i as f32 / n as f32) / 3.,
+          (cell[1] + 1. - j as f32 / n as f32) / 2.,
+        ]);
+      }
+    }
+  }
+
+  let mut indices: Vec<u16> = vec![];
+  let mut indices_wireframe: Vec<u16> = vec![];
+
+  let vertices_per_face = (n + 1) * (n + 1);
+
+  for k in 0..6 {
+    for j in 0..n {
+      for i in 0..n {
+        let idx0 = k * vertices_per_face + j * (n + 1) + i;
+        let idx1 = idx0 + 1;
+        let idx2 = idx0 + n + 2;
+        let idx3 = idx0 + n + 1;
+
+        // UV 球と同じく時計回り
+        let shape = vec![idx0, idx2, idx1, idx2, idx0, idx3];
+        let wireframe = vec![idx0, idx1, idx0, idx3];
+
+        indices.extend(shape);
+        indices_wireframe.extend(wireframe);
+      }
+    }
+  }
+
+  Sphere {
+    positions,
+    normals,
+    uvs,
+    indices,
+    indices_wireframe,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Standard deviation of the triangle areas over their mean. Triangles
+  /// collapsed onto the UV sphere's poles are left out.
+  fn area_variation(sphere: &Sphere) -> f32 {
+    let areas: Vec<f32> = sphere
+      .indices
+      .chunks_exact(3)
+      .map(|tri| {
+        let [a, b, c] =
+          [0, 1, 2].map(|k| Vector3::from(sphere.positions[tri[k] as usize]));
+        (b - a).cross(c - a).magnitude() / 2.
+      })
+      .filter(|&area| area > 1e-6)
+      .collect();
+
+    let mean = areas.iter().sum::<f32>() / areas.len() as f32;
+    let variance = areas.iter().map(|a| (a - mean).powi(2)).sum::<f32>()
+      / areas.len() as f32;
+    variance.sqrt() / mean
+  }
+
+  #[test]
+  fn cube_sphere_triangles_are_more_even_than_uv_sphere() {
+    let uv = create_sphere_data_kind(1., 16, SphereKind::UvSphere);
+    let cube = create_sphere_data_kind(1., 8, SphereKind::CubeSphere);
+
+    let uv_variation = area_variation(&uv);
+    let cube_variation = area_variation(&cube);
+    assert!(
+      cube_variation < uv_variation,
+      "cube sphere {} vs UV sphere {}",
+      cube_variation,
+      uv_variation
+    );
+  }
+
+  #[test]
+  fn cube_sphere_vertices_lie_on_the_sphere() {
+    let sphere = create_sphere_data_kind(2., 5, SphereKind::CubeSphere);
+
+    assert_eq!(sphere.positions.len(), 6 * 6 * 6);
+    for position in &sphere.positions {
+      assert!((Vector3::from(*position).magnitude() - 2.).abs() < 1e-5);
+    }
+  }
+}
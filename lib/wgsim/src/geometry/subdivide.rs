@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use cgmath::*;
+
+use super::Mesh;
+
+fn edge_key(a: u32, b: u32) -> (u32, u32) {
+  (a.min(b), a.max(b))
+}
+
+impl Mesh {
+  /// One step of Loop subdivision: every triangle is split into four and the
+  /// vertices are moved towards a smooth limit surface.
+  ///
+  /// - existing vertex with `n` neighbours: `(1 - n * beta) * v + beta * sum`,
+  ///   where `beta = 3 / (8 * n)` (`3 / 16` for `n = 3`)
+  /// - new edge vertex: `3/8 * (a + b) + 1/8 * (c + d)` from the two adjacent
+  ///   triangles
+  ///
+  /// Boundary edges and vertices use the 1/2 and 3/4-1/8-1/8 curve rules so
+  /// open meshes don't pull away from their border. Loop is an approximating
+  /// scheme, so convex shapes shrink slightly (a sphere loses a few percent
+  /// of its radius).
+  ///
+  /// Adjacency is derived from the indices only: `weld` generator output
+  /// first. Normals are recomputed.
+  pub fn loop_subdivide(&self) -> Mesh {
+    let vertex_count = self.positions.len();
+
+    // 辺ごとに向かい側の頂点を集め、新しい頂点の番号を振る
+    let mut opposite: HashMap<(u32, u32), Vec<u32>> = HashMap::new();
+    let mut edge_vertex: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut edges: Vec<(u32, u32)> = vec![];
+
+    for tri in self.indices.chunks_exact(3) {
+      for k in 0..3 {
+        let key = edge_key(tri[k], tri[(k + 1) % 3]);
+        opposite.entry(key).or_default().push(tri[(k + 2) % 3]);
+        edge_vertex.entry(key).or_insert_with(|| {
+          edges.push(key);
+          (vertex_count + edges.len() - 1) as u32
+        });
+      }
+    }
+
+    let mut neighbours: Vec<Vec<u32>> = vec![vec![]; vertex_count];
+    let mut boundary_neighbours: Vec<Vec<u32>> = vec![vec![]; vertex_count];
+    for &(a, b) in &edges {
+      neighbours[a as usize].push(b);
+      neighbours[b as usize].push(a);
+      if opposite[&(a, b)].len() == 1 {
+        boundary_neighbours[a as usize].push(b);
+        boundary_neighbours[b as usize].push(a);
+      }
+    }
+
+    let p = |i: u32| Vector3::from(self.positions[i as usize]);
+
+    //
+    // even vertices (moved originals)
+    //
+
+    let mut positions: Vec<[f32; 3]> = (0..vertex_count as u32)
+      .map(|v| {
+        let ring = &neighbours[v as usize];
+        let border = &boundary_neighbours[v as usize];
+        let moved = match border.len() {
+          0 if !ring.is_empty() => {
+            let n = ring.len() as f32;
+            let beta = if ring.len() > 3 {
+              3. / (8. * n)
+            } else {
+              3. / 16.
+            };
+            let sum = ring.iter().fold(Vector3::zero(), |acc, &i| acc + p(i));
+            p(v) * (1. - n * beta) + sum * beta
+          }
+          2 => p(v) * 0.75 + (p(border[0]) + p(border[1])) * 0.125,
+          // 孤立点や非多様体の角はそのまま残す
+          _ => p(v),
+        };
+        moved.into()
+      })
+      .collect();
+
+    //
+    // odd vertices (one per edge)
+    //
+
+    positions.extend(edges.iter().map(|&(a, b)| {
+      let midpoint = match opposite[&(a, b)].as_slice() {
+        &[c, d] => (p(a) + p(b)) * 0.375 + (p(c) + p(d)) * 0.125,
+        _ => (p(a) + p(b)) * 0.5,
+      };
+      [midpoint.x, midpoint.y, midpoint.z]
+    }));
+
+    //
+    // faces
+    //
+
+    let mut indices = Vec::with_capacity(self.indices.len() * 4);
+    for tri in self.indices.chunks_exact(3) {
+      let [a, b, c] = [tri[0], tri[1], tri[2]];
+      let ab = edge_vertex[&edge_key(a, b)];
+      let bc = edge_vertex[&edge_key(b, c)];
+      let ca = edge_vertex[&edge_key(c, a)];
+
+      indices.extend([a, ab, ca, ab, b, bc, ca, bc, c, ab, bc, ca]);
+    }
+
+    let mut mesh = Mesh {
+      positions,
+      normals: vec![],
+      indices,
+    };
+    mesh.recompute_normals();
+
+    mesh
+  }
+
+  /// Welds the mesh and applies `loop_subdivide` while the result stays
+  /// within `target_tris` triangles.
+  ///
+  /// Each step multiplies the triangle count by 4, so the budget is only met
+  /// to within that factor: the result has between `target_tris / 4` and
+  /// `target_tris` triangles. A mesh whose next step would already exceed
+  /// the budget is returned unchanged; it is never decimated.
+  pub fn subdivide_to_budget(&self, target_tris: usize) -> Mesh {
+    if !fits_budget(self.triangle_count(), target_tris) {
+      return self.clone();
+    }
+
+    let mut mesh = self.weld().loop_subdivide();
+    while fits_budget(mesh.triangle_count(), target_tris) {
+      mesh = mesh.loop_subdivide();
+    }
+
+    mesh
+  }
+}
+
+/// Whether one more subdivision step stays within the budget.
+fn fits_budget(triangle_count: usize, target_tris: usize) -> bool {
+  triangle_count > 0 && triangle_count * 4 <= target_tris
+}
@@ -0,0 +1,118 @@
+use cgmath::*;
+
+/// Per-vertex tangents for normal mapping (Lengyel's method).
+///
+/// `xyz` is the tangent, pointing toward increasing u and orthogonalized
+/// against the normal. `w` is the handedness (±1): the bitangent, pointing
+/// toward increasing v, is `w * cross(normal, tangent.xyz)`. Mirrored UVs
+/// thus keep a consistent tangent frame.
+///
+/// Triangles whose UVs are degenerate are skipped; vertices that end up
+/// without a tangent get an arbitrary one perpendicular to the normal.
+pub fn compute_tangents(
+  positions: &[[f32; 3]],
+  normals: &[[f32; 3]],
+  uvs: &[[f32; 2]],
+  indices: &[u32],
+) -> Vec<[f32; 4]> {
+  let mut tangents = vec![Vector3::<f32>::zero(); positions.len()];
+  let mut bitangents = vec![Vector3::<f32>::zero(); positions.len()];
+
+  for tri in indices.chunks_exact(3) {
+    let [i0, i1, i2] = [0, 1, 2].map(|k| tri[k] as usize);
+    let [p0, p1, p2] = [i0, i1, i2].map(|i| Vector3::from(positions[i]));
+    let [w0, w1, w2] = [i0, i1, i2].map(|i| Vector2::from(uvs[i]));
+
+    let e1 = p1 - p0;
+    let e2 = p2 - p0;
+    let d1 = w1 - w0;
+    let d2 = w2 - w0;
+
+    let det = d1.x * d2.y - d2.x * d1.y;
+    if det.abs() <= f32::EPSILON {
+      continue;
+    }
+    let r = 1. / det;
+
+    let sdir = (e1 * d2.y - e2 * d1.y) * r;
+    let tdir = (e2 * d1.x - e1 * d2.x) * r;
+
+    for i in [i0, i1, i2] {
+      tangents[i] += sdir;
+      bitangents[i] += tdir;
+    }
+  }
+
+  (0..positions.len())
+    .map(|i| {
+      let n = Vector3::from(normals[i]);
+      let t = tangents[i];
+
+      // Gram-Schmidt で法線と直交させる
+      let t = t - n * n.dot(t);
+      let t = if t.magnitude2() > 0. {
+        t.normalize()
+      } else {
+        any_perpendicular(n)
+      };
+
+      let w = if n.cross(t).dot(bitangents[i]) < 0. {
+        -1.
+      } else {
+        1.
+      };
+
+      [t.x, t.y, t.z, w]
+    })
+    .collect()
+}
+
+fn any_perpendicular(n: Vector3<f32>) -> Vector3<f32> {
+  let axis = if n.x.abs() < 0.9 {
+    Vector3::unit_x()
+  } else {
+    Vector3::unit_y()
+  };
+  let t = axis - n * n.dot(axis);
+  if t.magnitude2() > 0. {
+    t.normalize()
+  } else {
+    axis
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EPSILON: f32 = 1e-5;
+
+  #[test]
+  fn flat_xy_quad_has_positive_x_tangents() {
+    let positions =
+      [[-1., -1., 0.], [1., -1., 0.], [1., 1., 0.], [-1., 1., 0.]];
+    let normals = [[0., 0., 1.]; 4];
+    let uvs = [[0., 0.], [1., 0.], [1., 1.], [0., 1.]];
+    let indices = [0, 1, 2, 0, 2, 3];
+
+    for [x, y, z, w] in compute_tangents(&positions, &normals, &uvs, &indices) {
+      assert!((x - 1.).abs() < EPSILON, "tangent x {}", x);
+      assert!(y.abs() < EPSILON && z.abs() < EPSILON, "{} {}", y, z);
+      // v が +Y 方向に増えるので、cross(N, T) = +Y と同じ向きになる
+      assert_eq!(w, 1.);
+    }
+  }
+
+  #[test]
+  fn mirrored_uvs_flip_handedness() {
+    let positions =
+      [[-1., -1., 0.], [1., -1., 0.], [1., 1., 0.], [-1., 1., 0.]];
+    let normals = [[0., 0., 1.]; 4];
+    let uvs = [[0., 1.], [1., 1.], [1., 0.], [0., 0.]];
+    let indices = [0, 1, 2, 0, 2, 3];
+
+    for [_, _, _, w] in compute_tangents(&positions, &normals, &uvs, &indices) {
+      assert_eq!(w, -1.);
+    }
+  }
+}
@@ -0,0 +1,256 @@
+use std::ops::Range;
+
+use bytemuck::cast_slice;
+use wgpu::util::DeviceExt;
+
+use super::Mesh;
+
+/// Largest vertex count addressed with `Uint16` indices. 0xFFFF itself is
+/// left out because it is the primitive restart value of strip topologies.
+const MAX_UINT16_VERTICES: usize = u16::MAX as usize;
+
+const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+  wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+/// Vertex and index buffers of a mesh on the GPU.
+///
+/// Vertices are interleaved `position, normal` (see `vertex_buffer_layout`).
+/// Indices are stored in `index_format`, chosen from the vertex count by
+/// `index_format_for`; always bind the buffer with that format, since
+/// reading `Uint32` data as `Uint16` (or the other way around) draws garbage.
+pub struct IndexedMesh {
+  pub vertex_buffer: wgpu::Buffer,
+  pub index_buffer: wgpu::Buffer,
+  pub index_format: wgpu::IndexFormat,
+  pub index_count: u32,
+}
+
+impl IndexedMesh {
+  /// `@location(0)` position and `@location(1)` normal, both `vec3f`.
+  pub fn vertex_buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+      array_stride: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+      step_mode: wgpu::VertexStepMode::Vertex,
+      attributes: &VERTEX_ATTRIBUTES,
+    }
+  }
+
+  pub fn triangle_count(&self) -> u32 {
+    self.index_count / 3
+  }
+
+  pub fn draw<'a>(
+    &'a self,
+    render_pass: &mut wgpu::RenderPass<'a>,
+    instances: Range<u32>,
+  ) {
+    render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+    render_pass
+      .set_index_buffer(self.index_buffer.slice(..), self.index_format);
+    render_pass.draw_indexed(0..self.index_count, 0, instances);
+  }
+}
+
+/// `Uint16` while every vertex fits in 16 bits, `Uint32` beyond that.
+///
+/// Writing indices of a mesh with more vertices as `u16` silently wraps
+/// them around and connects unrelated vertices.
+pub fn index_format_for(vertex_count: usize) -> wgpu::IndexFormat {
+  if vertex_count <= MAX_UINT16_VERTICES {
+    wgpu::IndexFormat::Uint16
+  } else {
+    wgpu::IndexFormat::Uint32
+  }
+}
+
+/// Subdivides `mesh` towards a budget of `target_tris` triangles
+/// (`Mesh::subdivide_to_budget`) and uploads the result.
+///
+/// The budget decouples the detail on screen from the generator's fixed
+/// resolution. It is approximate: the uploaded mesh has between a quarter of
+/// the budget and the budget itself, or the original triangles if the mesh is
+/// already above a quarter of it. Pass 0 to upload the mesh as is.
+pub fn upload_mesh(
+  device: &wgpu::Device,
+  mesh: &Mesh,
+  target_tris: usize,
+) -> IndexedMesh {
+  let mesh = mesh.subdivide_to_budget(target_tris);
+
+  let vertices: Vec<[f32; 6]> = mesh
+    .positions
+    .iter()
+    .zip(&mesh.normals)
+    .map(|(p, n)| [p[0], p[1], p[2], n[0], n[1], n[2]])
+    .collect();
+
+  let vertex_buffer =
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Mesh Vertex Buffer"),
+      contents: cast_slice(&vertices),
+      usage: wgpu::BufferUsages::VERTEX,
+    });
+
+  debug_assert!(
+    mesh.indices.iter().all(|&i| (i as usize) < mesh.positions.len()),
+    "mesh index out of range"
+  );
+
+  let index_format = index_format_for(mesh.positions.len());
+  let indices16: Vec<u16>;
+  let index_data: &[u8] = match index_format {
+    wgpu::IndexFormat::Uint16 => {
+      indices16 = mesh.indices.iter().map(|&i| i as u16).collect();
+      cast_slice(&indices16)
+    }
+    wgpu::IndexFormat::Uint32 => cast_slice(&mesh.indices),
+  };
+
+  let index_buffer =
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Mesh Index Buffer"),
+      contents: index_data,
+      usage: wgpu::BufferUsages::INDEX,
+    });
+
+  IndexedMesh {
+    vertex_buffer,
+    index_buffer,
+    index_format,
+    index_count: mesh.indices.len() as u32,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ctx::{DrawingContext, Size};
+  use crate::util;
+
+  const GREEN_SHADER: &str = "
+    @vertex
+    fn vs_main(@location(0) position: vec3f) -> @builtin(position) vec4f {
+      return vec4f(position, 1.);
+    }
+
+    @fragment
+    fn fs_main() -> @location(0) vec4f {
+      return vec4f(0., 1., 0., 1.);
+    }
+  ";
+
+  #[test]
+  fn index_format_switches_to_uint32_past_u16() {
+    assert_eq!(
+      index_format_for(u16::MAX as usize),
+      wgpu::IndexFormat::Uint16
+    );
+    assert_eq!(index_format_for(70000), wgpu::IndexFormat::Uint32);
+  }
+
+  #[test]
+  fn uint32_indices_past_u16_are_drawn() {
+    // 256 バイト（コピーの行の揃え）ちょうどの幅にする
+    let (width, format) = (64, wgpu::TextureFormat::Rgba8Unorm);
+    let ctx = pollster::block_on(DrawingContext::new_for_texture(
+      Size::new(width, 1),
+      format,
+    ))
+    .expect("no GPU adapter for offscreen rendering");
+    let device = &ctx.device;
+
+    // 使わない頂点で 65535 個を超えさせ、最後の3頂点で画面全体を覆う。
+    // u16 に切り詰められると、原点に潰れた頂点を指して何も描かれない
+    const FILLER: usize = 70_000;
+    let mut positions = vec![[0., 0., 0.]; FILLER];
+    positions.extend([[-1., -1., 0.], [3., -1., 0.], [-1., 3., 0.]]);
+    let mesh = Mesh {
+      normals: vec![[0., 0., 1.]; positions.len()],
+      positions,
+      indices: (FILLER as u32..FILLER as u32 + 3).collect(),
+    };
+
+    let indexed = upload_mesh(device, &mesh, 0);
+    assert_eq!(indexed.index_format, wgpu::IndexFormat::Uint32);
+
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: None,
+      source: wgpu::ShaderSource::Wgsl(GREEN_SHADER.into()),
+    });
+    let pipeline =
+      device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: None,
+        layout: None,
+        vertex: wgpu::VertexState {
+          module: &module,
+          entry_point: "vs_main",
+          buffers: &[IndexedMesh::vertex_buffer_layout()],
+          compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &module,
+          entry_point: "fs_main",
+          targets: &[Some(format.into())],
+          compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+      });
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: None,
+      size: wgpu::Extent3d {
+        width,
+        height: 1,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+        | wgpu::TextureUsages::COPY_SRC,
+      view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: None,
+      size: (width * 4) as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+      mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    {
+      let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(util::create_color_attachment(&view))],
+        ..Default::default()
+      });
+      pass.set_pipeline(&pipeline);
+      indexed.draw(&mut pass, 0..1);
+    }
+    encoder.copy_texture_to_buffer(
+      texture.as_image_copy(),
+      wgpu::ImageCopyBuffer {
+        buffer: &output_buffer,
+        layout: wgpu::ImageDataLayout {
+          offset: 0,
+          bytes_per_row: Some(width * 4),
+          rows_per_image: None,
+        },
+      },
+      texture.size(),
+    );
+    ctx.queue.submit(std::iter::once(encoder.finish()));
+
+    let pixels: Vec<u8> =
+      pollster::block_on(util::read_buffer(device, &ctx.queue, &output_buffer));
+    for pixel in pixels.chunks_exact(4) {
+      assert_eq!(pixel, [0, 255, 0, 255]);
+    }
+  }
+}
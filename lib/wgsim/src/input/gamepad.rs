@@ -0,0 +1,133 @@
+use gilrs::{Axis, Button, EventType, GamepadId, Gilrs};
+
+const DEFAULT_DEAD_ZONE: f32 = 0.15;
+
+/// Sticks and triggers of the active gamepad, after dead-zone filtering.
+///
+/// Stick axes are in `-1.0..=1.0` with +y pointing up, triggers in
+/// `0.0..=1.0`. Everything is zero while no gamepad is connected.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GamepadState {
+  pub left_stick: [f32; 2],
+  pub right_stick: [f32; 2],
+  pub left_trigger: f32,
+  pub right_trigger: f32,
+}
+
+/// Polls the first connected gamepad once per frame.
+///
+/// Controllers can be plugged in or removed mid-session: the first one to
+/// connect (or send any input) becomes active, and when it disconnects the
+/// next connected one takes over.
+///
+/// Sticks never rest exactly at zero, so a radial dead zone is applied to each
+/// stick as a whole rather than per axis (per-axis dead zones snap diagonal
+/// input to the axes). Values inside the dead zone become zero, and the rest
+/// of the range is rescaled to start from zero so the output does not jump at
+/// the edge. Triggers use the same rescaling on their single axis.
+pub struct Gamepad {
+  gilrs: Gilrs,
+  active: Option<GamepadId>,
+  dead_zone: f32,
+  state: GamepadState,
+}
+
+impl Gamepad {
+  pub fn new() -> Result<Self, gilrs::Error> {
+    let gilrs = Gilrs::new()?;
+    let active = gilrs.gamepads().next().map(|(id, _)| id);
+
+    Ok(Self {
+      gilrs,
+      active,
+      dead_zone: DEFAULT_DEAD_ZONE,
+      state: GamepadState::default(),
+    })
+  }
+
+  /// Sets the dead-zone radius, in `0.0..1.0`.
+  pub fn with_dead_zone(mut self, dead_zone: f32) -> Self {
+    self.dead_zone = dead_zone.clamp(0., 0.99);
+    self
+  }
+
+  pub fn is_connected(&self) -> bool {
+    self.active.is_some()
+  }
+
+  pub fn state(&self) -> &GamepadState {
+    &self.state
+  }
+
+  /// Drains pending gamepad events and refreshes the state.
+  pub fn poll(&mut self) -> &GamepadState {
+    while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+      match event {
+        EventType::Connected => {
+          log::info!("Gamepad connected: {}", self.gilrs.gamepad(id).name());
+          if self.active.is_none() {
+            self.active = Some(id);
+          }
+        }
+        EventType::Disconnected => {
+          log::info!("Gamepad disconnected: {}", self.gilrs.gamepad(id).name());
+          if self.active == Some(id) {
+            self.active = self
+              .gilrs
+              .gamepads()
+              .find(|(other, gamepad)| *other != id && gamepad.is_connected())
+              .map(|(other, _)| other);
+          }
+        }
+        _ => {
+          if self.active.is_none() {
+            self.active = Some(id);
+          }
+        }
+      }
+    }
+
+    self.state = match self.active {
+      Some(id) => self.read_state(id),
+      None => GamepadState::default(),
+    };
+
+    &self.state
+  }
+
+  fn read_state(&self, id: GamepadId) -> GamepadState {
+    let gamepad = self.gilrs.gamepad(id);
+    let trigger =
+      |button| gamepad.button_data(button).map_or(0., |data| data.value());
+
+    GamepadState {
+      left_stick: self.apply_stick_dead_zone([
+        gamepad.value(Axis::LeftStickX),
+        gamepad.value(Axis::LeftStickY),
+      ]),
+      right_stick: self.apply_stick_dead_zone([
+        gamepad.value(Axis::RightStickX),
+        gamepad.value(Axis::RightStickY),
+      ]),
+      left_trigger: self.rescale(trigger(Button::LeftTrigger2)),
+      right_trigger: self.rescale(trigger(Button::RightTrigger2)),
+    }
+  }
+
+  fn apply_stick_dead_zone(&self, [x, y]: [f32; 2]) -> [f32; 2] {
+    let magnitude = (x * x + y * y).sqrt();
+    if magnitude <= self.dead_zone {
+      return [0., 0.];
+    }
+
+    let scale = self.rescale(magnitude.min(1.)) / magnitude;
+    [x * scale, y * scale]
+  }
+
+  fn rescale(&self, value: f32) -> f32 {
+    if value <= self.dead_zone {
+      return 0.;
+    }
+    ((value - self.dead_zone) / (1. - self.dead_zone)).min(1.)
+  }
+}
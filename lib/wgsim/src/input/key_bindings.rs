@@ -0,0 +1,186 @@
+use std::fmt::Debug;
+
+use winit::event::{ElementState, KeyEvent, WindowEvent};
+use winit::keyboard::{KeyCode, ModifiersState, PhysicalKey};
+
+/// A sample-specific action that can be bound to a key.
+pub trait Action: Copy + Eq + Debug {
+  fn help(&self) -> &'static str;
+}
+
+#[derive(Clone)]
+pub struct KeyBinding<A: Action> {
+  pub key: KeyCode,
+  pub mods: ModifiersState,
+  pub action: A,
+}
+
+impl<A: Action> KeyBinding<A> {
+  pub fn new(key: KeyCode, mods: ModifiersState, action: A) -> Self {
+    Self { key, mods, action }
+  }
+
+  pub fn is_triggered_by(&self, key: &KeyCode, mods: &ModifiersState) -> bool {
+    &self.key == key && &self.mods == mods
+  }
+}
+
+/// Table of key bindings for a sample.
+///
+/// Keys are physical (`KeyCode`), so bindings stay in place regardless of
+/// the keyboard layout.
+#[derive(Clone)]
+pub struct KeyBindings<A: Action> {
+  bindings: Vec<KeyBinding<A>>,
+  mods: ModifiersState,
+}
+
+impl<A: Action> KeyBindings<A> {
+  pub fn new() -> Self {
+    Self {
+      bindings: Vec::new(),
+      mods: ModifiersState::empty(),
+    }
+  }
+
+  pub fn bind(self, key: KeyCode, action: A) -> Self {
+    self.bind_with_mods(key, ModifiersState::empty(), action)
+  }
+
+  pub fn bind_with_mods(
+    mut self,
+    key: KeyCode,
+    mods: ModifiersState,
+    action: A,
+  ) -> Self {
+    self.bindings.push(KeyBinding::new(key, mods, action));
+    self
+  }
+
+  /// Moves `action` to `key` (without modifiers). If another action was
+  /// already bound to that key, it takes over the old key of `action`.
+  pub fn remap(&mut self, action: A, key: KeyCode) {
+    self.remap_with_mods(action, key, ModifiersState::empty());
+  }
+
+  pub fn remap_with_mods(
+    &mut self,
+    action: A,
+    key: KeyCode,
+    mods: ModifiersState,
+  ) {
+    let Some(index) = self.bindings.iter().position(|b| b.action == action)
+    else {
+      self.bindings.push(KeyBinding::new(key, mods, action));
+      return;
+    };
+
+    let old = (self.bindings[index].key, self.bindings[index].mods);
+    if let Some(other) = self
+      .bindings
+      .iter_mut()
+      .find(|b| b.action != action && b.is_triggered_by(&key, &mods))
+    {
+      (other.key, other.mods) = old;
+    }
+
+    self.bindings[index].key = key;
+    self.bindings[index].mods = mods;
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &KeyBinding<A>> {
+    self.bindings.iter()
+  }
+
+  pub fn find(&self, key: &KeyCode, mods: &ModifiersState) -> Option<A> {
+    self
+      .bindings
+      .iter()
+      .find_map(|b| b.is_triggered_by(key, mods).then_some(b.action))
+  }
+
+  /// Tracks modifier state and returns the action for a pressed key.
+  pub fn process_event(&mut self, event: &WindowEvent) -> Option<A> {
+    match event {
+      WindowEvent::ModifiersChanged(modifiers) => {
+        self.mods = modifiers.state();
+        None
+      }
+      WindowEvent::KeyboardInput {
+        event:
+          KeyEvent {
+            physical_key: PhysicalKey::Code(key),
+            state: ElementState::Pressed,
+            ..
+          },
+        ..
+      } => {
+        // a modifier key pressed on its own should match a plain binding
+        let mods = self.mods - key_to_modifier(key);
+        self.find(key, &mods)
+      }
+      _ => None,
+    }
+  }
+
+  /// One line per binding, e.g. `Ctrl+R      - Reset`.
+  pub fn help_lines(&self) -> Vec<String> {
+    self
+      .bindings
+      .iter()
+      .map(|b| {
+        format!(
+          "{:<12} - {}",
+          format!("{}{}", modifiers_to_string(b.mods), key_to_string(b.key)),
+          b.action.help()
+        )
+      })
+      .collect()
+  }
+
+  pub fn print_help(&self) {
+    log::info!("Keyboard bindings:");
+    for line in self.help_lines() {
+      log::info!("  {}", line);
+    }
+  }
+}
+
+impl<A: Action> Default for KeyBindings<A> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+pub fn modifiers_to_string(mods: ModifiersState) -> String {
+  let mut mods_line = String::new();
+  for (modifier, desc) in [
+    (ModifiersState::SUPER, "Super+"),
+    (ModifiersState::ALT, "Alt+"),
+    (ModifiersState::CONTROL, "Ctrl+"),
+    (ModifiersState::SHIFT, "Shift+"),
+  ] {
+    if mods.contains(modifier) {
+      mods_line.push_str(desc);
+    }
+  }
+  mods_line
+}
+
+pub fn key_to_string(key: KeyCode) -> String {
+  let name = format!("{:?}", key);
+  match name.strip_prefix("Key").or(name.strip_prefix("Digit")) {
+    Some(short) if !short.is_empty() => short.to_string(),
+    _ => name,
+  }
+}
+
+fn key_to_modifier(key: &KeyCode) -> ModifiersState {
+  match key {
+    KeyCode::ShiftLeft | KeyCode::ShiftRight => ModifiersState::SHIFT,
+    KeyCode::ControlLeft | KeyCode::ControlRight => ModifiersState::CONTROL,
+    KeyCode::AltLeft | KeyCode::AltRight => ModifiersState::ALT,
+    KeyCode::SuperLeft | KeyCode::SuperRight => ModifiersState::SUPER,
+    _ => ModifiersState::empty(),
+  }
+}
@@ -0,0 +1,11 @@
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod key_bindings;
+mod pointer;
+
+#[cfg(feature = "gamepad")]
+pub use gamepad::{Gamepad, GamepadState};
+pub use key_bindings::{
+  key_to_string, modifiers_to_string, Action, KeyBinding, KeyBindings,
+};
+pub(crate) use pointer::{PointerEvent, PointerTracker};
@@ -0,0 +1,82 @@
+use winit::{
+  dpi::PhysicalPosition,
+  event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent},
+};
+
+/// Pixels of a `MouseScrollDelta::PixelDelta` (trackpads) counted as one
+/// line of a mouse wheel.
+const PIXELS_PER_LINE: f32 = 20.;
+
+/// Mouse input condensed into what camera controls need, see
+/// `Render::on_scroll` and `Render::on_mouse_drag`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PointerEvent {
+  /// Lines scrolled, positive away from the user.
+  Scroll(f32),
+  /// Cursor movement in physical pixels while `button` is held.
+  Drag {
+    dx: f32,
+    dy: f32,
+    button: MouseButton,
+  },
+}
+
+/// Tracks the cursor position and the held button across window events.
+#[derive(Debug, Default)]
+pub(crate) struct PointerTracker {
+  cursor: Option<PhysicalPosition<f64>>,
+  /// 最初に押されたボタン（離されるまでドラッグはこのボタン扱い）
+  held: Option<MouseButton>,
+}
+
+impl PointerTracker {
+  /// Feed every window event, also those the renderer handles itself, so
+  /// the tracked state never misses a press or release.
+  pub fn process(&mut self, event: &WindowEvent) -> Option<PointerEvent> {
+    match event {
+      WindowEvent::MouseWheel { delta, .. } => {
+        let lines = match delta {
+          MouseScrollDelta::LineDelta(_, y) => *y,
+          MouseScrollDelta::PixelDelta(delta) => {
+            delta.y as f32 / PIXELS_PER_LINE
+          }
+        };
+        Some(PointerEvent::Scroll(lines))
+      }
+      WindowEvent::MouseInput { button, state, .. } => {
+        match state {
+          ElementState::Pressed if self.held.is_none() => {
+            self.held = Some(*button);
+          }
+          ElementState::Released if self.held == Some(*button) => {
+            self.held = None;
+          }
+          _ => {}
+        }
+        None
+      }
+      WindowEvent::CursorMoved { position, .. } => {
+        let prev = self.cursor.replace(*position);
+        match (prev, self.held) {
+          (Some(prev), Some(button)) => Some(PointerEvent::Drag {
+            dx: (position.x - prev.x) as f32,
+            dy: (position.y - prev.y) as f32,
+            button,
+          }),
+          _ => None,
+        }
+      }
+      // 戻ってきたときに、離れた位置との差分で飛ばないようにする
+      WindowEvent::CursorLeft { .. } => {
+        self.cursor = None;
+        None
+      }
+      // フォーカスを失ってから離されたボタンは届かない
+      WindowEvent::Focused(false) => {
+        self.held = None;
+        None
+      }
+      _ => None,
+    }
+  }
+}
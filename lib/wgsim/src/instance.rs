@@ -0,0 +1,164 @@
+use cgmath::{Matrix, Matrix4, SquareMatrix};
+use wgpu::util::DeviceExt;
+
+const MAT4_SIZE: wgpu::BufferAddress =
+  std::mem::size_of::<[f32; 16]>() as wgpu::BufferAddress;
+
+/// Transforms and colors of objects drawn with one instanced draw call.
+///
+/// The shaders read them from storage buffers indexed by `instance_index`:
+/// the model matrix, the normal matrix (inverse transpose of the model
+/// matrix, derived here) and an RGBA color per instance.
+pub struct InstanceData {
+  pub transforms: Vec<Matrix4<f32>>,
+  pub colors: Vec<[f32; 4]>,
+}
+
+/// Storage buffers created by `InstanceData::upload`.
+pub struct InstanceBuffers {
+  pub model: wgpu::Buffer,
+  pub normal: wgpu::Buffer,
+  pub color: wgpu::Buffer,
+  count: usize,
+}
+
+impl InstanceData {
+  pub fn new(transforms: Vec<Matrix4<f32>>, colors: Vec<[f32; 4]>) -> Self {
+    assert_eq!(
+      transforms.len(),
+      colors.len(),
+      "every instance needs both a transform and a color"
+    );
+    Self { transforms, colors }
+  }
+
+  pub fn len(&self) -> usize {
+    self.transforms.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.transforms.is_empty()
+  }
+
+  pub fn upload(&self, device: &wgpu::Device) -> InstanceBuffers {
+    let model = self
+      .transforms
+      .iter()
+      .map(|m| *(m.as_ref() as &[f32; 16]))
+      .collect::<Vec<_>>();
+    let normal = self
+      .transforms
+      .iter()
+      .map(|m| *(normal_mat(m).as_ref() as &[f32; 16]))
+      .collect::<Vec<_>>();
+
+    let create = |label, contents: &[u8]| {
+      device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(label),
+        contents,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+      })
+    };
+
+    InstanceBuffers {
+      model: create("Instance Model Buffer", bytemuck::cast_slice(&model)),
+      normal: create("Instance Normal Buffer", bytemuck::cast_slice(&normal)),
+      color: create(
+        "Instance Color Buffer",
+        bytemuck::cast_slice(&self.colors),
+      ),
+      count: self.len(),
+    }
+  }
+
+  /// Updates instance `i` here and on the GPU, leaving the other instances'
+  /// data in the buffers untouched.
+  pub fn update_transform(
+    &mut self,
+    queue: &wgpu::Queue,
+    buffers: &InstanceBuffers,
+    i: usize,
+    transform: Matrix4<f32>,
+  ) {
+    self.transforms[i] = transform;
+    buffers.write_transform(queue, i, &transform);
+  }
+}
+
+impl InstanceBuffers {
+  pub fn count(&self) -> usize {
+    self.count
+  }
+
+  /// Writes the model and normal matrices of instance `i` only.
+  pub fn write_transform(
+    &self,
+    queue: &wgpu::Queue,
+    i: usize,
+    transform: &Matrix4<f32>,
+  ) {
+    assert!(i < self.count, "instance {} of {}", i, self.count);
+
+    let offset = MAT4_SIZE * i as wgpu::BufferAddress;
+    let model: &[f32; 16] = transform.as_ref();
+    let normal = normal_mat(transform);
+    let normal: &[f32; 16] = normal.as_ref();
+
+    queue.write_buffer(&self.model, offset, bytemuck::cast_slice(model));
+    queue.write_buffer(&self.normal, offset, bytemuck::cast_slice(normal));
+  }
+
+  pub fn write_color(&self, queue: &wgpu::Queue, i: usize, color: [f32; 4]) {
+    assert!(i < self.count, "instance {} of {}", i, self.count);
+
+    let offset = (std::mem::size_of::<[f32; 4]>() * i) as wgpu::BufferAddress;
+    queue.write_buffer(&self.color, offset, bytemuck::cast_slice(&color));
+  }
+}
+
+/// 拡大縮小が均一でない場合もあるので、逆転置行列を使う
+fn normal_mat(m: &Matrix4<f32>) -> Matrix4<f32> {
+  m.invert().unwrap_or(Matrix4::identity()).transpose()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ctx::{DrawingContext, Size};
+  use cgmath::Vector3;
+
+  #[test]
+  fn buffers_hold_one_entry_per_instance() {
+    const COUNT: usize = 50;
+
+    let ctx = pollster::block_on(DrawingContext::new_for_texture(
+      Size::new(1, 1),
+      wgpu::TextureFormat::Rgba8Unorm,
+    ))
+    .expect("no GPU adapter for offscreen rendering");
+
+    let data = InstanceData::new(
+      (0..COUNT)
+        .map(|i| Matrix4::from_translation(Vector3::new(i as f32, 0., 0.)))
+        .collect(),
+      vec![[1., 1., 1., 1.]; COUNT],
+    );
+    let buffers = data.upload(&ctx.device);
+
+    assert_eq!(buffers.count(), COUNT);
+    let mat4_size = std::mem::size_of::<[f32; 16]>() as wgpu::BufferAddress;
+    let color_size = std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress;
+    assert_eq!(
+      buffers.model.size(),
+      COUNT as wgpu::BufferAddress * mat4_size
+    );
+    assert_eq!(
+      buffers.normal.size(),
+      COUNT as wgpu::BufferAddress * mat4_size
+    );
+    assert_eq!(
+      buffers.color.size(),
+      COUNT as wgpu::BufferAddress * color_size
+    );
+  }
+}
@@ -1,11 +1,27 @@
+pub mod adaptive;
+pub mod anim;
 pub mod app;
+pub mod camera;
 pub mod compute;
 pub mod ctx;
+pub mod debug;
+pub mod depth;
 pub mod export;
 pub mod fps;
 pub mod geometry;
+pub mod input;
+pub mod instance;
+pub mod light;
 pub mod matrix;
+pub mod mipmap;
+pub mod overlay;
+pub mod post;
 pub mod ppl;
 pub mod render;
+pub mod shader;
+pub mod shadow;
 pub mod surface_cfg;
+pub mod trail;
+#[cfg(feature = "ui")]
+pub mod ui;
 pub mod util;
@@ -0,0 +1,101 @@
+use bytemuck::{cast_slice, Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+/// A point light as laid out in a WGSL storage buffer:
+///
+/// ```wgsl
+/// struct Light {
+///   position: vec3f,
+///   intensity: f32,
+///   color: vec3f,
+/// }
+/// ```
+///
+/// `intensity` sits between the two `vec3f` so that the struct has no
+/// implicit padding except at the end (32 bytes in total).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct Light {
+  pub position: [f32; 3],
+  pub intensity: f32,
+  pub color: [f32; 3],
+  _padding: f32,
+}
+
+impl Light {
+  pub fn new(position: [f32; 3], color: [f32; 3], intensity: f32) -> Self {
+    Self {
+      position,
+      intensity,
+      color,
+      _padding: 0.,
+    }
+  }
+}
+
+/// Blinn-Phong material parameters as laid out in a WGSL uniform:
+///
+/// ```wgsl
+/// struct Material {
+///   ambient: f32,
+///   diffuse: f32,
+///   specular: f32,
+///   shininess: f32,
+/// }
+/// ```
+///
+/// The intensities scale the light color; `shininess` is the exponent of
+/// the specular highlight.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct Material {
+  pub ambient: f32,
+  pub diffuse: f32,
+  pub specular: f32,
+  pub shininess: f32,
+}
+
+impl Default for Material {
+  fn default() -> Self {
+    Self {
+      ambient: 0.2,
+      diffuse: 0.8,
+      specular: 0.4,
+      shininess: 30.,
+    }
+  }
+}
+
+/// Uploads `lights` as a read-only storage buffer (`array<Light>`) and their
+/// number as a `u32` uniform, returned in that order.
+///
+/// A storage binding cannot be empty, so an empty slice still allocates one
+/// zeroed light; the shader skips it because the count is 0. Both buffers
+/// have `COPY_DST`, so lights can be moved with `queue.write_buffer` as long
+/// as their number does not grow.
+pub fn upload_point_lights(
+  device: &wgpu::Device,
+  lights: &[Light],
+) -> (wgpu::Buffer, wgpu::Buffer) {
+  let placeholder = [Light::zeroed()];
+  let contents = if lights.is_empty() {
+    &placeholder[..]
+  } else {
+    lights
+  };
+
+  let lights_buffer =
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Point Lights Storage Buffer"),
+      contents: cast_slice(contents),
+      usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    });
+  let count_buffer =
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Point Light Count Buffer"),
+      contents: cast_slice(&[lights.len() as u32]),
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+
+  (lights_buffer, count_buffer)
+}
@@ -1,6 +1,37 @@
+//! Matrix helpers. They all follow `Convention`, which the tests at the
+//! bottom of this file pin down.
+
 use cgmath::*;
 use std::f32::consts::PI;
 
+/// Coordinate conventions shared by the matrix helpers, the geometry
+/// generators and the cameras.
+///
+/// - World and view space are right-handed with +Y up (`look_at_rh`).
+/// - In view space the camera sits at the origin and looks down -Z, so
+///   visible points have negative z.
+/// - Clip space is WebGPU's: NDC x and y in [-1, 1] with +Y up, z in
+///   [0, 1] with 0 at the near plane. cgmath builds OpenGL matrices (z in
+///   [-1, 1]), so `OPENGL_TO_WGPU_MATRIX` remaps z; no axis is flipped.
+/// - Framebuffer and texture coordinates have +Y down, so a full-screen
+///   pass computes `uv = (x + 1, 1 - y) / 2` from NDC.
+/// - Front faces are counter-clockwise seen from outside
+///   (`geometry::Mesh`, wgpu's default `FrontFace::Ccw`).
+///
+/// Assets authored for another convention (e.g. left-handed or Z-up OBJ
+/// files) must be converted on load, otherwise they render mirrored or
+/// inside out.
+pub struct Convention;
+
+impl Convention {
+  /// Direction the camera looks in view space.
+  pub const VIEW_FORWARD: [f32; 3] = [0., 0., -1.];
+  pub const VIEW_UP: [f32; 3] = [0., 1., 0.];
+  /// NDC depth at the near and far planes.
+  pub const NDC_DEPTH_RANGE: [f32; 2] = [0., 1.];
+  pub const FRONT_FACE: wgpu::FrontFace = wgpu::FrontFace::Ccw;
+}
+
 // cgmath is built for OpenGL's coordinate system
 #[rustfmt::skip]
 pub const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
@@ -10,6 +41,10 @@ pub const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
   0.0, 0.0, 0.5, 1.0,
 );
 
+/// Clip planes used by `create_projection_mat` in perspective mode.
+pub const PERSPECTIVE_NEAR: f32 = 0.1;
+pub const PERSPECTIVE_FAR: f32 = 1000.;
+
 pub fn create_model_mat(
   [translation_x, translation_y, translation_z]: [f32; 3],
   [rotation_x, rotation_y, rotation_z]: [f32; 3],
@@ -42,6 +77,75 @@ pub fn create_model_mat_with_rotation(rotation: [f32; 3]) -> Matrix4<f32> {
   create_model_mat([0., 0., 0.], rotation, [1., 1., 1.])
 }
 
+/// Splits an affine `mat` into translation, rotation and scale, so that
+/// `compose(decompose(mat))` gives `mat` back.
+///
+/// Shear cannot be represented and is lost. A mirroring matrix (negative
+/// determinant) gets a negative x scale. A zero scale leaves the rotation
+/// of that axis undefined; the identity is returned for it.
+pub fn decompose(
+  mat: Matrix4<f32>,
+) -> (Vector3<f32>, Quaternion<f32>, Vector3<f32>) {
+  let translation = mat.w.truncate();
+
+  let axes = [mat.x.truncate(), mat.y.truncate(), mat.z.truncate()];
+  let mut scale = Vector3::new(
+    axes[0].magnitude(),
+    axes[1].magnitude(),
+    axes[2].magnitude(),
+  );
+  if Matrix3::from_cols(axes[0], axes[1], axes[2]).determinant() < 0. {
+    scale.x = -scale.x;
+  }
+
+  if scale.x == 0. || scale.y == 0. || scale.z == 0. {
+    return (translation, Quaternion::one(), scale);
+  }
+
+  let rotation_mat =
+    Matrix3::from_cols(axes[0] / scale.x, axes[1] / scale.y, axes[2] / scale.z);
+  let rotation = Quaternion::from(rotation_mat).normalize();
+
+  (translation, rotation, scale)
+}
+
+/// Builds `translation * rotation * scale`, the order of `create_model_mat`.
+pub fn compose(
+  translation: Vector3<f32>,
+  rotation: Quaternion<f32>,
+  scale: Vector3<f32>,
+) -> Matrix4<f32> {
+  Matrix4::from_translation(translation)
+    * Matrix4::from(rotation)
+    * Matrix4::from_nonuniform_scale(scale.x, scale.y, scale.z)
+}
+
+/// Interpolates between two affine transforms for keyframe animation:
+/// translation and scale linearly, rotation along the shorter arc
+/// (spherical). `t` is 0 at `a` and 1 at `b`.
+///
+/// Interpolating the matrices element-wise instead would shrink and shear
+/// the object halfway through a rotation.
+pub fn slerp_transforms(
+  a: Matrix4<f32>,
+  b: Matrix4<f32>,
+  t: f32,
+) -> Matrix4<f32> {
+  let (translation_a, rotation_a, scale_a) = decompose(a);
+  let (translation_b, mut rotation_b, scale_b) = decompose(b);
+
+  // q と -q は同じ回転なので、内積が負なら反転して近い方の弧をたどる
+  if rotation_a.dot(rotation_b) < 0. {
+    rotation_b = -rotation_b;
+  }
+
+  compose(
+    translation_a.lerp(translation_b, t),
+    rotation_a.slerp(rotation_b, t),
+    scale_a.lerp(scale_b, t),
+  )
+}
+
 pub fn create_view_mat(
   camera_position: Point3<f32>,
   look_direction: Point3<f32>,
@@ -55,7 +159,12 @@ pub fn create_projection_mat(
   is_perspective: bool,
 ) -> Matrix4<f32> {
   let project_mat = if is_perspective {
-    create_perspective_mat(Rad(2. * PI / 5.), aspect, 0.1, 1000.)
+    create_perspective_mat(
+      Rad(2. * PI / 5.),
+      aspect,
+      PERSPECTIVE_NEAR,
+      PERSPECTIVE_FAR,
+    )
   } else {
     create_ortho_mat(-4., 4., -3., 3., -1., 6.)
   };
@@ -83,6 +192,17 @@ pub fn create_ortho_mat(
   OPENGL_TO_WGPU_MATRIX * ortho(left, right, bottom, top, near, far)
 }
 
+/// Maps pixel coordinates of a `width` x `height` target to clip space:
+/// (0, 0) is the top-left corner and +Y points down, like framebuffer
+/// coordinates (see `Convention`). z = 0 lands in the middle of the depth
+/// range; z in [-1, 1] stays visible.
+///
+/// Rebuild it on resize instead of regenerating vertices, so 2D geometry
+/// can stay in pixels.
+pub fn create_ortho_2d(width: f32, height: f32) -> Matrix4<f32> {
+  create_ortho_mat(0., width, height, 0., -1., 1.)
+}
+
 pub fn create_vp_ortho_mat(
   left: f32,
   right: f32,
@@ -116,3 +236,165 @@ pub fn create_vp_mat(
 
   (view_mat, project_mat, vp_mat)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Relative tolerance of the convention checks.
+  const CONVENTION_EPSILON: f32 = 1e-3;
+
+  /// Checks that `view_mat` follows `Convention`: `eye` maps to the origin and
+  /// `target` lies in front of the camera, on the -Z axis of view space.
+  fn check_view_convention(
+    view_mat: &Matrix4<f32>,
+    eye: Point3<f32>,
+    target: Point3<f32>,
+  ) -> Result<(), String> {
+    let eye_view = view_mat.transform_point(eye);
+    let target_view = view_mat.transform_point(target);
+    let distance = eye.distance(target);
+    let tolerance = CONVENTION_EPSILON * distance.max(1.);
+
+    if eye_view.to_vec().magnitude() > tolerance {
+      return Err(format!(
+        "view matrix does not move the eye to the origin: {:?}",
+        eye_view
+      ));
+    }
+
+    let forward = Vector3::from(Convention::VIEW_FORWARD) * distance;
+    if (target_view.to_vec() - forward).magnitude() > tolerance {
+      return Err(format!(
+        "view matrix does not look down -Z: target maps to {:?}, expected {:?}",
+        target_view, forward
+      ));
+    }
+
+    Ok(())
+  }
+
+  /// Checks that `project_mat` follows `Convention`: view-space points on the
+  /// near and far planes land on NDC z 0 and 1, and +X/+Y stay right/up.
+  /// Works for perspective and orthographic projections.
+  fn check_projection_convention(
+    project_mat: &Matrix4<f32>,
+    near: f32,
+    far: f32,
+  ) -> Result<(), String> {
+    let to_ndc = |p: Vector3<f32>| {
+      let clip = *project_mat * p.extend(1.);
+      clip.truncate() / clip.w
+    };
+    let forward = Vector3::from(Convention::VIEW_FORWARD);
+    let [near_z, far_z] = Convention::NDC_DEPTH_RANGE;
+
+    let near_ndc = to_ndc(forward * near);
+    let far_ndc = to_ndc(forward * far);
+    if (near_ndc.z - near_z).abs() > CONVENTION_EPSILON
+      || (far_ndc.z - far_z).abs() > CONVENTION_EPSILON
+    {
+      return Err(format!(
+        "projection does not map near/far to NDC z {:?}: got [{}, {}]",
+        Convention::NDC_DEPTH_RANGE,
+        near_ndc.z,
+        far_ndc.z
+      ));
+    }
+
+    let offset = to_ndc(forward * near + Vector3::new(1., 1., 0.) * 1e-3);
+    if offset.x <= near_ndc.x || offset.y <= near_ndc.y {
+      return Err(format!(
+        "projection flips an axis: view-space +X/+Y map to NDC {:?}",
+        offset
+      ));
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn view_mat_looks_down_negative_z() {
+    let cameras = [
+      (Point3::new(3., 1.5, 3.), Point3::new(0., 0., 0.)),
+      (Point3::new(0., 0., 5.), Point3::new(0., 0., 0.)),
+      (Point3::new(-2., 4., -6.), Point3::new(1., 0., 2.)),
+    ];
+
+    for (eye, target) in cameras {
+      let view_mat = create_view_mat(eye, target, Vector3::unit_y());
+      assert_eq!(check_view_convention(&view_mat, eye, target), Ok(()));
+    }
+  }
+
+  #[test]
+  fn perspective_maps_near_and_far_to_ndc_zero_and_one() {
+    for aspect in [0.5, 1., 16. / 9.] {
+      let project_mat = create_projection_mat(aspect, true);
+      assert_eq!(
+        check_projection_convention(
+          &project_mat,
+          PERSPECTIVE_NEAR,
+          PERSPECTIVE_FAR
+        ),
+        Ok(())
+      );
+    }
+
+    let project_mat = create_perspective_mat(Deg(60.).into(), 1., 0.5, 20.);
+    assert_eq!(check_projection_convention(&project_mat, 0.5, 20.), Ok(()));
+  }
+
+  #[test]
+  fn ortho_maps_near_and_far_to_ndc_zero_and_one() {
+    let project_mat = create_ortho_mat(-4., 4., -3., 3., -1., 6.);
+    assert_eq!(check_projection_convention(&project_mat, -1., 6.), Ok(()));
+  }
+
+  #[test]
+  fn visible_depth_stays_in_ndc_range() {
+    let project_mat = create_projection_mat(1., true);
+    let [near_z, far_z] = Convention::NDC_DEPTH_RANGE;
+
+    for z in [-0.2, -1., -10., -100., -999.] {
+      let clip = project_mat * Vector4::new(0., 0., z, 1.);
+      let ndc_z = clip.z / clip.w;
+      assert!(
+        (near_z..=far_z).contains(&ndc_z),
+        "view z {} maps to NDC z {}",
+        z,
+        ndc_z
+      );
+    }
+  }
+
+  #[test]
+  fn decompose_undoes_compose() {
+    let translation = Vector3::new(1., -2., 3.);
+    let rotation = Quaternion::from_axis_angle(
+      Vector3::new(1., 2., 3.).normalize(),
+      Deg(40.),
+    );
+    let scale = Vector3::new(2., 0.5, 3.);
+
+    let (t, r, s) = decompose(compose(translation, rotation, scale));
+
+    assert!(t.abs_diff_eq(&translation, 1e-5), "{:?}", t);
+    assert!(s.abs_diff_eq(&scale, 1e-5), "{:?}", s);
+    // q と -q は同じ回転
+    assert!((r.dot(rotation).abs() - 1.).abs() < 1e-5, "{:?}", r);
+  }
+
+  #[test]
+  fn mirrored_matrix_round_trips() {
+    let mat = compose(
+      Vector3::new(0., 1., 0.),
+      Quaternion::from_angle_y(Deg(30.)),
+      Vector3::new(-1., 2., 1.),
+    );
+
+    let (t, r, s) = decompose(mat);
+    assert!(s.x < 0.);
+    assert!(compose(t, r, s).abs_diff_eq(&mat, 1e-5));
+  }
+}
@@ -0,0 +1,145 @@
+use crate::util;
+
+/// Fills the mip levels of a texture from its level 0 with a chain of
+/// render passes, each level drawn from the one above it.
+///
+/// Every pass samples the previous level with a linear filter at the center
+/// of each destination pixel, which averages a 2x2 block (a box filter). For
+/// odd sizes the last row/column is only partly covered, which is fine for
+/// previews but not an exact downsample.
+///
+/// The texture needs `RENDER_ATTACHMENT | TEXTURE_BINDING` and a renderable,
+/// filterable `format`. sRGB formats are averaged in linear space. Keep one
+/// generator per format around when regenerating every frame.
+pub struct MipmapGenerator {
+  pipeline: wgpu::RenderPipeline,
+  bind_group_layout: wgpu::BindGroupLayout,
+  sampler: wgpu::Sampler,
+  format: wgpu::TextureFormat,
+}
+
+impl MipmapGenerator {
+  pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+    let shader =
+      device.create_shader_module(wgpu::include_wgsl!("./blit.wgsl"));
+
+    let bind_group_layout = util::create_bind_group_layout(
+      device,
+      &[
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        wgpu::BindingType::Texture {
+          sample_type: wgpu::TextureSampleType::Float { filterable: true },
+          view_dimension: wgpu::TextureViewDimension::D2,
+          multisampled: false,
+        },
+      ],
+      &[wgpu::ShaderStages::FRAGMENT, wgpu::ShaderStages::FRAGMENT],
+    );
+
+    let pipeline_layout =
+      device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Mipmap Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let pipeline =
+      device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Mipmap Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &[Some(format.into())],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+      });
+
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some("Mipmap Sampler"),
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      ..Default::default()
+    });
+
+    Self {
+      pipeline,
+      bind_group_layout,
+      sampler,
+      format,
+    }
+  }
+
+  /// Creates the views and bind groups for levels `1..mip_levels` of
+  /// `texture` once, so `generate` can be recorded every frame.
+  pub fn prepare(
+    &self,
+    device: &wgpu::Device,
+    texture: &wgpu::Texture,
+    mip_levels: u32,
+  ) -> MipChain {
+    debug_assert_eq!(
+      texture.format(),
+      self.format,
+      "the generator was created for another format"
+    );
+
+    let level_view = |level| {
+      texture.create_view(&wgpu::TextureViewDescriptor {
+        label: Some("Mip Level View"),
+        base_mip_level: level,
+        mip_level_count: Some(1),
+        ..Default::default()
+      })
+    };
+
+    let levels = (1..mip_levels.min(texture.mip_level_count()))
+      .map(|level| {
+        let bind_group = util::create_bind_group(
+          device,
+          &self.bind_group_layout,
+          &[
+            wgpu::BindingResource::Sampler(&self.sampler),
+            wgpu::BindingResource::TextureView(&level_view(level - 1)),
+          ],
+        );
+        (bind_group, level_view(level))
+      })
+      .collect();
+
+    MipChain { levels }
+  }
+
+  /// Records one pass per level, each reading the level above it.
+  pub fn generate(&self, encoder: &mut wgpu::CommandEncoder, chain: &MipChain) {
+    for (bind_group, dst_view) in &chain.levels {
+      let mut render_pass =
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+          label: Some("Mipmap Pass"),
+          color_attachments: &[Some(util::create_color_attachment(dst_view))],
+          ..Default::default()
+        });
+
+      render_pass.set_pipeline(&self.pipeline);
+      render_pass.set_bind_group(0, bind_group, &[]);
+      render_pass.draw(0..3, 0..1);
+    }
+  }
+}
+
+/// Per-level bind groups and views of one texture, from
+/// `MipmapGenerator::prepare`.
+pub struct MipChain {
+  levels: Vec<(wgpu::BindGroup, wgpu::TextureView)>,
+}
@@ -0,0 +1,84 @@
+use winit::event::{ElementState, KeyEvent, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use crate::ctx::DrawingContext;
+use crate::input::{Action, KeyBindings};
+
+use super::TextPanel;
+
+/// A panel listing the sample's key bindings, toggled with F1.
+///
+/// Samples that animate can check `is_visible` in `update` to pause while
+/// the help is shown.
+pub struct HelpOverlay {
+  panel: TextPanel,
+  visible: bool,
+}
+
+impl HelpOverlay {
+  pub fn new<A: Action>(
+    ctx: &DrawingContext,
+    font: &'static [u8],
+    key_bindings: &KeyBindings<A>,
+  ) -> Self {
+    let mut lines = vec!["F1           - Toggle this help".to_string()];
+    lines.extend(key_bindings.help_lines());
+
+    Self {
+      panel: TextPanel::new(ctx, font, lines),
+      visible: false,
+    }
+  }
+
+  pub fn is_visible(&self) -> bool {
+    self.visible
+  }
+
+  pub fn toggle(&mut self) {
+    self.visible = !self.visible;
+  }
+
+  /// Rebuilds the list, e.g. after a binding was remapped.
+  pub fn update_bindings<A: Action>(
+    &mut self,
+    ctx: &DrawingContext,
+    key_bindings: &KeyBindings<A>,
+  ) {
+    let mut lines = vec![self.panel.lines()[0].clone()];
+    lines.extend(key_bindings.help_lines());
+    self.panel.set_lines(ctx, lines);
+  }
+
+  /// Returns `true` when F1 was pressed and the overlay was toggled.
+  pub fn process_event(&mut self, event: &WindowEvent) -> bool {
+    match event {
+      WindowEvent::KeyboardInput {
+        event:
+          KeyEvent {
+            physical_key: PhysicalKey::Code(KeyCode::F1),
+            state: ElementState::Pressed,
+            ..
+          },
+        ..
+      } => {
+        self.toggle();
+        true
+      }
+      _ => false,
+    }
+  }
+
+  pub fn resize(&mut self, ctx: &DrawingContext) {
+    self.panel.resize(ctx);
+  }
+
+  pub fn draw(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    view: &wgpu::TextureView,
+  ) {
+    if self.visible {
+      self.panel.draw(encoder, view);
+    }
+  }
+}
@@ -0,0 +1,5 @@
+mod help;
+mod panel;
+
+pub use help::HelpOverlay;
+pub use panel::{Anchor, TextPanel};
@@ -0,0 +1,311 @@
+use bytemuck::cast_slice;
+use meshtext::{IndexedMeshText, MeshGenerator, TextSection};
+use wgpu::util::DeviceExt;
+
+use crate::ctx::{DrawingContext, Size};
+use crate::util;
+
+const FONT_SIZE: f32 = 18.;
+const LINE_HEIGHT: f32 = 1.4;
+const MARGIN: f32 = 12.;
+const PADDING: f32 = 10.;
+
+const PANEL_COLOR: [f32; 4] = [0., 0., 0., 0.6];
+const TEXT_COLOR: [f32; 4] = [1., 1., 1., 1.];
+
+/// The corner of the target view a `TextPanel` is placed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Anchor {
+  #[default]
+  TopLeft,
+  TopRight,
+}
+
+/// Lines of text on a translucent panel in a corner (top-left by default),
+/// drawn over whatever is already in the target view.
+///
+/// Glyphs are triangulated with meshtext, so no atlas is needed. The mesh is
+/// built in pixel units and rebuilt on `resize` and `set_lines`.
+pub struct TextPanel {
+  pipeline: wgpu::RenderPipeline,
+  panel_bind_group: wgpu::BindGroup,
+  text_bind_group: wgpu::BindGroup,
+
+  vertex_buffer: wgpu::Buffer,
+  index_buffer: wgpu::Buffer,
+  panel_index_count: u32,
+  text_index_count: u32,
+
+  font: &'static [u8],
+  lines: Vec<String>,
+  anchor: Anchor,
+  size: Size,
+}
+
+impl TextPanel {
+  pub fn new(
+    ctx: &DrawingContext,
+    font: &'static [u8],
+    lines: Vec<String>,
+  ) -> Self {
+    let shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./overlay.wgsl"));
+
+    let bind_group_layout = util::create_bind_group_layout_for_buffer(
+      &ctx.device,
+      &[wgpu::BufferBindingType::Uniform],
+      &[wgpu::ShaderStages::FRAGMENT],
+    );
+    let create_color_bind_group = |color: [f32; 4]| {
+      let buffer =
+        ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+          label: Some("Overlay Color Buffer"),
+          contents: cast_slice(&color),
+          usage: wgpu::BufferUsages::UNIFORM,
+        });
+      util::create_bind_group(
+        &ctx.device,
+        &bind_group_layout,
+        &[buffer.as_entire_binding()],
+      )
+    };
+    let panel_bind_group = create_color_bind_group(PANEL_COLOR);
+    let text_bind_group = create_color_bind_group(TEXT_COLOR);
+
+    let pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Overlay Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    // 解決済みのビューに直接描くので、MSAAの有無にかかわらずsample_countは1
+    let pipeline =
+      ctx.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Overlay Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<[f32; 2]>()
+              as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &wgpu::vertex_attr_array![0 => Float32x2],
+          }],
+          compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &[Some(wgpu::ColorTargetState {
+            format: ctx.format(),
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::ALL,
+          })],
+          compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+      });
+
+    let anchor = Anchor::default();
+    let size = *ctx.size();
+    let mesh = build_mesh(font, &lines, anchor, size);
+    let (vertex_buffer, index_buffer) = mesh.create_buffers(&ctx.device);
+
+    Self {
+      pipeline,
+      panel_bind_group,
+      text_bind_group,
+      vertex_buffer,
+      index_buffer,
+      panel_index_count: mesh.panel_index_count,
+      text_index_count: mesh.text_index_count,
+      font,
+      lines,
+      anchor,
+      size,
+    }
+  }
+
+  pub fn with_anchor(mut self, ctx: &DrawingContext, anchor: Anchor) -> Self {
+    self.anchor = anchor;
+    self.rebuild(ctx);
+    self
+  }
+
+  pub fn lines(&self) -> &[String] {
+    &self.lines
+  }
+
+  pub fn set_lines(&mut self, ctx: &DrawingContext, lines: Vec<String>) {
+    self.lines = lines;
+    self.rebuild(ctx);
+  }
+
+  pub fn resize(&mut self, ctx: &DrawingContext) {
+    self.resize_to(ctx, *ctx.size());
+  }
+
+  /// Lays the panel out for a view of `size` instead of `ctx.size()`, e.g.
+  /// the surface itself when the renderer draws at a reduced resolution.
+  pub fn resize_to(&mut self, ctx: &DrawingContext, size: Size) {
+    self.size = size;
+    self.rebuild(ctx);
+  }
+
+  pub fn draw(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    view: &wgpu::TextureView,
+  ) {
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Overlay Render Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view,
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Load,
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+    render_pass
+      .set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+    render_pass.set_bind_group(0, &self.panel_bind_group, &[]);
+    render_pass.draw_indexed(0..self.panel_index_count, 0, 0..1);
+
+    let text_end = self.panel_index_count + self.text_index_count;
+    render_pass.set_bind_group(0, &self.text_bind_group, &[]);
+    render_pass.draw_indexed(self.panel_index_count..text_end, 0, 0..1);
+  }
+
+  fn rebuild(&mut self, ctx: &DrawingContext) {
+    let mesh = build_mesh(self.font, &self.lines, self.anchor, self.size);
+    let (vertex_buffer, index_buffer) = mesh.create_buffers(&ctx.device);
+
+    self.vertex_buffer = vertex_buffer;
+    self.index_buffer = index_buffer;
+    self.panel_index_count = mesh.panel_index_count;
+    self.text_index_count = mesh.text_index_count;
+  }
+}
+
+struct PanelMesh {
+  vertices: Vec<f32>,
+  indices: Vec<u32>,
+  panel_index_count: u32,
+  text_index_count: u32,
+}
+
+impl PanelMesh {
+  fn create_buffers(
+    &self,
+    device: &wgpu::Device,
+  ) -> (wgpu::Buffer, wgpu::Buffer) {
+    let vertex_buffer =
+      device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Overlay Vertex Buffer"),
+        contents: cast_slice(&self.vertices),
+        usage: wgpu::BufferUsages::VERTEX,
+      });
+    let index_buffer =
+      device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Overlay Index Buffer"),
+        contents: cast_slice(&self.indices),
+        usage: wgpu::BufferUsages::INDEX,
+      });
+
+    (vertex_buffer, index_buffer)
+  }
+}
+
+/// パネル（先頭の四角形）と各行のグリフを1つのメッシュにまとめる
+fn build_mesh(
+  font: &'static [u8],
+  lines: &[String],
+  anchor: Anchor,
+  size: Size,
+) -> PanelMesh {
+  let width = size.width.max(1) as f32;
+  let height = size.height.max(1) as f32;
+
+  // pixel -> NDC
+  let to_x = |px: f32| px / width * 2. - 1.;
+  let to_y = |px: f32| 1. - px / height * 2.;
+
+  let mut generator = MeshGenerator::new(font);
+  let mut text_vertices: Vec<f32> = Vec::new();
+  let mut text_indices: Vec<u32> = Vec::new();
+  let mut text_width: f32 = 0.;
+
+  let left = MARGIN + PADDING;
+  for (i, line) in lines.iter().enumerate() {
+    if line.trim().is_empty() {
+      continue;
+    }
+
+    let baseline = MARGIN + PADDING + FONT_SIZE * (LINE_HEIGHT * i as f32 + 1.);
+    // column-major 3x3: em単位 -> NDC
+    let transform = [
+      FONT_SIZE / width * 2.,
+      0.,
+      0.,
+      0.,
+      FONT_SIZE / height * 2.,
+      0.,
+      to_x(left),
+      to_y(baseline),
+      1.,
+    ];
+    let data: IndexedMeshText = generator
+      .generate_section_2d(line, Some(&transform))
+      .expect("failed to generate glyph.");
+
+    let offset = (text_vertices.len() / 2) as u32;
+    for x in data.vertices.iter().step_by(2) {
+      text_width = text_width.max((x + 1.) / 2. * width - left);
+    }
+    text_vertices.extend_from_slice(&data.vertices);
+    text_indices.extend(data.indices.iter().map(|i| i + offset));
+  }
+
+  let panel_right = left + text_width + PADDING;
+  let panel_bottom = MARGIN
+    + PADDING * 2.
+    + FONT_SIZE * (LINE_HEIGHT * lines.len().saturating_sub(1) as f32 + 1.3);
+  let (x0, y0) = (to_x(MARGIN), to_y(MARGIN));
+  let (x1, y1) = (to_x(panel_right), to_y(panel_bottom));
+
+  let mut vertices = vec![x0, y0, x1, y0, x1, y1, x0, y1];
+  let mut indices = vec![0, 1, 2, 0, 2, 3];
+  let panel_index_count = indices.len() as u32;
+
+  vertices.extend(text_vertices);
+  indices.extend(text_indices.iter().map(|i| i + 4));
+
+  // 左上に組んだものを右端に寄せる
+  if anchor == Anchor::TopRight {
+    let shift = (width - MARGIN - panel_right) / width * 2.;
+    for x in vertices.iter_mut().step_by(2) {
+      *x += shift;
+    }
+  }
+
+  PanelMesh {
+    vertices,
+    indices,
+    panel_index_count,
+    text_index_count: text_indices.len() as u32,
+  }
+}
@@ -0,0 +1,408 @@
+use bytemuck::{cast_slice, Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+use super::tone_map::{ToneMapParams, TONE_MAP_OPS_WGSL};
+use super::{PostEffect, ToneMapOperator};
+use crate::{ctx::DrawingContext, util};
+
+const DEFAULT_THRESHOLD: f32 = 0.8;
+const DEFAULT_INTENSITY: f32 = 0.8;
+const DEFAULT_BLUR_PASSES: u32 = 2;
+
+/// The bright areas are extracted and blurred at this fraction of the
+/// scene size, which widens the blur and makes it cheaper.
+const DOWNSAMPLE: u32 = 2;
+const BLOOM_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Mirrors `Params` in `bloom.wgsl`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct BloomParams {
+  threshold: f32,
+  intensity: f32,
+  tone_map: ToneMapParams,
+}
+
+/// Bloom: lets colors above a threshold bleed into their surroundings.
+///
+/// The scene is rendered into an `Rgba16Float` target so values above 1
+/// survive until here. Then
+///
+/// 1. everything but the part of each pixel brighter than `threshold` (by
+///    its largest channel) is cut away, at half the scene size
+/// 2. the result is blurred with a separable 9-tap gaussian, horizontally
+///    then vertically, `blur_passes` times
+/// 3. it is added to the scene, scaled by `intensity`, and tone mapped
+///    while writing to the surface
+///
+/// The tone mapping defaults to `ToneMapOperator::ExposureOnly` at an
+/// exposure of 1, which clips whatever is still above 1; see
+/// `with_tone_map`.
+pub struct BloomPass {
+  threshold: f32,
+  intensity: f32,
+  blur_passes: u32,
+  tone_map: ToneMapOperator,
+  exposure: f32,
+  gpu: Option<BloomGpu>,
+}
+
+struct BloomGpu {
+  threshold_pipeline: wgpu::RenderPipeline,
+  blur_pipeline: wgpu::RenderPipeline,
+  composite_pipeline: wgpu::RenderPipeline,
+  bind_group_layout: wgpu::BindGroupLayout,
+  blur_bind_group_layout: wgpu::BindGroupLayout,
+  sampler: wgpu::Sampler,
+  params_uniform_buffer: wgpu::Buffer,
+  /// Horizontal and vertical.
+  direction_uniform_buffers: [wgpu::Buffer; 2],
+  targets: Option<BloomTargets>,
+}
+
+/// Everything that depends on the scene size.
+struct BloomTargets {
+  /// Ping-pong pair: each blur pass goes from 0 to 1 and back to 0.
+  views: [wgpu::TextureView; 2],
+  threshold_bind_group: wgpu::BindGroup,
+  blur_bind_groups: [wgpu::BindGroup; 2],
+  composite_bind_group: wgpu::BindGroup,
+}
+
+impl Default for BloomPass {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl BloomPass {
+  pub fn new() -> Self {
+    Self {
+      threshold: DEFAULT_THRESHOLD,
+      intensity: DEFAULT_INTENSITY,
+      blur_passes: DEFAULT_BLUR_PASSES,
+      tone_map: ToneMapOperator::ExposureOnly,
+      exposure: 1.,
+      gpu: None,
+    }
+  }
+
+  /// Brightness above which colors bloom, 0.8 by default.
+  pub fn with_threshold(mut self, threshold: f32) -> Self {
+    self.threshold = threshold.max(0.);
+    self
+  }
+
+  /// Scale of the blurred highlights added to the scene, 0.8 by default.
+  pub fn with_intensity(mut self, intensity: f32) -> Self {
+    self.intensity = intensity.max(0.);
+    self
+  }
+
+  /// Each pass widens the glow, 2 by default.
+  pub fn with_blur_passes(mut self, passes: u32) -> Self {
+    self.blur_passes = passes.max(1);
+    self
+  }
+
+  /// Tone maps the composited scene, like a `ToneMap` effect after the
+  /// bloom.
+  pub fn with_tone_map(
+    mut self,
+    operator: ToneMapOperator,
+    exposure: f32,
+  ) -> Self {
+    self.tone_map = operator;
+    self.exposure = exposure.max(0.);
+    self
+  }
+
+  fn gpu(&self) -> &BloomGpu {
+    self.gpu.as_ref().expect("BloomPass::init must be called before use")
+  }
+}
+
+impl PostEffect for BloomPass {
+  fn init(&mut self, ctx: &DrawingContext) {
+    let source =
+      format!("{}{}", TONE_MAP_OPS_WGSL, include_str!("./bloom.wgsl"));
+    let shader =
+      ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("bloom.wgsl"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+      });
+    let blur_shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./bloom_blur.wgsl"));
+
+    let sampler =
+      util::create_sampler(&ctx.device, util::SamplerOptions::default());
+
+    let params_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("bloom params uniform buffer"),
+        contents: cast_slice(&[BloomParams {
+          threshold: self.threshold,
+          intensity: self.intensity,
+          tone_map: ToneMapParams::new(self.tone_map, self.exposure),
+        }]),
+        usage: wgpu::BufferUsages::UNIFORM,
+      });
+    let direction_uniform_buffers =
+      [[1f32, 0., 0., 0.], [0., 1., 0., 0.]].map(|direction| {
+        ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+          label: Some("bloom direction uniform buffer"),
+          contents: cast_slice(&direction),
+          usage: wgpu::BufferUsages::UNIFORM,
+        })
+      });
+
+    let sampler_binding =
+      wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering);
+    let texture_binding = wgpu::BindingType::Texture {
+      sample_type: wgpu::TextureSampleType::Float { filterable: true },
+      view_dimension: wgpu::TextureViewDimension::D2,
+      multisampled: false,
+    };
+    let uniform_binding = wgpu::BindingType::Buffer {
+      ty: wgpu::BufferBindingType::Uniform,
+      has_dynamic_offset: false,
+      min_binding_size: None,
+    };
+
+    // しきい値とシーンへの合成で共用する（しきい値のパスは binding 3 を使わない）
+    let bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[
+        sampler_binding,
+        texture_binding,
+        uniform_binding,
+        texture_binding,
+      ],
+      &[wgpu::ShaderStages::FRAGMENT; 4],
+    );
+    let blur_bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[sampler_binding, texture_binding, uniform_binding],
+      &[wgpu::ShaderStages::FRAGMENT; 3],
+    );
+
+    let threshold_pipeline = create_pipeline(
+      ctx,
+      "Bloom Threshold Pipeline",
+      &bind_group_layout,
+      &shader,
+      "fs_threshold",
+      BLOOM_FORMAT,
+    );
+    let blur_pipeline = create_pipeline(
+      ctx,
+      "Bloom Blur Pipeline",
+      &blur_bind_group_layout,
+      &blur_shader,
+      "fs_main",
+      BLOOM_FORMAT,
+    );
+    let composite_pipeline = create_pipeline(
+      ctx,
+      "Bloom Composite Pipeline",
+      &bind_group_layout,
+      &shader,
+      "fs_composite",
+      ctx.surface_format(),
+    );
+
+    self.gpu = Some(BloomGpu {
+      threshold_pipeline,
+      blur_pipeline,
+      composite_pipeline,
+      bind_group_layout,
+      blur_bind_group_layout,
+      sampler,
+      params_uniform_buffer,
+      direction_uniform_buffers,
+      targets: None,
+    });
+  }
+
+  fn resize(&mut self, ctx: &DrawingContext, input_view: &wgpu::TextureView) {
+    let gpu =
+      self.gpu.as_mut().expect("BloomPass::init must be called before use");
+
+    let size = ctx.size();
+    let views = [0, 1].map(|_| {
+      create_bloom_texture(
+        ctx,
+        (size.width / DOWNSAMPLE).max(1),
+        (size.height / DOWNSAMPLE).max(1),
+      )
+      .create_view(&wgpu::TextureViewDescriptor::default())
+    });
+
+    let threshold_bind_group = util::create_bind_group(
+      &ctx.device,
+      &gpu.bind_group_layout,
+      &[
+        wgpu::BindingResource::Sampler(&gpu.sampler),
+        wgpu::BindingResource::TextureView(input_view),
+        gpu.params_uniform_buffer.as_entire_binding(),
+        // 使わないが、レイアウトを合成と共用しているので何か渡す
+        wgpu::BindingResource::TextureView(&views[1]),
+      ],
+    );
+    // 0 を横方向にぼかして 1 へ、1 を縦方向にぼかして 0 へ
+    let blur_bind_groups = [0, 1].map(|i| {
+      util::create_bind_group(
+        &ctx.device,
+        &gpu.blur_bind_group_layout,
+        &[
+          wgpu::BindingResource::Sampler(&gpu.sampler),
+          wgpu::BindingResource::TextureView(&views[i]),
+          gpu.direction_uniform_buffers[i].as_entire_binding(),
+        ],
+      )
+    });
+    let composite_bind_group = util::create_bind_group(
+      &ctx.device,
+      &gpu.bind_group_layout,
+      &[
+        wgpu::BindingResource::Sampler(&gpu.sampler),
+        wgpu::BindingResource::TextureView(input_view),
+        gpu.params_uniform_buffer.as_entire_binding(),
+        wgpu::BindingResource::TextureView(&views[0]),
+      ],
+    );
+
+    gpu.targets = Some(BloomTargets {
+      views,
+      threshold_bind_group,
+      blur_bind_groups,
+      composite_bind_group,
+    });
+  }
+
+  fn apply(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    _input_view: &wgpu::TextureView,
+    output_view: &wgpu::TextureView,
+  ) {
+    let gpu = self.gpu();
+    let targets = gpu
+      .targets
+      .as_ref()
+      .expect("BloomPass::resize must be called before apply");
+
+    draw_fullscreen(
+      encoder,
+      "Bloom Threshold Pass",
+      &targets.views[0],
+      &gpu.threshold_pipeline,
+      &targets.threshold_bind_group,
+    );
+
+    for _ in 0..self.blur_passes {
+      // 横方向は 0 -> 1、縦方向は 1 -> 0
+      draw_fullscreen(
+        encoder,
+        "Bloom Blur Pass",
+        &targets.views[1],
+        &gpu.blur_pipeline,
+        &targets.blur_bind_groups[0],
+      );
+      draw_fullscreen(
+        encoder,
+        "Bloom Blur Pass",
+        &targets.views[0],
+        &gpu.blur_pipeline,
+        &targets.blur_bind_groups[1],
+      );
+    }
+
+    draw_fullscreen(
+      encoder,
+      "Bloom Composite Pass",
+      output_view,
+      &gpu.composite_pipeline,
+      &targets.composite_bind_group,
+    );
+  }
+}
+
+fn create_pipeline(
+  ctx: &DrawingContext,
+  label: &str,
+  bind_group_layout: &wgpu::BindGroupLayout,
+  shader: &wgpu::ShaderModule,
+  fs_entry_point: &str,
+  format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+  let pipeline_layout =
+    ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: Some(label),
+      bind_group_layouts: &[bind_group_layout],
+      push_constant_ranges: &[],
+    });
+
+  ctx.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+    label: Some(label),
+    layout: Some(&pipeline_layout),
+    vertex: wgpu::VertexState {
+      module: shader,
+      entry_point: "vs_main",
+      buffers: &[],
+      compilation_options: wgpu::PipelineCompilationOptions::default(),
+    },
+    fragment: Some(wgpu::FragmentState {
+      module: shader,
+      entry_point: fs_entry_point,
+      targets: &[Some(format.into())],
+      compilation_options: wgpu::PipelineCompilationOptions::default(),
+    }),
+    primitive: wgpu::PrimitiveState::default(),
+    depth_stencil: None,
+    multisample: wgpu::MultisampleState::default(),
+    multiview: None,
+    cache: None,
+  })
+}
+
+fn create_bloom_texture(
+  ctx: &DrawingContext,
+  width: u32,
+  height: u32,
+) -> wgpu::Texture {
+  ctx.device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("Bloom Texture"),
+    size: wgpu::Extent3d {
+      width,
+      height,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format: BLOOM_FORMAT,
+    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+      | wgpu::TextureUsages::TEXTURE_BINDING,
+    view_formats: &[],
+  })
+}
+
+fn draw_fullscreen(
+  encoder: &mut wgpu::CommandEncoder,
+  label: &str,
+  target_view: &wgpu::TextureView,
+  pipeline: &wgpu::RenderPipeline,
+  bind_group: &wgpu::BindGroup,
+) {
+  let mut render_pass =
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some(label),
+      color_attachments: &[Some(util::create_color_attachment(target_view))],
+      ..Default::default()
+    });
+
+  render_pass.set_pipeline(pipeline);
+  render_pass.set_bind_group(0, bind_group, &[]);
+  render_pass.draw(0..3, 0..1);
+}
@@ -0,0 +1,49 @@
+@group(0) @binding(0) var input_sampler: sampler;
+@group(0) @binding(1) var input_texture: texture_2d<f32>;
+// (1, 0) なら横方向、(0, 1) なら縦方向にぼかす
+@group(0) @binding(2) var<uniform> direction: vec2f;
+
+// 9タップのガウシアンを、隣り合う2テクセルの線形補間でまとめて5回のサンプルにする
+const OFFSET_1: f32 = 1.3846153846;
+const OFFSET_2: f32 = 3.2307692308;
+const WEIGHT_0: f32 = 0.2270270270;
+const WEIGHT_1: f32 = 0.3162162162;
+const WEIGHT_2: f32 = 0.0702702703;
+
+struct VertexOutput {
+  @builtin(position) position: vec4f,
+  @location(0) uv: vec2f,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> VertexOutput {
+  // 画面全体を覆う大きな三角形
+  var pos = array<vec2f, 3>(
+    vec2f(-1.0, -1.0),
+    vec2f( 3.0, -1.0),
+    vec2f(-1.0,  3.0),
+  );
+
+  var output: VertexOutput;
+  output.position = vec4f(pos[i], 0.0, 1.0);
+  output.uv = vec2f(pos[i].x + 1.0, 1.0 - pos[i].y) * 0.5;
+  return output;
+}
+
+fn sample_input(uv: vec2f) -> vec3f {
+  return textureSampleLevel(input_texture, input_sampler, uv, 0.0).rgb;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+  let texel_step = direction / vec2f(textureDimensions(input_texture));
+
+  let offset_1 = texel_step * OFFSET_1;
+  let offset_2 = texel_step * OFFSET_2;
+
+  var color = sample_input(in.uv) * WEIGHT_0;
+  color += (sample_input(in.uv + offset_1) + sample_input(in.uv - offset_1)) * WEIGHT_1;
+  color += (sample_input(in.uv + offset_2) + sample_input(in.uv - offset_2)) * WEIGHT_2;
+
+  return vec4f(color, 1.0);
+}
@@ -0,0 +1,242 @@
+use bytemuck::cast_slice;
+use wgpu::util::DeviceExt;
+
+use crate::{ctx::DrawingContext, matrix, util};
+
+const DEFAULT_FOCUS_DISTANCE: f32 = 20.;
+const DEFAULT_APERTURE: f32 = 8.;
+const DEFAULT_MAX_RADIUS: f32 = 12.;
+
+const MIN_FOCUS_DISTANCE: f32 = 0.01;
+
+/// Depth-of-field post-process: blurs the scene color by the circle of
+/// confusion (CoC) computed from the depth buffer.
+///
+/// The depth buffer holds `d` in [0, 1] from the projection built by
+/// `matrix::create_perspective_mat`. Inverting it gives the view distance
+///
+/// `z = near * far / (far - d * (far - near))`
+///
+/// so `near`/`far` must match the projection (see `with_clip_planes`; the
+/// defaults are `matrix::PERSPECTIVE_NEAR`/`PERSPECTIVE_FAR`).
+///
+/// A thin lens focused at `focus_distance` blurs a point at `z` into a disc
+/// proportional to `|z - focus_distance| / z`. Points behind the focus plane
+/// approach `aperture` pixels as `z` goes to infinity, points in front of it
+/// grow without bound, so the radius is clamped to `max_radius` pixels.
+///
+/// The blur gathers 48 taps on a disc of `max_radius`; each tap counts if its
+/// own CoC reaches the center, which lets blurry foreground spill over sharp
+/// background. Background taps are limited to the center's CoC so they do not
+/// bleed onto an in-focus foreground.
+///
+/// Bind the scene with `set_inputs` (again after every resize) before
+/// `draw`. With MSAA, resolve the depth first with `depth::DepthResolvePass`.
+pub struct DepthOfField {
+  pipeline: wgpu::RenderPipeline,
+  bind_group_layout: wgpu::BindGroupLayout,
+  bind_group: Option<wgpu::BindGroup>,
+  sampler: wgpu::Sampler,
+  params_uniform_buffer: wgpu::Buffer,
+  focus_distance: f32,
+  aperture: f32,
+  max_radius: f32,
+  near: f32,
+  far: f32,
+}
+
+impl DepthOfField {
+  pub fn new(ctx: &DrawingContext) -> Self {
+    let shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./dof.wgsl"));
+
+    let sampler = ctx.device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some("dof sampler"),
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      ..Default::default()
+    });
+
+    let params_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("dof params uniform buffer"),
+        contents: cast_slice(&[0f32; 8]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+
+    let bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        wgpu::BindingType::Texture {
+          sample_type: wgpu::TextureSampleType::Float { filterable: true },
+          view_dimension: wgpu::TextureViewDimension::D2,
+          multisampled: false,
+        },
+        wgpu::BindingType::Texture {
+          sample_type: wgpu::TextureSampleType::Depth,
+          view_dimension: wgpu::TextureViewDimension::D2,
+          multisampled: false,
+        },
+        wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+      ],
+      &[
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::ShaderStages::FRAGMENT,
+      ],
+    );
+
+    let pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Depth of Field Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let pipeline =
+      ctx.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Depth of Field Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &[Some(ctx.format().into())],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+      });
+
+    let dof = Self {
+      pipeline,
+      bind_group_layout,
+      bind_group: None,
+      sampler,
+      params_uniform_buffer,
+      focus_distance: DEFAULT_FOCUS_DISTANCE,
+      aperture: DEFAULT_APERTURE,
+      max_radius: DEFAULT_MAX_RADIUS,
+      near: matrix::PERSPECTIVE_NEAR,
+      far: matrix::PERSPECTIVE_FAR,
+    };
+    dof.write_params(&ctx.queue);
+
+    dof
+  }
+
+  /// Near/far planes of the projection the depth buffer was rendered with.
+  pub fn with_clip_planes(
+    mut self,
+    queue: &wgpu::Queue,
+    near: f32,
+    far: f32,
+  ) -> Self {
+    self.near = near;
+    self.far = far;
+    self.write_params(queue);
+    self
+  }
+
+  /// Largest blur radius in pixels.
+  pub fn with_max_radius(mut self, queue: &wgpu::Queue, radius: f32) -> Self {
+    self.max_radius = radius.max(0.);
+    self.write_params(queue);
+    self
+  }
+
+  pub fn focus_distance(&self) -> f32 {
+    self.focus_distance
+  }
+
+  /// Distance from the camera that stays sharp.
+  pub fn set_focus_distance(&mut self, queue: &wgpu::Queue, distance: f32) {
+    self.focus_distance = distance.max(MIN_FOCUS_DISTANCE);
+    self.write_params(queue);
+  }
+
+  pub fn aperture(&self) -> f32 {
+    self.aperture
+  }
+
+  /// Blur radius in pixels of a point at infinity. 0 disables the blur.
+  pub fn set_aperture(&mut self, queue: &wgpu::Queue, aperture: f32) {
+    self.aperture = aperture.max(0.);
+    self.write_params(queue);
+  }
+
+  pub fn max_radius(&self) -> f32 {
+    self.max_radius
+  }
+
+  /// Sets the scene color to blur and the single-sample depth it was
+  /// rendered with. `color_view` must not be the target passed to `draw`.
+  pub fn set_inputs(
+    &mut self,
+    ctx: &DrawingContext,
+    color_view: &wgpu::TextureView,
+    depth_view: &wgpu::TextureView,
+  ) {
+    self.bind_group = Some(util::create_bind_group(
+      &ctx.device,
+      &self.bind_group_layout,
+      &[
+        wgpu::BindingResource::Sampler(&self.sampler),
+        wgpu::BindingResource::TextureView(color_view),
+        wgpu::BindingResource::TextureView(depth_view),
+        self.params_uniform_buffer.as_entire_binding(),
+      ],
+    ));
+  }
+
+  /// Writes the blurred scene into `target_view`.
+  pub fn draw(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    target_view: &wgpu::TextureView,
+  ) {
+    let bind_group = self
+      .bind_group
+      .as_ref()
+      .expect("DepthOfField::set_inputs must be called before draw");
+
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Depth of Field Pass"),
+        color_attachments: &[Some(util::create_color_attachment(target_view))],
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+  }
+
+  fn write_params(&self, queue: &wgpu::Queue) {
+    let params = [
+      self.focus_distance,
+      self.aperture,
+      self.max_radius,
+      self.near,
+      self.far,
+      0.,
+      0.,
+      0.,
+    ];
+    queue.write_buffer(&self.params_uniform_buffer, 0, cast_slice(&params));
+  }
+}
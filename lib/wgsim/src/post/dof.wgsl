@@ -0,0 +1,86 @@
+struct Params {
+  focus_distance: f32,
+  aperture: f32,
+  max_radius: f32,
+  near: f32,
+  far: f32,
+}
+
+@group(0) @binding(0) var color_sampler: sampler;
+@group(0) @binding(1) var color_texture: texture_2d<f32>;
+@group(0) @binding(2) var depth_texture: texture_depth_2d;
+@group(0) @binding(3) var<uniform> params: Params;
+
+const TAP_COUNT: i32 = 48;
+const GOLDEN_ANGLE: f32 = 2.39996323;
+
+struct VertexOutput {
+  @builtin(position) position: vec4f,
+  @location(0) uv: vec2f,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> VertexOutput {
+  // 画面全体を覆う大きな三角形
+  var pos = array<vec2f, 3>(
+    vec2f(-1.0, -1.0),
+    vec2f( 3.0, -1.0),
+    vec2f(-1.0,  3.0),
+  );
+
+  var output: VertexOutput;
+  output.position = vec4f(pos[i], 0.0, 1.0);
+  output.uv = vec2f(pos[i].x + 1.0, 1.0 - pos[i].y) * 0.5;
+  return output;
+}
+
+// 深度バッファの値 [0, 1] からカメラまでの距離に戻す
+fn linearize_depth(d: f32) -> f32 {
+  let n = params.near;
+  let f = params.far;
+  return n * f / (f - d * (f - n));
+}
+
+// 錯乱円の半径（ピクセル）
+fn circle_of_confusion(z: f32) -> f32 {
+  let coc = params.aperture * abs(z - params.focus_distance) / z;
+  return min(coc, params.max_radius);
+}
+
+fn load_depth(uv: vec2f) -> f32 {
+  let dims = vec2f(textureDimensions(depth_texture));
+  let coord = clamp(vec2i(uv * dims), vec2i(0), vec2i(dims) - 1);
+  return linearize_depth(textureLoad(depth_texture, coord, 0));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+  let texel = 1.0 / vec2f(textureDimensions(color_texture));
+
+  let center_z = load_depth(in.uv);
+  let center_coc = circle_of_confusion(center_z);
+
+  var color = textureSampleLevel(color_texture, color_sampler, in.uv, 0.0);
+  var total = 1.0;
+
+  // Vogel 円盤上のサンプルを集める
+  for (var i = 0; i < TAP_COUNT; i++) {
+    let r = sqrt((f32(i) + 0.5) / f32(TAP_COUNT)) * params.max_radius;
+    let theta = f32(i) * GOLDEN_ANGLE;
+    let offset = vec2f(cos(theta), sin(theta)) * r;
+    let uv = in.uv + offset * texel;
+
+    let sample_z = load_depth(uv);
+    var sample_coc = circle_of_confusion(sample_z);
+    // 奥にあるサンプルが手前のピントの合った物体ににじまないようにする
+    if (sample_z > center_z) {
+      sample_coc = min(sample_coc, center_coc);
+    }
+
+    let weight = smoothstep(r - 1.0, r + 1.0, sample_coc);
+    color += textureSampleLevel(color_texture, color_sampler, uv, 0.0) * weight;
+    total += weight;
+  }
+
+  return color / total;
+}
@@ -0,0 +1,117 @@
+use crate::ctx::DrawingContext;
+
+/// A full-screen pass the app runs between the renderer and the surface
+/// (`App::with_post_effect`).
+///
+/// The app renders the scene into an offscreen texture of `input_format()`
+/// and `DrawingContext::size()`, and `DrawingContext::format()` returns
+/// `input_format()` so the renderer's pipelines target it. `apply` then
+/// writes the final image into a view in `DrawingContext::surface_format()`.
+///
+/// The effect is created before the device exists, so GPU resources are
+/// built in `init`, which runs again with the new context after a device
+/// loss.
+pub trait PostEffect {
+  fn input_format(&self) -> wgpu::TextureFormat {
+    wgpu::TextureFormat::Rgba16Float
+  }
+
+  /// Builds the pipelines for `ctx`. `resize` follows right after.
+  fn init(&mut self, ctx: &DrawingContext);
+
+  /// Called whenever the input texture is recreated (startup, resize,
+  /// render scale change), to rebuild size-dependent textures and the bind
+  /// groups that read `input_view`.
+  fn resize(&mut self, ctx: &DrawingContext, input_view: &wgpu::TextureView);
+
+  /// Reads `input_view`, the view last passed to `resize`, and writes the
+  /// result into `output_view`.
+  fn apply(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    input_view: &wgpu::TextureView,
+    output_view: &wgpu::TextureView,
+  );
+}
+
+/// The offscreen texture the scene is drawn into and the effect that reads
+/// it.
+pub(crate) struct PostProcess {
+  effect: Box<dyn PostEffect>,
+  texture: wgpu::Texture,
+  view: wgpu::TextureView,
+}
+
+impl PostProcess {
+  /// Expects `format()` to be `effect.input_format()` already.
+  pub fn new(ctx: &DrawingContext, mut effect: Box<dyn PostEffect>) -> Self {
+    effect.init(ctx);
+
+    let texture = create_input_texture(ctx);
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    effect.resize(ctx, &view);
+
+    Self {
+      effect,
+      texture,
+      view,
+    }
+  }
+
+  pub fn into_effect(self) -> Box<dyn PostEffect> {
+    self.effect
+  }
+
+  pub fn texture(&self) -> &wgpu::Texture {
+    &self.texture
+  }
+
+  pub fn resize(&mut self, ctx: &DrawingContext) {
+    self.texture = create_input_texture(ctx);
+    self.view =
+      self.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    self.effect.resize(ctx, &self.view);
+  }
+
+  pub fn apply(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    output_view: &wgpu::TextureView,
+  ) {
+    self.effect.apply(encoder, &self.view, output_view);
+  }
+
+  /// Acquires the next surface texture and applies the effect onto it.
+  pub fn present(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    surface: &wgpu::Surface,
+  ) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
+    let frame = surface.get_current_texture()?;
+    let view =
+      frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    self.apply(encoder, &view);
+
+    Ok(frame)
+  }
+}
+
+fn create_input_texture(ctx: &DrawingContext) -> wgpu::Texture {
+  let size = ctx.size();
+
+  ctx.device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("Post Effect Input"),
+    size: wgpu::Extent3d {
+      width: size.width,
+      height: size.height,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format: ctx.format(),
+    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+      | wgpu::TextureUsages::TEXTURE_BINDING,
+    view_formats: &[],
+  })
+}
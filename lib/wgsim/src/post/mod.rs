@@ -0,0 +1,10 @@
+mod bloom;
+mod dof;
+mod effect;
+mod tone_map;
+
+pub use bloom::BloomPass;
+pub use dof::DepthOfField;
+pub use effect::PostEffect;
+pub(crate) use effect::PostProcess;
+pub use tone_map::{ToneMap, ToneMapOperator};
@@ -0,0 +1,265 @@
+use bytemuck::{cast_slice, Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+use super::PostEffect;
+use crate::{ctx::DrawingContext, util};
+
+/// WGSL of `fn tone_map(color: vec3f, operator: u32, exposure: f32) -> vec3f`,
+/// to be prepended to shaders that map HDR colors themselves.
+pub(crate) const TONE_MAP_OPS_WGSL: &str = include_str!("./tone_map_ops.wgsl");
+
+/// How linear HDR colors are brought into `[0, 1]`. All of them multiply
+/// by the exposure first.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMapOperator {
+  /// Clips everything above 1.
+  ExposureOnly = 0,
+  /// `c / (1 + c)`: never clips, but washes out bright colors.
+  Reinhard = 1,
+  /// Narkowicz's fit of the ACES filmic curve: keeps more contrast than
+  /// Reinhard and desaturates highlights.
+  #[default]
+  Aces = 2,
+}
+
+impl ToneMapOperator {
+  /// CPU version of `tone_map` in `tone_map_ops.wgsl`, e.g. to check what a
+  /// pixel should become. Keep the two in sync.
+  pub fn apply(self, color: [f32; 3], exposure: f32) -> [f32; 3] {
+    color.map(|c| {
+      let exposed = c * exposure;
+
+      match self {
+        ToneMapOperator::ExposureOnly => exposed.clamp(0., 1.),
+        ToneMapOperator::Reinhard => exposed / (1. + exposed),
+        ToneMapOperator::Aces => aces_filmic(exposed),
+      }
+    })
+  }
+}
+
+/// Narkowicz's ACES fit, as `aces_filmic` in `tone_map_ops.wgsl`.
+fn aces_filmic(x: f32) -> f32 {
+  let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+  ((x * (a * x + b)) / (x * (c * x + d) + e)).clamp(0., 1.)
+}
+
+/// Mirrors `Params` in `tone_map.wgsl` and the tone mapping fields of the
+/// bloom params.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub(crate) struct ToneMapParams {
+  pub exposure: f32,
+  pub operator: u32,
+}
+
+impl ToneMapParams {
+  pub fn new(operator: ToneMapOperator, exposure: f32) -> Self {
+    Self {
+      exposure,
+      operator: operator as u32,
+    }
+  }
+}
+
+/// Tone mapping: maps the `Rgba16Float` scene onto the surface with a
+/// `ToneMapOperator`, after scaling it by `exposure`. The surface is
+/// expected to be sRGB, which encodes the result.
+pub struct ToneMap {
+  operator: ToneMapOperator,
+  exposure: f32,
+  gpu: Option<ToneMapGpu>,
+}
+
+struct ToneMapGpu {
+  pipeline: wgpu::RenderPipeline,
+  bind_group_layout: wgpu::BindGroupLayout,
+  bind_group: Option<wgpu::BindGroup>,
+  sampler: wgpu::Sampler,
+  params_uniform_buffer: wgpu::Buffer,
+}
+
+impl Default for ToneMap {
+  fn default() -> Self {
+    Self::new(ToneMapOperator::default())
+  }
+}
+
+impl ToneMap {
+  pub fn new(operator: ToneMapOperator) -> Self {
+    Self {
+      operator,
+      exposure: 1.,
+      gpu: None,
+    }
+  }
+
+  /// Factor applied before the operator, 1 by default.
+  pub fn with_exposure(mut self, exposure: f32) -> Self {
+    self.exposure = exposure.max(0.);
+    self
+  }
+
+  pub fn operator(&self) -> ToneMapOperator {
+    self.operator
+  }
+
+  pub fn exposure(&self) -> f32 {
+    self.exposure
+  }
+}
+
+impl PostEffect for ToneMap {
+  fn init(&mut self, ctx: &DrawingContext) {
+    let source =
+      format!("{}{}", TONE_MAP_OPS_WGSL, include_str!("./tone_map.wgsl"));
+    let shader =
+      ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("tone_map.wgsl"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+      });
+
+    let sampler =
+      util::create_sampler(&ctx.device, util::SamplerOptions::default());
+
+    let params = ToneMapParams::new(self.operator, self.exposure);
+    let params_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("tone map params uniform buffer"),
+        contents: cast_slice(&[params]),
+        usage: wgpu::BufferUsages::UNIFORM,
+      });
+
+    let bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        wgpu::BindingType::Texture {
+          sample_type: wgpu::TextureSampleType::Float { filterable: true },
+          view_dimension: wgpu::TextureViewDimension::D2,
+          multisampled: false,
+        },
+        wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+      ],
+      &[wgpu::ShaderStages::FRAGMENT; 3],
+    );
+
+    let pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Tone Map Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let pipeline =
+      ctx.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Tone Map Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &[Some(ctx.surface_format().into())],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+      });
+
+    self.gpu = Some(ToneMapGpu {
+      pipeline,
+      bind_group_layout,
+      bind_group: None,
+      sampler,
+      params_uniform_buffer,
+    });
+  }
+
+  fn resize(&mut self, ctx: &DrawingContext, input_view: &wgpu::TextureView) {
+    let gpu =
+      self.gpu.as_mut().expect("ToneMap::init must be called before use");
+
+    gpu.bind_group = Some(util::create_bind_group(
+      &ctx.device,
+      &gpu.bind_group_layout,
+      &[
+        wgpu::BindingResource::Sampler(&gpu.sampler),
+        wgpu::BindingResource::TextureView(input_view),
+        gpu.params_uniform_buffer.as_entire_binding(),
+      ],
+    ));
+  }
+
+  fn apply(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    _input_view: &wgpu::TextureView,
+    output_view: &wgpu::TextureView,
+  ) {
+    let gpu =
+      self.gpu.as_ref().expect("ToneMap::init must be called before use");
+    let bind_group = gpu
+      .bind_group
+      .as_ref()
+      .expect("ToneMap::resize must be called before apply");
+
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Tone Map Pass"),
+        color_attachments: &[Some(util::create_color_attachment(output_view))],
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&gpu.pipeline);
+    render_pass.set_bind_group(0, bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // 1 を超える成分と超えない成分を持つ HDR の色
+  const HDR_PIXEL: [f32; 3] = [4., 1., 0.25];
+
+  fn assert_close(actual: [f32; 3], expected: [f32; 3]) {
+    for (a, e) in actual.iter().zip(expected) {
+      assert!((a - e).abs() < 1e-4, "{:?} != {:?}", actual, expected);
+    }
+  }
+
+  #[test]
+  fn exposure_only_clips_above_one() {
+    let mapped = ToneMapOperator::ExposureOnly.apply(HDR_PIXEL, 1.);
+    assert_close(mapped, [1., 1., 0.25]);
+  }
+
+  #[test]
+  fn reinhard_compresses_without_clipping() {
+    let mapped = ToneMapOperator::Reinhard.apply(HDR_PIXEL, 1.);
+    assert_close(mapped, [0.8, 0.5, 0.2]);
+
+    // 露出は演算子の前に掛かる
+    let mapped = ToneMapOperator::Reinhard.apply(HDR_PIXEL, 0.5);
+    assert_close(mapped, [2. / 3., 1. / 3., 0.125 / 1.125]);
+  }
+
+  #[test]
+  fn aces_matches_the_narkowicz_curve() {
+    let mapped = ToneMapOperator::Aces.apply(HDR_PIXEL, 1.);
+    assert_close(mapped, [40.28 / 41.38, 2.54 / 3.16, 0.164375 / 0.439375]);
+  }
+}
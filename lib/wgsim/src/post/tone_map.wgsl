@@ -0,0 +1,36 @@
+// tone_map_ops.wgsl の後ろにつなげてコンパイルする
+
+struct Params {
+  exposure: f32,
+  operator: u32,
+}
+
+@group(0) @binding(0) var input_sampler: sampler;
+@group(0) @binding(1) var input_texture: texture_2d<f32>;
+@group(0) @binding(2) var<uniform> params: Params;
+
+struct VertexOutput {
+  @builtin(position) position: vec4f,
+  @location(0) uv: vec2f,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> VertexOutput {
+  // 画面全体を覆う大きな三角形
+  var pos = array<vec2f, 3>(
+    vec2f(-1.0, -1.0),
+    vec2f( 3.0, -1.0),
+    vec2f(-1.0,  3.0),
+  );
+
+  var output: VertexOutput;
+  output.position = vec4f(pos[i], 0.0, 1.0);
+  output.uv = vec2f(pos[i].x + 1.0, 1.0 - pos[i].y) * 0.5;
+  return output;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+  let color = textureSampleLevel(input_texture, input_sampler, in.uv, 0.0);
+  return vec4f(tone_map(color.rgb, params.operator, params.exposure), color.a);
+}
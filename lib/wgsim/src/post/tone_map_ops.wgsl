@@ -0,0 +1,32 @@
+// トーンマッピングの演算子（ToneMapOperator と同じ番号）
+const TONE_MAP_EXPOSURE_ONLY: u32 = 0u;
+const TONE_MAP_REINHARD: u32 = 1u;
+const TONE_MAP_ACES: u32 = 2u;
+
+// Narkowicz による ACES Filmic カーブの近似
+fn aces_filmic(x: vec3f) -> vec3f {
+  let a = 2.51;
+  let b = 0.03;
+  let c = 2.43;
+  let d = 0.59;
+  let e = 0.14;
+  return clamp((x * (a * x + b)) / (x * (c * x + d) + e), vec3f(0.0), vec3f(1.0));
+}
+
+// 線形の HDR の色を [0, 1] に収める（sRGB への変換はサーフェスに任せる）
+fn tone_map(color: vec3f, operator: u32, exposure: f32) -> vec3f {
+  let exposed = color * exposure;
+
+  switch operator {
+    case TONE_MAP_REINHARD: {
+      return exposed / (1.0 + exposed);
+    }
+    case TONE_MAP_ACES: {
+      return aces_filmic(exposed);
+    }
+    default: {
+      // 1 を超えた分は切り捨てる
+      return clamp(exposed, vec3f(0.0), vec3f(1.0));
+    }
+  }
+}
@@ -1,7 +1,43 @@
+use std::collections::HashMap;
+use std::fmt;
+
 use crate::ctx::DrawingContext;
+use crate::shader;
+use crate::util::DEPTH_FORMAT;
+
+/// A render pipeline that failed validation, with the pipeline label and
+/// entry points it was built from. See `RenderPipelineBuilder::build_checked`.
+#[derive(Debug)]
+pub struct ShaderBuildError {
+  pub label: String,
+  pub vs_entry: String,
+  pub fs_entry: Option<String>,
+  pub source: wgpu::Error,
+}
+
+impl fmt::Display for ShaderBuildError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "failed to build \"{}\" (vs: {}",
+      self.label, self.vs_entry
+    )?;
+    if let Some(fs_entry) = &self.fs_entry {
+      write!(f, ", fs: {}", fs_entry)?;
+    }
+    write!(f, ")\n{}", self.source)
+  }
+}
+
+impl std::error::Error for ShaderBuildError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    Some(&self.source)
+  }
+}
 
 pub struct RenderPipelineBuilder<'a> {
   ctx: &'a DrawingContext<'a>,
+  label: &'a str,
   pipeline_layout: Option<&'a wgpu::PipelineLayout>,
 
   depth_stencil: Option<wgpu::DepthStencilState>,
@@ -15,12 +51,14 @@ pub struct RenderPipelineBuilder<'a> {
   targets: Vec<Option<wgpu::ColorTargetState>>,
 
   primitive: wgpu::PrimitiveState,
+  sample_count: Option<u32>,
 }
 
 impl<'a> RenderPipelineBuilder<'a> {
   pub fn new(ctx: &'a DrawingContext) -> Self {
     Self {
       ctx,
+      label: "Render Pipeline",
       depth_stencil: None,
       pipeline_layout: None,
       vs_shader: None,
@@ -30,9 +68,16 @@ impl<'a> RenderPipelineBuilder<'a> {
       vertex_buffer_layout: &[],
       targets: vec![Some(ctx.format().into())],
       primitive: wgpu::PrimitiveState::default(),
+      sample_count: None,
     }
   }
 
+  /// Shows up in validation errors and graphics debuggers.
+  pub fn label(mut self, label: &'a str) -> Self {
+    self.label = label;
+    self
+  }
+
   pub fn enable_depth_stencil(
     mut self,
     custom_depth_stencil: Option<wgpu::DepthStencilState>,
@@ -40,22 +85,47 @@ impl<'a> RenderPipelineBuilder<'a> {
     if let Some(depth_stencil) = custom_depth_stencil {
       self.depth_stencil = Some(depth_stencil);
     } else {
-      self.depth_stencil = Some(wgpu::DepthStencilState {
-        format: wgpu::TextureFormat::Depth24Plus,
-        depth_write_enabled: true,
-        depth_compare: wgpu::CompareFunction::LessEqual,
-        stencil: wgpu::StencilState::default(),
-        bias: wgpu::DepthBiasState::default(),
-      });
+      self.depth_stencil = Some(default_depth_stencil());
     }
     self
   }
 
+  /// Enables the default depth state first if it is not enabled yet.
+  pub fn depth_compare(mut self, compare: wgpu::CompareFunction) -> Self {
+    self.depth_stencil_mut().depth_compare = compare;
+    self
+  }
+
+  /// Transparent passes usually test against the depth of an earlier pass
+  /// without writing their own.
+  pub fn depth_write(mut self, enabled: bool) -> Self {
+    self.depth_stencil_mut().depth_write_enabled = enabled;
+    self
+  }
+
+  /// Has to match the format of the depth texture in the render pass
+  /// (see `util::create_depth_view_with_format`).
+  pub fn depth_format(mut self, format: wgpu::TextureFormat) -> Self {
+    self.depth_stencil_mut().format = format;
+    self
+  }
+
+  fn depth_stencil_mut(&mut self) -> &mut wgpu::DepthStencilState {
+    self.depth_stencil.get_or_insert_with(default_depth_stencil)
+  }
+
   pub fn pipeline_layout(mut self, layout: &'a wgpu::PipelineLayout) -> Self {
     self.pipeline_layout = Some(layout);
     self
   }
 
+  /// Overrides `ctx.sample_count`, for passes that don't render into the
+  /// (possibly multisampled) main target, e.g. a shadow map.
+  pub fn sample_count(mut self, count: u32) -> Self {
+    self.sample_count = Some(count);
+    self
+  }
+
   pub fn vs_shader(
     mut self,
     module: &'a wgpu::ShaderModule,
@@ -84,14 +154,107 @@ impl<'a> RenderPipelineBuilder<'a> {
     self
   }
 
+  /// Blends into the color target instead of overwriting it, e.g. with
+  /// `BlendState::ALPHA_BLENDING` or `PREMULTIPLIED_ALPHA_BLENDING`. Without
+  /// it the target is written as is (`REPLACE`).
+  pub fn blend(mut self, blend: wgpu::BlendState) -> Self {
+    for target in self.targets.iter_mut().flatten() {
+      target.blend = Some(blend);
+    }
+    self
+  }
+
+  /// Replaces the default color target (the context's format, no blending,
+  /// all channels written). A later `blend` applies on top of it.
+  pub fn color_target(mut self, target: wgpu::ColorTargetState) -> Self {
+    self.targets = vec![Some(target)];
+    self
+  }
+
   pub fn primitive(mut self, primitive: wgpu::PrimitiveState) -> Self {
     self.primitive = primitive;
     self
   }
 
+  /// Defaults to `TriangleList`.
+  pub fn topology(mut self, topology: wgpu::PrimitiveTopology) -> Self {
+    self.primitive.topology = topology;
+    self
+  }
+
+  /// For indexed strip topologies (`LineStrip`, `TriangleStrip`): the
+  /// format of the index buffer, whose maximum value restarts the strip.
+  /// Must stay `None` for list topologies.
+  pub fn strip_index_format(
+    mut self,
+    format: Option<wgpu::IndexFormat>,
+  ) -> Self {
+    self.primitive.strip_index_format = format;
+    self
+  }
+
+  /// `None` (the default) draws both sides, e.g. for double-sided planes.
+  pub fn cull_mode(mut self, cull_mode: Option<wgpu::Face>) -> Self {
+    self.primitive.cull_mode = cull_mode;
+    self
+  }
+
+  pub fn front_face(mut self, front_face: wgpu::FrontFace) -> Self {
+    self.primitive.front_face = front_face;
+    self
+  }
+
+  /// `Line` and `Point` need a device feature (`POLYGON_MODE_LINE` /
+  /// `POLYGON_MODE_POINT`). The drawing context enables them when the
+  /// adapter has them; otherwise `build` logs a warning and fills the
+  /// polygons instead.
+  pub fn polygon_mode(mut self, polygon_mode: wgpu::PolygonMode) -> Self {
+    self.primitive.polygon_mode = polygon_mode;
+    self
+  }
+
+  fn supported_primitive(&self) -> wgpu::PrimitiveState {
+    let mut primitive = self.primitive;
+
+    let required = match primitive.polygon_mode {
+      wgpu::PolygonMode::Fill => return primitive,
+      wgpu::PolygonMode::Line => wgpu::Features::POLYGON_MODE_LINE,
+      wgpu::PolygonMode::Point => wgpu::Features::POLYGON_MODE_POINT,
+    };
+    if !self.ctx.device.features().contains(required) {
+      log::warn!(
+        "{:?} is not supported by this device, falling back to Fill",
+        required
+      );
+      primitive.polygon_mode = wgpu::PolygonMode::Fill;
+    }
+
+    primitive
+  }
+
+  /// Like `build`, but a validation error (missing entry point, mismatched
+  /// stage interface or layout, ...) is returned with the pipeline label
+  /// and entry points instead of panicking.
+  ///
+  /// Shader modules are validated when they are created, so WGSL syntax
+  /// errors only end up here if the modules were created with
+  /// `shader::capture_validation` (or `shader::load_wgsl`).
+  pub fn build_checked(
+    &self,
+  ) -> Result<wgpu::RenderPipeline, ShaderBuildError> {
+    shader::capture_validation(&self.ctx.device, || self.build()).map_err(
+      |source| ShaderBuildError {
+        label: self.label.to_string(),
+        vs_entry: self.vs_entry.to_string(),
+        fs_entry: self.fs_shader.map(|_| self.fs_entry.to_string()),
+        source,
+      },
+    )
+  }
+
   pub fn build(&self) -> wgpu::RenderPipeline {
     self.ctx.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-      label: Some("Render Pipeline"),
+      label: Some(self.label),
       layout: self.pipeline_layout,
       vertex: wgpu::VertexState {
         module: &self.vs_shader.unwrap(),
@@ -105,10 +268,10 @@ impl<'a> RenderPipelineBuilder<'a> {
         targets: self.targets.as_slice(),
         compilation_options: wgpu::PipelineCompilationOptions::default(),
       }),
-      primitive: self.primitive,
+      primitive: self.supported_primitive(),
       depth_stencil: self.depth_stencil.clone(),
       multisample: wgpu::MultisampleState {
-        count: self.ctx.sample_count,
+        count: self.sample_count.unwrap_or(self.ctx.sample_count),
         ..Default::default()
       },
       multiview: None,
@@ -124,6 +287,8 @@ pub struct ComputePipelineBuilder<'a> {
 
   cs_shader: Option<&'a wgpu::ShaderModule>,
   cs_entry: &'a str,
+
+  constants: HashMap<String, f64>,
 }
 
 impl<'a> ComputePipelineBuilder<'a> {
@@ -133,6 +298,7 @@ impl<'a> ComputePipelineBuilder<'a> {
       pipeline_layout: None,
       cs_shader: None,
       cs_entry: "cs_main",
+      constants: HashMap::new(),
     }
   }
 
@@ -151,6 +317,18 @@ impl<'a> ComputePipelineBuilder<'a> {
     self
   }
 
+  /// Sets the WGSL pipeline-overridable constant `override name: T;` (or
+  /// the one with `@id(name)`) to `value`, converted to the constant's
+  /// type. Overrides without a default must be set here.
+  ///
+  /// The naga of wgpu 22 only takes overrides in function bodies and in
+  /// other overrides, not yet in `@workgroup_size` or array lengths; for
+  /// those, prepend a `const` to the source instead.
+  pub fn constant(mut self, name: &str, value: f64) -> Self {
+    self.constants.insert(name.to_string(), value);
+    self
+  }
+
   pub fn build(&self) -> wgpu::ComputePipeline {
     self.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
       label: Some("Compute Pipeline"),
@@ -159,8 +337,21 @@ impl<'a> ComputePipelineBuilder<'a> {
       module: &self.cs_shader.unwrap(),
       entry_point: &self.cs_entry,
 
-      compilation_options: wgpu::PipelineCompilationOptions::default(),
+      compilation_options: wgpu::PipelineCompilationOptions {
+        constants: &self.constants,
+        ..Default::default()
+      },
       cache: None,
     })
   }
 }
+
+fn default_depth_stencil() -> wgpu::DepthStencilState {
+  wgpu::DepthStencilState {
+    format: DEPTH_FORMAT,
+    depth_write_enabled: true,
+    depth_compare: wgpu::CompareFunction::LessEqual,
+    stencil: wgpu::StencilState::default(),
+    bias: wgpu::DepthBiasState::default(),
+  }
+}
@@ -1,8 +1,16 @@
+use std::error::Error;
 use std::future::Future;
 
-use winit::event::WindowEvent;
+use winit::event::{DeviceEvent, MouseButton, WindowEvent};
 
-use crate::ctx::{DrawingContext, Size};
+use crate::ctx::{ContextError, DrawingContext, Size};
+use crate::export;
+#[cfg(feature = "gamepad")]
+use crate::input::GamepadState;
+
+/// Nominal frames per second of exported animations, used by the default
+/// `Render::update_frame`.
+pub const EXPORT_FRAME_RATE: f32 = 30.;
 
 pub enum RenderTarget<'a> {
   Surface(&'a wgpu::Surface<'a>),
@@ -13,6 +21,13 @@ pub enum RenderTarget<'a> {
 pub trait Render<'a> {
   type Initial;
 
+  /// Builds all GPU resources from `initial`.
+  ///
+  /// This may run more than once: after a device loss the context is
+  /// recreated and `new` is called again with the same `initial`. Anything
+  /// that only lives in `Self` (values changed by key input, simulation
+  /// buffers, camera moved by the user, ...) is reset to what `initial`
+  /// describes, so keep everything needed to rebuild the scene in `Initial`.
   fn new(
     ctx: &DrawingContext<'a>,
     initial: &Self::Initial,
@@ -22,10 +37,50 @@ pub trait Render<'a> {
       ctx.resize(size);
     }
   }
+  /// Called by `App` for every window event. Return true to consume it;
+  /// `on_scroll` and `on_mouse_drag` are then not called for it.
   fn process_event(&mut self, event: &WindowEvent) -> bool {
     false
   }
+  /// Mouse wheel or trackpad scroll, in lines (trackpad pixels are
+  /// converted), positive away from the user.
+  fn on_scroll(&mut self, delta: f32) {}
+  /// Cursor movement in physical pixels while `button` is held, the button
+  /// pressed first if there are several. Use it for camera controls
+  /// instead of tracking the drag state in `process_event`.
+  fn on_mouse_drag(&mut self, dx: f32, dy: f32, button: MouseButton) {}
+  /// Raw input that is not tied to the window, e.g. relative mouse motion
+  /// (`DeviceEvent::MouseMotion`), which keeps arriving while the cursor is
+  /// grabbed and cannot move.
+  fn process_device_event(&mut self, event: &DeviceEvent) {}
+  /// Called once per frame, before `update`, when the app polls a gamepad.
+  #[cfg(feature = "gamepad")]
+  fn process_gamepad(&mut self, state: &GamepadState) {}
   fn update(&mut self, ctx: &DrawingContext, dt: std::time::Duration) {}
+  /// Called when a file watched with `App::with_shader_watch` changed.
+  /// Rebuild the affected pipelines from the files on disk (see
+  /// `shader::load_wgsl`), and keep the old ones if the new source doesn't
+  /// compile.
+  fn reload_shaders(&mut self, ctx: &DrawingContext) {}
+  /// Called instead of `update` when frames are exported, with the index of
+  /// the frame and the number of frames to be recorded. Override it to tie
+  /// the animation to the frame count, e.g. one full turn over
+  /// `total_frames` so a looping GIF closes seamlessly.
+  ///
+  /// By default it calls `update` with the time the frame would have at
+  /// `EXPORT_FRAME_RATE`, so recordings don't depend on how fast frames
+  /// render.
+  fn update_frame(
+    &mut self,
+    ctx: &DrawingContext,
+    frame_index: usize,
+    total_frames: usize,
+  ) {
+    let dt = std::time::Duration::from_secs_f32(
+      frame_index as f32 / EXPORT_FRAME_RATE,
+    );
+    self.update(ctx, dt);
+  }
   fn draw(
     &mut self,
     encoder: &mut wgpu::CommandEncoder,
@@ -45,3 +100,93 @@ pub trait Render<'a> {
     }
   }
 }
+
+/// Renders a single frame of `width` x `height` offscreen and returns its
+/// pixels as tightly packed RGBA rows, e.g. for thumbnails or image
+/// comparisons (see `util::compare_images`).
+///
+/// If the size differs from `ctx.size()`, `renderer` is resized first (like
+/// on a window resize) so its depth and MSAA textures match, and stays at
+/// that size. Only 8-bit RGBA/BGRA formats are supported; BGRA is returned
+/// as RGBA.
+pub async fn render_once<'a, R>(
+  ctx: &mut DrawingContext<'a>,
+  renderer: &mut R,
+  width: u32,
+  height: u32,
+) -> Result<Vec<u8>, Box<dyn Error>>
+where
+  R: Render<'a>,
+{
+  let size = ctx.size();
+  if size.width != width || size.height != height {
+    renderer.resize(ctx, Size { width, height });
+  }
+
+  export::read_frame(ctx, renderer).await
+}
+
+/// Drives a `Render` implementor without a window, e.g. from `cargo test`.
+///
+/// The context draws into offscreen `Rgba8UnormSrgb` textures. `step`
+/// passes the accumulated time to `update` the same way `App` does (time
+/// since the first frame, not since the previous one).
+pub struct TestHarness<'a, R>
+where
+  R: Render<'a>,
+{
+  ctx: DrawingContext<'a>,
+  renderer: R,
+  elapsed: std::time::Duration,
+}
+
+impl<'a, R> TestHarness<'a, R>
+where
+  R: Render<'a>,
+{
+  pub fn new(
+    initial: &R::Initial,
+    width: u32,
+    height: u32,
+  ) -> Result<Self, ContextError> {
+    let ctx = pollster::block_on(DrawingContext::new_for_texture(
+      Size { width, height },
+      wgpu::TextureFormat::Rgba8UnormSrgb,
+    ))?;
+    let renderer = pollster::block_on(R::new(&ctx, initial));
+
+    Ok(Self {
+      ctx,
+      renderer,
+      elapsed: std::time::Duration::ZERO,
+    })
+  }
+
+  pub fn ctx(&self) -> &DrawingContext<'a> {
+    &self.ctx
+  }
+
+  pub fn renderer(&self) -> &R {
+    &self.renderer
+  }
+
+  pub fn renderer_mut(&mut self) -> &mut R {
+    &mut self.renderer
+  }
+
+  /// Advances the clock by `dt` and calls `Render::update`.
+  pub fn step(&mut self, dt: std::time::Duration) {
+    self.elapsed += dt;
+    self.renderer.update(&self.ctx, self.elapsed);
+  }
+
+  /// Calls `Render::draw` into an offscreen texture of the context size and
+  /// returns its pixels as tightly packed RGBA rows.
+  pub fn draw_to_vec(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+    pollster::block_on(export::read_frame(&self.ctx, &mut self.renderer))
+  }
+
+  pub fn resize(&mut self, width: u32, height: u32) {
+    self.renderer.resize(&mut self.ctx, Size { width, height });
+  }
+}
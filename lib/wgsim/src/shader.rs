@@ -0,0 +1,144 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use notify::{EventKind, RecursiveMode, Watcher};
+
+/// Runs `f` inside a validation error scope and returns the first error it
+/// raised instead of handing it to the device's uncaptured error handler,
+/// which panics by default.
+pub fn capture_validation<T>(
+  device: &wgpu::Device,
+  f: impl FnOnce() -> T,
+) -> Result<T, wgpu::Error> {
+  device.push_error_scope(wgpu::ErrorFilter::Validation);
+  let value = f();
+
+  match pollster::block_on(device.pop_error_scope()) {
+    Some(e) => Err(e),
+    None => Ok(value),
+  }
+}
+
+/// Reads and compiles a WGSL file at runtime, the counterpart of
+/// `include_wgsl!` for hot-reloading. A shader that fails to compile is an
+/// `Err` rather than a panic.
+pub fn load_wgsl(
+  device: &wgpu::Device,
+  path: &Path,
+) -> Result<wgpu::ShaderModule, Box<dyn Error>> {
+  let source = fs::read_to_string(path)?;
+
+  let module = capture_validation(device, || {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: path.to_str(),
+      source: wgpu::ShaderSource::Wgsl(source.into()),
+    })
+  })?;
+
+  Ok(module)
+}
+
+/// Watches WGSL files for changes. See `App::with_shader_watch`.
+///
+/// The parent directories are watched instead of the files themselves,
+/// since many editors save by writing a new file and renaming it over the
+/// old one, which ends a watch on the file.
+pub struct ShaderWatcher {
+  _watcher: notify::RecommendedWatcher,
+  rx: mpsc::Receiver<notify::Result<notify::Event>>,
+  files: Vec<PathBuf>,
+}
+
+impl ShaderWatcher {
+  pub fn new(paths: &[&Path]) -> Result<Self, Box<dyn Error>> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+      let file = path.canonicalize()?;
+      let dir = file.parent().ok_or("shader path has no parent directory")?;
+      watcher.watch(dir, RecursiveMode::NonRecursive)?;
+      files.push(file);
+    }
+
+    Ok(Self {
+      _watcher: watcher,
+      rx,
+      files,
+    })
+  }
+
+  /// Drains the pending events and returns `true` if any watched file was
+  /// written since the last call. One save usually produces several
+  /// events, which are reported once.
+  pub fn poll_changed(&self) -> bool {
+    let mut changed = false;
+
+    for result in self.rx.try_iter() {
+      match result {
+        Ok(event) => {
+          let is_write =
+            matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_));
+          if is_write && event.paths.iter().any(|p| self.files.contains(p)) {
+            changed = true;
+          }
+        }
+        Err(e) => log::warn!("Shader watch error: {}", e),
+      }
+    }
+
+    changed
+  }
+}
+
+/// WGSL helpers for double-float ("df64") arithmetic: a value is stored as
+/// `vec2f(hi, lo)` with `hi + lo` exact, which gives about 48 bits of
+/// mantissa instead of 24, e.g. for deep fractal zooms. Prepend it to a
+/// shader, `format!("{}{}", DF64_WGSL, include_str!(...))`, and pass the
+/// values in with `split_f64`.
+///
+/// Provides `df64_add`, `df64_sub`, `df64_mul`, `df64_mul_f32`,
+/// `df64_from_f32` and `df64_to_f32`, built on the error-free `df64_two_sum`
+/// and `df64_two_prod`. They rely on every operation being rounded as
+/// written; a backend that reassociates float math (fast-math) cancels the
+/// error terms and the result falls back to f32 precision. The range is
+/// that of f32.
+pub const DF64_WGSL: &str = include_str!("./shader/df64.wgsl");
+
+/// Splits `value` into the `[hi, lo]` pair read by the `DF64_WGSL`
+/// helpers as a `vec2f`.
+pub fn split_f64(value: f64) -> [f32; 2] {
+  let hi = value as f32;
+  let lo = (value - hi as f64) as f32;
+  [hi, lo]
+}
+
+/// Arithmetic a shader is built with, see `DF64_WGSL`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+  #[default]
+  Single,
+  /// Emulated with two f32, several times slower.
+  Double,
+}
+
+impl Precision {
+  /// Smallest relative difference between two values the precision can
+  /// still tell apart.
+  pub fn epsilon(&self) -> f64 {
+    match self {
+      Precision::Single => f32::EPSILON as f64,
+      Precision::Double => (f32::EPSILON as f64).powi(2),
+    }
+  }
+
+  pub fn toggled(&self) -> Self {
+    match self {
+      Precision::Single => Precision::Double,
+      Precision::Double => Precision::Single,
+    }
+  }
+}
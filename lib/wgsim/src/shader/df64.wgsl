@@ -0,0 +1,67 @@
+// double-float: 値を hi + lo の2つの f32 で表し、約48ビットの仮数を得る
+// vec2f の x が hi、y が lo（|lo| <= hi の ULP の半分）
+
+// |a| >= |b| のときだけ正しい
+fn df64_quick_two_sum(a: f32, b: f32) -> vec2f {
+  let s = a + b;
+  let e = b - (s - a);
+  return vec2f(s, e);
+}
+
+// a + b を丸め誤差ごと返す（Knuth）
+fn df64_two_sum(a: f32, b: f32) -> vec2f {
+  let s = a + b;
+  let v = s - a;
+  let e = (a - (s - v)) + (b - v);
+  return vec2f(s, e);
+}
+
+// 仮数24ビットを上下12ビットずつに分ける（Dekker、4097 = 2^12 + 1）
+fn df64_split(a: f32) -> vec2f {
+  let t = 4097.0 * a;
+  let hi = t - (t - a);
+  return vec2f(hi, a - hi);
+}
+
+// a * b を丸め誤差ごと返す
+// fma が融合される保証はないので、分割して誤差を計算する
+fn df64_two_prod(a: f32, b: f32) -> vec2f {
+  let p = a * b;
+  let a_s = df64_split(a);
+  let b_s = df64_split(b);
+  let e = ((a_s.x * b_s.x - p) + a_s.x * b_s.y + a_s.y * b_s.x) + a_s.y * b_s.y;
+  return vec2f(p, e);
+}
+
+fn df64_from_f32(a: f32) -> vec2f {
+  return vec2f(a, 0.0);
+}
+
+fn df64_to_f32(a: vec2f) -> f32 {
+  return a.x + a.y;
+}
+
+fn df64_add(a: vec2f, b: vec2f) -> vec2f {
+  var s = df64_two_sum(a.x, b.x);
+  let t = df64_two_sum(a.y, b.y);
+  s.y += t.x;
+  s = df64_quick_two_sum(s.x, s.y);
+  s.y += t.y;
+  return df64_quick_two_sum(s.x, s.y);
+}
+
+fn df64_sub(a: vec2f, b: vec2f) -> vec2f {
+  return df64_add(a, -b);
+}
+
+fn df64_mul(a: vec2f, b: vec2f) -> vec2f {
+  var p = df64_two_prod(a.x, b.x);
+  p.y += a.x * b.y + a.y * b.x;
+  return df64_quick_two_sum(p.x, p.y);
+}
+
+fn df64_mul_f32(a: vec2f, b: f32) -> vec2f {
+  var p = df64_two_prod(a.x, b);
+  p.y += a.y * b;
+  return df64_quick_two_sum(p.x, p.y);
+}
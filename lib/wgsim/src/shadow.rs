@@ -0,0 +1,218 @@
+use bytemuck::cast_slice;
+use cgmath::{InnerSpace, Matrix4, Point3, SquareMatrix, Vector3};
+
+use crate::{matrix, util};
+
+/// Format of the shadow map. Its depth pipeline must use the same format
+/// (see `ShadowMap::depth_stencil_state`).
+pub const SHADOW_MAP_FORMAT: wgpu::TextureFormat =
+  wgpu::TextureFormat::Depth32Float;
+
+/// A depth texture rendered from a directional light, and everything needed
+/// to sample it as a shadow in the main pass.
+///
+/// Each frame:
+///
+/// 1. Render the occluders into `begin_depth_pass` with a depth-only
+///    pipeline (no fragment stage, `depth_stencil_state`, sample count 1)
+///    whose vertex shader multiplies by the light's view-projection matrix
+///    (`light_vp_buffer`).
+/// 2. Bind `bind_group` in the main pass and compare the fragment's
+///    light-space depth against the map with `textureSampleCompare`.
+///
+/// The bind group layout is:
+///
+/// - `@binding(0)` light view-projection `mat4x4f` (vertex and fragment)
+/// - `@binding(1)` `texture_depth_2d` (fragment)
+/// - `@binding(2)` `sampler_comparison` (fragment)
+///
+/// The bind group contains the shadow map itself, so it cannot be bound
+/// while the depth pass renders into it.
+pub struct ShadowMap {
+  size: u32,
+  view: wgpu::TextureView,
+  light_vp_buffer: wgpu::Buffer,
+  light_vp_mat: Matrix4<f32>,
+  bind_group_layout: wgpu::BindGroupLayout,
+  bind_group: wgpu::BindGroup,
+}
+
+impl ShadowMap {
+  /// `size` is the width and height of the map in texels.
+  pub fn new(device: &wgpu::Device, size: u32) -> Self {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("Shadow Map"),
+      size: wgpu::Extent3d {
+        width: size,
+        height: size,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: SHADOW_MAP_FORMAT,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+        | wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    // 参照値 <= 深度 なら光が届いている（1.0）
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some("Shadow Map Sampler"),
+      address_mode_u: wgpu::AddressMode::ClampToEdge,
+      address_mode_v: wgpu::AddressMode::ClampToEdge,
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      compare: Some(wgpu::CompareFunction::LessEqual),
+      ..Default::default()
+    });
+
+    let light_vp_mat = Matrix4::identity();
+    let light_vp_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Light View-Projection Buffer"),
+      size: std::mem::size_of::<[f32; 16]>() as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+
+    let bind_group_layout = util::create_bind_group_layout(
+      device,
+      &[
+        wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        wgpu::BindingType::Texture {
+          sample_type: wgpu::TextureSampleType::Depth,
+          view_dimension: wgpu::TextureViewDimension::D2,
+          multisampled: false,
+        },
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+      ],
+      &[
+        wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::ShaderStages::FRAGMENT,
+      ],
+    );
+    let bind_group = util::create_bind_group(
+      device,
+      &bind_group_layout,
+      &[
+        light_vp_buffer.as_entire_binding(),
+        wgpu::BindingResource::TextureView(&view),
+        wgpu::BindingResource::Sampler(&sampler),
+      ],
+    );
+
+    Self {
+      size,
+      view,
+      light_vp_buffer,
+      light_vp_mat,
+      bind_group_layout,
+      bind_group,
+    }
+  }
+
+  /// Depth state for the depth-only pipeline. The bias pushes the stored
+  /// depth slightly away from the light, so that surfaces don't shadow
+  /// themselves (shadow acne).
+  pub fn depth_stencil_state() -> wgpu::DepthStencilState {
+    wgpu::DepthStencilState {
+      format: SHADOW_MAP_FORMAT,
+      depth_write_enabled: true,
+      depth_compare: wgpu::CompareFunction::LessEqual,
+      stencil: wgpu::StencilState::default(),
+      bias: wgpu::DepthBiasState {
+        constant: 2,
+        slope_scale: 2.,
+        clamp: 0.,
+      },
+    }
+  }
+
+  /// Points the light along `direction` at a sphere of `radius` around
+  /// `center`, which should enclose every occluder and receiver, and
+  /// uploads the resulting matrix.
+  ///
+  /// Directional light has parallel rays, so the projection is orthographic.
+  /// A tighter sphere gives sharper shadows for the same map size.
+  pub fn set_light(
+    &mut self,
+    queue: &wgpu::Queue,
+    direction: Vector3<f32>,
+    center: Point3<f32>,
+    radius: f32,
+  ) {
+    self.light_vp_mat = light_vp_mat(direction, center, radius);
+    let light_vp_ref: &[f32; 16] = self.light_vp_mat.as_ref();
+    queue.write_buffer(&self.light_vp_buffer, 0, cast_slice(light_vp_ref));
+  }
+
+  pub fn size(&self) -> u32 {
+    self.size
+  }
+
+  pub fn light_vp_mat(&self) -> &Matrix4<f32> {
+    &self.light_vp_mat
+  }
+
+  /// The uniform holding `light_vp_mat`, for the depth pass's own bind
+  /// group.
+  pub fn light_vp_buffer(&self) -> &wgpu::Buffer {
+    &self.light_vp_buffer
+  }
+
+  pub fn begin_depth_pass<'e>(
+    &'e self,
+    encoder: &'e mut wgpu::CommandEncoder,
+  ) -> wgpu::RenderPass<'e> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("Shadow Depth Pass"),
+      color_attachments: &[],
+      depth_stencil_attachment: Some(
+        util::create_stored_depth_stencil_attachment(&self.view),
+      ),
+      ..Default::default()
+    })
+  }
+
+  pub fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+    &self.bind_group_layout
+  }
+
+  pub fn bind_group(&self) -> &wgpu::BindGroup {
+    &self.bind_group
+  }
+}
+
+fn light_vp_mat(
+  direction: Vector3<f32>,
+  center: Point3<f32>,
+  radius: f32,
+) -> Matrix4<f32> {
+  let direction = direction.normalize();
+  let eye = center - direction * radius * 2.;
+
+  // 真上・真下から照らすときは、上方向をZ軸に切り替える
+  let up = if direction.y.abs() > 0.99 {
+    Vector3::unit_z()
+  } else {
+    Vector3::unit_y()
+  };
+
+  let view_mat = matrix::create_view_mat(eye, center, up);
+  let project_mat = matrix::create_ortho_mat(
+    -radius,
+    radius,
+    -radius,
+    radius,
+    radius,
+    radius * 3.,
+  );
+
+  project_mat * view_mat
+}
@@ -1,7 +1,88 @@
+use crate::ctx::ContextError;
+
+/// A requested present mode, resolved against what the surface supports
+/// when the configuration is built.
+///
+/// `Fifo` (vsync) is always supported, so it is the default and the last
+/// fallback. `Immediate` and `Mailbox` first fall back to each other, so
+/// benchmarks stay uncapped where possible. The `Auto*` modes are resolved
+/// by wgpu itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PresentModePref(pub wgpu::PresentMode);
+
+impl Default for PresentModePref {
+  fn default() -> Self {
+    Self(wgpu::PresentMode::Fifo)
+  }
+}
+
+impl PresentModePref {
+  pub fn resolve(&self, supported: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+    use wgpu::PresentMode::*;
+
+    let candidates: &[wgpu::PresentMode] = match self.0 {
+      Immediate => &[Immediate, Mailbox],
+      Mailbox => &[Mailbox, Immediate],
+      FifoRelaxed => &[FifoRelaxed],
+      // Auto系はwgpuが対応するモードに読み替えるので、そのまま使える
+      mode @ (AutoVsync | AutoNoVsync) => return mode,
+      Fifo => &[],
+    };
+
+    candidates
+      .iter()
+      .copied()
+      .find(|mode| supported.contains(mode))
+      .unwrap_or(Fifo)
+  }
+}
+
+/// Picks the first format whose `is_srgb()` matches `prefer_srgb`, or the
+/// surface's first format if there is none. `None` only if `formats` is
+/// empty, i.e. the surface is unsupported by the adapter.
+///
+/// With an sRGB format the GPU encodes the shader's linear output on write,
+/// which is what the tutorial's `state.rs` relies on; with a linear format
+/// the same colors come out darker unless the shader encodes them itself.
+pub fn select_format(
+  formats: &[wgpu::TextureFormat],
+  prefer_srgb: bool,
+) -> Option<wgpu::TextureFormat> {
+  formats
+    .iter()
+    .copied()
+    .find(|format| format.is_srgb() == prefer_srgb)
+    .or(formats.first().copied())
+}
+
+/// Uses `requested` if the surface supports it, otherwise warns and falls
+/// back to `select_format`.
+fn resolve_format(
+  requested: Option<wgpu::TextureFormat>,
+  formats: &[wgpu::TextureFormat],
+  prefer_srgb: bool,
+) -> Option<wgpu::TextureFormat> {
+  match requested {
+    Some(format) if formats.contains(&format) => Some(format),
+    Some(format) => {
+      let fallback = select_format(formats, prefer_srgb)?;
+      log::warn!(
+        "Surface format {:?} is not supported by the surface, using {:?}",
+        format,
+        fallback
+      );
+      Some(fallback)
+    }
+    None => select_format(formats, prefer_srgb),
+  }
+}
+
+#[derive(Clone)]
 pub struct SurfaceConfigBuilder<'a> {
   usage: wgpu::TextureUsages,
   format: Option<wgpu::TextureFormat>,
-  present_mode: wgpu::PresentMode,
+  prefer_srgb: bool,
+  present_mode: PresentModePref,
   alpha_mode: Option<wgpu::CompositeAlphaMode>,
   view_formats: &'a [wgpu::TextureFormat],
   desired_maximum_frame_latency: u32,
@@ -12,7 +93,8 @@ impl<'a> SurfaceConfigBuilder<'a> {
     Self {
       usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
       format: None,
-      present_mode: wgpu::PresentMode::Fifo,
+      prefer_srgb: true,
+      present_mode: PresentModePref::default(),
       alpha_mode: None,
       view_formats: &[],
       desired_maximum_frame_latency: 2,
@@ -24,29 +106,122 @@ impl<'a> SurfaceConfigBuilder<'a> {
     self
   }
 
+  /// Without an explicit `format`, picks a linear format instead of an sRGB
+  /// one (see `select_format`).
+  pub fn prefer_linear(mut self) -> Self {
+    self.prefer_srgb = false;
+    self
+  }
+
+  /// See `PresentModePref` for the fallback when `mode` is unsupported.
+  pub fn present_mode(mut self, mode: wgpu::PresentMode) -> Self {
+    self.present_mode = PresentModePref(mode);
+    self
+  }
+
   pub fn alpha_mode(mut self, mode: wgpu::CompositeAlphaMode) -> Self {
     self.alpha_mode = Some(mode);
     self
   }
 
+  /// Requests premultiplied alpha so the surface can be composited over the
+  /// desktop. Falls back to the first supported mode if it is unavailable.
+  pub fn transparent(self) -> Self {
+    self.alpha_mode(wgpu::CompositeAlphaMode::PreMultiplied)
+  }
+
   pub fn build(
     &self,
     adapter: &'a wgpu::Adapter,
     surface: &wgpu::Surface,
     width: u32,
     height: u32,
-  ) -> wgpu::SurfaceConfiguration {
+  ) -> Result<wgpu::SurfaceConfiguration, ContextError> {
     let surface_caps = surface.get_capabilities(&adapter);
 
-    wgpu::SurfaceConfiguration {
+    // 対応するフォーマットが無いのは、サーフェスがアダプターで使えないとき
+    let format =
+      resolve_format(self.format, &surface_caps.formats, self.prefer_srgb)
+        .ok_or(ContextError::SurfaceUnsupported)?;
+
+    let alpha_mode = match self.alpha_mode {
+      Some(mode) if surface_caps.alpha_modes.contains(&mode) => mode,
+      Some(mode) => {
+        log::warn!(
+          "Alpha mode {:?} is not supported by the surface, using {:?}",
+          mode,
+          surface_caps.alpha_modes[0]
+        );
+        surface_caps.alpha_modes[0]
+      }
+      None => surface_caps.alpha_modes[0],
+    };
+
+    let present_mode = self.present_mode.resolve(&surface_caps.present_modes);
+    if present_mode != self.present_mode.0 {
+      log::warn!(
+        "Present mode {:?} is not supported by the surface, using {:?}",
+        self.present_mode.0,
+        present_mode
+      );
+    }
+    log::info!("Present mode: {:?}", present_mode);
+
+    Ok(wgpu::SurfaceConfiguration {
       usage: self.usage,
-      format: self.format.unwrap_or(surface_caps.formats[0]),
+      format,
       width,
       height,
-      present_mode: self.present_mode,
-      alpha_mode: self.alpha_mode.unwrap_or(surface_caps.alpha_modes[0]),
+      present_mode,
+      alpha_mode,
       view_formats: self.view_formats.to_vec(),
       desired_maximum_frame_latency: self.desired_maximum_frame_latency,
-    }
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use wgpu::TextureFormat::*;
+
+  const FORMATS: [wgpu::TextureFormat; 3] =
+    [Bgra8Unorm, Bgra8UnormSrgb, Rgba16Float];
+
+  #[test]
+  fn srgb_is_picked_by_default() {
+    assert_eq!(select_format(&FORMATS, true), Some(Bgra8UnormSrgb));
+  }
+
+  #[test]
+  fn linear_is_picked_when_srgb_is_not_preferred() {
+    assert_eq!(select_format(&FORMATS, false), Some(Bgra8Unorm));
+  }
+
+  #[test]
+  fn first_format_is_used_without_a_match() {
+    assert_eq!(select_format(&[Rgba16Float], true), Some(Rgba16Float));
+  }
+
+  #[test]
+  fn no_format_without_surface_support() {
+    assert_eq!(select_format(&[], true), None);
+    assert_eq!(resolve_format(Some(Bgra8Unorm), &[], true), None);
+  }
+
+  #[test]
+  fn supported_override_is_kept() {
+    assert_eq!(
+      resolve_format(Some(Rgba16Float), &FORMATS, true),
+      Some(Rgba16Float)
+    );
+  }
+
+  #[test]
+  fn unsupported_override_falls_back() {
+    assert_eq!(
+      resolve_format(Some(Rgba8UnormSrgb), &FORMATS, true),
+      Some(Bgra8UnormSrgb)
+    );
   }
 }
@@ -0,0 +1,228 @@
+use bytemuck::cast_slice;
+use wgpu::util::DeviceExt;
+
+use crate::{ctx::DrawingContext, util};
+
+use super::PersistentTarget;
+
+const MIN_FADE_FACTOR: f32 = 0.0;
+const MAX_FADE_FACTOR: f32 = 1.0;
+
+/// Full-screen pass that copies the previous frame of a `PersistentTarget`
+/// into the current one, multiplied by `factor` (e.g. 0.95).
+///
+/// The pass leaves the current view bound with its contents stored, so the
+/// caller can then draw additively on top of it with `LoadOp::Load`, and
+/// finally show the result with `present`.
+pub struct FadePass {
+  pipeline: wgpu::RenderPipeline,
+  bind_group_layout: wgpu::BindGroupLayout,
+  sampler: wgpu::Sampler,
+  factor_uniform_buffer: wgpu::Buffer,
+  // present はそのまま写すので、係数 1 のバッファを別に持つ
+  unit_factor_uniform_buffer: wgpu::Buffer,
+  factor: f32,
+}
+
+impl FadePass {
+  pub fn new(
+    ctx: &DrawingContext,
+    format: wgpu::TextureFormat,
+    factor: f32,
+  ) -> Self {
+    let factor = factor.clamp(MIN_FADE_FACTOR, MAX_FADE_FACTOR);
+
+    let shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./fade.wgsl"));
+
+    let sampler = ctx.device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some("fade sampler"),
+      mag_filter: wgpu::FilterMode::Nearest,
+      min_filter: wgpu::FilterMode::Nearest,
+      ..Default::default()
+    });
+
+    let factor_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("fade factor uniform buffer"),
+        contents: cast_slice(&[factor]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+    let unit_factor_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("unit fade factor uniform buffer"),
+        contents: cast_slice(&[1f32]),
+        usage: wgpu::BufferUsages::UNIFORM,
+      });
+
+    let bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        wgpu::BindingType::Texture {
+          sample_type: wgpu::TextureSampleType::Float { filterable: true },
+          view_dimension: wgpu::TextureViewDimension::D2,
+          multisampled: false,
+        },
+        wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+      ],
+      &[
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::ShaderStages::FRAGMENT,
+      ],
+    );
+
+    let pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Fade Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let pipeline =
+      ctx.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Fade Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+          module: &shader,
+          entry_point: "vs_main",
+          buffers: &[],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &shader,
+          entry_point: "fs_main",
+          targets: &[Some(format.into())],
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+      });
+
+    Self {
+      pipeline,
+      bind_group_layout,
+      sampler,
+      factor_uniform_buffer,
+      unit_factor_uniform_buffer,
+      factor,
+    }
+  }
+
+  pub fn factor(&self) -> f32 {
+    self.factor
+  }
+
+  pub fn set_factor(&mut self, queue: &wgpu::Queue, factor: f32) {
+    self.factor = factor.clamp(MIN_FADE_FACTOR, MAX_FADE_FACTOR);
+    queue.write_buffer(
+      &self.factor_uniform_buffer,
+      0,
+      cast_slice(&[self.factor]),
+    );
+  }
+
+  /// Creates the bind groups for both textures of `target` once, so `draw`
+  /// and `present` can be recorded every frame. Call it again after
+  /// `PersistentTarget::resize` returns `true`.
+  pub fn prepare(
+    &self,
+    device: &wgpu::Device,
+    target: &PersistentTarget,
+  ) -> FadeBindGroups {
+    let bind_group = |source, factor_uniform_buffer: &wgpu::Buffer| {
+      util::create_bind_group(
+        device,
+        &self.bind_group_layout,
+        &[
+          wgpu::BindingResource::Sampler(&self.sampler),
+          wgpu::BindingResource::TextureView(source),
+          factor_uniform_buffer.as_entire_binding(),
+        ],
+      )
+    };
+
+    // [i] は current が i のときに使う
+    let views = target.views();
+    FadeBindGroups {
+      fade: [1, 0]
+        .map(|prev| bind_group(&views[prev], &self.factor_uniform_buffer)),
+      present: [0, 1].map(|current| {
+        bind_group(&views[current], &self.unit_factor_uniform_buffer)
+      }),
+    }
+  }
+
+  /// Writes `prev_view * factor` into `current_view` of the target.
+  pub fn draw(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: &PersistentTarget,
+    bind_groups: &FadeBindGroups,
+  ) {
+    self.draw_pass(
+      encoder,
+      &bind_groups.fade[target.current_index()],
+      target.current_view(),
+      "Fade Pass",
+    );
+  }
+
+  /// Copies `current_view` of the target into `view` unchanged, e.g. onto
+  /// the surface once the frame is drawn. `view` must have the format the
+  /// pass was created with.
+  pub fn present(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: &PersistentTarget,
+    bind_groups: &FadeBindGroups,
+    view: &wgpu::TextureView,
+  ) {
+    self.draw_pass(
+      encoder,
+      &bind_groups.present[target.current_index()],
+      view,
+      "Trail Present Pass",
+    );
+  }
+
+  fn draw_pass(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    bind_group: &wgpu::BindGroup,
+    destination: &wgpu::TextureView,
+    label: &str,
+  ) {
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view: destination,
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+  }
+}
+
+/// Bind groups of one `PersistentTarget`, from `FadePass::prepare`.
+pub struct FadeBindGroups {
+  fade: [wgpu::BindGroup; 2],
+  present: [wgpu::BindGroup; 2],
+}
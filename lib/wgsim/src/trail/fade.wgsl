@@ -0,0 +1,29 @@
+@group(0) @binding(0) var prev_sampler: sampler;
+@group(0) @binding(1) var prev_texture: texture_2d<f32>;
+@group(0) @binding(2) var<uniform> fade_factor: f32;
+
+struct VertexOutput {
+  @builtin(position) position: vec4f,
+  @location(0) uv: vec2f,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> VertexOutput {
+  // 画面全体を覆う大きな三角形
+  var pos = array<vec2f, 3>(
+    vec2f(-1.0, -1.0),
+    vec2f( 3.0, -1.0),
+    vec2f(-1.0,  3.0),
+  );
+
+  var output: VertexOutput;
+  output.position = vec4f(pos[i], 0.0, 1.0);
+  output.uv = vec2f(pos[i].x + 1.0, 1.0 - pos[i].y) * 0.5;
+  return output;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+  let prev = textureSample(prev_texture, prev_sampler, in.uv);
+  return vec4f(prev.rgb * fade_factor, prev.a);
+}
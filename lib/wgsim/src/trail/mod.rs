@@ -0,0 +1,5 @@
+mod fade;
+mod persistent;
+
+pub use fade::{FadeBindGroups, FadePass};
+pub use persistent::PersistentTarget;
@@ -0,0 +1,135 @@
+use crate::ctx::{DrawingContext, Size};
+
+/// A color target whose contents survive across frames.
+///
+/// Unlike the MSAA texture, which is recreated every resize and cleared every
+/// frame, this keeps the previous frame around so it can be faded and drawn
+/// over (e.g. particle trails). It is only recreated when the size changes.
+///
+/// A render pass cannot sample the texture it is writing to (framebuffer
+/// feedback), so two textures are ping-ponged: each frame reads the previous
+/// result from `prev_view` and writes into `current_view`, then `swap` is
+/// called before the next frame.
+pub struct PersistentTarget {
+  textures: [wgpu::Texture; 2],
+  views: [wgpu::TextureView; 2],
+  format: wgpu::TextureFormat,
+  size: Size,
+  current: usize,
+}
+
+impl PersistentTarget {
+  pub fn new(ctx: &DrawingContext, format: wgpu::TextureFormat) -> Self {
+    let size = *ctx.size();
+    let (textures, views) = create_textures(&ctx.device, size, format);
+
+    Self {
+      textures,
+      views,
+      format,
+      size,
+      current: 0,
+    }
+  }
+
+  /// Recreates the textures only if the context size has changed.
+  /// Returns `true` when the accumulated contents were discarded.
+  pub fn resize(&mut self, ctx: &DrawingContext) -> bool {
+    let size = *ctx.size();
+    if size.width == self.size.width && size.height == self.size.height {
+      return false;
+    }
+
+    let (textures, views) = create_textures(&ctx.device, size, self.format);
+    self.textures = textures;
+    self.views = views;
+    self.size = size;
+    self.current = 0;
+
+    true
+  }
+
+  pub fn format(&self) -> wgpu::TextureFormat {
+    self.format
+  }
+
+  pub fn size(&self) -> &Size {
+    &self.size
+  }
+
+  /// The texture written in this frame.
+  pub fn current_texture(&self) -> &wgpu::Texture {
+    &self.textures[self.current]
+  }
+
+  /// The view written in this frame.
+  pub fn current_view(&self) -> &wgpu::TextureView {
+    &self.views[self.current]
+  }
+
+  /// The view holding the result of the previous frame.
+  pub fn prev_view(&self) -> &wgpu::TextureView {
+    &self.views[1 - self.current]
+  }
+
+  /// Which of the two textures `current_view` is, 0 or 1.
+  pub fn current_index(&self) -> usize {
+    self.current
+  }
+
+  pub(crate) fn views(&self) -> &[wgpu::TextureView; 2] {
+    &self.views
+  }
+
+  pub fn swap(&mut self) {
+    self.current = 1 - self.current;
+  }
+
+  /// Clears both textures, e.g. when the trails should be reset.
+  pub fn clear(&self, encoder: &mut wgpu::CommandEncoder) {
+    for view in &self.views {
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Persistent Target Clear Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view,
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        ..Default::default()
+      });
+    }
+  }
+}
+
+fn create_textures(
+  device: &wgpu::Device,
+  size: Size,
+  format: wgpu::TextureFormat,
+) -> ([wgpu::Texture; 2], [wgpu::TextureView; 2]) {
+  let textures = [0, 1].map(|_| {
+    device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("persistent target texture"),
+      size: wgpu::Extent3d {
+        width: size.width.max(1),
+        height: size.height.max(1),
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+        | wgpu::TextureUsages::TEXTURE_BINDING
+        | wgpu::TextureUsages::COPY_SRC,
+      view_formats: &[],
+    })
+  });
+  let views = [0, 1].map(|i| {
+    textures[i].create_view(&wgpu::TextureViewDescriptor::default())
+  });
+
+  (textures, views)
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use winit::{event::WindowEvent, window::Window};
+
+/// An egui layer drawn over a finished frame, for tweaking parameters at
+/// runtime (see `App::with_ui`).
+///
+/// Feed it every `WindowEvent` before the renderer (`on_window_event`) and
+/// call `draw` with the resolved surface view once the frame's own passes
+/// are recorded. The UI is drawn single-sampled on top with `LoadOp::Load`.
+pub struct EguiLayer {
+  window: Arc<Window>,
+  ctx: egui::Context,
+  state: egui_winit::State,
+  renderer: egui_wgpu::Renderer,
+}
+
+impl EguiLayer {
+  /// `target_format` is that of the view passed to `draw`, usually the
+  /// surface format.
+  pub fn new(
+    device: &wgpu::Device,
+    window: Arc<Window>,
+    target_format: wgpu::TextureFormat,
+  ) -> Self {
+    let ctx = egui::Context::default();
+    let state = egui_winit::State::new(
+      ctx.clone(),
+      egui::ViewportId::ROOT,
+      &window,
+      Some(window.scale_factor() as f32),
+      None,
+      Some(device.limits().max_texture_dimension_2d as usize),
+    );
+    let renderer =
+      egui_wgpu::Renderer::new(device, target_format, None, 1, false);
+
+    Self {
+      window,
+      ctx,
+      state,
+      renderer,
+    }
+  }
+
+  pub fn context(&self) -> &egui::Context {
+    &self.ctx
+  }
+
+  /// Returns whether egui used the event (e.g. a drag on a slider), in
+  /// which case it should not also move the camera.
+  pub fn on_window_event(&mut self, event: &WindowEvent) -> bool {
+    let response = self.state.on_window_event(&self.window, event);
+    if response.repaint {
+      self.window.request_redraw();
+    }
+    response.consumed
+  }
+
+  /// Runs `run_ui` to build this frame's UI and records drawing it onto
+  /// `view`.
+  pub fn draw(
+    &mut self,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    encoder: &mut wgpu::CommandEncoder,
+    view: &wgpu::TextureView,
+    run_ui: impl FnMut(&egui::Context),
+  ) {
+    let raw_input = self.state.take_egui_input(&self.window);
+    let output = self.ctx.run(raw_input, run_ui);
+    self.state.handle_platform_output(&self.window, output.platform_output);
+
+    let paint_jobs =
+      self.ctx.tessellate(output.shapes, output.pixels_per_point);
+    let size = self.window.inner_size();
+    let screen_descriptor = egui_wgpu::ScreenDescriptor {
+      size_in_pixels: [size.width, size.height],
+      pixels_per_point: output.pixels_per_point,
+    };
+
+    for (id, image_delta) in &output.textures_delta.set {
+      self.renderer.update_texture(device, queue, *id, image_delta);
+    }
+    // ペイントコールバックは使わないので、追加のコマンドバッファは返ってこない
+    self.renderer.update_buffers(
+      device,
+      queue,
+      encoder,
+      &paint_jobs,
+      &screen_descriptor,
+    );
+
+    let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("Egui Pass"),
+      color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+        view,
+        resolve_target: None,
+        ops: wgpu::Operations {
+          load: wgpu::LoadOp::Load,
+          store: wgpu::StoreOp::Store,
+        },
+      })],
+      ..Default::default()
+    });
+    // egui_wgpu::Renderer::render はエンコーダーの寿命に縛られないパスを求める
+    self.renderer.render(
+      &mut render_pass.forget_lifetime(),
+      &paint_jobs,
+      &screen_descriptor,
+    );
+
+    for id in &output.textures_delta.free {
+      self.renderer.free_texture(id);
+    }
+  }
+}
@@ -0,0 +1,21 @@
+use crate::light::Material;
+
+/// Sliders for the four Blinn-Phong parameters of `material`. Returns
+/// whether any of them changed this frame, e.g. to upload the uniform only
+/// then.
+pub fn material_sliders(ui: &mut egui::Ui, material: &mut Material) -> bool {
+  let sliders = [
+    ui.add(egui::Slider::new(&mut material.ambient, 0. ..=1.).text("ambient")),
+    ui.add(egui::Slider::new(&mut material.diffuse, 0. ..=1.).text("diffuse")),
+    ui.add(
+      egui::Slider::new(&mut material.specular, 0. ..=1.).text("specular"),
+    ),
+    ui.add(
+      egui::Slider::new(&mut material.shininess, 1. ..=200.)
+        .logarithmic(true)
+        .text("shininess"),
+    ),
+  ];
+
+  sliders.iter().any(|response| response.changed())
+}
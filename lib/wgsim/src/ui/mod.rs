@@ -0,0 +1,6 @@
+mod layer;
+mod material;
+
+pub use egui;
+pub use layer::EguiLayer;
+pub use material::material_sliders;
@@ -1,4 +1,10 @@
+use std::error::Error;
+use std::path::Path;
+
+use wgpu::util::DeviceExt;
+
 use crate::ctx::DrawingContext;
+use crate::mipmap::MipmapGenerator;
 
 pub fn create_bind_group_layout_for_buffer(
   device: &wgpu::Device,
@@ -62,14 +68,198 @@ pub fn create_bind_group(
   })
 }
 
+/// Packs `args` back to back for `draw_indexed_indirect`, the `i`-th draw
+/// starting at `i * size_of::<DrawIndexedIndirectArgs>()`.
+///
+/// The buffer is also a `STORAGE` buffer so a compute pass can rewrite the
+/// counts (e.g. for culling). A non-zero `first_instance` is only honored
+/// with `Features::INDIRECT_FIRST_INSTANCE`; otherwise the draw is skipped.
+pub fn create_indirect_buffer(
+  device: &wgpu::Device,
+  args: &[wgpu::util::DrawIndexedIndirectArgs],
+) -> wgpu::Buffer {
+  let contents =
+    args.iter().flat_map(|args| args.as_bytes()).copied().collect::<Vec<u8>>();
+
+  device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+    label: Some("Indirect Buffer"),
+    contents: &contents,
+    usage: wgpu::BufferUsages::INDIRECT
+      | wgpu::BufferUsages::STORAGE
+      | wgpu::BufferUsages::COPY_DST,
+  })
+}
+
+/// Highest `anisotropy_clamp` wgpu accepts.
+pub const MAX_ANISOTROPY: u16 = 16;
+
+/// Filtering and addressing for `create_sampler`. The defaults are the
+/// linear, clamp-to-edge sampler the examples used to spell out by hand.
+#[derive(Debug, Clone, Copy)]
+pub struct SamplerOptions<'a> {
+  pub label: Option<&'a str>,
+  /// Used for all of u, v and w.
+  pub address_mode: wgpu::AddressMode,
+  pub mag_filter: wgpu::FilterMode,
+  pub min_filter: wgpu::FilterMode,
+  pub mipmap_filter: wgpu::FilterMode,
+  /// 1 turns anisotropic filtering off. Clamped to `1..=MAX_ANISOTROPY`.
+  pub anisotropy_clamp: u16,
+}
+
+impl Default for SamplerOptions<'_> {
+  fn default() -> Self {
+    Self {
+      label: Some("Sampler"),
+      address_mode: wgpu::AddressMode::ClampToEdge,
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      mipmap_filter: wgpu::FilterMode::Nearest,
+      anisotropy_clamp: 1,
+    }
+  }
+}
+
+/// Creates a sampler from `options`, fixing up combinations wgpu rejects.
+///
+/// wgpu has no limit to query for anisotropy: values above
+/// `MAX_ANISOTROPY` are a validation error, and adapters without
+/// `DownlevelFlags::ANISOTROPIC_FILTERING` silently ignore the clamp. Any
+/// clamp above 1 also requires all three filters to be linear, so they are
+/// switched to linear (with a warning) instead of failing.
+pub fn create_sampler(
+  device: &wgpu::Device,
+  options: SamplerOptions,
+) -> wgpu::Sampler {
+  let anisotropy_clamp = options.anisotropy_clamp.clamp(1, MAX_ANISOTROPY);
+  if anisotropy_clamp != options.anisotropy_clamp {
+    log::warn!(
+      "anisotropy_clamp {} is out of range, using {}",
+      options.anisotropy_clamp,
+      anisotropy_clamp
+    );
+  }
+
+  let mut filters = [
+    options.mag_filter,
+    options.min_filter,
+    options.mipmap_filter,
+  ];
+  if anisotropy_clamp > 1
+    && filters.iter().any(|f| *f != wgpu::FilterMode::Linear)
+  {
+    log::warn!(
+      "anisotropic filtering needs linear filters, overriding {:?}",
+      filters
+    );
+    filters = [wgpu::FilterMode::Linear; 3];
+  }
+  let [mag_filter, min_filter, mipmap_filter] = filters;
+
+  device.create_sampler(&wgpu::SamplerDescriptor {
+    label: options.label,
+    address_mode_u: options.address_mode,
+    address_mode_v: options.address_mode,
+    address_mode_w: options.address_mode,
+    mag_filter,
+    min_filter,
+    mipmap_filter,
+    anisotropy_clamp,
+    ..Default::default()
+  })
+}
+
+/// Number of levels in a full mip chain for `width` x `height`, down to 1x1.
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+  32 - width.max(height).max(1).leading_zeros()
+}
+
+/// Fills levels `1..mip_levels` of `texture` from level 0 and submits the
+/// passes right away. See `MipmapGenerator` for the requirements on the
+/// texture, and to regenerate every frame without rebuilding the pipeline.
+pub fn generate_mipmaps(
+  device: &wgpu::Device,
+  queue: &wgpu::Queue,
+  texture: &wgpu::Texture,
+  format: wgpu::TextureFormat,
+  mip_levels: u32,
+) {
+  let mut encoder =
+    device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+      label: Some("Mipmap Encoder"),
+    });
+  let generator = MipmapGenerator::new(device, format);
+  let chain = generator.prepare(device, texture, mip_levels);
+  generator.generate(&mut encoder, &chain);
+  queue.submit(std::iter::once(encoder.finish()));
+}
+
+/// Uploads `image` as a 2D texture with `mip_levels` levels (1 for none, or
+/// `mip_level_count` for a full chain) and fills the levels below 0.
+/// `format` has to be `Rgba8Unorm` or `Rgba8UnormSrgb`.
+pub fn create_image_texture(
+  device: &wgpu::Device,
+  queue: &wgpu::Queue,
+  image: &image::RgbaImage,
+  format: wgpu::TextureFormat,
+  mip_levels: u32,
+) -> wgpu::Texture {
+  let (width, height) = image.dimensions();
+  let size = wgpu::Extent3d {
+    width,
+    height,
+    depth_or_array_layers: 1,
+  };
+
+  let texture = device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("Image Texture"),
+    size,
+    mip_level_count: mip_levels.clamp(1, mip_level_count(width, height)),
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format,
+    usage: wgpu::TextureUsages::COPY_DST
+      | wgpu::TextureUsages::RENDER_ATTACHMENT
+      | wgpu::TextureUsages::TEXTURE_BINDING,
+    view_formats: &[],
+  });
+
+  queue.write_texture(
+    texture.as_image_copy(),
+    image,
+    wgpu::ImageDataLayout {
+      offset: 0,
+      bytes_per_row: Some(4 * width),
+      rows_per_image: Some(height),
+    },
+    size,
+  );
+
+  if texture.mip_level_count() > 1 {
+    generate_mipmaps(device, queue, &texture, format, mip_levels);
+  }
+
+  texture
+}
+
+/// Clears to black; see `create_color_attachment_with`.
 pub fn create_color_attachment(
   texture_view: &wgpu::TextureView,
-) -> wgpu::RenderPassColorAttachment {
+) -> wgpu::RenderPassColorAttachment<'_> {
+  create_color_attachment_with(texture_view, wgpu::Color::BLACK)
+}
+
+/// Clears to `color`, e.g. `DrawingContext::clear_color` read in
+/// `Render::new`.
+pub fn create_color_attachment_with(
+  texture_view: &wgpu::TextureView,
+  color: wgpu::Color,
+) -> wgpu::RenderPassColorAttachment<'_> {
   wgpu::RenderPassColorAttachment {
     view: texture_view,
     resolve_target: None,
     ops: wgpu::Operations {
-      load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+      load: wgpu::LoadOp::Clear(color),
       store: wgpu::StoreOp::Store,
     },
   }
@@ -96,21 +286,68 @@ pub fn create_msaa_texture_view(init: &DrawingContext) -> wgpu::TextureView {
   msaa_texture.create_view(&wgpu::TextureViewDescriptor::default())
 }
 
+/// Single-sample color target in the surface format that a post-effect can
+/// sample from. Use it as the resolve target of the MSAA scene pass.
+pub fn create_sampleable_color_view(
+  init: &DrawingContext,
+) -> wgpu::TextureView {
+  let size = init.size();
+
+  let texture = init.device.create_texture(&wgpu::TextureDescriptor {
+    label: None,
+    size: wgpu::Extent3d {
+      width: size.width,
+      height: size.height,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format: init.format(),
+    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+      | wgpu::TextureUsages::TEXTURE_BINDING,
+    view_formats: &[],
+  });
+
+  texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// Clears to black; see `create_msaa_color_attachment_with`.
 pub fn create_msaa_color_attachment<'a>(
   texture_view: &'a wgpu::TextureView,
   msaa_view: &'a wgpu::TextureView,
+) -> wgpu::RenderPassColorAttachment<'a> {
+  create_msaa_color_attachment_with(texture_view, msaa_view, wgpu::Color::BLACK)
+}
+
+pub fn create_msaa_color_attachment_with<'a>(
+  texture_view: &'a wgpu::TextureView,
+  msaa_view: &'a wgpu::TextureView,
+  color: wgpu::Color,
 ) -> wgpu::RenderPassColorAttachment<'a> {
   wgpu::RenderPassColorAttachment {
     view: msaa_view,
     resolve_target: Some(texture_view),
     ops: wgpu::Operations {
-      load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+      load: wgpu::LoadOp::Clear(color),
       store: wgpu::StoreOp::Store,
     },
   }
 }
 
+/// Depth format used by `create_depth_view` and
+/// `RenderPipelineBuilder::enable_depth_stencil(None)`.
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24Plus;
+
 pub fn create_depth_view(init: &DrawingContext) -> wgpu::TextureView {
+  create_depth_view_with_format(init, DEPTH_FORMAT)
+}
+
+/// The pipeline's `depth_format` must match `format`.
+pub fn create_depth_view_with_format(
+  init: &DrawingContext,
+  format: wgpu::TextureFormat,
+) -> wgpu::TextureView {
   let size = init.size();
 
   let depth_texture = init.device.create_texture(&wgpu::TextureDescriptor {
@@ -123,7 +360,7 @@ pub fn create_depth_view(init: &DrawingContext) -> wgpu::TextureView {
     mip_level_count: 1,
     sample_count: init.sample_count,
     dimension: wgpu::TextureDimension::D2,
-    format: wgpu::TextureFormat::Depth24Plus,
+    format,
     usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
     view_formats: &[],
   });
@@ -143,3 +380,601 @@ pub fn create_depth_stencil_attachment(
     stencil_ops: None,
   }
 }
+
+/// Like `create_depth_view`, but the depth can also be bound as a texture
+/// (e.g. for `depth::DepthResolvePass`).
+pub fn create_sampleable_depth_view(
+  init: &DrawingContext,
+) -> wgpu::TextureView {
+  let size = init.size();
+
+  let depth_texture = init.device.create_texture(&wgpu::TextureDescriptor {
+    label: None,
+    size: wgpu::Extent3d {
+      width: size.width,
+      height: size.height,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: init.sample_count,
+    dimension: wgpu::TextureDimension::D2,
+    format: DEPTH_FORMAT,
+    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+      | wgpu::TextureUsages::TEXTURE_BINDING,
+    view_formats: &[],
+  });
+
+  depth_texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// Keeps the depth values after the pass so they can be read later.
+pub fn create_stored_depth_stencil_attachment(
+  depth_view: &wgpu::TextureView,
+) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+  wgpu::RenderPassDepthStencilAttachment {
+    view: depth_view,
+    depth_ops: Some(wgpu::Operations {
+      load: wgpu::LoadOp::Clear(1.0),
+      store: wgpu::StoreOp::Store,
+    }),
+    stencil_ops: None,
+  }
+}
+
+pub trait AsBindingResource {
+  fn as_binding_resource(&self) -> wgpu::BindingResource<'_>;
+}
+
+impl AsBindingResource for wgpu::Buffer {
+  fn as_binding_resource(&self) -> wgpu::BindingResource<'_> {
+    self.as_entire_binding()
+  }
+}
+
+impl AsBindingResource for wgpu::TextureView {
+  fn as_binding_resource(&self) -> wgpu::BindingResource<'_> {
+    wgpu::BindingResource::TextureView(self)
+  }
+}
+
+/// How each binding of a `PingPong` bind group is filled.
+pub enum PingPongBinding<'a> {
+  /// The resource read in the step.
+  Current,
+  /// The resource written in the step.
+  Next,
+  /// The same resource in every bind group.
+  Shared(wgpu::BindingResource<'a>),
+  /// One resource per step, cycled by the bind group index.
+  PerStep(Vec<wgpu::BindingResource<'a>>),
+}
+
+/// The step arithmetic of `PingPong`, without any GPU resources.
+///
+/// Step `i` reads slot `i` and writes slot `(i + 1) % count`, so `current`
+/// and `next` never refer to the same slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PingPongIndex {
+  count: usize,
+  index: usize,
+}
+
+impl PingPongIndex {
+  pub fn new(count: usize) -> Self {
+    assert!(
+      count >= 2,
+      "PingPong needs at least 2 resources, got {}",
+      count
+    );
+    Self { count, index: 0 }
+  }
+
+  pub fn count(&self) -> usize {
+    self.count
+  }
+
+  /// The slot read in the current step.
+  pub fn current(&self) -> usize {
+    self.index
+  }
+
+  /// The slot written in the current step.
+  pub fn next(&self) -> usize {
+    (self.index + 1) % self.count
+  }
+
+  pub fn advance(&mut self) {
+    self.index = self.next();
+  }
+
+  pub fn reset(&mut self) {
+    self.index = 0;
+  }
+}
+
+/// N resources and the bind groups alternating between them.
+///
+/// The bind group at index `i` reads `resources[i]` and writes
+/// `resources[(i + 1) % n]`, so `current` and `next` never refer to the same
+/// resource (see `PingPongIndex`).
+pub struct PingPong<T> {
+  resources: Vec<T>,
+  bind_groups: Vec<wgpu::BindGroup>,
+  step: PingPongIndex,
+}
+
+impl<T: AsBindingResource> PingPong<T> {
+  pub fn new(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    resources: Vec<T>,
+    bindings: &[PingPongBinding],
+  ) -> Self {
+    let mut step = PingPongIndex::new(resources.len());
+
+    let bind_groups = (0..step.count())
+      .map(|i| {
+        let entries = bindings
+          .iter()
+          .map(|binding| match binding {
+            PingPongBinding::Current => {
+              resources[step.current()].as_binding_resource()
+            }
+            PingPongBinding::Next => {
+              resources[step.next()].as_binding_resource()
+            }
+            PingPongBinding::Shared(resource) => resource.clone(),
+            PingPongBinding::PerStep(resources) => {
+              resources[i % resources.len()].clone()
+            }
+          })
+          .collect::<Vec<_>>();
+        step.advance();
+
+        create_bind_group(device, layout, &entries)
+      })
+      .collect();
+
+    Self {
+      resources,
+      bind_groups,
+      step: PingPongIndex::new(step.count()),
+    }
+  }
+}
+
+impl<T> PingPong<T> {
+  pub fn count(&self) -> usize {
+    self.step.count()
+  }
+
+  pub fn index(&self) -> usize {
+    self.step.current()
+  }
+
+  /// The bind group for the current step.
+  pub fn current(&self) -> &wgpu::BindGroup {
+    &self.bind_groups[self.step.current()]
+  }
+
+  /// The bind group for the step after `advance`.
+  pub fn next(&self) -> &wgpu::BindGroup {
+    &self.bind_groups[self.step.next()]
+  }
+
+  /// The resource read in the current step.
+  pub fn current_resource(&self) -> &T {
+    &self.resources[self.step.current()]
+  }
+
+  /// The resource written in the current step.
+  pub fn next_resource(&self) -> &T {
+    &self.resources[self.step.next()]
+  }
+
+  pub fn advance(&mut self) {
+    self.step.advance();
+  }
+
+  pub fn reset(&mut self) {
+    self.step.reset();
+  }
+}
+
+/// Submits the encoders in a single `queue.submit`, in the given order.
+///
+/// On a single queue, submission order is all GPU work needs to be ordered.
+/// What wgpu guarantees:
+/// - Within one command encoder, passes run in recording order, and wgpu
+///   inserts the barriers needed when a later pass reads a buffer or texture
+///   written by an earlier one (e.g. compute writes, render reads).
+/// - Command buffers in one `queue.submit` run in slice order, with the same
+///   hazard tracking between them.
+/// - Separate `queue.submit` calls on the same queue run in call order.
+/// - `queue.write_buffer`/`write_texture` run before the command buffers of
+///   the next `submit`, not at the point they were called.
+///
+/// So a render pass that reads what a compute pass wrote sees the result
+/// whether both share an encoder, are passed here together, or go through
+/// separate `submit` calls, as long as the compute work is submitted
+/// first. This helper only saves collecting the command buffers by hand;
+/// nothing waits for the GPU.
+///
+/// `submit` returns before the GPU has finished, but that only matters to
+/// the CPU: see `wait_for_submission`.
+pub fn ensure_ordering(
+  queue: &wgpu::Queue,
+  encoders: impl IntoIterator<Item = wgpu::CommandEncoder>,
+) -> wgpu::SubmissionIndex {
+  queue.submit(encoders.into_iter().map(|encoder| encoder.finish()))
+}
+
+/// Blocks until the given submission has finished on the GPU.
+///
+/// Only needed before the CPU reads results, e.g. mapping a buffer. Later
+/// work on the same queue is already ordered after the submission, so there
+/// is no need to wait between GPU passes.
+pub fn wait_for_submission(
+  device: &wgpu::Device,
+  submission_index: wgpu::SubmissionIndex,
+) {
+  device.poll(wgpu::Maintain::WaitForSubmissionIndex(submission_index));
+}
+
+/// Copies `buffer` to a staging buffer and reads it back as a `Vec<T>`.
+///
+/// `buffer` needs `COPY_SRC` usage and a size that is a multiple of
+/// `size_of::<T>()`. The copy is submitted on `queue`, so work submitted
+/// earlier is finished before the data is read.
+pub async fn read_buffer<T: bytemuck::Pod>(
+  device: &wgpu::Device,
+  queue: &wgpu::Queue,
+  buffer: &wgpu::Buffer,
+) -> Vec<T> {
+  let size = buffer.size();
+
+  let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("staging buffer for read_buffer"),
+    size,
+    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+    mapped_at_creation: false,
+  });
+
+  let mut command_encoder = device
+    .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+  command_encoder.copy_buffer_to_buffer(buffer, 0, &staging_buffer, 0, size);
+  queue.submit(std::iter::once(command_encoder.finish()));
+
+  let buffer_slice = staging_buffer.slice(..);
+  let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+  buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+    tx.send(result).unwrap();
+  });
+  device.poll(wgpu::Maintain::Wait);
+  rx.receive().await.unwrap().unwrap();
+
+  let data_view = buffer_slice.get_mapped_range();
+  let data = bytemuck::cast_slice(&data_view).to_vec();
+
+  drop(data_view);
+  staging_buffer.unmap();
+
+  data
+}
+
+/// Per-channel (RGBA) error between two images, normalized to 0..1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageDiff {
+  pub max_error: [f32; 4],
+  pub mean_error: [f32; 4],
+  /// Pixels where any channel differs by more than the tolerance.
+  pub pixels_over_tolerance: usize,
+}
+
+impl ImageDiff {
+  pub fn is_match(&self) -> bool {
+    self.pixels_over_tolerance == 0
+  }
+}
+
+/// Compares two tightly packed RGBA8 images of `width` x `height`, e.g. a
+/// frame from `render::render_once` against a golden image.
+///
+/// `tolerance` is the largest per-channel difference (0..1) that still
+/// counts as equal, to absorb rounding differences between GPUs.
+pub fn compare_images(
+  actual: &[u8],
+  expected: &[u8],
+  width: u32,
+  height: u32,
+  tolerance: f32,
+) -> ImageDiff {
+  let len = (width * height * 4) as usize;
+  assert_eq!(
+    actual.len(),
+    len,
+    "actual is not {}x{} RGBA8",
+    width,
+    height
+  );
+  assert_eq!(
+    expected.len(),
+    len,
+    "expected is not {}x{} RGBA8",
+    width,
+    height
+  );
+
+  let mut max_error = [0.; 4];
+  let mut error_sum = [0.; 4];
+  let mut pixels_over_tolerance = 0;
+
+  for (a, e) in actual.chunks_exact(4).zip(expected.chunks_exact(4)) {
+    let mut over = false;
+    for c in 0..4 {
+      let error = a[c].abs_diff(e[c]) as f32 / 255.;
+      max_error[c] = f32::max(max_error[c], error);
+      error_sum[c] += error as f64;
+      over |= error > tolerance;
+    }
+    if over {
+      pixels_over_tolerance += 1;
+    }
+  }
+
+  let pixel_count = (width * height).max(1) as f64;
+
+  ImageDiff {
+    max_error,
+    mean_error: error_sum.map(|sum| (sum / pixel_count) as f32),
+    pixels_over_tolerance,
+  }
+}
+
+/// Saves a PNG that shows where two RGBA8 images differ: the expected image
+/// in dim gray, with differing pixels in red (brighter for larger errors).
+pub fn save_diff_visualization(
+  path: &Path,
+  actual: &[u8],
+  expected: &[u8],
+  width: u32,
+  height: u32,
+) -> Result<(), Box<dyn Error>> {
+  let data = actual
+    .chunks_exact(4)
+    .zip(expected.chunks_exact(4))
+    .flat_map(|(a, e)| {
+      let error = (0..4).map(|c| a[c].abs_diff(e[c])).max().unwrap_or(0);
+      if error == 0 {
+        let luma = (e[0] as u32 + e[1] as u32 + e[2] as u32) / 3 / 4;
+        [luma as u8, luma as u8, luma as u8, 255]
+      } else {
+        // 小さな差も見えるように、最低でも半分の明るさにする
+        [128 + error / 2, 0, 0, 255]
+      }
+    })
+    .collect();
+
+  let img = image::RgbaImage::from_raw(width, height, data)
+    .ok_or("image data does not match the size")?;
+  img.save_with_format(path, image::ImageFormat::Png)?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ping_pong_index_alternates_two_slots() {
+    let mut step = PingPongIndex::new(2);
+
+    for swap in 0..6 {
+      assert_eq!(step.current(), swap % 2);
+      assert_eq!(step.next(), (swap + 1) % 2);
+      step.advance();
+    }
+
+    step.reset();
+    assert_eq!((step.current(), step.next()), (0, 1));
+  }
+
+  #[test]
+  fn ping_pong_index_cycles_without_aliasing() {
+    for count in [2, 3] {
+      let mut step = PingPongIndex::new(count);
+      let mut visited = vec![];
+
+      for _ in 0..count {
+        assert_ne!(step.current(), step.next());
+        visited.push(step.current());
+        step.advance();
+      }
+
+      // 一周すると最初のスロットに戻り、その間に全スロットを1回ずつ読む
+      assert_eq!(step.current(), 0);
+      assert_eq!(visited, (0..count).collect::<Vec<_>>());
+    }
+  }
+
+  #[test]
+  #[should_panic]
+  fn ping_pong_index_rejects_a_single_slot() {
+    PingPongIndex::new(1);
+  }
+
+  #[test]
+  fn identical_images_have_no_diff() {
+    let image: Vec<u8> = (0..4 * 4 * 4).map(|i| (i * 7) as u8).collect();
+    let diff = compare_images(&image, &image, 4, 4, 0.);
+
+    assert!(diff.is_match());
+    assert_eq!(diff.max_error, [0.; 4]);
+    assert_eq!(diff.mean_error, [0.; 4]);
+  }
+
+  #[test]
+  fn one_changed_pixel_is_detected() {
+    let expected = vec![128; 4 * 4 * 4];
+    let mut actual = expected.clone();
+    // (2, 1) の緑だけを変える
+    actual[(4 + 2) * 4 + 1] = 200;
+
+    let diff = compare_images(&actual, &expected, 4, 4, 0.1);
+    assert!(!diff.is_match());
+    assert_eq!(diff.pixels_over_tolerance, 1);
+    assert_eq!(diff.max_error[0], 0.);
+    assert_eq!(diff.max_error[1], 72. / 255.);
+
+    // 許容誤差より小さい差は一致とみなす
+    assert!(compare_images(&actual, &expected, 4, 4, 0.3).is_match());
+  }
+
+  const ORDERING_SHADER: &str = "
+    @group(0) @binding(0) var<storage, read_write> written: vec4f;
+    @group(0) @binding(0) var<storage, read> color: vec4f;
+
+    @compute @workgroup_size(1)
+    fn cs_main() {
+      written = vec4f(0.0, 1.0, 0.0, 1.0);
+    }
+
+    @vertex
+    fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4f {
+      let uv = vec2f(f32((i << 1u) & 2u), f32(i & 2u));
+      return vec4f(uv * 2.0 - 1.0, 0.0, 1.0);
+    }
+
+    @fragment
+    fn fs_main() -> @location(0) vec4f {
+      return color;
+    }
+  ";
+
+  #[test]
+  fn render_submitted_after_compute_sees_its_output() {
+    // 256 バイト（コピーの行の揃え）ちょうどの幅にする
+    let (width, format) = (64, wgpu::TextureFormat::Rgba8Unorm);
+    let ctx = pollster::block_on(DrawingContext::new_for_texture(
+      crate::ctx::Size::new(width, 1),
+      format,
+    ))
+    .expect("no GPU adapter for offscreen rendering");
+    let device = &ctx.device;
+
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: None,
+      source: wgpu::ShaderSource::Wgsl(ORDERING_SHADER.into()),
+    });
+    let color_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: None,
+      size: 16,
+      usage: wgpu::BufferUsages::STORAGE,
+      mapped_at_creation: false,
+    });
+
+    let compute_pipeline =
+      device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: None,
+        layout: None,
+        module: &module,
+        entry_point: "cs_main",
+        compilation_options: Default::default(),
+        cache: None,
+      });
+    let render_pipeline =
+      device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: None,
+        layout: None,
+        vertex: wgpu::VertexState {
+          module: &module,
+          entry_point: "vs_main",
+          buffers: &[],
+          compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &module,
+          entry_point: "fs_main",
+          targets: &[Some(format.into())],
+          compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+      });
+    let bind_group = |layout: wgpu::BindGroupLayout| {
+      create_bind_group(device, &layout, &[color_buffer.as_entire_binding()])
+    };
+    let compute_bind_group =
+      bind_group(compute_pipeline.get_bind_group_layout(0));
+    let render_bind_group =
+      bind_group(render_pipeline.get_bind_group_layout(0));
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: None,
+      size: wgpu::Extent3d {
+        width,
+        height: 1,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+        | wgpu::TextureUsages::COPY_SRC,
+      view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label: None,
+      size: (width * 4) as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+      mapped_at_creation: false,
+    });
+
+    // コンピュートとレンダーを別々の submit に分ける
+    let mut encoder = device.create_command_encoder(&Default::default());
+    {
+      let mut pass = encoder.begin_compute_pass(&Default::default());
+      pass.set_pipeline(&compute_pipeline);
+      pass.set_bind_group(0, &compute_bind_group, &[]);
+      pass.dispatch_workgroups(1, 1, 1);
+    }
+    ctx.queue.submit(std::iter::once(encoder.finish()));
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    {
+      let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(create_color_attachment(&view))],
+        ..Default::default()
+      });
+      pass.set_pipeline(&render_pipeline);
+      pass.set_bind_group(0, &render_bind_group, &[]);
+      pass.draw(0..3, 0..1);
+    }
+    encoder.copy_texture_to_buffer(
+      texture.as_image_copy(),
+      wgpu::ImageCopyBuffer {
+        buffer: &output_buffer,
+        layout: wgpu::ImageDataLayout {
+          offset: 0,
+          bytes_per_row: Some(width * 4),
+          rows_per_image: None,
+        },
+      },
+      texture.size(),
+    );
+    ctx.queue.submit(std::iter::once(encoder.finish()));
+
+    let pixels: Vec<u8> =
+      pollster::block_on(read_buffer(device, &ctx.queue, &output_buffer));
+    for pixel in pixels.chunks_exact(4) {
+      assert_eq!(pixel, [0, 255, 0, 255]);
+    }
+  }
+}
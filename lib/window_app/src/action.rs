@@ -16,6 +16,8 @@ pub enum Action {
   ShowWindowMenu,
   RequestResize,
   DumpMonitors,
+  CopyWindowTitle,
+  PasteIntoTitle,
 }
 
 impl Action {
@@ -37,6 +39,8 @@ impl Action {
       Action::ShowWindowMenu => "Show window menu",
       Action::RequestResize => "Request a resize",
       Action::DumpMonitors => "Dump monitor information",
+      Action::CopyWindowTitle => "Copy window title to clipboard",
+      Action::PasteIntoTitle => "Set window title from clipboard",
     }
   }
 }
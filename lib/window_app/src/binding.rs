@@ -2,6 +2,9 @@ use winit::{event::MouseButton, keyboard::ModifiersState};
 
 use crate::action::Action;
 
+#[cfg(feature = "gamepad")]
+pub use gilrs::Button as GamepadButton;
+
 pub struct Binding<T: Eq> {
   pub trigger: T,
   pub mods: ModifiersState,
@@ -50,3 +53,49 @@ pub fn mouse_button_to_string(button: MouseButton) -> &'static str {
     MouseButton::Other(_) => "",
   }
 }
+
+/// Face buttons mapped to window actions, for `with_gamepad_bindings`.
+#[cfg(feature = "gamepad")]
+pub const DEFAULT_GAMEPAD_BINDINGS: &[Binding<GamepadButton>] = &[
+  gamepad_binding(GamepadButton::South, Action::ToggleFullscreen),
+  gamepad_binding(GamepadButton::East, Action::Minimize),
+  gamepad_binding(GamepadButton::West, Action::ToggleMaximize),
+  gamepad_binding(GamepadButton::North, Action::CreateNewWindow),
+  gamepad_binding(GamepadButton::Select, Action::PrintHelp),
+];
+
+#[cfg(feature = "gamepad")]
+const fn gamepad_binding(
+  trigger: GamepadButton,
+  action: Action,
+) -> Binding<GamepadButton> {
+  Binding {
+    trigger,
+    mods: ModifiersState::empty(),
+    action,
+  }
+}
+
+#[cfg(feature = "gamepad")]
+pub fn gamepad_button_to_string(button: GamepadButton) -> &'static str {
+  match button {
+    GamepadButton::South => "South",
+    GamepadButton::East => "East",
+    GamepadButton::North => "North",
+    GamepadButton::West => "West",
+    GamepadButton::LeftTrigger => "LB",
+    GamepadButton::LeftTrigger2 => "LT",
+    GamepadButton::RightTrigger => "RB",
+    GamepadButton::RightTrigger2 => "RT",
+    GamepadButton::Select => "Select",
+    GamepadButton::Start => "Start",
+    GamepadButton::Mode => "Mode",
+    GamepadButton::LeftThumb => "LS",
+    GamepadButton::RightThumb => "RS",
+    GamepadButton::DPadUp => "DPadUp",
+    GamepadButton::DPadDown => "DPadDown",
+    GamepadButton::DPadLeft => "DPadLeft",
+    GamepadButton::DPadRight => "DPadRight",
+    _ => "",
+  }
+}
@@ -3,9 +3,16 @@ pub mod binding;
 pub mod render;
 mod state;
 
-use std::{collections::HashMap, error::Error, sync::Arc};
+use std::{
+  collections::HashMap,
+  error::Error,
+  sync::Arc,
+  time::{Duration, Instant},
+};
 
 use action::Action;
+#[cfg(feature = "gamepad")]
+use binding::{gamepad_button_to_string, GamepadButton};
 use binding::{modifiers_to_string, mouse_button_to_string, Binding};
 use log::{error, info};
 use render::Render;
@@ -14,11 +21,16 @@ use winit::{
   application::ApplicationHandler,
   dpi::{PhysicalPosition, PhysicalSize},
   event::{DeviceEvent, DeviceId, MouseButton, MouseScrollDelta, WindowEvent},
-  event_loop::ActiveEventLoop,
+  event_loop::{ActiveEventLoop, ControlFlow},
   keyboard::{Key, ModifiersState},
   window::{WindowAttributes, WindowId},
 };
 
+/// How often gamepads are polled while gamepad bindings are registered.
+#[cfg(feature = "gamepad")]
+const GAMEPAD_POLL_INTERVAL: std::time::Duration =
+  std::time::Duration::from_millis(16);
+
 pub struct Application<'a, T, R>
 where
   R: Render<T>,
@@ -26,6 +38,15 @@ where
   windows: HashMap<WindowId, WindowState<'a, T, R>>,
   key_bindings: &'a [Binding<&'static str>],
   mouse_bindings: &'a [Binding<MouseButton>],
+  resize_debounce: Option<Duration>,
+  #[cfg(feature = "gamepad")]
+  gamepad_bindings: &'a [Binding<GamepadButton>],
+  /// `None` when no gamepad backend is available on this platform.
+  #[cfg(feature = "gamepad")]
+  gilrs: Option<gilrs::Gilrs>,
+  /// Gamepad actions go to this window, or any window if none is focused.
+  #[cfg(feature = "gamepad")]
+  focused_window: Option<WindowId>,
   renderer: R,
   inputs: T,
 }
@@ -39,6 +60,15 @@ where
       windows: Default::default(),
       key_bindings: &[],
       mouse_bindings: &[],
+      resize_debounce: None,
+      #[cfg(feature = "gamepad")]
+      gamepad_bindings: &[],
+      #[cfg(feature = "gamepad")]
+      gilrs: gilrs::Gilrs::new()
+        .inspect_err(|err| error!("Gamepad input disabled: {err}"))
+        .ok(),
+      #[cfg(feature = "gamepad")]
+      focused_window: None,
       renderer,
       inputs,
     }
@@ -58,6 +88,20 @@ where
     self.mouse_bindings = mouse_bindings;
   }
 
+  /// Only resize once no `Resized` event has arrived for `debounce`,
+  /// instead of at every intermediate size while the window is dragged.
+  pub fn with_resize_debounce(&mut self, debounce: Duration) {
+    self.resize_debounce = Some(debounce);
+  }
+
+  #[cfg(feature = "gamepad")]
+  pub fn with_gamepad_bindings(
+    &mut self,
+    gamepad_bindings: &'a [Binding<GamepadButton>],
+  ) {
+    self.gamepad_bindings = gamepad_bindings;
+  }
+
   fn create_window(
     &mut self,
     event_loop: &ActiveEventLoop,
@@ -105,6 +149,8 @@ where
       Action::PrintHelp => self.print_help(),
       Action::RequestResize => window.swap_dimensions(),
       Action::DumpMonitors => self.dump_monitors(event_loop),
+      Action::CopyWindowTitle => window.copy_title(),
+      Action::PasteIntoTitle => window.paste_into_title(),
     }
   }
 
@@ -161,6 +207,57 @@ where
     })
   }
 
+  /// Process gamepad binding.
+  #[cfg(feature = "gamepad")]
+  fn process_gamepad_binding(
+    &self,
+    button: GamepadButton,
+    mods: &ModifiersState,
+  ) -> Option<Action> {
+    self.gamepad_bindings.iter().find_map(|binding| {
+      binding.is_triggered_by(&button, mods).then_some(binding.action)
+    })
+  }
+
+  /// Drains pending gamepad events and dispatches bound button presses.
+  #[cfg(feature = "gamepad")]
+  fn poll_gamepads(&mut self, event_loop: &ActiveEventLoop) {
+    let mut pressed = Vec::new();
+    if let Some(gilrs) = &mut self.gilrs {
+      while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event() {
+        match event {
+          gilrs::EventType::Connected => {
+            info!("Gamepad connected: {}", gilrs.gamepad(id).name());
+          }
+          gilrs::EventType::Disconnected => {
+            info!("Gamepad disconnected: {}", gilrs.gamepad(id).name());
+          }
+          gilrs::EventType::ButtonPressed(button, _) => {
+            info!("Gamepad button {button:?} pressed");
+            pressed.push(button);
+          }
+          _ => (),
+        }
+      }
+    }
+
+    for button in pressed {
+      let window_id = match self
+        .focused_window
+        .filter(|id| self.windows.contains_key(id))
+        .or_else(|| self.windows.keys().next().copied())
+      {
+        Some(window_id) => window_id,
+        None => return,
+      };
+
+      let mods = self.windows[&window_id].modifiers;
+      if let Some(action) = Self::process_gamepad_binding(self, button, &mods) {
+        self.handle_action_with_window(event_loop, window_id, action);
+      }
+    }
+  }
+
   fn print_help(&self) {
     info!("Keyboard bindings:");
     for binding in self.key_bindings {
@@ -182,6 +279,19 @@ where
         binding.action.help(),
       );
     }
+    #[cfg(feature = "gamepad")]
+    {
+      info!("Gamepad bindings:");
+      for binding in self.gamepad_bindings {
+        info!(
+          "{}{:<10} - {} ({})",
+          modifiers_to_string(binding.mods),
+          gamepad_button_to_string(binding.trigger),
+          binding.action,
+          binding.action.help(),
+        );
+      }
+    }
   }
 
   fn can_create_surfaces(&mut self, event_loop: &ActiveEventLoop) {
@@ -208,12 +318,17 @@ impl<T, R: Render<T>> ApplicationHandler for Application<'_, T, R> {
     };
 
     match event {
-      WindowEvent::Resized(size) => {
-        window.resize(size);
-      }
+      WindowEvent::Resized(size) => match self.resize_debounce {
+        Some(_) => window.queue_resize(size),
+        None => window.resize(size),
+      },
       WindowEvent::Focused(focused) => {
         if focused {
           info!("Window={window_id:?} focused");
+          #[cfg(feature = "gamepad")]
+          {
+            self.focused_window = Some(window_id);
+          }
         } else {
           info!("Window={window_id:?} unfocused");
         }
@@ -310,6 +425,16 @@ impl<T, R: Render<T>> ApplicationHandler for Application<'_, T, R> {
       WindowEvent::DoubleTapGesture { .. } => {
         info!("Smart zoom");
       }
+      WindowEvent::HoveredFile(path) => {
+        info!("File hovered: {}", path.display());
+      }
+      WindowEvent::HoveredFileCancelled => {
+        info!("File hover cancelled");
+      }
+      WindowEvent::DroppedFile(path) => {
+        info!("File dropped: {}", path.display());
+        window.file_dropped(&path);
+      }
       _ => (),
     }
   }
@@ -324,6 +449,28 @@ impl<T, R: Render<T>> ApplicationHandler for Application<'_, T, R> {
   }
 
   fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+    let mut wake_at: Option<Instant> = None;
+
+    #[cfg(feature = "gamepad")]
+    if self.gilrs.is_some() && !self.gamepad_bindings.is_empty() {
+      self.poll_gamepads(event_loop);
+      // Gamepads don't wake the event loop, so come back to poll them.
+      wake_at = Some(Instant::now() + GAMEPAD_POLL_INTERVAL);
+    }
+
+    if let Some(debounce) = self.resize_debounce {
+      for window in self.windows.values_mut() {
+        if let Some(settle_at) = window.apply_pending_resize(debounce) {
+          wake_at = Some(wake_at.map_or(settle_at, |at| at.min(settle_at)));
+        }
+      }
+    }
+
+    event_loop.set_control_flow(match wake_at {
+      Some(at) => ControlFlow::WaitUntil(at),
+      None => ControlFlow::Wait,
+    });
+
     if self.windows.is_empty() {
       info!("No windows left, exiting...");
       event_loop.exit();
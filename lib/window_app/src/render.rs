@@ -1,4 +1,4 @@
-use std::{error::Error, num::NonZeroU32, sync::Arc};
+use std::{error::Error, num::NonZeroU32, path::Path, sync::Arc};
 
 use winit::window::Window;
 
@@ -6,4 +6,7 @@ pub trait Render<T> {
   fn new(window: Arc<&Window>, inputs: &T) -> Self;
   fn resize(&mut self, width: NonZeroU32, height: NonZeroU32);
   fn draw(&mut self) -> Result<(), Box<dyn Error>>;
+
+  /// Called when a file is dropped onto the window. Does nothing by default.
+  fn on_file_dropped(&mut self, _path: &Path) {}
 }
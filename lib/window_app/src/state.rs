@@ -1,6 +1,14 @@
-use std::{error::Error, marker::PhantomData, mem, num::NonZeroU32, sync::Arc};
+use std::{
+  error::Error,
+  marker::PhantomData,
+  mem,
+  num::NonZeroU32,
+  path::Path,
+  sync::Arc,
+  time::{Duration, Instant},
+};
 
-use log::info;
+use log::{error, info};
 use winit::{
   dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
   keyboard::ModifiersState,
@@ -30,6 +38,8 @@ where
   pub panned: PhysicalPosition<f32>,
   /// Cursor position over the window.
   cursor_position: Option<PhysicalPosition<f64>>,
+  /// The latest size from `Resized` and when it arrived, while debouncing.
+  pending_resize: Option<(PhysicalSize<u32>, Instant)>,
 
   renderer: &'a mut R,
   inputs: PhantomData<T>,
@@ -54,6 +64,7 @@ where
       panned: Default::default(),
       zoom: Default::default(),
       cursor_position: Default::default(),
+      pending_resize: Default::default(),
       renderer,
       inputs: PhantomData,
     };
@@ -156,6 +167,29 @@ where
     self.window.request_redraw();
   }
 
+  /// Remember the new size and resize later in `apply_pending_resize`.
+  pub fn queue_resize(&mut self, size: PhysicalSize<u32>) {
+    self.pending_resize = Some((size, Instant::now()));
+  }
+
+  /// Resize once no new size has been queued for `debounce`.
+  ///
+  /// Returns when to check again if the size has not settled yet.
+  pub fn apply_pending_resize(
+    &mut self,
+    debounce: Duration,
+  ) -> Option<Instant> {
+    let (size, resized_at) = self.pending_resize?;
+    let settle_at = resized_at + debounce;
+    if Instant::now() < settle_at {
+      return Some(settle_at);
+    }
+
+    self.pending_resize = None;
+    self.resize(size);
+    None
+  }
+
   /// Drag the window.
   pub fn drag_window(&self) {
     if let Err(err) = self.window.drag_window() {
@@ -238,6 +272,31 @@ where
     }
   }
 
+  /// Copy the window title to the system clipboard.
+  pub fn copy_title(&self) {
+    let title = self.window.title();
+    match arboard::Clipboard::new().and_then(|mut c| c.set_text(&title)) {
+      Ok(()) => info!("Copied window title {title:?}"),
+      Err(err) => error!("Error copying window title: {err}"),
+    }
+  }
+
+  /// Replace the window title with the text on the system clipboard.
+  pub fn paste_into_title(&self) {
+    match arboard::Clipboard::new().and_then(|mut c| c.get_text()) {
+      Ok(text) => {
+        info!("Setting window title to {text:?}");
+        self.window.set_title(&text);
+      }
+      Err(err) => error!("Error reading clipboard: {err}"),
+    }
+  }
+
+  pub fn file_dropped(&mut self, path: &Path) {
+    self.renderer.on_file_dropped(path);
+    self.window.request_redraw();
+  }
+
   pub fn draw(&mut self) -> Result<(), Box<dyn Error>> {
     if self.occluded {
       info!("Skipping drawing occluded window={:?}", self.window.id());
@@ -0,0 +1,65 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  RandomizeShapeColor,
+  RandomizeWireframeColor,
+  NextPlotMode,
+  IncreaseAmbient,
+  DecreaseAmbient,
+  IncreaseDiffuse,
+  DecreaseDiffuse,
+  IncreaseSpecular,
+  DecreaseSpecular,
+  IncreaseShininess,
+  DecreaseShininess,
+  IncreaseRotationSpeed,
+  DecreaseRotationSpeed,
+  ToggleDepthView,
+  ToggleNormalView,
+  ToggleAxes,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::RandomizeShapeColor => "Randomize shape color",
+      Action::RandomizeWireframeColor => "Randomize wireframe color",
+      Action::NextPlotMode => "Switch shape / wireframe / both",
+      Action::IncreaseAmbient => "Increase ambient intensity",
+      Action::DecreaseAmbient => "Decrease ambient intensity",
+      Action::IncreaseDiffuse => "Increase diffuse intensity",
+      Action::DecreaseDiffuse => "Decrease diffuse intensity",
+      Action::IncreaseSpecular => "Increase specular intensity",
+      Action::DecreaseSpecular => "Decrease specular intensity",
+      Action::IncreaseShininess => "Increase specular shininess",
+      Action::DecreaseShininess => "Decrease specular shininess",
+      Action::IncreaseRotationSpeed => "Increase rotation speed",
+      Action::DecreaseRotationSpeed => "Decrease rotation speed",
+      Action::ToggleDepthView => "Toggle depth buffer view",
+      Action::ToggleNormalView => "Toggle world-space normal view",
+      Action::ToggleAxes => "Toggle world axes",
+    }
+  }
+}
+
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new()
+    .bind(KeyCode::ControlLeft, Action::RandomizeShapeColor)
+    .bind(KeyCode::AltLeft, Action::RandomizeWireframeColor)
+    .bind(KeyCode::Space, Action::NextPlotMode)
+    .bind(KeyCode::KeyQ, Action::IncreaseAmbient)
+    .bind(KeyCode::KeyA, Action::DecreaseAmbient)
+    .bind(KeyCode::KeyW, Action::IncreaseDiffuse)
+    .bind(KeyCode::KeyS, Action::DecreaseDiffuse)
+    .bind(KeyCode::KeyE, Action::IncreaseSpecular)
+    .bind(KeyCode::KeyD, Action::DecreaseSpecular)
+    .bind(KeyCode::KeyR, Action::IncreaseShininess)
+    .bind(KeyCode::KeyF, Action::DecreaseShininess)
+    .bind(KeyCode::KeyT, Action::IncreaseRotationSpeed)
+    .bind(KeyCode::KeyG, Action::DecreaseRotationSpeed)
+    .bind(KeyCode::KeyZ, Action::ToggleDepthView)
+    .bind(KeyCode::KeyN, Action::ToggleNormalView)
+    .bind(KeyCode::KeyX, Action::ToggleAxes)
+}
@@ -1,5 +1,4 @@
 struct LightUniforms {
-  light_position: vec4f,
   eye_position: vec4f,
   color: vec4f,
   specular_color: vec4f,
@@ -16,6 +15,27 @@ struct MaterialUniforms {
 
 @binding(1) @group(1) var<uniform> material: MaterialUniforms;
 
+struct EdgeUniforms {
+  color: vec3f,
+  // 0 なら辺を描かない
+  show_edges: u32,
+  // 0 なら面を描かず、辺だけを残す
+  show_faces: u32,
+  // 辺の太さ（ピクセル）
+  width: f32,
+}
+
+@binding(2) @group(1) var<uniform> edges: EdgeUniforms;
+
+struct PointLight {
+  position: vec3f,
+  intensity: f32,
+  color: vec3f,
+}
+
+@binding(0) @group(2) var<storage, read> point_lights: array<PointLight>;
+@binding(1) @group(2) var<uniform> light_count: u32;
+
 fn blinn_phong(N: vec3f, L: vec3f, V: vec3f) -> vec2f {
   let H = normalize(L + V);
   var diffuse = material.diffuse * max(dot(N, L), 0.0);
@@ -28,18 +48,48 @@ fn blinn_phong(N: vec3f, L: vec3f, V: vec3f) -> vec2f {
 struct Varyings {
   @location(0) v_position: vec4f,
   @location(1) v_normal: vec4f,
+  @location(2) v_barycentric: vec3f,
+}
+
+// 辺の上で 1、辺から width ピクセル以上離れると 0
+// fwidth で 1 ピクセルあたりの重心座標の変化量を求め、距離をピクセル単位にそろえる
+fn edge_factor(barycentric: vec3f) -> f32 {
+  let d = fwidth(barycentric);
+  let a = smoothstep(vec3f(0.0), d * edges.width, barycentric);
+  return 1.0 - min(min(a.x, a.y), a.z);
 }
 
 @fragment
 fn fs_main(in: Varyings) -> @location(0) vec4f {
+  // fwidth は分岐の前（一様な制御フローの中）で呼ぶ必要がある
+  var edge = edge_factor(in.v_barycentric);
+  if (edges.show_edges == 0u) {
+    edge = 0.0;
+  }
+  if (edges.show_faces == 0u) {
+    if (edge < 0.01) {
+      discard;
+    }
+    return vec4(edges.color, 1.0);
+  }
+
   var N = normalize(in.v_normal.xyz);
-  let L = normalize(light.light_position.xyz - in.v_position.xyz);
   let V = normalize(light.eye_position.xyz - in.v_position.xyz);
-  
-  let bp = blinn_phong(N, L, V);
-  let diffuse = bp[0];
-  let specular = bp[1];
-  
-  let final_color = light.color * (material.ambient + diffuse) + light.specular_color * specular;
-  return vec4(final_color.rgb, 1.0);
-}
\ No newline at end of file
+
+  var final_color = light.color.rgb * material.ambient;
+
+  // 各点光源の寄与を足し合わせる
+  for (var i = 0u; i < light_count; i++) {
+    let point_light = point_lights[i];
+    let L = normalize(point_light.position - in.v_position.xyz);
+
+    let bp = blinn_phong(N, L, V);
+    let diffuse = bp[0];
+    let specular = bp[1];
+
+    let radiance = point_light.color * point_light.intensity;
+    final_color += radiance * (light.color.rgb * diffuse + light.specular_color.rgb * specular);
+  }
+
+  return vec4(mix(final_color, edges.color, edge), 1.0);
+}
@@ -1,6 +1,9 @@
+mod action;
+
 use std::{iter, mem};
 use std::{sync::Arc, time};
 
+use action::Action;
 use anyhow::Result;
 use bytemuck::{Pod, Zeroable};
 use cgmath::*;
@@ -11,42 +14,61 @@ use wgpu_helper::transforms as wt;
 use wgpu_helper::vertex_data as vd;
 use wgpu_helper::vertex_data::cube::Cube;
 use wgpu_helper::wgpu_simplified as ws;
-use winit::event::{ElementState, KeyEvent};
-use winit::keyboard::{KeyCode, PhysicalKey};
+use wgsim::camera::OrbitCamera;
+use wgsim::debug::{DepthToColor, LineRenderer, NormalsToColor};
+use wgsim::geometry::{self, BarycentricVertex};
+use wgsim::input::KeyBindings;
+use wgsim::light::{self, Light, Material};
+#[cfg(feature = "ui")]
+use wgsim::ui::{egui, material_sliders, EguiLayer};
+use wgsim::util;
+use winit::keyboard::KeyCode;
 use winit::{dpi::PhysicalSize, event::WindowEvent, window::Window};
 
-fn create_vertices() -> (Vec<Vertex>, Vec<u16>, Vec<u16>) {
+// 深度表示で黒になる距離（カメラから立方体の奥までが収まるように）
+const DEPTH_VIEW_MAX_DISTANCE: f32 = 8.;
+
+// 辺の太さ（ピクセル）
+const EDGE_WIDTH: f32 = 1.5;
+
+// 立方体（一辺2）からはみ出して見えるワールド座標軸の長さ
+const AXES_LENGTH: f32 = 2.5;
+
+// 重心座標を頂点に持たせて、辺はフラグメントシェーダーで描く
+// （ワイヤーフレーム用のパイプラインとインデックスバッファは不要になる）
+// 面の対角線も辺として描かれる
+fn create_vertices() -> (Vec<BarycentricVertex>, Vec<u32>) {
   let Cube {
-    positions,
-    normals,
-    indices,
-    indices_wireframe,
-    ..
+    positions, indices, ..
   } = vd::cube::create_cube_data(2.);
+  let indices = indices.into_iter().map(u32::from).collect::<Vec<_>>();
 
-  let data = (0..positions.len())
-    .map(|i| Vertex {
-      position: positions[i],
-      normal: normals[i],
-    })
-    .collect::<Vec<Vertex>>();
-
-  (data, indices, indices_wireframe)
+  geometry::with_barycentric(&positions, &indices)
 }
 
 pub fn run(title: &str) -> Result<()> {
   env_logger::init();
 
   let args = std::env::args().collect::<Vec<String>>();
-  let sample_count = args.get(2).map(|s| s.parse::<u32>()).transpose()?;
+  let sample_count = args
+    .iter()
+    .skip(2)
+    .find(|arg| !arg.starts_with("--"))
+    .map(|s| s.parse::<u32>())
+    .transpose()?;
   let sample_count = sample_count.unwrap_or(1);
 
-  let (vertex_data, index_data_1, index_data_2) = create_vertices();
+  let (vertex_data, index_data) = create_vertices();
+
+  // 環境光を上げるキーをQから↑に付け替える例
+  // （↑に割り当て済みのアクションがあれば、そちらがQに移る）
+  let mut key_bindings = action::default_key_bindings();
+  key_bindings.remap(Action::IncreaseAmbient, KeyCode::ArrowUp);
+  key_bindings.print_help();
 
   let inputs = Inputs {
     vertex_data,
-    index_data_1,
-    index_data_2,
+    index_data,
     sample_count,
   };
 
@@ -57,9 +79,16 @@ pub fn run(title: &str) -> Result<()> {
     specular_color: [1., 1., 1.],
     object_color: [1., 0., 0.],
     wireframe_color: [1., 1., 0.],
-    material: IMaterial::default(),
+    material: Material::default(),
     plot_mode: PlotMode::Both,
     rotation_speed: 1.,
+    key_bindings,
+    // 立方体を囲むように色の異なる3つの点光源を置く
+    point_lights: vec![
+      Light::new([4., 2., 0.], [1., 0.3, 0.3], 1.),
+      Light::new([-2., 2., 3.5], [0.3, 1., 0.3], 1.),
+      Light::new([-2., -2., -3.5], [0.3, 0.3, 1.], 1.),
+    ],
   };
 
   let mut app: App<State> = App::new(title, inputs, initial);
@@ -75,10 +104,20 @@ enum PlotMode {
   Both,
 }
 
+impl PlotMode {
+  /// `(show_edges, show_faces)` of the edge uniforms.
+  fn flags(&self) -> (u32, u32) {
+    match self {
+      PlotMode::Shape => (0, 1),
+      PlotMode::Wireframe => (1, 0),
+      PlotMode::Both => (1, 1),
+    }
+  }
+}
+
 pub struct Inputs {
-  pub vertex_data: Vec<Vertex>,
-  pub index_data_1: Vec<u16>,
-  pub index_data_2: Vec<u16>,
+  pub vertex_data: Vec<BarycentricVertex>,
+  pub index_data: Vec<u32>,
   pub sample_count: u32,
 }
 
@@ -89,38 +128,56 @@ struct Initial {
   pub specular_color: [f32; 3],
   pub object_color: [f32; 3],
   pub wireframe_color: [f32; 3],
-  pub material: IMaterial,
+  pub material: Material,
   pub plot_mode: PlotMode,
   pub rotation_speed: f32,
+  pub key_bindings: KeyBindings<Action>,
+  pub point_lights: Vec<Light>,
 }
 
 struct State<'a> {
   /// drawing context
   init: ws::WgpuContext<'a>,
-  pipelines: Vec<wgpu::RenderPipeline>,
+  pipeline: wgpu::RenderPipeline,
 
   /// drawing data
   vertex_buffer: wgpu::Buffer,
-  index_buffers: Vec<wgpu::Buffer>,
-  indices_lens: Vec<u32>,
+  index_buffer: wgpu::Buffer,
+  indices_len: u32,
   uniform_bind_groups: Vec<wgpu::BindGroup>,
   uniform_buffers: Vec<wgpu::Buffer>,
   msaa_texture_view: wgpu::TextureView,
   depth_texture_view: wgpu::TextureView,
 
+  /// depth buffer visualization
+  depth_to_color: DepthToColor,
+  show_depth: bool,
+
+  /// normal visualization
+  normals_to_color: NormalsToColor,
+  show_normals: bool,
+
+  /// debug lines
+  line_renderer: LineRenderer,
+  show_axes: bool,
+
   /// transformation matrices
-  view_mat: Matrix4<f32>,
+  camera: OrbitCamera,
   project_mat: Matrix4<f32>,
 
   /// rendering settings
   plot_mode: PlotMode,
   rotation_speed: f32,
 
+  /// input
+  key_bindings: KeyBindings<Action>,
+
   /// lighting parameters
-  ambient: f32,
-  diffuse: f32,
-  specular: f32,
-  shininess: f32,
+  material: Material,
+
+  /// runtime UI
+  #[cfg(feature = "ui")]
+  egui_layer: EguiLayer,
 }
 
 impl<'a> Render for State<'a> {
@@ -132,7 +189,12 @@ impl<'a> Render for State<'a> {
     inputs: &Inputs,
     initial: &Initial,
   ) -> Self {
+    #[cfg(feature = "ui")]
+    let ui_window = window.clone();
     let init = ws::WgpuContext::new(window, inputs.sample_count, None).await;
+    #[cfg(feature = "ui")]
+    let egui_layer =
+      EguiLayer::new(&init.device, ui_window, init.config.format);
 
     let vs_shader = init
       .device
@@ -142,7 +204,7 @@ impl<'a> Render for State<'a> {
       .create_shader_module(wgpu::include_wgsl!("./blinn-phong-frag.wgsl"));
 
     let aspect = init.config.width as f32 / init.config.height as f32;
-    let (view_mat, project_mat, _) = wt::create_vp_mat(
+    let (_, project_mat, _) = wt::create_vp_mat(
       initial.camera_position,
       initial.look_direction,
       initial.up_direction,
@@ -157,154 +219,130 @@ impl<'a> Render for State<'a> {
         mapped_at_creation: false,
       });
 
-    let light_position: &[f32; 3] = initial.camera_position.as_ref();
     let eye_position: &[f32; 3] = initial.camera_position.as_ref();
 
-    let light_uniform_buffer_1 =
+    let light_uniform_buffer =
       init.device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("Light Uniform Buffer 1"),
-        size: (mem::size_of::<[f32; 4]>() * 4) as wgpu::BufferAddress,
+        label: Some("Light Uniform Buffer"),
+        size: (mem::size_of::<[f32; 4]>() * 3) as wgpu::BufferAddress,
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         mapped_at_creation: false,
       });
     init.queue.write_buffer(
-      &light_uniform_buffer_1,
+      &light_uniform_buffer,
       4 * 4 * 0,
-      bytemuck::cast_slice(light_position),
-    );
-    init.queue.write_buffer(
-      &light_uniform_buffer_1,
-      4 * 4 * 1,
       bytemuck::cast_slice(eye_position),
     );
     init.queue.write_buffer(
-      &light_uniform_buffer_1,
-      4 * 4 * 2,
+      &light_uniform_buffer,
+      4 * 4 * 1,
       bytemuck::cast_slice(initial.specular_color.as_ref()),
     );
     init.queue.write_buffer(
-      &light_uniform_buffer_1,
-      4 * 4 * 3,
+      &light_uniform_buffer,
+      4 * 4 * 2,
       bytemuck::cast_slice(initial.object_color.as_ref()),
     );
 
-    let light_uniform_buffer_2 =
-      init.device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("Light Uniform Buffer 2 (for Wireframe)"),
-        size: (mem::size_of::<[f32; 4]>() * 4) as wgpu::BufferAddress,
+    let edge_uniforms =
+      EdgeUniforms::new(initial.wireframe_color, initial.plot_mode, EDGE_WIDTH);
+    let edge_uniform_buffer =
+      init.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Edge Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[edge_uniforms]),
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
       });
-    init.queue.write_buffer(
-      &light_uniform_buffer_2,
-      4 * 4 * 0,
-      bytemuck::cast_slice(light_position),
-    );
-    init.queue.write_buffer(
-      &light_uniform_buffer_2,
-      4 * 4 * 1,
-      bytemuck::cast_slice(eye_position),
-    );
-    init.queue.write_buffer(
-      &light_uniform_buffer_2,
-      4 * 4 * 2,
-      bytemuck::cast_slice(initial.specular_color.as_ref()),
-    );
-    init.queue.write_buffer(
-      &light_uniform_buffer_2,
-      4 * 4 * 3,
-      bytemuck::cast_slice(initial.wireframe_color.as_ref()),
-    );
 
     let material_uniform_buffer =
       init.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Material Uniform Buffer"),
-        contents: bytemuck::cast_slice(initial.material.as_array().as_ref()),
+        contents: bytemuck::cast_slice(&[initial.material]),
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
       });
 
-    let (vert_bind_group_layout_1, vert_bind_group_1) =
-      ws::create_uniform_bind_group(
-        &init.device,
-        vec![wgpu::ShaderStages::VERTEX],
-        &[matrix_uniform_buffer.as_entire_binding()],
-      );
-    let (vert_bind_group_layout_2, vert_bind_group_2) =
+    let (vert_bind_group_layout, vert_bind_group) =
       ws::create_uniform_bind_group(
         &init.device,
         vec![wgpu::ShaderStages::VERTEX],
         &[matrix_uniform_buffer.as_entire_binding()],
       );
 
-    let (frag_bind_group_layout_1, frag_bind_group_1) =
+    let (frag_bind_group_layout, frag_bind_group) =
       ws::create_uniform_bind_group(
         &init.device,
-        vec![wgpu::ShaderStages::FRAGMENT, wgpu::ShaderStages::FRAGMENT],
+        vec![wgpu::ShaderStages::FRAGMENT; 3],
         &[
-          light_uniform_buffer_1.as_entire_binding(),
+          light_uniform_buffer.as_entire_binding(),
           material_uniform_buffer.as_entire_binding(),
+          edge_uniform_buffer.as_entire_binding(),
         ],
       );
-    let (frag_bind_group_layout_2, frag_bind_group_2) =
-      ws::create_uniform_bind_group(
+
+    let (point_lights_buffer, light_count_buffer) =
+      light::upload_point_lights(&init.device, &initial.point_lights);
+    let point_lights_bind_group_layout =
+      util::create_bind_group_layout_for_buffer(
         &init.device,
-        vec![wgpu::ShaderStages::FRAGMENT, wgpu::ShaderStages::FRAGMENT],
         &[
-          light_uniform_buffer_2.as_entire_binding(),
-          material_uniform_buffer.as_entire_binding(),
+          wgpu::BufferBindingType::Storage { read_only: true },
+          wgpu::BufferBindingType::Uniform,
         ],
+        &[wgpu::ShaderStages::FRAGMENT, wgpu::ShaderStages::FRAGMENT],
       );
+    let point_lights_bind_group = util::create_bind_group(
+      &init.device,
+      &point_lights_bind_group_layout,
+      &[
+        point_lights_buffer.as_entire_binding(),
+        light_count_buffer.as_entire_binding(),
+      ],
+    );
 
-    let vertex_buffer_layout_1 = wgpu::VertexBufferLayout {
-      array_stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
-      step_mode: wgpu::VertexStepMode::Vertex,
-      attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3],
-    };
-    let pipeline_layout_1 =
+    let pipeline_layout =
       init.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("Render Pipeline Layout 1"),
+        label: Some("Render Pipeline Layout"),
         bind_group_layouts: &[
-          &vert_bind_group_layout_1,
-          &frag_bind_group_layout_1,
+          &vert_bind_group_layout,
+          &frag_bind_group_layout,
+          &point_lights_bind_group_layout,
         ],
         push_constant_ranges: &[],
       });
-    let mut ppl_1 = ws::RenderSet {
+    let mut ppl = ws::RenderSet {
       vs_shader: Some(&vs_shader),
       fs_shader: Some(&fs_shader),
-      pipeline_layout: Some(&pipeline_layout_1),
-      vertex_buffer_layout: &[vertex_buffer_layout_1],
+      pipeline_layout: Some(&pipeline_layout),
+      vertex_buffer_layout: &[BarycentricVertex::vertex_buffer_layout()],
       ..Default::default()
     };
-    let pipeline_1 = ppl_1.new(&init);
+    let pipeline = ppl.new(&init);
+
+    // 法線の確認用：同じ頂点バッファと行列のバインドグループで描ける
+    let normals_to_color = NormalsToColor::new(
+      &init.device,
+      init.config.format,
+      init.sample_count,
+      BarycentricVertex::vertex_buffer_layout(),
+      &vert_bind_group_layout,
+    );
 
-    let vertex_buffer_layout_2 = wgpu::VertexBufferLayout {
-      array_stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
-      step_mode: wgpu::VertexStepMode::Vertex,
-      attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3],
-    };
-    let pipeline_layout_2 =
-      init.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("Render Pipeline Layout 2 (for Wireframe)"),
-        bind_group_layouts: &[
-          &vert_bind_group_layout_2,
-          &frag_bind_group_layout_2,
-        ],
-        push_constant_ranges: &[],
-      });
-    let mut ppl_2 = ws::RenderSet {
-      topology: wgpu::PrimitiveTopology::LineList,
-      vs_shader: Some(&vs_shader),
-      fs_shader: Some(&fs_shader),
-      pipeline_layout: Some(&pipeline_layout_2),
-      vertex_buffer_layout: &[vertex_buffer_layout_2],
-      ..Default::default()
-    };
-    let pipeline_2 = ppl_2.new(&init);
+    // 軸などのデバッグ用の線も、同じ行列のバインドグループで描ける
+    let line_renderer = LineRenderer::new(
+      &init.device,
+      init.config.format,
+      init.sample_count,
+      &vert_bind_group_layout,
+    );
 
     let msaa_texture_view = ws::create_msaa_texture_view(&init);
     let depth_texture_view = ws::create_depth_view(&init);
 
+    // near/far は wt::create_projection_mat と同じ 0.1/1000 なので既定値のままでよい
+    let mut depth_to_color =
+      DepthToColor::new(&init.device, init.config.format, init.sample_count);
+    depth_to_color.set_max_distance(&init.queue, DEPTH_VIEW_MAX_DISTANCE);
+    depth_to_color.set_input(&init.device, &depth_texture_view);
+
     let vertex_buffer =
       init.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Vertex Buffer"),
@@ -312,50 +350,52 @@ impl<'a> Render for State<'a> {
         usage: wgpu::BufferUsages::VERTEX,
       });
 
-    let index_buffer_1 =
+    let index_buffer =
       init.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Index Buffer"),
-        contents: bytemuck::cast_slice(&inputs.index_data_1),
-        usage: wgpu::BufferUsages::INDEX,
-      });
-    let index_buffer_2 =
-      init.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Index Buffer 2 (for Wireframe)"),
-        contents: bytemuck::cast_slice(&inputs.index_data_2),
+        contents: bytemuck::cast_slice(&inputs.index_data),
         usage: wgpu::BufferUsages::INDEX,
       });
 
     Self {
       init,
-      pipelines: vec![pipeline_1, pipeline_2],
+      pipeline,
       vertex_buffer,
-      index_buffers: vec![index_buffer_1, index_buffer_2],
+      index_buffer,
       uniform_bind_groups: vec![
-        vert_bind_group_1,
-        frag_bind_group_1,
-        vert_bind_group_2,
-        frag_bind_group_2,
+        vert_bind_group,
+        frag_bind_group,
+        point_lights_bind_group,
       ],
       uniform_buffers: vec![
         matrix_uniform_buffer,
-        light_uniform_buffer_1,
+        light_uniform_buffer,
         material_uniform_buffer,
-        light_uniform_buffer_2,
+        edge_uniform_buffer,
+        point_lights_buffer,
+        light_count_buffer,
       ],
-      view_mat,
+      camera: OrbitCamera::new(
+        initial.camera_position,
+        initial.look_direction,
+        initial.up_direction,
+      ),
       project_mat,
       msaa_texture_view,
       depth_texture_view,
-      indices_lens: vec![
-        inputs.index_data_1.len() as u32,
-        inputs.index_data_2.len() as u32,
-      ],
+      depth_to_color,
+      show_depth: false,
+      normals_to_color,
+      show_normals: false,
+      line_renderer,
+      show_axes: true,
+      indices_len: inputs.index_data.len() as u32,
       plot_mode: initial.plot_mode,
       rotation_speed: initial.rotation_speed,
-      ambient: initial.material.ambient_intensity,
-      diffuse: initial.material.diffuse_intensity,
-      specular: initial.material.specular_intensity,
-      shininess: initial.material.specular_shininess,
+      key_bindings: initial.key_bindings.clone(),
+      material: initial.material,
+      #[cfg(feature = "ui")]
+      egui_layer,
     }
   }
 
@@ -374,6 +414,9 @@ impl<'a> Render for State<'a> {
         wt::create_projection_mat(size.width as f32 / size.height as f32, true);
 
       self.depth_texture_view = ws::create_depth_view(&self.init);
+      self
+        .depth_to_color
+        .set_input(&self.init.device, &self.depth_texture_view);
 
       if self.init.sample_count > 1 {
         self.msaa_texture_view = ws::create_msaa_texture_view(&self.init);
@@ -382,99 +425,97 @@ impl<'a> Render for State<'a> {
   }
 
   fn process_event(&mut self, event: &WindowEvent) -> bool {
-    match event {
-      WindowEvent::KeyboardInput {
-        event:
-          KeyEvent {
-            physical_key,
-            state: ElementState::Pressed,
-            ..
-          },
-        ..
-      } => match physical_key {
-        PhysicalKey::Code(KeyCode::ControlLeft) => {
-          let new_color: [f32; 3] =
-            [rand::random(), rand::random(), rand::random()];
-          self.change_shape_color(new_color);
-          true
-        }
-        PhysicalKey::Code(KeyCode::AltLeft) => {
-          let new_color: [f32; 3] =
-            [rand::random(), rand::random(), rand::random()];
-          self.change_wireframe_color(new_color);
-          true
-        }
-        PhysicalKey::Code(KeyCode::Space) => {
-          self.plot_mode = self.plot_mode.next();
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyQ) => {
-          self.ambient += 0.01;
-          println!("ambient intensity = {}", self.ambient);
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyA) => {
-          self.ambient -= 0.05;
-          if self.ambient < 0. {
-            self.ambient = 0.;
-          }
-          println!("ambient intensity = {}", self.ambient);
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyW) => {
-          self.diffuse += 0.05;
-          println!("diffuse intensity = {}", self.diffuse);
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyS) => {
-          self.diffuse -= 0.05;
-          if self.diffuse < 0. {
-            self.diffuse = 0.;
-          }
-          println!("diffuse intensity = {}", self.diffuse);
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyE) => {
-          self.specular += 0.05;
-          println!("specular intensity = {}", self.specular);
-          true
-        }
-        PhysicalKey::Code(KeyCode::KeyD) => {
-          self.specular -= 0.05;
-          if self.specular < 0. {
-            self.specular = 0.;
-          }
-          println!("specular intensity = {}", self.specular);
-          true
+    // スライダーを操作している間は、カメラやキー操作に渡さない
+    #[cfg(feature = "ui")]
+    if self.egui_layer.on_window_event(event) {
+      return true;
+    }
+
+    let action = match self.key_bindings.process_event(event) {
+      Some(action) => action,
+      None => return self.camera.process_event(event),
+    };
+
+    match action {
+      Action::RandomizeShapeColor => {
+        let new_color: [f32; 3] =
+          [rand::random(), rand::random(), rand::random()];
+        self.change_shape_color(new_color);
+      }
+      Action::RandomizeWireframeColor => {
+        let new_color: [f32; 3] =
+          [rand::random(), rand::random(), rand::random()];
+        self.change_wireframe_color(new_color);
+      }
+      Action::NextPlotMode => {
+        self.plot_mode = self.plot_mode.next();
+        self.change_plot_mode(self.plot_mode);
+      }
+      Action::IncreaseAmbient => {
+        self.material.ambient += 0.01;
+        log::info!("ambient intensity = {}", self.material.ambient);
+      }
+      Action::DecreaseAmbient => {
+        self.material.ambient -= 0.05;
+        if self.material.ambient < 0. {
+          self.material.ambient = 0.;
         }
-        PhysicalKey::Code(KeyCode::KeyR) => {
-          self.shininess += 5.;
-          println!("specular shininess = {}", self.shininess);
-          true
+        log::info!("ambient intensity = {}", self.material.ambient);
+      }
+      Action::IncreaseDiffuse => {
+        self.material.diffuse += 0.05;
+        log::info!("diffuse intensity = {}", self.material.diffuse);
+      }
+      Action::DecreaseDiffuse => {
+        self.material.diffuse -= 0.05;
+        if self.material.diffuse < 0. {
+          self.material.diffuse = 0.;
         }
-        PhysicalKey::Code(KeyCode::KeyF) => {
-          self.shininess -= 5.;
-          if self.shininess < 0. {
-            self.shininess = 0.;
-          }
-          println!("specular shininess = {}", self.shininess);
-          true
+        log::info!("diffuse intensity = {}", self.material.diffuse);
+      }
+      Action::IncreaseSpecular => {
+        self.material.specular += 0.05;
+        log::info!("specular intensity = {}", self.material.specular);
+      }
+      Action::DecreaseSpecular => {
+        self.material.specular -= 0.05;
+        if self.material.specular < 0. {
+          self.material.specular = 0.;
         }
-        PhysicalKey::Code(KeyCode::KeyT) => {
-          self.rotation_speed += 0.1;
-          true
+        log::info!("specular intensity = {}", self.material.specular);
+      }
+      Action::IncreaseShininess => {
+        self.material.shininess += 5.;
+        log::info!("specular shininess = {}", self.material.shininess);
+      }
+      Action::DecreaseShininess => {
+        self.material.shininess -= 5.;
+        if self.material.shininess < 0. {
+          self.material.shininess = 0.;
         }
-        PhysicalKey::Code(KeyCode::KeyG) => {
-          self.rotation_speed -= 0.1;
-          if self.rotation_speed < 0. {
-            self.rotation_speed = 0.;
-          }
-          true
+        log::info!("specular shininess = {}", self.material.shininess);
+      }
+      Action::IncreaseRotationSpeed => {
+        self.rotation_speed += 0.1;
+      }
+      Action::DecreaseRotationSpeed => {
+        self.rotation_speed -= 0.1;
+        if self.rotation_speed < 0. {
+          self.rotation_speed = 0.;
         }
-        _ => false,
-      },
-      _ => false,
+      }
+      Action::ToggleDepthView => {
+        self.show_depth = !self.show_depth;
+      }
+      Action::ToggleNormalView => {
+        self.show_normals = !self.show_normals;
+      }
+      Action::ToggleAxes => {
+        self.show_axes = !self.show_axes;
+      }
     }
+
+    true
   }
 
   fn update(&mut self, dt: time::Duration) {
@@ -482,7 +523,7 @@ impl<'a> Render for State<'a> {
 
     let model_mat =
       wt::create_model_mat_with_rotation([dt.sin(), dt.cos(), 0.]);
-    let view_proj_mat = self.project_mat * self.view_mat;
+    let view_proj_mat = self.project_mat * self.camera.view_mat();
 
     let normal_mat = (model_mat.invert().unwrap()).transpose();
 
@@ -506,12 +547,26 @@ impl<'a> Render for State<'a> {
       bytemuck::cast_slice(normal_ref),
     );
 
-    let material = [self.ambient, self.diffuse, self.specular, self.shininess];
+    // 視点がマウスで動くので、スペキュラー計算に使う視点位置も更新する
+    let eye_position: [f32; 3] = self.camera.eye().into();
+    self.init.queue.write_buffer(
+      &self.uniform_buffers[1],
+      4 * 4 * 0,
+      bytemuck::cast_slice(&eye_position),
+    );
+
+    let material = [self.material];
     self.init.queue.write_buffer(
       &self.uniform_buffers[2],
       0,
       bytemuck::cast_slice(&material),
     );
+
+    // 線は毎フレーム積み直す（積まなければ何も描かれない）
+    if self.show_axes {
+      self.line_renderer.add_axes(Matrix4::from_scale(AXES_LENGTH));
+    }
+    self.line_renderer.flush(&self.init.device, &self.init.queue);
   }
 
   fn draw(&mut self) -> Result<(), wgpu::SurfaceError> {
@@ -533,8 +588,12 @@ impl<'a> Render for State<'a> {
     } else {
       msaa_attach
     };
-    let depth_attachment =
-      ws::create_depth_stencil_attachment(&self.depth_texture_view);
+    // 深度を表示するときは、パスの後も深度を残しておく
+    let depth_attachment = if self.show_depth {
+      util::create_stored_depth_stencil_attachment(&self.depth_texture_view)
+    } else {
+      ws::create_depth_stencil_attachment(&self.depth_texture_view)
+    };
 
     let mut render_pass =
       encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -545,17 +604,46 @@ impl<'a> Render for State<'a> {
         occlusion_query_set: None,
       });
 
-    match self.plot_mode {
-      PlotMode::Shape => self.draw_shape(&mut render_pass),
-      PlotMode::Wireframe => self.draw_wireframe(&mut render_pass),
-      PlotMode::Both => {
-        self.draw_shape(&mut render_pass);
-        self.draw_wireframe(&mut render_pass);
-      }
+    // 面と辺のどちらを描くかはユニフォームで切り替えるので、描画は1回で済む
+    if self.show_normals {
+      // 法線の表示では、ワールド座標の法線を色にして面だけを描く
+      self.normals_to_color.set_pipeline(&mut render_pass);
+    } else {
+      render_pass.set_pipeline(&self.pipeline);
+      render_pass.set_bind_group(1, &self.uniform_bind_groups[1], &[]);
+      render_pass.set_bind_group(2, &self.uniform_bind_groups[2], &[]);
     }
+    render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+    render_pass
+      .set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+    render_pass.set_bind_group(0, &self.uniform_bind_groups[0], &[]);
+    render_pass.draw_indexed(0..self.indices_len, 0, 0..1);
+
+    self.line_renderer.draw(&mut render_pass, &self.uniform_bind_groups[0]);
 
     drop(render_pass);
 
+    if self.show_depth {
+      self.depth_to_color.draw(&mut encoder, &view);
+    }
+
+    // マテリアルのスライダーを重ねる（値は次の update でユニフォームに書き込まれる）
+    #[cfg(feature = "ui")]
+    {
+      let material = &mut self.material;
+      self.egui_layer.draw(
+        &self.init.device,
+        &self.init.queue,
+        &mut encoder,
+        &view,
+        |ctx| {
+          egui::Window::new("Material").show(ctx, |ui| {
+            material_sliders(ui, material);
+          });
+        },
+      );
+    }
+
     self.init.queue.submit(iter::once(encoder.finish()));
     frame.present();
 
@@ -567,7 +655,7 @@ impl State<'_> {
   fn change_shape_color(&self, color: [f32; 3]) {
     self.init.queue.write_buffer(
       &self.uniform_buffers[1],
-      4 * 4 * 2,
+      4 * 4 * 1,
       bytemuck::cast_slice(&color),
     );
   }
@@ -575,70 +663,41 @@ impl State<'_> {
   fn change_wireframe_color(&self, color: [f32; 3]) {
     self.init.queue.write_buffer(
       &self.uniform_buffers[3],
-      4 * 4 * 2,
+      0,
       bytemuck::cast_slice(&color),
     );
   }
 
-  fn draw_shape(&self, render_pass: &mut wgpu::RenderPass) {
-    render_pass.set_pipeline(&self.pipelines[0]);
-    render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-    render_pass.set_index_buffer(
-      self.index_buffers[0].slice(..),
-      wgpu::IndexFormat::Uint16,
-    );
-    render_pass.set_bind_group(0, &self.uniform_bind_groups[0], &[]);
-    render_pass.set_bind_group(1, &self.uniform_bind_groups[1], &[]);
-    render_pass.draw_indexed(0..self.indices_lens[0], 0, 0..1);
-  }
-
-  fn draw_wireframe(&self, render_pass: &mut wgpu::RenderPass) {
-    render_pass.set_pipeline(&self.pipelines[1]);
-    render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-    render_pass.set_index_buffer(
-      self.index_buffers[1].slice(..),
-      wgpu::IndexFormat::Uint16,
+  fn change_plot_mode(&self, plot_mode: PlotMode) {
+    let (show_edges, show_faces) = plot_mode.flags();
+    self.init.queue.write_buffer(
+      &self.uniform_buffers[3],
+      4 * 3,
+      bytemuck::cast_slice(&[show_edges, show_faces]),
     );
-    render_pass.set_bind_group(0, &self.uniform_bind_groups[2], &[]);
-    render_pass.set_bind_group(1, &self.uniform_bind_groups[3], &[]);
-    render_pass.draw_indexed(0..self.indices_lens[1], 0, 0..1);
   }
 }
 
+/// Mirrors `EdgeUniforms` in `blinn-phong-frag.wgsl`.
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Pod, Zeroable)]
-struct IMaterial {
-  ambient_intensity: f32,
-  diffuse_intensity: f32,
-  specular_intensity: f32,
-  specular_shininess: f32,
+struct EdgeUniforms {
+  color: [f32; 3],
+  show_edges: u32,
+  show_faces: u32,
+  width: f32,
+  _padding: [f32; 2],
 }
 
-impl Default for IMaterial {
-  fn default() -> Self {
+impl EdgeUniforms {
+  fn new(color: [f32; 3], plot_mode: PlotMode, width: f32) -> Self {
+    let (show_edges, show_faces) = plot_mode.flags();
     Self {
-      ambient_intensity: 0.2,
-      diffuse_intensity: 0.8,
-      specular_intensity: 0.4,
-      specular_shininess: 30.,
+      color,
+      show_edges,
+      show_faces,
+      width,
+      _padding: [0.; 2],
     }
   }
 }
-
-impl IMaterial {
-  fn as_array(&self) -> [f32; 4] {
-    [
-      self.ambient_intensity,
-      self.diffuse_intensity,
-      self.specular_intensity,
-      self.specular_shininess,
-    ]
-  }
-}
-
-#[repr(C)]
-#[derive(Copy, Clone, Debug, Pod, Zeroable)]
-pub struct Vertex {
-  pub position: [f32; 3],
-  pub normal: [f32; 3],
-}
@@ -9,12 +9,14 @@ struct Uniforms {
 struct Input {
   @location(0) pos: vec3f,
   @location(1) normal: vec3f,
+  @location(2) barycentric: vec3f,
 }
 
 struct Output {
   @builtin(position) position: vec4f,
   @location(0) v_position: vec4f,
   @location(1) v_normal: vec4f,
+  @location(2) v_barycentric: vec3f,
 }
 
 @vertex
@@ -24,5 +26,6 @@ fn vs_main(in: Input) -> Output {
   output.v_position = m_position;
   output.v_normal = unif.normal_mat * vec4(in.normal, 1.0);
   output.position = unif.view_project_mat * m_position;
+  output.v_barycentric = in.barycentric;
   return output;
 }
\ No newline at end of file
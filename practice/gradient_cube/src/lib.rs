@@ -0,0 +1,260 @@
+use std::error::Error;
+
+use bytemuck::cast_slice;
+use cgmath::{Matrix, Matrix4, Point3, SquareMatrix, Vector3};
+use wgpu::util::DeviceExt;
+use wgsim::app::App;
+use wgsim::camera::OrbitCamera;
+use wgsim::ctx::{DrawingContext, Size};
+use wgsim::geometry::generator as ge;
+use wgsim::geometry::{self, ColoredVertex, Mesh};
+use wgsim::matrix;
+use wgsim::ppl::RenderPipelineBuilder;
+use wgsim::render::{Render, RenderTarget};
+use wgsim::util;
+use winit::event::MouseButton;
+
+pub fn run() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = Initial {
+    camera_position: Point3::new(3., 1.5, 3.),
+    look_direction: Point3::new(0., 0., 0.),
+    up_direction: Vector3::unit_y(),
+    light_direction: [-0.5, -1., -0.5],
+    rotation_speed: 0.5,
+    bottom_color: [0.1, 0.3, 0.9, 1.],
+    top_color: [1., 0.6, 0.2, 1.],
+  };
+
+  let mut app: App<State> = App::new("gradient_cube", initial).with_msaa();
+  app.run()?;
+
+  Ok(())
+}
+
+struct Initial {
+  pub camera_position: Point3<f32>,
+  pub look_direction: Point3<f32>,
+  pub up_direction: Vector3<f32>,
+  pub light_direction: [f32; 3],
+  pub rotation_speed: f32,
+  pub bottom_color: [f32; 4],
+  pub top_color: [f32; 4],
+}
+
+struct State {
+  pipeline: wgpu::RenderPipeline,
+  bind_group: wgpu::BindGroup,
+  uniform_buffer: wgpu::Buffer,
+  vertex_buffer: wgpu::Buffer,
+  index_buffer: wgpu::Buffer,
+  index_count: u32,
+  msaa_texture_view: wgpu::TextureView,
+  depth_texture_view: wgpu::TextureView,
+
+  camera: OrbitCamera,
+  project_mat: Matrix4<f32>,
+  light_direction: [f32; 3],
+  rotation_speed: f32,
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    let shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./shader.wgsl"));
+
+    //
+    // uniform
+    //
+
+    // view-projection, model, normal 行列と、光の向き
+    let uniform_buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Uniform Buffer"),
+      size: (std::mem::size_of::<[f32; 16]>() * 3
+        + std::mem::size_of::<[f32; 4]>()) as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+
+    //
+    // bind group
+    //
+
+    // 色は頂点にあるので、色用のストレージバッファとバインドグループは要らない
+    let bind_group_layout = util::create_bind_group_layout_for_buffer(
+      &ctx.device,
+      &[wgpu::BufferBindingType::Uniform],
+      &[wgpu::ShaderStages::VERTEX_FRAGMENT],
+    );
+    let bind_group = util::create_bind_group(
+      &ctx.device,
+      &bind_group_layout,
+      &[uniform_buffer.as_entire_binding()],
+    );
+
+    //
+    // pipeline
+    //
+
+    let pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Render Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let vertex_buffer_layout = [ColoredVertex::vertex_buffer_layout()];
+
+    let pipeline = RenderPipelineBuilder::new(&ctx)
+      .vs_shader(&shader, "vs_main")
+      .fs_shader(&shader, "fs_main")
+      .pipeline_layout(&pipeline_layout)
+      .vertex_buffer_layout(&vertex_buffer_layout)
+      .enable_depth_stencil(None)
+      .build();
+
+    //
+    // vertex and index buffers
+    //
+
+    // 下から上へのグラデーション
+    let mesh = Mesh::from(ge::create_cube_data(2.));
+    let vertices = geometry::with_gradient(
+      &mesh,
+      [0., 1., 0.],
+      initial.bottom_color,
+      initial.top_color,
+    );
+
+    let vertex_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Vertex Buffer"),
+        contents: cast_slice(&vertices),
+        usage: wgpu::BufferUsages::VERTEX,
+      });
+    let index_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Index Buffer"),
+        contents: cast_slice(&mesh.indices),
+        usage: wgpu::BufferUsages::INDEX,
+      });
+
+    Self {
+      pipeline,
+      bind_group,
+      uniform_buffer,
+      vertex_buffer,
+      index_buffer,
+      index_count: mesh.indices.len() as u32,
+      msaa_texture_view: util::create_msaa_texture_view(&ctx),
+      depth_texture_view: util::create_depth_view(&ctx),
+
+      camera: OrbitCamera::new(
+        initial.camera_position,
+        initial.look_direction,
+        initial.up_direction,
+      ),
+      project_mat: matrix::create_projection_mat(ctx.aspect_ratio(), true),
+      light_direction: initial.light_direction,
+      rotation_speed: initial.rotation_speed,
+    }
+  }
+
+  fn resize(&mut self, ctx: &mut DrawingContext<'_>, size: Size) {
+    if size.width > 0 && size.height > 0 {
+      ctx.resize(size);
+
+      self.project_mat = matrix::create_projection_mat(
+        size.width as f32 / size.height as f32,
+        true,
+      );
+
+      self.depth_texture_view = util::create_depth_view(ctx);
+
+      if ctx.sample_count > 1 {
+        self.msaa_texture_view = util::create_msaa_texture_view(ctx);
+      }
+    }
+  }
+
+  fn on_scroll(&mut self, delta: f32) {
+    self.camera.scroll(delta);
+  }
+
+  fn on_mouse_drag(&mut self, dx: f32, dy: f32, button: MouseButton) {
+    if button == MouseButton::Left {
+      self.camera.drag(dx, dy);
+    }
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, dt: std::time::Duration) {
+    let t = self.rotation_speed * dt.as_secs_f32();
+
+    let model_mat = matrix::create_model_mat_with_rotation([0., t, 0.]);
+    let normal_mat = (model_mat.invert().unwrap()).transpose();
+    let vp_mat = self.project_mat * self.camera.view_mat();
+    let [lx, ly, lz] = self.light_direction;
+
+    let vp_ref: &[f32; 16] = vp_mat.as_ref();
+    let model_ref: &[f32; 16] = model_mat.as_ref();
+    let normal_ref: &[f32; 16] = normal_mat.as_ref();
+
+    let mut data: Vec<f32> = vec![];
+    data.extend(vp_ref);
+    data.extend(model_ref);
+    data.extend(normal_ref);
+    data.extend([lx, ly, lz, 0.]);
+
+    ctx.queue.write_buffer(&self.uniform_buffer, 0, cast_slice(&data));
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: RenderTarget,
+    sample_count: u32,
+  ) -> Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+    let (view, frame) = match target {
+      RenderTarget::Surface(surface) => {
+        let frame = surface.get_current_texture()?;
+        let view =
+          frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, Some(frame))
+      }
+      RenderTarget::Texture(texture) => {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, None)
+      }
+    };
+
+    let color_attachment = if sample_count == 1 {
+      util::create_color_attachment(&view)
+    } else {
+      util::create_msaa_color_attachment(&view, &self.msaa_texture_view)
+    };
+    let depth_attachment =
+      util::create_depth_stencil_attachment(&self.depth_texture_view);
+
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render Pass"),
+        color_attachments: &[Some(color_attachment)],
+        depth_stencil_attachment: Some(depth_attachment),
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, &self.bind_group, &[]);
+    render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+    render_pass
+      .set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+    render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+
+    drop(render_pass);
+
+    Ok(frame)
+  }
+}
@@ -0,0 +1,43 @@
+struct Uniforms {
+  view_project_mat: mat4x4f,
+  model_mat: mat4x4f,
+  normal_mat: mat4x4f,
+  light_direction: vec4f,
+}
+
+@group(0) @binding(0) var<uniform> unif: Uniforms;
+
+struct Input {
+  @location(0) position: vec3f,
+  @location(1) normal: vec3f,
+  @location(2) color: vec4f,
+}
+
+struct Output {
+  @builtin(position) position: vec4f,
+  @location(0) v_normal: vec4f,
+  @location(1) v_color: vec4f,
+}
+
+@vertex
+fn vs_main(in: Input) -> Output {
+  var output: Output;
+
+  output.v_normal = unif.normal_mat * vec4f(in.normal, 0.0);
+  // 頂点の色は三角形の中で補間される
+  output.v_color = in.color;
+  output.position = unif.view_project_mat * unif.model_mat * vec4f(in.position, 1.0);
+  return output;
+}
+
+const AMBIENT = 0.3;
+const DIFFUSE = 0.7;
+
+@fragment
+fn fs_main(in: Output) -> @location(0) vec4f {
+  let N = normalize(in.v_normal.xyz);
+  let L = normalize(-unif.light_direction.xyz);
+  let diffuse = DIFFUSE * max(dot(N, L), 0.0);
+
+  return vec4f(in.v_color.rgb * (AMBIENT + diffuse), in.v_color.a);
+}
@@ -0,0 +1,26 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  IncreaseBudget,
+  DecreaseBudget,
+  TogglePoints,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::IncreaseBudget => "Raise triangle budget",
+      Action::DecreaseBudget => "Lower triangle budget",
+      Action::TogglePoints => "Toggle point cloud",
+    }
+  }
+}
+
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new()
+    .bind(KeyCode::ArrowUp, Action::IncreaseBudget)
+    .bind(KeyCode::ArrowDown, Action::DecreaseBudget)
+    .bind(KeyCode::KeyP, Action::TogglePoints)
+}
@@ -0,0 +1,300 @@
+mod action;
+
+use std::error::Error;
+
+use action::Action;
+use bytemuck::cast_slice;
+use cgmath::{Matrix, Matrix4, Point3, SquareMatrix, Vector3};
+use wgsim::app::App;
+use wgsim::camera::OrbitCamera;
+use wgsim::ctx::{DrawingContext, Size};
+use wgsim::geometry::generator as ge;
+use wgsim::geometry::{upload_mesh, IndexedMesh, Mesh};
+use wgsim::input::KeyBindings;
+use wgsim::matrix;
+use wgsim::ppl::RenderPipelineBuilder;
+use wgsim::render::{Render, RenderTarget};
+use wgsim::util;
+use winit::event::WindowEvent;
+
+// 粗い球（6 リング × 8 セグメント = 96 三角形）
+const SPHERE_RINGS: u16 = 6;
+const SPHERE_SEGMENTS: u16 = 8;
+
+// 1 回の細分割で三角形は 4 倍になる
+const BUDGET_STEP: usize = 4;
+const MAX_BUDGET: usize = 400_000;
+
+pub fn run() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = Initial {
+    camera_position: Point3::new(3., 1.5, 3.),
+    look_direction: Point3::new(0., 0., 0.),
+    up_direction: Vector3::unit_y(),
+    light_direction: [-0.5, -1., -0.5],
+    mesh: ge::create_sphere_data(1.5, SPHERE_RINGS, SPHERE_SEGMENTS).into(),
+    key_bindings: action::default_key_bindings(),
+  };
+  initial.key_bindings.print_help();
+
+  let mut app: App<State> = App::new("sphere_subdivision", initial).with_msaa();
+  app.run()?;
+
+  Ok(())
+}
+
+struct Initial {
+  pub camera_position: Point3<f32>,
+  pub look_direction: Point3<f32>,
+  pub up_direction: Vector3<f32>,
+  pub light_direction: [f32; 3],
+
+  pub mesh: Mesh,
+  pub key_bindings: KeyBindings<Action>,
+}
+
+struct State {
+  pipeline: wgpu::RenderPipeline,
+  points_pipeline: wgpu::RenderPipeline,
+  bind_group: wgpu::BindGroup,
+  uniform_buffer: wgpu::Buffer,
+
+  msaa_texture_view: wgpu::TextureView,
+  depth_texture_view: wgpu::TextureView,
+
+  source_mesh: Mesh,
+  mesh: IndexedMesh,
+  triangle_budget: usize,
+  mesh_updated: bool,
+  show_points: bool,
+
+  camera: OrbitCamera,
+  project_mat: Matrix4<f32>,
+  light_direction: [f32; 3],
+
+  key_bindings: KeyBindings<Action>,
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    let shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./shader.wgsl"));
+
+    //
+    // uniform
+    //
+
+    // view-projection, model, normal 行列と、光の向き・視点
+    let uniform_buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Uniform Buffer"),
+      size: (std::mem::size_of::<[f32; 16]>() * 3
+        + std::mem::size_of::<[f32; 4]>() * 2)
+        as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+
+    let bind_group_layout = util::create_bind_group_layout_for_buffer(
+      &ctx.device,
+      &[wgpu::BufferBindingType::Uniform],
+      &[wgpu::ShaderStages::VERTEX_FRAGMENT],
+    );
+
+    let bind_group = util::create_bind_group(
+      &ctx.device,
+      &bind_group_layout,
+      &[uniform_buffer.as_entire_binding()],
+    );
+
+    //
+    // pipeline
+    //
+
+    let pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Render Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let vertex_buffer_layout = [IndexedMesh::vertex_buffer_layout()];
+
+    let pipeline = RenderPipelineBuilder::new(&ctx)
+      .vs_shader(&shader, "vs_main")
+      .fs_shader(&shader, "fs_main")
+      .pipeline_layout(&pipeline_layout)
+      .vertex_buffer_layout(&vertex_buffer_layout)
+      .enable_depth_stencil(None)
+      .build();
+
+    // インデックスをそのまま点として描くと、細分割で増えた頂点が見える
+    let points_pipeline = RenderPipelineBuilder::new(&ctx)
+      .vs_shader(&shader, "vs_main")
+      .fs_shader(&shader, "fs_main")
+      .pipeline_layout(&pipeline_layout)
+      .vertex_buffer_layout(&vertex_buffer_layout)
+      .enable_depth_stencil(None)
+      .topology(wgpu::PrimitiveTopology::PointList)
+      .build();
+
+    //
+    // mesh
+    //
+
+    // 予算を元の三角形数にしておくと、細分割せずにそのまま転送される
+    let triangle_budget = initial.mesh.triangle_count();
+    let mesh = upload_mesh(&ctx.device, &initial.mesh, triangle_budget);
+
+    Self {
+      pipeline,
+      points_pipeline,
+      bind_group,
+      uniform_buffer,
+      msaa_texture_view: util::create_msaa_texture_view(&ctx),
+      depth_texture_view: util::create_depth_view(&ctx),
+      source_mesh: initial.mesh.clone(),
+      mesh,
+      triangle_budget,
+      mesh_updated: false,
+      show_points: false,
+      camera: OrbitCamera::new(
+        initial.camera_position,
+        initial.look_direction,
+        initial.up_direction,
+      ),
+      project_mat: matrix::create_projection_mat(ctx.aspect_ratio(), true),
+      light_direction: initial.light_direction,
+      key_bindings: initial.key_bindings.clone(),
+    }
+  }
+
+  fn resize(&mut self, ctx: &mut DrawingContext<'_>, size: Size) {
+    if size.width > 0 && size.height > 0 {
+      ctx.resize(size.into());
+
+      self.project_mat = matrix::create_projection_mat(
+        size.width as f32 / size.height as f32,
+        true,
+      );
+
+      self.depth_texture_view = util::create_depth_view(ctx);
+
+      if ctx.sample_count > 1 {
+        self.msaa_texture_view = util::create_msaa_texture_view(&ctx);
+      }
+    }
+  }
+
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    let action = match self.key_bindings.process_event(event) {
+      Some(action) => action,
+      None => return self.camera.process_event(event),
+    };
+
+    let min_budget = self.source_mesh.triangle_count();
+    match action {
+      Action::TogglePoints => {
+        self.show_points = !self.show_points;
+        return true;
+      }
+      Action::IncreaseBudget => {
+        self.triangle_budget =
+          MAX_BUDGET.min(self.triangle_budget * BUDGET_STEP);
+      }
+      Action::DecreaseBudget => {
+        self.triangle_budget =
+          min_budget.max(self.triangle_budget / BUDGET_STEP);
+      }
+    }
+    self.mesh_updated = true;
+
+    true
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, _dt: std::time::Duration) {
+    //
+    // 予算が変わったら頂点・インデックスバッファを作り直す
+    //
+
+    if self.mesh_updated {
+      self.mesh =
+        upload_mesh(&ctx.device, &self.source_mesh, self.triangle_budget);
+      log::info!(
+        "triangle budget: {}, triangles: {}",
+        self.triangle_budget,
+        self.mesh.triangle_count()
+      );
+      self.mesh_updated = false;
+    }
+
+    let model_mat = matrix::create_initial_model_mat();
+    let normal_mat = (model_mat.invert().unwrap()).transpose();
+    let vp_mat = self.project_mat * self.camera.view_mat();
+    let eye = self.camera.eye();
+    let [lx, ly, lz] = self.light_direction;
+
+    let vp_ref: &[f32; 16] = vp_mat.as_ref();
+    let model_ref: &[f32; 16] = model_mat.as_ref();
+    let normal_ref: &[f32; 16] = normal_mat.as_ref();
+
+    let mut data: Vec<f32> = vec![];
+    data.extend(vp_ref);
+    data.extend(model_ref);
+    data.extend(normal_ref);
+    data.extend([lx, ly, lz, 0.]);
+    data.extend([eye.x, eye.y, eye.z, 1.]);
+
+    ctx.queue.write_buffer(&self.uniform_buffer, 0, cast_slice(&data));
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: RenderTarget,
+    sample_count: u32,
+  ) -> anyhow::Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+    let (view, frame) = match target {
+      RenderTarget::Surface(surface) => {
+        let frame = surface.get_current_texture()?;
+        let view =
+          frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, Some(frame))
+      }
+      RenderTarget::Texture(texture) => {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, None)
+      }
+    };
+
+    let color_attachment = if sample_count == 1 {
+      util::create_color_attachment(&view)
+    } else {
+      util::create_msaa_color_attachment(&view, &self.msaa_texture_view)
+    };
+    let depth_attachment =
+      util::create_depth_stencil_attachment(&self.depth_texture_view);
+
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render Pass"),
+        color_attachments: &[Some(color_attachment)],
+        depth_stencil_attachment: Some(depth_attachment),
+        ..Default::default()
+      });
+
+    if self.show_points {
+      render_pass.set_pipeline(&self.points_pipeline);
+    } else {
+      render_pass.set_pipeline(&self.pipeline);
+    }
+    render_pass.set_bind_group(0, &self.bind_group, &[]);
+    self.mesh.draw(&mut render_pass, 0..1);
+
+    drop(render_pass);
+
+    Ok(frame)
+  }
+}
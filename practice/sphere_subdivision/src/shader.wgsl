@@ -0,0 +1,51 @@
+struct Uniforms {
+  view_project_mat: mat4x4f,
+  model_mat: mat4x4f,
+  normal_mat: mat4x4f,
+  light_direction: vec4f,
+  eye_position: vec4f,
+}
+
+@group(0) @binding(0) var<uniform> unif: Uniforms;
+
+struct Input {
+  @location(0) position: vec3f,
+  @location(1) normal: vec3f,
+}
+
+struct Output {
+  @builtin(position) position: vec4f,
+  @location(0) v_position: vec4f,
+  @location(1) v_normal: vec4f,
+}
+
+@vertex
+fn vs_main(in: Input) -> Output {
+  var output: Output;
+
+  let m_position = unif.model_mat * vec4f(in.position, 1.0);
+  output.v_position = m_position;
+  output.v_normal = unif.normal_mat * vec4f(in.normal, 0.0);
+  output.position = unif.view_project_mat * m_position;
+  return output;
+}
+
+const OBJECT_COLOR = vec3f(0.855, 0.792, 0.969);
+const AMBIENT = 0.2;
+const DIFFUSE = 0.8;
+const SPECULAR = 0.4;
+const SHININESS = 30.0;
+
+@fragment
+fn fs_main(in: Output) -> @location(0) vec4f {
+  let N = normalize(in.v_normal.xyz);
+  let L = normalize(-unif.light_direction.xyz);
+  let V = normalize(unif.eye_position.xyz - in.v_position.xyz);
+  let H = normalize(L + V);
+
+  let diffuse = DIFFUSE * max(dot(N, L), 0.0);
+  let specular = SPECULAR * pow(max(dot(N, H), 0.0), SHININESS);
+
+  let color = OBJECT_COLOR * (AMBIENT + diffuse) + vec3f(specular);
+  return vec4f(color, 1.0);
+}
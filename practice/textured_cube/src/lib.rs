@@ -0,0 +1,354 @@
+use std::error::Error;
+
+use bytemuck::cast_slice;
+use cgmath::{Matrix, Matrix4, Point3, SquareMatrix, Vector3};
+use image::GenericImageView;
+use wgpu::util::DeviceExt;
+use wgsim::app::App;
+use wgsim::camera::OrbitCamera;
+use wgsim::ctx::{DrawingContext, Size};
+use wgsim::geometry::generator as ge;
+use wgsim::geometry::Cube;
+use wgsim::matrix;
+use wgsim::ppl::RenderPipelineBuilder;
+use wgsim::render::{Render, RenderTarget};
+use wgsim::util::{self, SamplerOptions};
+use winit::event::MouseButton;
+
+pub fn run() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let img_bytes =
+    include_bytes!("../../../assets/img/stained-glass_512x512.png");
+  let image = image::load_from_memory(img_bytes).unwrap();
+
+  let initial = Initial {
+    camera_position: Point3::new(3., 1.5, 3.),
+    look_direction: Point3::new(0., 0., 0.),
+    up_direction: Vector3::unit_y(),
+    light_direction: [-0.5, -1., -0.5],
+    rotation_speed: 0.5,
+    image,
+  };
+
+  let mut app: App<State> = App::new("textured_cube", initial).with_msaa();
+  app.run()?;
+
+  Ok(())
+}
+
+struct Initial {
+  pub camera_position: Point3<f32>,
+  pub look_direction: Point3<f32>,
+  pub up_direction: Vector3<f32>,
+  pub light_direction: [f32; 3],
+  pub rotation_speed: f32,
+
+  pub image: image::DynamicImage,
+}
+
+struct State {
+  pipeline: wgpu::RenderPipeline,
+  bind_group: wgpu::BindGroup,
+  uniform_buffer: wgpu::Buffer,
+
+  vertex_buffer: wgpu::Buffer,
+  index_buffer: wgpu::Buffer,
+  index_count: u32,
+
+  msaa_texture_view: wgpu::TextureView,
+  depth_texture_view: wgpu::TextureView,
+
+  camera: OrbitCamera,
+  project_mat: Matrix4<f32>,
+  light_direction: [f32; 3],
+  rotation_speed: f32,
+}
+
+/// position, normal, uv をまとめた頂点データ
+fn create_vertices() -> (Vec<f32>, Vec<u16>) {
+  let Cube {
+    positions,
+    normals,
+    uvs,
+    indices,
+    ..
+  } = ge::create_cube_data(2.);
+
+  let vertices = (0..positions.len())
+    .flat_map(|i| {
+      let [px, py, pz] = positions[i];
+      let [nx, ny, nz] = normals[i];
+      let [u, v] = uvs[i];
+      [px, py, pz, nx, ny, nz, u, v]
+    })
+    .collect();
+
+  (vertices, indices)
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    let shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./shader.wgsl"));
+
+    //
+    // texture & sampler
+    //
+
+    let (width, height) = initial.image.dimensions();
+    let size = wgpu::Extent3d {
+      width,
+      height,
+      depth_or_array_layers: 1,
+    };
+
+    let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("image texture"),
+      size,
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: wgpu::TextureFormat::Rgba8UnormSrgb,
+      usage: wgpu::TextureUsages::COPY_DST
+        | wgpu::TextureUsages::TEXTURE_BINDING,
+      view_formats: &[],
+    });
+    ctx.queue.write_texture(
+      texture.as_image_copy(),
+      &initial.image.to_rgba8(),
+      wgpu::ImageDataLayout {
+        offset: 0,
+        bytes_per_row: Some(4 * width),
+        rows_per_image: Some(height),
+      },
+      size,
+    );
+    let texture_view =
+      texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let sampler = util::create_sampler(
+      &ctx.device,
+      SamplerOptions {
+        // 斜めから見た面がぼやけないよう、異方性フィルタリングを使う
+        anisotropy_clamp: 16,
+        mipmap_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+      },
+    );
+
+    //
+    // uniform
+    //
+
+    // view-projection, model, normal 行列と、光の向き
+    let uniform_buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Uniform Buffer"),
+      size: (std::mem::size_of::<[f32; 16]>() * 3
+        + std::mem::size_of::<[f32; 4]>()) as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+
+    //
+    // bind group
+    //
+
+    let bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[
+        wgpu::BindingType::Buffer {
+          ty: wgpu::BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        wgpu::BindingType::Texture {
+          sample_type: wgpu::TextureSampleType::Float { filterable: true },
+          view_dimension: wgpu::TextureViewDimension::D2,
+          multisampled: false,
+        },
+        wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+      ],
+      &[
+        wgpu::ShaderStages::VERTEX_FRAGMENT,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::ShaderStages::FRAGMENT,
+      ],
+    );
+
+    let bind_group = util::create_bind_group(
+      &ctx.device,
+      &bind_group_layout,
+      &[
+        uniform_buffer.as_entire_binding(),
+        wgpu::BindingResource::TextureView(&texture_view),
+        wgpu::BindingResource::Sampler(&sampler),
+      ],
+    );
+
+    //
+    // pipeline
+    //
+
+    let pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Render Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let vertex_buffer_layout = [wgpu::VertexBufferLayout {
+      array_stride: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+      step_mode: wgpu::VertexStepMode::Vertex,
+      attributes: &wgpu::vertex_attr_array![
+        0 => Float32x3,
+        1 => Float32x3,
+        2 => Float32x2
+      ],
+    }];
+
+    let pipeline = RenderPipelineBuilder::new(&ctx)
+      .vs_shader(&shader, "vs_main")
+      .fs_shader(&shader, "fs_main")
+      .pipeline_layout(&pipeline_layout)
+      .vertex_buffer_layout(&vertex_buffer_layout)
+      .enable_depth_stencil(None)
+      .build();
+
+    //
+    // vertex and index buffers
+    //
+
+    let (vertices, indices) = create_vertices();
+
+    let vertex_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Vertex Buffer"),
+        contents: cast_slice(&vertices),
+        usage: wgpu::BufferUsages::VERTEX,
+      });
+    let index_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Index Buffer"),
+        contents: cast_slice(&indices),
+        usage: wgpu::BufferUsages::INDEX,
+      });
+
+    Self {
+      pipeline,
+      bind_group,
+      uniform_buffer,
+      vertex_buffer,
+      index_buffer,
+      index_count: indices.len() as u32,
+      msaa_texture_view: util::create_msaa_texture_view(&ctx),
+      depth_texture_view: util::create_depth_view(&ctx),
+      camera: OrbitCamera::new(
+        initial.camera_position,
+        initial.look_direction,
+        initial.up_direction,
+      ),
+      project_mat: matrix::create_projection_mat(ctx.aspect_ratio(), true),
+      light_direction: initial.light_direction,
+      rotation_speed: initial.rotation_speed,
+    }
+  }
+
+  fn resize(&mut self, ctx: &mut DrawingContext<'_>, size: Size) {
+    if size.width > 0 && size.height > 0 {
+      ctx.resize(size.into());
+
+      self.project_mat = matrix::create_projection_mat(
+        size.width as f32 / size.height as f32,
+        true,
+      );
+
+      self.depth_texture_view = util::create_depth_view(ctx);
+
+      if ctx.sample_count > 1 {
+        self.msaa_texture_view = util::create_msaa_texture_view(&ctx);
+      }
+    }
+  }
+
+  // ドラッグとホイールは App が取り出して渡してくれるので、イベントを直接見なくてよい
+  fn on_scroll(&mut self, delta: f32) {
+    self.camera.scroll(delta);
+  }
+
+  fn on_mouse_drag(&mut self, dx: f32, dy: f32, button: MouseButton) {
+    if button == MouseButton::Left {
+      self.camera.drag(dx, dy);
+    }
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, dt: std::time::Duration) {
+    let t = self.rotation_speed * dt.as_secs_f32();
+
+    let model_mat = matrix::create_model_mat_with_rotation([0., t, 0.]);
+    let normal_mat = (model_mat.invert().unwrap()).transpose();
+    let vp_mat = self.project_mat * self.camera.view_mat();
+    let [lx, ly, lz] = self.light_direction;
+
+    let vp_ref: &[f32; 16] = vp_mat.as_ref();
+    let model_ref: &[f32; 16] = model_mat.as_ref();
+    let normal_ref: &[f32; 16] = normal_mat.as_ref();
+
+    let mut data: Vec<f32> = vec![];
+    data.extend(vp_ref);
+    data.extend(model_ref);
+    data.extend(normal_ref);
+    data.extend([lx, ly, lz, 0.]);
+
+    ctx.queue.write_buffer(&self.uniform_buffer, 0, cast_slice(&data));
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: RenderTarget,
+    sample_count: u32,
+  ) -> anyhow::Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+    let (view, frame) = match target {
+      RenderTarget::Surface(surface) => {
+        let frame = surface.get_current_texture()?;
+        let view =
+          frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, Some(frame))
+      }
+      RenderTarget::Texture(texture) => {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, None)
+      }
+    };
+
+    let color_attachment = if sample_count == 1 {
+      util::create_color_attachment(&view)
+    } else {
+      util::create_msaa_color_attachment(&view, &self.msaa_texture_view)
+    };
+    let depth_attachment =
+      util::create_depth_stencil_attachment(&self.depth_texture_view);
+
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render Pass"),
+        color_attachments: &[Some(color_attachment)],
+        depth_stencil_attachment: Some(depth_attachment),
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, &self.bind_group, &[]);
+    render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+    render_pass
+      .set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+    render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+
+    drop(render_pass);
+
+    Ok(frame)
+  }
+}
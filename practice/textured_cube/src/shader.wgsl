@@ -0,0 +1,51 @@
+struct Uniforms {
+  view_project_mat: mat4x4f,
+  model_mat: mat4x4f,
+  normal_mat: mat4x4f,
+  light_direction: vec4f,
+}
+
+@group(0) @binding(0) var<uniform> unif: Uniforms;
+@group(0) @binding(1) var tex: texture_2d<f32>;
+@group(0) @binding(2) var samp: sampler;
+
+struct Input {
+  @location(0) position: vec3f,
+  @location(1) normal: vec3f,
+  @location(2) uv: vec2f,
+}
+
+struct Output {
+  @builtin(position) position: vec4f,
+  @location(0) v_normal: vec4f,
+  @location(1) v_uv: vec2f,
+}
+
+@vertex
+fn vs_main(in: Input) -> Output {
+  var output: Output;
+
+  output.v_normal = unif.normal_mat * vec4f(in.normal, 0.0);
+  output.v_uv = in.uv;
+  output.position = unif.view_project_mat * unif.model_mat * vec4f(in.position, 1.0);
+  return output;
+}
+
+const AMBIENT = 0.3;
+const DIFFUSE = 0.7;
+const BACK_FACE_COLOR = vec3f(1.0, 0.0, 1.0);
+
+@fragment
+fn fs_main(in: Output, @builtin(front_facing) front_facing: bool) -> @location(0) vec4f {
+  // 巻き順が法線と合っていなければ裏面として描かれるので、目立つ色にする
+  if (!front_facing) {
+    return vec4f(BACK_FACE_COLOR, 1.0);
+  }
+
+  let N = normalize(in.v_normal.xyz);
+  let L = normalize(-unif.light_direction.xyz);
+  let diffuse = DIFFUSE * max(dot(N, L), 0.0);
+
+  let color = textureSample(tex, samp, in.v_uv).rgb;
+  return vec4f(color * (AMBIENT + diffuse), 1.0);
+}
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use winit::{
   application::ApplicationHandler,
   dpi::LogicalSize,
-  event::{ElementState, KeyEvent, WindowEvent},
+  event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
   event_loop::ActiveEventLoop,
   keyboard::{KeyCode, PhysicalKey},
   window::Window,
@@ -11,6 +11,11 @@ use winit::{
 
 use crate::state::WindowState;
 
+/// ホイール1目盛りあたりの拡大率
+const ZOOM_STEP: f32 = 1.1;
+/// トラックパッドなどのピクセル単位のスクロールを、目盛りに換算する量
+const PIXELS_PER_LINE: f32 = 40.0;
+
 #[derive(Default)]
 pub struct Application<'a> {
   window_state: Option<WindowState<'a>>,
@@ -97,6 +102,26 @@ impl<'a> ApplicationHandler for Application<'a> {
       } => {
         event_loop.exit();
       }
+      WindowEvent::MouseWheel { delta, .. } => {
+        let lines = match delta {
+          MouseScrollDelta::LineDelta(_, y) => y,
+          MouseScrollDelta::PixelDelta(px) => px.y as f32 / PIXELS_PER_LINE,
+        };
+        state.zoom_by(ZOOM_STEP.powf(lines));
+      }
+      WindowEvent::MouseInput {
+        button: MouseButton::Middle,
+        state: button_state,
+        ..
+      } => {
+        state.set_dragging(button_state.is_pressed());
+      }
+      WindowEvent::CursorMoved { position, .. } => {
+        state.cursor_moved(position);
+      }
+      WindowEvent::CursorLeft { .. } => {
+        state.cursor_left();
+      }
       WindowEvent::RedrawRequested => match state.render() {
         Ok(_) => {}
         Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
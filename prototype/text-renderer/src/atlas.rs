@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use etagere::{size2, AllocatorOptions, AtlasAllocator};
+use ttf_parser::GlyphId;
+
+#[derive(Debug)]
+pub enum AtlasError {
+  /// 最大サイズのアトラスにも収まらない
+  Full { max_size: i32 },
+}
+
+impl fmt::Display for AtlasError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      AtlasError::Full { max_size } => write!(
+        f,
+        "glyphs do not fit in the atlas even at {}x{}",
+        max_size, max_size
+      ),
+    }
+  }
+}
+
+impl std::error::Error for AtlasError {}
+
+struct Entry {
+  alloc: etagere::Allocation,
+  size: (u16, u16),
+  last_used: u64,
+}
+
+/// グリフごとのアトラス上の領域を覚えておき、同じグリフには同じ領域を返す
+///
+/// 空きがなくなったら、今回の `pack` で使っていないグリフを古い順に追い出す。
+/// それでも足りなければ、アトラスを2倍にして詰め直す（`max_size` まで）。
+pub struct GlyphCache {
+  atlas: AtlasAllocator,
+  size: i32,
+  max_size: i32,
+  entries: HashMap<GlyphId, Entry>,
+  /// `pack` を呼ぶたびに進む。`last_used` がこれと同じグリフは追い出さない
+  tick: u64,
+}
+
+impl GlyphCache {
+  pub fn new(size: i32, max_size: i32) -> Self {
+    Self {
+      atlas: create_allocator(size),
+      size,
+      max_size: max_size.max(size),
+      entries: HashMap::new(),
+      tick: 0,
+    }
+  }
+
+  /// 詰め直しで大きくなることがあるので、`pack` の後に読み直す
+  pub fn size(&self) -> i32 {
+    self.size
+  }
+
+  /// `glyphs` のすべてをアトラスに置き、各グリフの左上の位置を返す
+  ///
+  /// 途中で詰め直すと前に置いたグリフも動くので、位置は最後にまとめて読む。
+  pub fn pack(
+    &mut self,
+    glyphs: impl IntoIterator<Item = (GlyphId, (u16, u16))>,
+  ) -> Result<HashMap<GlyphId, (i32, i32)>, AtlasError> {
+    self.tick += 1;
+
+    let mut ids = Vec::new();
+    for (id, size) in glyphs {
+      self.allocate(id, size)?;
+      ids.push(id);
+    }
+
+    let positions = ids
+      .into_iter()
+      .map(|id| {
+        let origin = self.entries[&id].alloc.rectangle.min;
+        (id, (origin.x, origin.y))
+      })
+      .collect();
+
+    Ok(positions)
+  }
+
+  pub fn dump_svg(
+    &self,
+    output: &mut dyn std::io::Write,
+  ) -> std::io::Result<()> {
+    self.atlas.dump_svg(output)
+  }
+
+  fn allocate(
+    &mut self,
+    id: GlyphId,
+    size: (u16, u16),
+  ) -> Result<(), AtlasError> {
+    if let Some(entry) = self.entries.get_mut(&id) {
+      entry.last_used = self.tick;
+      return Ok(());
+    }
+
+    loop {
+      if let Some(alloc) =
+        self.atlas.allocate(size2(size.0 as i32, size.1 as i32))
+      {
+        let last_used = self.tick;
+        self.entries.insert(
+          id,
+          Entry {
+            alloc,
+            size,
+            last_used,
+          },
+        );
+        return Ok(());
+      }
+
+      if !self.evict_least_recently_used() {
+        self.grow()?;
+      }
+    }
+  }
+
+  /// 今回の `pack` で使っていないグリフのうち、最も古いものを追い出す
+  fn evict_least_recently_used(&mut self) -> bool {
+    let oldest = self
+      .entries
+      .iter()
+      .filter(|(_, entry)| entry.last_used < self.tick)
+      .min_by_key(|(_, entry)| entry.last_used)
+      .map(|(id, _)| *id);
+
+    match oldest.and_then(|id| self.entries.remove(&id)) {
+      Some(entry) => {
+        self.atlas.deallocate(entry.alloc.id);
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// 2倍のアトラスを作り、残っているグリフを大きい順に詰め直す
+  fn grow(&mut self) -> Result<(), AtlasError> {
+    let mut entries = self
+      .entries
+      .iter()
+      .map(|(id, entry)| (*id, entry.size, entry.last_used))
+      .collect::<Vec<_>>();
+    entries
+      .sort_by_key(|(_, (w, h), _)| std::cmp::Reverse(*w as u32 * *h as u32));
+
+    let mut size = self.size;
+    'retry: loop {
+      if size >= self.max_size {
+        return Err(AtlasError::Full {
+          max_size: self.max_size,
+        });
+      }
+      size = (size * 2).min(self.max_size);
+
+      let mut atlas = create_allocator(size);
+      let mut packed = HashMap::with_capacity(entries.len());
+      for &(id, (w, h), last_used) in &entries {
+        let Some(alloc) = atlas.allocate(size2(w as i32, h as i32)) else {
+          continue 'retry;
+        };
+        packed.insert(
+          id,
+          Entry {
+            alloc,
+            size: (w, h),
+            last_used,
+          },
+        );
+      }
+
+      log::info!("glyph atlas grew to {}x{}", size, size);
+
+      self.atlas = atlas;
+      self.size = size;
+      self.entries = packed;
+
+      return Ok(());
+    }
+  }
+}
+
+fn create_allocator(size: i32) -> AtlasAllocator {
+  AtlasAllocator::with_options(
+    size2(size, size),
+    &AllocatorOptions {
+      alignment: size2(2, 1),
+      ..Default::default()
+    },
+  )
+}
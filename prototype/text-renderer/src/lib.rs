@@ -1,4 +1,5 @@
 mod app;
+mod atlas;
 mod renderer;
 mod sdf;
 mod state;
@@ -6,6 +7,7 @@ mod state;
 use std::error::Error;
 
 use app::Application;
+use atlas::GlyphCache;
 use winit::event_loop::EventLoop;
 
 pub fn run() -> Result<(), Box<dyn Error>> {
@@ -20,17 +22,32 @@ pub fn run() -> Result<(), Box<dyn Error>> {
 }
 
 pub fn proto() -> Result<(), Box<dyn Error>> {
-  use etagere::size2;
+  // 輪郭から3チャンネルの距離を作り、小さな文字でも角を尖らせる（sdf_methodは使わない）
+  build_atlas(sdf::SdfMethod::Ssedt8, true)
+}
+
+/// 比較用に、ビットマップから従来のEDTで1チャンネルのSDFアトラスを作る
+pub fn proto_edt() -> Result<(), Box<dyn Error>> {
+  build_atlas(sdf::SdfMethod::Edt, false)
+}
+
+fn build_atlas(
+  sdf_method: sdf::SdfMethod,
+  msdf: bool,
+) -> Result<(), Box<dyn Error>> {
   use std::collections::HashMap;
   use ttf_parser as ttf;
 
   env_logger::init();
 
   let version = 13;
+  // "AV" などの字間を、フォントのカーニング情報で詰める
+  let kerning = true;
 
   const ATLAS_FONT_SIZE: u16 = 48;
   const ATLAS_GAP: u16 = 2;
   const ATLAS_RADIUS: u16 = ATLAS_FONT_SIZE / 6; // sometimes called `spread`
+  const ATLAS_MAX_SIZE: i32 = 4096;
 
   let text = "abcdefghijklmnopqrstuvwxyz\nABCDEFGHIJKLMNOPQRSTUVWXYZ";
   let chars = text.chars();
@@ -145,44 +162,18 @@ pub fn proto() -> Result<(), Box<dyn Error>> {
     (glyph_size.powi(2) * num_glyphs as f32).sqrt().ceil() as i32;
   println!("atlas_size: {}", atlas_size);
 
-  let mut atlas = etagere::AtlasAllocator::with_options(
-    size2(atlas_size, atlas_size),
-    &etagere::AllocatorOptions {
-      alignment: size2(2, 1),
-      ..Default::default()
-    },
-  );
-
-  // TODO: キャッシュの仕組みを用意し、allocateがNoneの場合に対応する
-  let allocations = sizes
-    .iter()
-    .map(|(id, (w, h))| {
-      let alloc = atlas.allocate(size2(*w as i32, *h as i32)).unwrap();
-      (*id, alloc)
-    })
-    .collect::<HashMap<_, _>>();
+  // 収まらなければ、キャッシュがアトラスを大きくして詰め直す
+  let mut glyph_cache = GlyphCache::new(atlas_size, ATLAS_MAX_SIZE);
+  let atlas_positions =
+    glyph_cache.pack(sizes.iter().map(|(id, size)| (**id, *size)))?;
+  let atlas_size = glyph_cache.size();
 
   let mut atlas_svg =
     std::fs::File::create(std::format!("export/font-atlas-v{}.svg", version))?;
-  atlas.dump_svg(&mut atlas_svg)?;
-
-  let atlas_positions = allocations
-    .iter()
-    .map(|(id, alloc)| {
-      let rect = alloc.rectangle.to_rect();
-      let loc = (rect.origin.x, rect.origin.y);
-      (**id, loc)
-    })
-    .collect::<HashMap<_, _>>();
+  glyph_cache.dump_svg(&mut atlas_svg)?;
 
   //println!("atlas_positions: {:?}", atlas_positions);
 
-  for (_, alloc) in allocations {
-    //if let Some(alloc) = alloc {
-    atlas.deallocate(alloc.id);
-    //}
-  }
-
   let uv_map = glyph_map
     .keys()
     .map(|g_id| {
@@ -244,12 +235,44 @@ pub fn proto() -> Result<(), Box<dyn Error>> {
 
   // --- toSDF ---
 
-  let sdf = crate::sdf::to_sdf(
-    &bitmap,
-    atlas_size as usize,
-    atlas_size as usize,
-    ATLAS_RADIUS as f64,
-  );
+  let (sdf, sdf_color) = if msdf {
+    let mut rgb = vec![0u8; (atlas_size * atlas_size * 3) as usize];
+
+    for (g_id, glyph) in &positioned_glyphs {
+      let id = ttf::GlyphId(g_id.0);
+      let (Some(bbox), Some(shape)) = (
+        glyph.pixel_bounding_box(),
+        crate::sdf::msdf::Shape::from_glyph(&font_face, id),
+      ) else {
+        continue;
+      };
+
+      // ビットマップと同じく、バウンディングボックスの左上をアトラス上の位置に合わせる
+      let (at_x, at_y) = atlas_positions.get(&id).unwrap();
+      let (w, h) = sizes.get(&id).unwrap();
+      crate::sdf::msdf::draw_glyph(
+        &shape,
+        &mut rgb,
+        atlas_size as usize,
+        (*at_x as usize, *at_y as usize, *w as usize, *h as usize),
+        (-bbox.min.x as f64, -bbox.min.y as f64),
+        scale_factor as f64,
+        ATLAS_RADIUS as f64,
+      );
+    }
+
+    (rgb, png::ColorType::Rgb)
+  } else {
+    let sdf = crate::sdf::to_sdf_with(
+      sdf_method,
+      &bitmap,
+      atlas_size as usize,
+      atlas_size as usize,
+      ATLAS_RADIUS as f64,
+    );
+
+    (sdf, png::ColorType::Grayscale)
+  };
 
   let atlas_sdf_file =
     std::fs::File::create(std::format!("./export/atlas-svg-v{}.png", version))
@@ -257,11 +280,20 @@ pub fn proto() -> Result<(), Box<dyn Error>> {
   let ref mut w = std::io::BufWriter::new(atlas_sdf_file);
 
   let mut encoder = png::Encoder::new(w, atlas_size as u32, atlas_size as u32);
-  encoder.set_color(png::ColorType::Grayscale);
+  encoder.set_color(sdf_color);
 
   let mut writer = encoder.write_header().unwrap();
   writer.write_image_data(&sdf).unwrap();
 
+  // テクスチャはRGBA。シェーダーはRGBの中央値をとるので、SDFは3チャンネルに複製する
+  let atlas_rgba = match sdf_color {
+    png::ColorType::Rgb => sdf
+      .chunks_exact(3)
+      .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+      .collect::<Vec<_>>(),
+    _ => sdf.iter().flat_map(|&v| [v, v, v, 255]).collect::<Vec<_>>(),
+  };
+
   // --- getTextShape ---
 
   let font_size = 16;
@@ -272,6 +304,7 @@ pub fn proto() -> Result<(), Box<dyn Error>> {
 
   let mut cursor_x = 0.;
   let mut cursor_y = 0.;
+  let mut prev_g_id: Option<ttf::GlyphId> = None;
 
   let char_rects = text
     .chars()
@@ -284,6 +317,7 @@ pub fn proto() -> Result<(), Box<dyn Error>> {
             '\n' => {
               cursor_x = 0.;
               cursor_y += line_height as f32 * scale_factor;
+              prev_g_id = None;
             }
             _ => {}
           }
@@ -293,6 +327,12 @@ pub fn proto() -> Result<(), Box<dyn Error>> {
 
       let g_id = g_id.unwrap();
       let glyph = glyph_map.get(&g_id).unwrap();
+
+      if let (true, Some(prev)) = (kerning, prev_g_id) {
+        cursor_x += pair_kerning(&font_face, prev, g_id) as f32 * scale_factor;
+      }
+      prev_g_id = Some(g_id);
+
       let Glyph {
         y,
         width,
@@ -330,7 +370,7 @@ pub fn proto() -> Result<(), Box<dyn Error>> {
   let event_loop = EventLoop::builder().build()?;
   let mut app = Application::new(
     (atlas_size as u32, atlas_size as u32),
-    sdf,
+    atlas_rgba,
     char_rects,
     [16., 16.],
     font_size as f32,
@@ -342,3 +382,69 @@ pub fn proto() -> Result<(), Box<dyn Error>> {
 
   Ok(())
 }
+
+/// 左右のグリフの組に対する字間の調整量（フォント単位）
+///
+/// 古い `kern` テーブルを優先し、なければ GPOS の `kern` フィーチャーの
+/// ペア調整（PairAdjustment）から探す。
+fn pair_kerning(
+  face: &ttf_parser::Face,
+  left: ttf_parser::GlyphId,
+  right: ttf_parser::GlyphId,
+) -> i16 {
+  use ttf_parser::gpos::{PairAdjustment, PositioningSubtable};
+  use ttf_parser::Tag;
+
+  let tables = face.tables();
+
+  if let Some(kern) = tables.kern {
+    let value = kern
+      .subtables
+      .into_iter()
+      .filter(|st| st.horizontal && !st.variable)
+      .find_map(|st| st.glyphs_kerning(left, right));
+    if let Some(value) = value {
+      return value;
+    }
+  }
+
+  let Some(gpos) = tables.gpos else {
+    return 0;
+  };
+
+  let kern_tag = Tag::from_bytes(b"kern");
+  let lookups = gpos
+    .features
+    .into_iter()
+    .filter(|feature| feature.tag == kern_tag)
+    .flat_map(|feature| feature.lookup_indices)
+    .filter_map(|index| gpos.lookups.get(index));
+
+  for lookup in lookups {
+    for subtable in lookup.subtables.into_iter::<PositioningSubtable>() {
+      let PositioningSubtable::Pair(pair) = subtable else {
+        continue;
+      };
+
+      let records = match pair {
+        PairAdjustment::Format1 { coverage, sets } => coverage
+          .get(left)
+          .and_then(|index| sets.get(index))
+          .and_then(|set| set.get(right)),
+        PairAdjustment::Format2 {
+          coverage,
+          classes,
+          matrix,
+        } => coverage.get(left).and_then(|_| {
+          matrix.get((classes.0.get(left), classes.1.get(right)))
+        }),
+      };
+
+      if let Some((first, _)) = records {
+        return first.x_advance;
+      }
+    }
+  }
+
+  0
+}
@@ -1,17 +1,37 @@
 use wgpu::{include_wgsl, util::DeviceExt};
 
-const TEXT_STRUCT_SIZE: usize = 16; // TODO: 内訳を明記
+/// 1グリフあたりのf32の数（text.wgsl の `Glyph`）
+///
+/// | offset | 内容                                     |
+/// | ------ | ---------------------------------------- |
+/// | 0      | position (x, y) : 矩形の左上（ピクセル） |
+/// | 2      | _unused                                  |
+/// | 3      | font_size                                |
+/// | 4      | color (r, g, b, a)                       |
+/// | 8      | size (w, h) : 矩形の大きさ（ピクセル）   |
+/// | 10     | uv (x, y) : アトラス上の左上             |
+/// | 12     | uv_size (w, h)                           |
+/// | 14     | window (w, h) : ビューポートの大きさ     |
+const TEXT_STRUCT_SIZE: usize = 16;
 const MAX_TEXT_COUNT: usize = 1024;
-const TEXT_BUFFER_SIZE: usize = TEXT_STRUCT_SIZE * MAX_TEXT_COUNT;
+const TEXT_BUFFER_SIZE: usize =
+  TEXT_STRUCT_SIZE * std::mem::size_of::<f32>() * MAX_TEXT_COUNT;
+
+/// text.wgsl の `View`（offset.x, offset.y, zoom, _unused）
+const VIEW_UNIFORM_SIZE: usize = 4 * std::mem::size_of::<f32>();
 
 const FULL_SCREEN_QUAD_VERTICES: [f32; 12] =
   [0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0];
 
+/// 配置済みのグリフの並び。`char_rects` と `uvs` は同じ順に対応する
 pub struct Text<'a> {
-  content: &'a str,
-  position: [f32; 2], // TODO: replace Size struct
-  font_size: f32,
-  color: [f32; 4], // TODO: replace Color struct
+  /// `position` からの相対位置と大きさ（x, y, w, h）
+  pub char_rects: &'a [(f32, f32, f32, f32)],
+  /// アトラス上の位置と大きさ（x, y, w, h、0..1）
+  pub uvs: &'a [[f32; 4]],
+  pub position: [f32; 2], // TODO: replace Size struct
+  pub font_size: f32,
+  pub color: [f32; 4], // TODO: replace Color struct
 }
 
 pub struct FontData {
@@ -23,6 +43,7 @@ pub struct UiRenderer {
   sampler: wgpu::Sampler,
   vertex_buffer: wgpu::Buffer,
   text_buffer: wgpu::Buffer,
+  view_buffer: wgpu::Buffer,
   text_bind_group_layout: wgpu::BindGroupLayout,
   text_pipeline: wgpu::RenderPipeline,
   glyph_data: Vec<f32>,
@@ -60,6 +81,13 @@ impl UiRenderer {
       mapped_at_creation: false,
     });
 
+    let view_buffer =
+      device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("view"),
+        contents: bytemuck::cast_slice(&[0.0f32, 0.0, 1.0, 0.0]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+
     let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
       address_mode_u: wgpu::AddressMode::ClampToEdge,
       address_mode_v: wgpu::AddressMode::ClampToEdge,
@@ -99,6 +127,16 @@ impl UiRenderer {
             },
             count: None,
           },
+          wgpu::BindGroupLayoutEntry {
+            binding: 3,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+              ty: wgpu::BufferBindingType::Uniform,
+              has_dynamic_offset: false,
+              min_binding_size: wgpu::BufferSize::new(VIEW_UNIFORM_SIZE as u64),
+            },
+            count: None,
+          },
         ],
       });
 
@@ -159,6 +197,7 @@ impl UiRenderer {
       sampler,
       vertex_buffer,
       text_buffer,
+      view_buffer,
       text_bind_group_layout,
       text_pipeline,
       glyph_data: vec![],
@@ -183,7 +222,8 @@ impl UiRenderer {
       mip_level_count: 1,
       sample_count: 1,
       dimension: wgpu::TextureDimension::D2,
-      format: wgpu::TextureFormat::R8Unorm,
+      // MSDF の3チャンネル（SDF のときは同じ値を複製したもの）
+      format: wgpu::TextureFormat::Rgba8Unorm,
       usage: wgpu::TextureUsages::TEXTURE_BINDING
         | wgpu::TextureUsages::COPY_DST
         | wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -217,6 +257,10 @@ impl UiRenderer {
                 .create_view(&wgpu::TextureViewDescriptor::default()),
             ),
           },
+          wgpu::BindGroupEntry {
+            binding: 3,
+            resource: self.view_buffer.as_entire_binding(),
+          },
         ],
       }),
     }
@@ -226,17 +270,31 @@ impl UiRenderer {
     self.viewport = (width as f32, height as f32);
   }
 
-  pub fn text(
-    &mut self,
-    char_rects: &Vec<(f32, f32, f32, f32)>,
-    [origin_x, origin_y]: [f32; 2],
-    font_size: f32,
-    [color_r, color_g, color_b, color_a]: [f32; 4],
-    uvs: &Vec<[f32; 4]>,
-  ) {
-    for (i, (x, y, w, h)) in char_rects.iter().enumerate() {
+  /// 描画全体を `zoom` 倍してから `offset`（ピクセル）だけずらす
+  pub fn set_view(&self, queue: &wgpu::Queue, zoom: f32, offset: [f32; 2]) {
+    queue.write_buffer(
+      &self.view_buffer,
+      0,
+      bytemuck::cast_slice(&[offset[0], offset[1], zoom, 0.0]),
+    );
+  }
+
+  /// グリフを `TEXT_STRUCT_SIZE` ずつ詰めて、次の `render` で描くものに加える
+  ///
+  /// `MAX_TEXT_COUNT` を超えた分は捨てる。
+  pub fn queue_text(&mut self, text: &Text) {
+    let [origin_x, origin_y] = text.position;
+    let font_size = text.font_size;
+    let [color_r, color_g, color_b, color_a] = text.color;
+
+    for ((x, y, w, h), uv) in text.char_rects.iter().zip(text.uvs) {
+      if self.glyph_count >= MAX_TEXT_COUNT {
+        log::warn!("more than {} glyphs, the rest is dropped", MAX_TEXT_COUNT);
+        break;
+      }
+
       let (shape_x, shape_y) = (x + origin_x, y + origin_y);
-      let [uv_x, uv_y, uv_z, uv_w] = uvs[i];
+      let [uv_x, uv_y, uv_z, uv_w] = *uv;
       let (viewport_w, viewport_h) = self.viewport;
 
       let new_text = vec![
@@ -249,6 +307,25 @@ impl UiRenderer {
     }
   }
 
+  pub fn clear_text(&mut self) {
+    self.glyph_data.clear();
+    self.glyph_count = 0;
+  }
+
+  /// `queue_text` で積んだグリフを、1グリフ1インスタンスの四角形として描く
+  ///
+  /// ストレージバッファへの書き込みは `render` が行う。
+  pub fn draw(&self, render_pass: &mut wgpu::RenderPass, font: &FontData) {
+    if self.glyph_count == 0 {
+      return;
+    }
+
+    render_pass.set_pipeline(&self.text_pipeline);
+    render_pass.set_bind_group(0, &font.text_bind_group, &[]);
+    render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+    render_pass.draw(0..6, 0..self.glyph_count as u32);
+  }
+
   pub fn render(
     &mut self,
     encoder: &mut wgpu::CommandEncoder,
@@ -256,6 +333,12 @@ impl UiRenderer {
     view: &wgpu::TextureView,
     font_data: &FontData,
   ) {
+    queue.write_buffer(
+      &self.text_buffer,
+      0,
+      bytemuck::cast_slice(self.glyph_data.as_slice()),
+    );
+
     let mut render_pass =
       encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
         color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -278,18 +361,6 @@ impl UiRenderer {
       1.0,
     );
 
-    queue.write_buffer(
-      &self.text_buffer,
-      0,
-      bytemuck::cast_slice(self.glyph_data.as_slice()),
-    );
-
-    render_pass.set_pipeline(&self.text_pipeline);
-    render_pass.set_bind_group(0, &font_data.text_bind_group, &[]);
-    render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-    render_pass.draw(0..6, 0..self.glyph_count as u32);
-
-    //self.glyph_count = 0;
-    //self.glyph_data.clear();
+    self.draw(&mut render_pass, font_data);
   }
 }
@@ -1,5 +1,28 @@
+pub mod msdf;
+
 const INF: f64 = 1e20;
 
+#[derive(Debug, Clone, Copy)]
+pub enum SdfMethod {
+  /// Felzenszwalb & Huttenlocher の EDT（アンチエイリアスの濃淡も距離に含める）
+  Edt,
+  /// 8点の Sequential Euclidean Distance Transform（ピクセル中心間の距離）
+  Ssedt8,
+}
+
+pub fn to_sdf_with(
+  method: SdfMethod,
+  image_data: &Vec<u8>,
+  width: usize,
+  height: usize,
+  radius: f64,
+) -> Vec<u8> {
+  match method {
+    SdfMethod::Edt => to_sdf(image_data, width, height, radius),
+    SdfMethod::Ssedt8 => to_sdf_8ssedt(image_data, width, height, radius),
+  }
+}
+
 pub fn to_sdf(
   image_data: &Vec<u8>,
   width: usize,
@@ -114,3 +137,151 @@ fn edt1d(
     grid[offset + q * stride] = f[r] + (q as f64 - r as f64).powi(2);
   }
 }
+
+/// 最も近い境界のピクセルまでのずれ
+#[derive(Clone, Copy)]
+struct Offset {
+  dx: i32,
+  dy: i32,
+}
+
+impl Offset {
+  const ZERO: Offset = Offset { dx: 0, dy: 0 };
+  /// まだ境界が見つかっていない
+  const FAR: Offset = Offset { dx: 9999, dy: 9999 };
+
+  fn dist_sq(&self) -> i64 {
+    self.dx as i64 * self.dx as i64 + self.dy as i64 * self.dy as i64
+  }
+}
+
+/// `to_sdf` と同じ並び・同じ値の対応（0.5が輪郭、内側ほど大きい）で返す
+pub fn to_sdf_8ssedt(
+  image_data: &[u8],
+  width: usize,
+  height: usize,
+  radius: f64,
+) -> Vec<u8> {
+  // 半分以上塗られているピクセルを内側とみなす
+  let inside = image_data.iter().map(|&a| a >= 128).collect::<Vec<_>>();
+
+  // 外側のピクセルから内側までの距離
+  let mut grid_outer = inside
+    .iter()
+    .map(|&is_inside| if is_inside { Offset::ZERO } else { Offset::FAR })
+    .collect::<Vec<_>>();
+  // 内側のピクセルから外側までの距離
+  let mut grid_inner = inside
+    .iter()
+    .map(|&is_inside| if is_inside { Offset::FAR } else { Offset::ZERO })
+    .collect::<Vec<_>>();
+
+  ssedt(&mut grid_outer, width, height);
+  ssedt(&mut grid_inner, width, height);
+
+  (0..width * height)
+    .map(|i| {
+      let d = (grid_outer[i].dist_sq() as f64).sqrt()
+        - (grid_inner[i].dist_sq() as f64).sqrt();
+      let value = 0.5 - d / radius;
+
+      (value * 255.0).clamp(0.0, 255.0) as u8
+    })
+    .collect()
+}
+
+fn ssedt(grid: &mut [Offset], width: usize, height: usize) {
+  let (w, h) = (width as i32, height as i32);
+
+  // 上から下へ：左・上・左上・右上を見てから、右から左へ戻って右を見る
+  for y in 0..h {
+    for x in 0..w {
+      compare(grid, w, h, x, y, -1, 0);
+      compare(grid, w, h, x, y, 0, -1);
+      compare(grid, w, h, x, y, -1, -1);
+      compare(grid, w, h, x, y, 1, -1);
+    }
+    for x in (0..w).rev() {
+      compare(grid, w, h, x, y, 1, 0);
+    }
+  }
+
+  // 下から上へ：右・下・左下・右下を見てから、左から右へ戻って左を見る
+  for y in (0..h).rev() {
+    for x in (0..w).rev() {
+      compare(grid, w, h, x, y, 1, 0);
+      compare(grid, w, h, x, y, 0, 1);
+      compare(grid, w, h, x, y, -1, 1);
+      compare(grid, w, h, x, y, 1, 1);
+    }
+    for x in 0..w {
+      compare(grid, w, h, x, y, -1, 0);
+    }
+  }
+}
+
+/// 隣のピクセル (x + ox, y + oy) の境界の方が近ければ、それを引き継ぐ
+fn compare(
+  grid: &mut [Offset],
+  width: i32,
+  height: i32,
+  x: i32,
+  y: i32,
+  ox: i32,
+  oy: i32,
+) {
+  let (nx, ny) = (x + ox, y + oy);
+  if nx < 0 || ny < 0 || nx >= width || ny >= height {
+    return;
+  }
+
+  let neighbor = grid[(ny * width + nx) as usize];
+  let candidate = Offset {
+    dx: neighbor.dx + ox,
+    dy: neighbor.dy + oy,
+  };
+
+  let i = (y * width + x) as usize;
+  if candidate.dist_sq() < grid[i].dist_sq() {
+    grid[i] = candidate;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SIZE: usize = 33;
+  const CENTER: usize = 16;
+  const CIRCLE_RADIUS: f64 = 10.;
+
+  fn filled_circle() -> Vec<u8> {
+    (0..SIZE * SIZE)
+      .map(|i| {
+        let (x, y) = ((i % SIZE) as f64, (i / SIZE) as f64);
+        let d =
+          ((x - CENTER as f64).powi(2) + (y - CENTER as f64).powi(2)).sqrt();
+        if d <= CIRCLE_RADIUS {
+          255
+        } else {
+          0
+        }
+      })
+      .collect()
+  }
+
+  #[test]
+  fn circle_center_is_the_deepest_inside_point() {
+    // 円の半径より大きい radius にして、中心の値が 255 で頭打ちにならないようにする
+    let sdf = to_sdf_8ssedt(&filled_circle(), SIZE, SIZE, 32.);
+    let center = sdf[CENTER * SIZE + CENTER];
+
+    assert_eq!(Some(&center), sdf.iter().max());
+    assert!(center > 128, "center {}", center);
+
+    // 中心から右へ進むほど、境界に近づき値が下がる
+    let row = &sdf[CENTER * SIZE + CENTER..(CENTER + 1) * SIZE];
+    assert!(row.windows(2).all(|w| w[0] >= w[1]), "{:?}", row);
+    assert!(row[0] > row[row.len() - 1]);
+  }
+}
@@ -0,0 +1,484 @@
+//! Multi-channel signed distance field（MSDF）
+//!
+//! 輪郭の辺を角ごとに色（R/G/B の組み合わせ）で塗り分け、チャンネルごとに
+//! その色を含む辺までの距離を書き込む。シェーダーで3チャンネルの中央値を
+//! とると、1チャンネルの SDF では丸まってしまう角が尖ったまま復元される。
+
+use ttf_parser as ttf;
+
+const RED: u8 = 0b001;
+const GREEN: u8 = 0b010;
+const BLUE: u8 = 0b100;
+const YELLOW: u8 = RED | GREEN;
+const MAGENTA: u8 = RED | BLUE;
+const CYAN: u8 = GREEN | BLUE;
+const WHITE: u8 = RED | GREEN | BLUE;
+
+/// 曲線を折れ線にするときの分割数
+const CURVE_STEPS: usize = 16;
+/// 辺のつなぎ目の向きがこれ（sin）以上変わったら角とみなす（約3度）
+const CORNER_CROSS_THRESHOLD: f64 = 0.05;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Vec2 {
+  x: f64,
+  y: f64,
+}
+
+impl Vec2 {
+  fn new(x: f64, y: f64) -> Self {
+    Self { x, y }
+  }
+
+  fn sub(self, other: Vec2) -> Vec2 {
+    Vec2::new(self.x - other.x, self.y - other.y)
+  }
+
+  fn add(self, other: Vec2) -> Vec2 {
+    Vec2::new(self.x + other.x, self.y + other.y)
+  }
+
+  fn scale(self, s: f64) -> Vec2 {
+    Vec2::new(self.x * s, self.y * s)
+  }
+
+  fn dot(self, other: Vec2) -> f64 {
+    self.x * other.x + self.y * other.y
+  }
+
+  fn cross(self, other: Vec2) -> f64 {
+    self.x * other.y - self.y * other.x
+  }
+
+  fn length(self) -> f64 {
+    self.dot(self).sqrt()
+  }
+
+  fn normalize(self) -> Vec2 {
+    let len = self.length();
+    if len == 0. {
+      Vec2::new(0., 0.)
+    } else {
+      self.scale(1. / len)
+    }
+  }
+}
+
+/// フォントの1つの線分・曲線を折れ線にしたもの
+struct Edge {
+  points: Vec<Vec2>,
+  color: u8,
+}
+
+impl Edge {
+  /// 制御点が端点と重なっていることがあるので、最初に動いた向きをとる
+  fn start_dir(&self) -> Vec2 {
+    let start = self.points[0];
+    let next = self.points.iter().find(|p| **p != start).unwrap_or(&start);
+    next.sub(start).normalize()
+  }
+
+  fn end_dir(&self) -> Vec2 {
+    let end = self.points[self.points.len() - 1];
+    let prev = self.points.iter().rev().find(|p| **p != end).unwrap_or(&end);
+    end.sub(*prev).normalize()
+  }
+}
+
+/// グリフの輪郭（フォント単位、Y軸は上向き）
+pub struct Shape {
+  contours: Vec<Vec<Edge>>,
+  /// 内側が辺の進行方向の左にあれば 1、右にあれば -1
+  orientation: f64,
+}
+
+impl Shape {
+  /// 輪郭のないグリフ（スペースなど）では `None`
+  pub fn from_glyph(face: &ttf::Face, glyph_id: ttf::GlyphId) -> Option<Self> {
+    let mut builder = ShapeBuilder::default();
+    face.outline_glyph(glyph_id, &mut builder)?;
+    Self::from_builder(builder)
+  }
+
+  fn from_builder(mut builder: ShapeBuilder) -> Option<Self> {
+    builder.finish_contour();
+
+    let contours = builder
+      .contours
+      .into_iter()
+      .filter(|contour| !contour.is_empty())
+      .collect::<Vec<_>>();
+    if contours.is_empty() {
+      return None;
+    }
+
+    // TrueType は外側の輪郭が時計回り、CFF は反時計回りなので、面積の符号で判断する
+    let area: f64 = contours
+      .iter()
+      .flatten()
+      .flat_map(|edge| edge.points.windows(2))
+      .map(|seg| seg[0].cross(seg[1]))
+      .sum();
+
+    let mut shape = Self {
+      contours,
+      orientation: if area > 0. { 1. } else { -1. },
+    };
+    shape.color_edges();
+
+    Some(shape)
+  }
+
+  /// 角で区切った辺のまとまりごとに色を変える（隣り合うまとまりは同じ色にしない）
+  fn color_edges(&mut self) {
+    for contour in &mut self.contours {
+      let n = contour.len();
+      let corners = (0..n)
+        .filter(|&i| {
+          let prev = &contour[(i + n - 1) % n];
+          is_corner(prev.end_dir(), contour[i].start_dir())
+        })
+        .collect::<Vec<_>>();
+
+      match corners.len() {
+        // 滑らかな輪郭は、どのチャンネルでも同じ距離でよい
+        0 => contour.iter_mut().for_each(|edge| edge.color = WHITE),
+        // 角が1つ（しずく形）なら、辺を3つに分けて両側の色を変える
+        1 if n >= 3 => {
+          let start = corners[0];
+          for k in 0..n {
+            contour[(start + k) % n].color =
+              [MAGENTA, WHITE, YELLOW][k * 3 / n];
+          }
+        }
+        1 => contour.iter_mut().for_each(|edge| edge.color = WHITE),
+        _ => {
+          let colors = [MAGENTA, YELLOW, CYAN];
+          let spline_count = corners.len();
+          let start = corners[0];
+          let mut spline = 0;
+
+          for k in 0..n {
+            let i = (start + k) % n;
+            if k > 0 && corners.contains(&i) {
+              spline += 1;
+            }
+
+            // 最後のまとまりが最初と同じ色にならないようにする
+            let is_last = spline == spline_count - 1;
+            contour[i].color = if is_last && spline % 3 == 0 {
+              colors[1]
+            } else {
+              colors[spline % 3]
+            };
+          }
+        }
+      }
+    }
+  }
+
+  /// 点 `p` から、`channel` の色を含む辺までの符号付き距離（内側が正）
+  fn channel_distance(&self, p: Vec2, channel: u8) -> f64 {
+    let mut best: Option<EdgeDistance> = None;
+
+    for edge in self.contours.iter().flatten() {
+      if edge.color & channel == 0 {
+        continue;
+      }
+
+      let d = edge_distance(edge, p);
+      let closer = match &best {
+        None => true,
+        Some(b) => {
+          let (da, db) = (d.distance.abs(), b.distance.abs());
+          // 角の頂点のように距離が同じなら、より垂直に近い辺を選ぶ
+          da < db - 1e-9 || (da <= db + 1e-9 && d.dot < b.dot)
+        }
+      };
+      if closer {
+        best = Some(d);
+      }
+    }
+
+    best.map_or(-f64::INFINITY, |b| b.pseudo_distance * self.orientation)
+  }
+}
+
+fn is_corner(a: Vec2, b: Vec2) -> bool {
+  a.dot(b) <= 0. || a.cross(b).abs() > CORNER_CROSS_THRESHOLD
+}
+
+struct EdgeDistance {
+  /// 辺上の最も近い点までの距離（辺の左側が正）
+  distance: f64,
+  /// 最も近い点が端点のとき、辺の向きと点への向きのなす角の |cos|
+  dot: f64,
+  /// 端点の先では、辺を延長した直線までの距離。角を尖らせるのはこれ
+  pseudo_distance: f64,
+}
+
+fn edge_distance(edge: &Edge, p: Vec2) -> EdgeDistance {
+  let last = edge.points.len() - 2;
+  let mut best = EdgeDistance {
+    distance: f64::INFINITY,
+    dot: 1.,
+    pseudo_distance: f64::INFINITY,
+  };
+
+  for (i, seg) in edge.points.windows(2).enumerate() {
+    let (a, b) = (seg[0], seg[1]);
+    let ab = b.sub(a);
+    let len_sq = ab.dot(ab);
+    if len_sq == 0. {
+      continue;
+    }
+
+    let t = p.sub(a).dot(ab) / len_sq;
+    let closest = a.add(ab.scale(t.clamp(0., 1.)));
+    let cross = ab.cross(p.sub(a));
+    let distance = p.sub(closest).length().copysign(cross);
+
+    if distance.abs() >= best.distance.abs() {
+      continue;
+    }
+
+    let dot = if (0. ..=1.).contains(&t) {
+      0.
+    } else {
+      ab.normalize().dot(p.sub(closest).normalize()).abs()
+    };
+
+    // 辺全体の始点より前、終点より後ろにある点だけ、延長線までの距離を使う
+    let is_outer_end = (i == 0 && t < 0.) || (i == last && t > 1.);
+    let pseudo_distance = if is_outer_end {
+      let perpendicular = cross / len_sq.sqrt();
+      if perpendicular.abs() <= distance.abs() {
+        perpendicular
+      } else {
+        distance
+      }
+    } else {
+      distance
+    };
+
+    best = EdgeDistance {
+      distance,
+      dot,
+      pseudo_distance,
+    };
+  }
+
+  best
+}
+
+/// `shape` の MSDF を、RGB のアトラス `atlas` の矩形 `(x, y, w, h)` に書き込む
+///
+/// `origin` は矩形内のピクセル座標で見たフォントの原点、`scale` はフォント
+/// 単位あたりのピクセル数。値の対応は `to_sdf` と同じ（0.5が輪郭、内側ほど大きい）。
+pub fn draw_glyph(
+  shape: &Shape,
+  atlas: &mut [u8],
+  atlas_width: usize,
+  (x, y, w, h): (usize, usize, usize, usize),
+  origin: (f64, f64),
+  scale: f64,
+  radius: f64,
+) {
+  for py in 0..h {
+    for px in 0..w {
+      // ピクセルの中心をフォント単位へ（ピクセルはY軸が下向き）
+      let p = Vec2::new(
+        (px as f64 + 0.5 - origin.0) / scale,
+        (origin.1 - (py as f64 + 0.5)) / scale,
+      );
+
+      let i = ((y + py) * atlas_width + (x + px)) * 3;
+      for (c, channel) in [RED, GREEN, BLUE].into_iter().enumerate() {
+        let distance = shape.channel_distance(p, channel) * scale;
+        let value = 0.5 + distance / radius;
+        atlas[i + c] = (value * 255.0).clamp(0.0, 255.0) as u8;
+      }
+    }
+  }
+}
+
+#[derive(Default)]
+struct ShapeBuilder {
+  contours: Vec<Vec<Edge>>,
+  current: Vec<Edge>,
+  start: Option<Vec2>,
+  pen: Vec2,
+}
+
+impl ShapeBuilder {
+  fn push(&mut self, points: Vec<Vec2>) {
+    // 長さのない辺は向きが決まらないので捨てる
+    if points.windows(2).all(|seg| seg[0] == seg[1]) {
+      return;
+    }
+    let to = points[points.len() - 1];
+    self.current.push(Edge {
+      points,
+      color: WHITE,
+    });
+    self.pen = to;
+  }
+
+  fn finish_contour(&mut self) {
+    if let Some(start) = self.start.take() {
+      if self.pen != start {
+        self.push(vec![self.pen, start]);
+      }
+    }
+    if !self.current.is_empty() {
+      self.contours.push(std::mem::take(&mut self.current));
+    }
+  }
+}
+
+impl ttf::OutlineBuilder for ShapeBuilder {
+  fn move_to(&mut self, x: f32, y: f32) {
+    self.finish_contour();
+    let p = Vec2::new(x as f64, y as f64);
+    self.start = Some(p);
+    self.pen = p;
+  }
+
+  fn line_to(&mut self, x: f32, y: f32) {
+    let from = self.pen;
+    self.push(vec![from, Vec2::new(x as f64, y as f64)]);
+  }
+
+  fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+    let p0 = self.pen;
+    let p1 = Vec2::new(x1 as f64, y1 as f64);
+    let p2 = Vec2::new(x as f64, y as f64);
+
+    let points = (0..=CURVE_STEPS)
+      .map(|i| {
+        let t = i as f64 / CURVE_STEPS as f64;
+        let u = 1. - t;
+        p0.scale(u * u).add(p1.scale(2. * u * t)).add(p2.scale(t * t))
+      })
+      .collect();
+    self.push(points);
+  }
+
+  fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+    let p0 = self.pen;
+    let p1 = Vec2::new(x1 as f64, y1 as f64);
+    let p2 = Vec2::new(x2 as f64, y2 as f64);
+    let p3 = Vec2::new(x as f64, y as f64);
+
+    let points = (0..=CURVE_STEPS)
+      .map(|i| {
+        let t = i as f64 / CURVE_STEPS as f64;
+        let u = 1. - t;
+        p0.scale(u * u * u)
+          .add(p1.scale(3. * u * u * t))
+          .add(p2.scale(3. * u * t * t))
+          .add(p3.scale(t * t * t))
+      })
+      .collect();
+    self.push(points);
+  }
+
+  fn close(&mut self) {
+    self.finish_contour();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ttf::OutlineBuilder;
+
+  use super::*;
+  use crate::sdf::to_sdf_8ssedt;
+
+  const SIZE: usize = 32;
+  const RADIUS: f64 = 8.;
+  /// フォントの原点を置くピクセル座標（フォント単位 = 1ピクセル）
+  const ORIGIN: (f64, f64) = (4., 28.);
+
+  /// 足の右上 (20, 6) が凸の角になる「L」
+  const L_OUTLINE: [(f32, f32); 6] = [
+    (0., 0.),
+    (20., 0.),
+    (20., 6.),
+    (6., 6.),
+    (6., 24.),
+    (0., 24.),
+  ];
+
+  fn l_shape() -> Shape {
+    let mut builder = ShapeBuilder::default();
+    let (x, y) = L_OUTLINE[0];
+    builder.move_to(x, y);
+    for &(x, y) in &L_OUTLINE[1..] {
+      builder.line_to(x, y);
+    }
+    builder.close();
+    Shape::from_builder(builder).unwrap()
+  }
+
+  /// ピクセルの中心が「L」の内側なら 255
+  fn l_bitmap() -> Vec<u8> {
+    (0..SIZE * SIZE)
+      .map(|i| {
+        let x = (i % SIZE) as f64 + 0.5 - ORIGIN.0;
+        let y = ORIGIN.1 - ((i / SIZE) as f64 + 0.5);
+        let in_foot = (0. ..20.).contains(&x) && (0. ..6.).contains(&y);
+        let in_stem = (0. ..6.).contains(&x) && (0. ..24.).contains(&y);
+        if in_foot || in_stem {
+          255
+        } else {
+          0
+        }
+      })
+      .collect()
+  }
+
+  /// シェーダーと同じく、3チャンネルの中央値を距離とみなす
+  fn median(rgb: &[u8]) -> u8 {
+    let (r, g, b) = (rgb[0], rgb[1], rgb[2]);
+    r.min(g).max(r.max(g).min(b))
+  }
+
+  #[test]
+  fn l_corner_stays_sharp_unlike_single_channel_sdf() {
+    let mut msdf = vec![0u8; SIZE * SIZE * 3];
+    draw_glyph(
+      &l_shape(),
+      &mut msdf,
+      SIZE,
+      (0, 0, SIZE, SIZE),
+      ORIGIN,
+      1.,
+      RADIUS,
+    );
+    let sdf = to_sdf_8ssedt(&l_bitmap(), SIZE, SIZE, RADIUS);
+
+    // 角 (20, 6) から斜めに2.5、足の右の辺から真横に2.5離れたピクセル
+    let corner = 19 * SIZE + 26;
+    let side = 24 * SIZE + 26;
+    let msdf_at = |i: usize| median(&msdf[i * 3..i * 3 + 3]) as i32;
+
+    // MSDF は角の先でも辺の延長線までの距離になり、等値線が四角いまま
+    assert!(
+      (msdf_at(corner) - msdf_at(side)).abs() <= 2,
+      "msdf corner {} side {}",
+      msdf_at(corner),
+      msdf_at(side)
+    );
+    assert!(msdf_at(side) < 128);
+
+    // 1チャンネルの SDF は角の頂点までの距離になり、等値線が丸まる
+    let (sdf_corner, sdf_side) = (sdf[corner] as i32, sdf[side] as i32);
+    assert!(
+      sdf_side - sdf_corner >= 8,
+      "sdf corner {} side {}",
+      sdf_corner,
+      sdf_side
+    );
+  }
+}
@@ -25,17 +25,24 @@ struct GlyphData {
   glyphs: array<Glyph>,
 };
 
+struct View {
+  offset: vec2f,
+  zoom: f32,
+};
+
 @group(0) @binding(0) var<storage> text: GlyphData;
 @group(0) @binding(1) var font_atlas_sampler: sampler;
 @group(0) @binding(2) var font_atlas: texture_2d<f32>;
+@group(0) @binding(3) var<uniform> view: View;
 // TODO: setup dpr
-// @group(0) @binding(3) var<uniform> dpr: f32;
+// @group(0) @binding(4) var<uniform> dpr: f32;
 
 @vertex
 fn vs_main(input: VertexInput) -> VertexOutput {
   var output: VertexOutput;
   let g = text.glyphs[input.instance];
-  let vertex = mix(g.position.xy, g.position.xy + g.size, input.position);
+  let local = mix(g.position.xy, g.position.xy + g.size, input.position);
+  let vertex = local * view.zoom + view.offset;
 
   output.position = vec4f(vertex / g.window * 2 - 1, 0, 1);
   output.position.y = -output.position.y;
@@ -49,14 +56,20 @@ fn vs_main(input: VertexInput) -> VertexOutput {
 fn fs_main(input: VertexOutput) -> @location(0) vec4f {
   let g = text.glyphs[input.instance];
 
-  // textureSample.a : 矩形を描画
-  // textureSample.r : 文字を描画
-  let distance = textureSample(font_atlas, font_atlas_sampler, input.uv).r;
+  // MSDF: 3チャンネルの中央値が輪郭までの距離になる（SDFは3チャンネルとも同じ値）
+  let texel = textureSample(font_atlas, font_atlas_sampler, input.uv);
+  let distance = median(texel.r, texel.g, texel.b);
 
-  var width = mix(0.4, 0.1, clamp(g.font_size, 0.0, 40.0) / 40.0);
+  // 拡大するほど画面上の文字は大きいので、輪郭のぼかしを狭くする
+  let screen_size = g.font_size * view.zoom;
+  var width = mix(0.4, 0.1, clamp(screen_size, 0.0, 40.0) / 40.0);
   // TODO: apply dpr
   width /= 2.0;
   let alpha = g.color.a * smoothstep(0.5 - width, 0.5 + width, distance);
 
   return vec4f(g.color.rgb, alpha);
 }
+
+fn median(r: f32, g: f32, b: f32) -> f32 {
+  return max(min(r, g), min(max(r, g), b));
+}
@@ -1,8 +1,14 @@
 use std::sync::Arc;
 
-use winit::{dpi::PhysicalSize, window::Window};
+use winit::{
+  dpi::{PhysicalPosition, PhysicalSize},
+  window::Window,
+};
 
-use crate::renderer::{FontData, UiRenderer};
+use crate::renderer::{FontData, Text, UiRenderer};
+
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 32.0;
 
 pub struct WindowState<'a> {
   pub window: Arc<Window>,
@@ -13,6 +19,13 @@ pub struct WindowState<'a> {
   queue: wgpu::Queue,
   ui: UiRenderer,
   font_data: Option<FontData>,
+  /// 拡大率（`MIN_ZOOM`..=`MAX_ZOOM`）
+  zoom: f32,
+  /// 拡大した後にずらす量（ピクセル）
+  panned: PhysicalPosition<f32>,
+  cursor_position: Option<PhysicalPosition<f64>>,
+  /// 中ボタンでドラッグ中か
+  dragging: bool,
 }
 
 impl<'a> WindowState<'a> {
@@ -63,6 +76,10 @@ impl<'a> WindowState<'a> {
       queue,
       ui,
       font_data: None,
+      zoom: 1.0,
+      panned: PhysicalPosition::new(0.0, 0.0),
+      cursor_position: None,
+      dragging: false,
     }
   }
 
@@ -80,6 +97,37 @@ impl<'a> WindowState<'a> {
     }
   }
 
+  pub fn cursor_moved(&mut self, position: PhysicalPosition<f64>) {
+    if let (true, Some(prev)) = (self.dragging, self.cursor_position) {
+      self.panned.x += (position.x - prev.x) as f32;
+      self.panned.y += (position.y - prev.y) as f32;
+    }
+    self.cursor_position = Some(position);
+  }
+
+  pub fn cursor_left(&mut self) {
+    self.cursor_position = None;
+    self.dragging = false;
+  }
+
+  pub fn set_dragging(&mut self, dragging: bool) {
+    self.dragging = dragging;
+  }
+
+  /// カーソルの下の点が動かないように `factor` 倍する
+  ///
+  /// カーソルがウィンドウの外にあるときは左上を中心にする。
+  pub fn zoom_by(&mut self, factor: f32) {
+    let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+    let scale = zoom / self.zoom;
+    let (cx, cy) =
+      self.cursor_position.map_or((0.0, 0.0), |p| (p.x as f32, p.y as f32));
+
+    self.panned.x = cx - (cx - self.panned.x) * scale;
+    self.panned.y = cy - (cy - self.panned.y) * scale;
+    self.zoom = zoom;
+  }
+
   pub fn set_font(
     &mut self,
     font_atlas_size: (u32, u32),
@@ -102,7 +150,14 @@ impl<'a> WindowState<'a> {
     color: [f32; 4],
     uvs: &Vec<[f32; 4]>,
   ) {
-    self.ui.text(char_rects, origin, font_size, color, uvs);
+    self.ui.clear_text();
+    self.ui.queue_text(&Text {
+      char_rects,
+      uvs,
+      position: origin,
+      font_size,
+      color,
+    });
   }
 
   pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
@@ -116,6 +171,7 @@ impl<'a> WindowState<'a> {
         label: Some("render blurred rectangles"),
       });
 
+    self.ui.set_view(&self.queue, self.zoom, [self.panned.x, self.panned.y]);
     self.ui.render(
       &mut encoder,
       &self.queue,
@@ -1,10 +1,12 @@
 use std::error::Error;
 use std::f32::consts::PI;
+use std::path::Path;
 use std::{mem, time};
 
 use bytemuck::{Pod, Zeroable};
 use cgmath::*;
 use wgpu::util::DeviceExt;
+use wgsim::anim::{Easing, Timeline};
 use wgsim::app::App;
 use wgsim::ctx::{DrawingContext, Size};
 use wgsim::export::Gif;
@@ -13,8 +15,15 @@ use wgsim::geometry::Cube;
 use wgsim::matrix;
 use wgsim::ppl::RenderPipelineBuilder;
 use wgsim::render::{Render, RenderTarget};
+use wgsim::shader;
 use wgsim::util;
 
+// 実行中に書き換えて保存すると、パイプラインが作り直される（run_shader_watch）
+const VS_PATH: &str =
+  concat!(env!("CARGO_MANIFEST_DIR"), "/src/shader-vert.wgsl");
+const FS_PATH: &str =
+  concat!(env!("CARGO_MANIFEST_DIR"), "/src/shader-frag.wgsl");
+
 pub fn run(title: &str) -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
@@ -33,9 +42,70 @@ pub fn run(title: &str) -> Result<(), Box<dyn Error>> {
     material: Material::default(),
 
     rotation_speed: 1.,
+    transparent: false,
+  };
+
+  let mut app: App<State> = App::new(title, initial)
+    .with_msaa()
+    .with_device_loss_simulation()
+    .with_resize_debounce(time::Duration::from_millis(100));
+  app.run()?;
+
+  Ok(())
+}
+
+pub fn run_transparent(title: &str) -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let (vertex_data, index_data) = create_vertices();
+
+  let initial = Initial {
+    vertex_data,
+    index_data,
+
+    camera_position: Point3::new(3., 1.5, 3.),
+    look_direction: Point3::new(0., 0., 0.),
+    up_direction: Vector3::unit_y(),
+
+    specular_color: [1., 1., 1.],
+    object_color: [0.855, 0.792, 0.969],
+    material: Material::default(),
+
+    rotation_speed: 1.,
+    transparent: true,
   };
 
-  let mut app: App<State> = App::new(title, initial).with_msaa();
+  let mut app: App<State> =
+    App::new(title, initial).with_msaa().with_transparent_window();
+  app.run()?;
+
+  Ok(())
+}
+
+pub fn run_shader_watch(title: &str) -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let (vertex_data, index_data) = create_vertices();
+
+  let initial = Initial {
+    vertex_data,
+    index_data,
+
+    camera_position: Point3::new(3., 1.5, 3.),
+    look_direction: Point3::new(0., 0., 0.),
+    up_direction: Vector3::unit_y(),
+
+    specular_color: [1., 1., 1.],
+    object_color: [0.855, 0.792, 0.969],
+    material: Material::default(),
+
+    rotation_speed: 1.,
+    transparent: false,
+  };
+
+  let mut app: App<State> = App::new(title, initial)
+    .with_msaa()
+    .with_shader_watch(&[Path::new(VS_PATH), Path::new(FS_PATH)]);
   app.run()?;
 
   Ok(())
@@ -59,10 +129,11 @@ pub async fn export_gif() -> Result<(), Box<dyn Error>> {
     material: Material::default(),
 
     rotation_speed: 2.5,
+    transparent: false,
   };
 
-  let mut gif = Gif::<State>::new(1024, initial, true).await;
-  gif.export("export/with_gif-msaa-5.gif", 50, 1).await?;
+  let mut gif = Gif::<State>::new(1024, initial, true).await?;
+  gif.export("export/with_gif-msaa-6.gif", 50, 1).await?;
 
   Ok(())
 }
@@ -91,6 +162,46 @@ fn create_vertices() -> (Vec<Vertex>, Vec<u16>) {
   (data, indices)
 }
 
+// 少し傾けた軸まわりに、1周の k/3 だけ回す
+fn turn(k: f32) -> Quaternion<f32> {
+  let axis = Vector3::new(0.3, 1., 0.).normalize();
+  Quaternion::from_axis_angle(axis, Rad(2. * PI / 3. * k))
+}
+
+// ウィンドウ表示用のタイムライン（回転と拡大率）
+// 1周 4 秒で、区間ごとにゆっくり動き出して止まりながら回り、その間に少し膨らむ
+fn create_timelines() -> (Timeline<Quaternion<f32>>, Timeline<f32>) {
+  let rotation = Timeline::new(turn(0.))
+    .key(4. / 3., turn(1.), Easing::EaseInOut)
+    .key(8. / 3., turn(2.), Easing::EaseInOut)
+    .key(4., turn(3.), Easing::EaseInOut)
+    .looped();
+  let scale = Timeline::new(1.)
+    .key(2., 1.15, Easing::Cubic)
+    .key(4., 1., Easing::Cubic)
+    .looped();
+
+  (rotation, scale)
+}
+
+// GIF 用のキーフレーム（モデル行列）
+// 最後のキーフレームから最初に戻ってループする
+fn create_keyframes() -> Vec<Matrix4<f32>> {
+  vec![
+    matrix::compose(Vector3::zero(), turn(0.), Vector3::new(1., 1., 1.)),
+    matrix::compose(
+      Vector3::new(0., 0.3, 0.),
+      turn(1.),
+      Vector3::new(1.15, 1.15, 1.15),
+    ),
+    matrix::compose(
+      Vector3::new(0., -0.3, 0.),
+      turn(2.),
+      Vector3::new(0.9, 0.9, 0.9),
+    ),
+  ]
+}
+
 struct Initial {
   pub vertex_data: Vec<Vertex>,
   pub index_data: Vec<u16>,
@@ -102,6 +213,7 @@ struct Initial {
   pub object_color: [f32; 3],
   pub material: Material,
   pub rotation_speed: f32,
+  pub transparent: bool,
 }
 
 #[repr(C)]
@@ -126,6 +238,7 @@ impl Default for Material {
 struct State {
   /// drawing context
   pipeline: wgpu::RenderPipeline,
+  pipeline_layout: wgpu::PipelineLayout,
 
   /// model data
   vertex_buffer: wgpu::Buffer,
@@ -144,8 +257,13 @@ struct State {
   view_mat: Matrix4<f32>,
   project_mat: Matrix4<f32>,
 
+  /// animation
+  rotation_timeline: Timeline<Quaternion<f32>>,
+  scale_timeline: Timeline<f32>,
+
   /// rendering settings
   rotation_speed: f32,
+  clear_color: wgpu::Color,
 }
 
 impl<'a> Render<'a> for State {
@@ -160,6 +278,8 @@ impl<'a> Render<'a> for State {
       .create_shader_module(wgpu::include_wgsl!("./shader-frag.wgsl"));
 
     let aspect = ctx.aspect_ratio();
+    let (rotation_timeline, scale_timeline) = create_timelines();
+
     let view_mat = matrix::create_view_mat(
       initial.camera_position,
       initial.look_direction,
@@ -242,12 +362,6 @@ impl<'a> Render<'a> for State {
       ],
     );
 
-    let vertex_buffer_layout = [wgpu::VertexBufferLayout {
-      array_stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
-      step_mode: wgpu::VertexStepMode::Vertex,
-      attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3],
-    }];
-
     let pipeline_layout =
       ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("Render Pipeline Layout"),
@@ -255,14 +369,8 @@ impl<'a> Render<'a> for State {
         push_constant_ranges: &[],
       });
 
-    let pipeline_builder = RenderPipelineBuilder::new(&ctx)
-      .vs_shader(&vs_shader, "vs_main")
-      .fs_shader(&fs_shader, "fs_main")
-      .pipeline_layout(&pipeline_layout)
-      .vertex_buffer_layout(&vertex_buffer_layout)
-      .enable_depth_stencil(None);
-
-    let pipeline = pipeline_builder.build();
+    let pipeline =
+      create_pipeline(ctx, &pipeline_layout, &vs_shader, &fs_shader);
 
     let msaa_texture_view = util::create_msaa_texture_view(&ctx);
     let depth_texture_view = util::create_depth_view(&ctx);
@@ -283,6 +391,7 @@ impl<'a> Render<'a> for State {
 
     Self {
       pipeline,
+      pipeline_layout,
       vertex_buffer: vertex_buffer.into(),
       index_buffer: index_buffer.into(),
       uniform_bind_groups: vec![vert_bind_group, frag_bind_group],
@@ -292,7 +401,15 @@ impl<'a> Render<'a> for State {
       msaa_texture_view,
       depth_texture_view,
       indices_len: initial.index_data.len() as u32,
+      rotation_timeline,
+      scale_timeline,
       rotation_speed: initial.rotation_speed,
+      // 透過ウィンドウでは背景をalpha=0でクリアし、デスクトップを透かす
+      clear_color: if initial.transparent {
+        wgpu::Color::TRANSPARENT
+      } else {
+        wgpu::Color::BLACK
+      },
     }
   }
 
@@ -313,33 +430,60 @@ impl<'a> Render<'a> for State {
     }
   }
 
-  fn update(&mut self, ctx: &DrawingContext, dt: time::Duration) {
-    let dt = self.rotation_speed * dt.as_secs_f32();
+  fn reload_shaders(&mut self, ctx: &DrawingContext) {
+    let shaders =
+      shader::load_wgsl(&ctx.device, Path::new(VS_PATH)).and_then(|vs| {
+        Ok((vs, shader::load_wgsl(&ctx.device, Path::new(FS_PATH))?))
+      });
+    let (vs_shader, fs_shader) = match shaders {
+      Ok(shaders) => shaders,
+      Err(e) => {
+        // コンパイルできなければ、前のパイプラインのまま描き続ける
+        eprintln!("Shader reload failed: {}", e);
+        return;
+      }
+    };
 
-    let model_mat =
-      matrix::create_model_mat_with_rotation([dt.sin(), dt.cos(), 0.]);
-    let view_proj_mat = self.project_mat * self.view_mat;
-    let normal_mat = (model_mat.invert().unwrap()).transpose();
+    let pipeline = shader::capture_validation(&ctx.device, || {
+      create_pipeline(ctx, &self.pipeline_layout, &vs_shader, &fs_shader)
+    });
+    match pipeline {
+      Ok(pipeline) => self.pipeline = pipeline,
+      Err(e) => eprintln!("Pipeline rebuild failed: {}", e),
+    }
+  }
 
-    let model_ref: &[f32; 16] = model_mat.as_ref();
-    let view_proj_ref: &[f32; 16] = view_proj_mat.as_ref();
-    let normal_ref: &[f32; 16] = normal_mat.as_ref();
+  fn update_frame(
+    &mut self,
+    ctx: &DrawingContext,
+    frame_index: usize,
+    total_frames: usize,
+  ) {
+    // キーフレームの間を補間する
+    // 1周分をちょうど total_frames で描き、ループの継ぎ目をなくす
+    let keyframes = create_keyframes();
+    let position =
+      keyframes.len() as f32 * frame_index as f32 / total_frames as f32;
+    let i = position.floor() as usize % keyframes.len();
+    let next = (i + 1) % keyframes.len();
 
-    ctx.queue.write_buffer(
-      &self.matrix_uniform_buffer,
-      16 * 4 * 0,
-      bytemuck::cast_slice(view_proj_ref),
-    );
-    ctx.queue.write_buffer(
-      &self.matrix_uniform_buffer,
-      16 * 4 * 1,
-      bytemuck::cast_slice(model_ref),
-    );
-    ctx.queue.write_buffer(
-      &self.matrix_uniform_buffer,
-      16 * 4 * 2,
-      bytemuck::cast_slice(normal_ref),
+    let model_mat =
+      matrix::slerp_transforms(keyframes[i], keyframes[next], position.fract());
+    self.write_matrices(ctx, model_mat);
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, dt: time::Duration) {
+    // 経過時間だけで決まるので、何度実行しても同じ動きになる
+    let time = self.rotation_speed * dt.as_secs_f32();
+    let rotation = self.rotation_timeline.sample(time);
+    let scale = self.scale_timeline.sample(time);
+
+    let model_mat = matrix::compose(
+      Vector3::zero(),
+      rotation,
+      Vector3::new(scale, scale, scale),
     );
+    self.write_matrices(ctx, model_mat);
   }
 
   fn draw(
@@ -364,11 +508,12 @@ impl<'a> Render<'a> for State {
     let color_attach = util::create_color_attachment(&view);
     let msaa_attach =
       util::create_msaa_color_attachment(&view, &self.msaa_texture_view);
-    let color_attachment = if sample_count == 1 {
+    let mut color_attachment = if sample_count == 1 {
       color_attach
     } else {
       msaa_attach
     };
+    color_attachment.ops.load = wgpu::LoadOp::Clear(self.clear_color);
     let depth_attachment =
       util::create_depth_stencil_attachment(&self.depth_texture_view);
 
@@ -394,3 +539,51 @@ impl<'a> Render<'a> for State {
     Ok(frame)
   }
 }
+
+impl State {
+  fn write_matrices(&self, ctx: &DrawingContext, model_mat: Matrix4<f32>) {
+    let view_proj_mat = self.project_mat * self.view_mat;
+    let normal_mat = (model_mat.invert().unwrap()).transpose();
+
+    let model_ref: &[f32; 16] = model_mat.as_ref();
+    let view_proj_ref: &[f32; 16] = view_proj_mat.as_ref();
+    let normal_ref: &[f32; 16] = normal_mat.as_ref();
+
+    ctx.queue.write_buffer(
+      &self.matrix_uniform_buffer,
+      16 * 4 * 0,
+      bytemuck::cast_slice(view_proj_ref),
+    );
+    ctx.queue.write_buffer(
+      &self.matrix_uniform_buffer,
+      16 * 4 * 1,
+      bytemuck::cast_slice(model_ref),
+    );
+    ctx.queue.write_buffer(
+      &self.matrix_uniform_buffer,
+      16 * 4 * 2,
+      bytemuck::cast_slice(normal_ref),
+    );
+  }
+}
+
+fn create_pipeline(
+  ctx: &DrawingContext,
+  pipeline_layout: &wgpu::PipelineLayout,
+  vs_shader: &wgpu::ShaderModule,
+  fs_shader: &wgpu::ShaderModule,
+) -> wgpu::RenderPipeline {
+  let vertex_buffer_layout = [wgpu::VertexBufferLayout {
+    array_stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
+    step_mode: wgpu::VertexStepMode::Vertex,
+    attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3],
+  }];
+
+  RenderPipelineBuilder::new(ctx)
+    .vs_shader(vs_shader, "vs_main")
+    .fs_shader(fs_shader, "fs_main")
+    .pipeline_layout(pipeline_layout)
+    .vertex_buffer_layout(&vertex_buffer_layout)
+    .enable_depth_stencil(None)
+    .build()
+}
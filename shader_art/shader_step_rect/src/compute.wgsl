@@ -9,13 +9,13 @@ struct CsInput {
 
 @compute @workgroup_size(8, 8)
 fn cs_main(in: CsInput) {
-  let tex_size = 512;
+  let tex_size = textureDimensions(output_texture);
   
   //
   // Map the global_id to the UV coordinates
   //
   
-  let uv = vec2f(f32(in.global_id.x), f32(in.global_id.y)) / f32(tex_size);
+  let uv = vec2f(in.global_id.xy) / vec2f(tex_size);
   
   //
   // Shader Art
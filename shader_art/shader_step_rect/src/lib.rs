@@ -1,3 +1,4 @@
+use std::f32::consts::PI;
 use std::{error::Error, path::Path};
 
 use wgsim::compute::pixel::ComputePixel;
@@ -5,14 +6,16 @@ use wgsim::compute::pixel::ComputePixel;
 pub async fn run() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
-  const IMG_SIZE: u32 = 512;
+  const WIDTH: u32 = 960;
+  const HEIGHT: u32 = 540;
   const EXPORT_PATH: &str = "export/shader-step-rect.png";
 
-  let pixel = ComputePixel::new(
+  let pixel = ComputePixel::new_with_size(
     wgpu::include_wgsl!("./compute.wgsl"),
     "cs_main",
     wgpu::TextureFormat::Rgba8Unorm,
-    IMG_SIZE,
+    WIDTH,
+    HEIGHT,
   )
   .await?;
 
@@ -23,3 +26,40 @@ pub async fn run() -> Result<(), Box<dyn Error>> {
 
   Ok(())
 }
+
+pub async fn run_animated() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  const WIDTH: u32 = 960;
+  const HEIGHT: u32 = 540;
+  const FRAMES: usize = 30;
+
+  let pixel = ComputePixel::new_with_size(
+    wgpu::include_wgsl!("./compute-animated.wgsl"),
+    "cs_main",
+    wgpu::TextureFormat::Rgba8Unorm,
+    WIDTH,
+    HEIGHT,
+  )
+  .await?;
+
+  // 1周期をFRAMES枚に分ける
+  let frames = pixel
+    .compute_frames(
+      |frame| {
+        let time = 2. * PI * frame as f32 / FRAMES as f32;
+        time.to_le_bytes().to_vec()
+      },
+      FRAMES,
+      8,
+      8,
+    )
+    .await?;
+
+  for (i, px_data) in frames.iter().enumerate() {
+    let path = format!("export/shader-step-rect-{:02}.png", i);
+    pixel.export_png(Path::new(&path), px_data)?;
+  }
+
+  Ok(())
+}
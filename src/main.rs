@@ -1,14 +1,29 @@
 use std::{env, error::Error};
 
+// サンプルのinfoログ（パラメータの変更など）は出し、wgpu内部とnagaはwarn以上に絞る
+// 優先順位：--quiet（常にwarn）> 指定済みのRUST_LOG > このフィルタ
+const DEFAULT_LOG_FILTER: &str = "info,wgpu_core=warn,wgpu_hal=warn,naga=warn";
+
 fn main() -> Result<(), Box<dyn Error>> {
   let args: Vec<String> = env::args().collect();
+  let quiet = args.iter().any(|arg| arg == "--quiet");
+  let args: Vec<&String> =
+    args.iter().filter(|arg| *arg != "--quiet").collect();
 
   if args.len() < 2 {
-    eprintln!("Usage: {} <workspace_member>", args[0]);
+    eprintln!("Usage: {} <workspace_member> [--quiet]", args[0]);
     return Ok(());
   }
 
-  let target = &args[1];
+  // サンプル側のenv_logger::init()がRUST_LOGを読むので、先に設定しておく
+  // --quiet: パラメータ変更などのinfoログを抑え、warn以上のみ出力する
+  if quiet {
+    env::set_var("RUST_LOG", "warn");
+  } else if env::var_os("RUST_LOG").is_none() {
+    env::set_var("RUST_LOG", DEFAULT_LOG_FILTER);
+  }
+
+  let target = args[1];
 
   match target.as_str() {
     "tutorial/ch01-window" => ch01_window::run(),
@@ -39,17 +54,43 @@ fn main() -> Result<(), Box<dyn Error>> {
     "tutorial/compute_mandelbrot_set" => {
       Ok(pollster::block_on(compute_mandelbrot_set::run())?)
     }
+    "tutorial/compute_mandelbrot_set:hdr" => {
+      Ok(pollster::block_on(compute_mandelbrot_set::run_hdr())?)
+    }
+    "tutorial/compute_mandelbrot_set:explore" => {
+      Ok(compute_mandelbrot_set::run_explorer()?)
+    }
+    "gpgpu/compute_prefix_sum" => {
+      Ok(pollster::block_on(compute_prefix_sum::run())?)
+    }
+    "gpgpu/compute_histogram" => {
+      Ok(pollster::block_on(compute_histogram::run())?)
+    }
+    "gpgpu/compute_nbody" => Ok(compute_nbody::run()?),
     "shader_art/shader_step_rect" => {
       Ok(pollster::block_on(shader_step_rect::run())?)
     }
+    "shader_art/shader_step_rect:animated" => {
+      Ok(pollster::block_on(shader_step_rect::run_animated())?)
+    }
     "image_processing/image_blur" => Ok(image_blur::run()?),
     "image_processing/image_average_filter" => Ok(image_average_filter::run()?),
     "image_processing/image_gaussian_filter" => {
       Ok(image_gaussian_filter::run()?)
     }
+    "image_processing/image_box_blur" => Ok(image_box_blur::run()?),
+    "image_processing/image_sobel" => Ok(image_sobel::run()?),
+    "image_processing/image_median" => Ok(image_median::run()?),
+    "image_processing/image_bilateral" => Ok(image_bilateral::run()?),
+    "image_processing/image_histogram_eq" => Ok(image_histogram_eq::run()?),
     "prototype/rect-renderer" => rect_renderer::run(),
     "prototype/text-renderer" => text_renderer::proto(),
+    "prototype/text-renderer:edt" => text_renderer::proto_edt(),
     "prototype/with_gif" => Ok(with_gif::run("with_gif")?),
+    "prototype/with_gif:transparent" => {
+      Ok(with_gif::run_transparent("with_gif")?)
+    }
+    "prototype/with_gif:watch" => Ok(with_gif::run_shader_watch("with_gif")?),
     "export/with_gif" => Ok(pollster::block_on(with_gif::export_gif())?),
     "with_gif/life_game" => Ok(with_gif_life_game::run()?),
     "export:gif/life_game" => {
@@ -61,9 +102,18 @@ fn main() -> Result<(), Box<dyn Error>> {
     "practice/rotate_cube_basic" => {
       Ok(rotate_cube_basic::run("rotate_cube_basic")?)
     }
+    "practice/sphere_subdivision" => Ok(sphere_subdivision::run()?),
+    "practice/textured_cube" => Ok(textured_cube::run()?),
+    "practice/gradient_cube" => Ok(gradient_cube::run()?),
     "instanced_cube_sphere_torus/base" => {
       Ok(instanced_cube_sphere_torus_base::run()?)
     }
+    "instanced_cube_sphere_torus/base:wireframe" => {
+      Ok(instanced_cube_sphere_torus_base::run_wireframe()?)
+    }
+    "instanced_cube_sphere_torus/base:indirect" => {
+      Ok(instanced_cube_sphere_torus_base::run_indirect()?)
+    }
     "instanced_cube_sphere_torus/direction_light_1" => {
       Ok(instanced_cube_sphere_torus_direction_light_1::run()?)
     }
@@ -72,14 +122,37 @@ fn main() -> Result<(), Box<dyn Error>> {
         instanced_cube_sphere_torus_direction_light_1::export_gif(),
       )?)
     }
+    "instanced_cube_sphere_torus/direction_light_1:bloom" => {
+      Ok(instanced_cube_sphere_torus_direction_light_1::run_bloom()?)
+    }
+    "instanced_cube_sphere_torus/direction_light_1:tone-map" => {
+      Ok(instanced_cube_sphere_torus_direction_light_1::run_tone_map()?)
+    }
+    "instanced_cube_sphere_torus/direction_light_1:dof" => {
+      Ok(instanced_cube_sphere_torus_direction_light_1::run_dof()?)
+    }
+    "instanced_cube_sphere_torus/direction_light_1:dof-adaptive" => {
+      Ok(instanced_cube_sphere_torus_direction_light_1::run_dof_adaptive()?)
+    }
+    "export-gif:instanced_cube_sphere_torus/direction_light_1:dof" => {
+      Ok(pollster::block_on(
+        instanced_cube_sphere_torus_direction_light_1::export_gif_dof(),
+      )?)
+    }
     "instanced_cube_sphere_torus/direction_light_2" => {
       Ok(instanced_cube_sphere_torus_direction_light_2::run()?)
     }
+    "instanced_cube_sphere_torus/direction_light_2:fly" => {
+      Ok(instanced_cube_sphere_torus_direction_light_2::run_fly()?)
+    }
     "export-gif:instanced_cube_sphere_torus/direction_light_2" => {
       Ok(pollster::block_on(
         instanced_cube_sphere_torus_direction_light_2::export_gif(),
       )?)
     }
+    "instanced_cube_sphere_torus/shadow_map" => {
+      Ok(instanced_cube_sphere_torus_shadow_map::run()?)
+    }
     "examples/empty_window" => Ok(empty_window::run()?),
     _ => {
       eprintln!("Not found: {}", target);
@@ -1,19 +1,12 @@
 use std::{error::Error, iter};
 
-use num_traits::FromBytes;
 use wgpu::util::DeviceExt;
 use wgpu_helper::context as helper_util;
+use wgsim::util;
 
 pub async fn run() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
-  //
-  // define constants
-  //
-
-  // std::mem::size_of::<i32>()で求められるが、ハードコーディングしてしまう
-  let buffer_size = 4;
-
   //
   // init wgpu
   //
@@ -149,17 +142,11 @@ pub async fn run() -> Result<(), Box<dyn Error>> {
   // result
   //
 
-  let non_atomic_result_data: Vec<i32> = get_gpu_buffer(
-    &device,
-    &queue,
-    &non_atomic_result_data_buffer,
-    buffer_size,
-  )
-  .await;
+  let non_atomic_result_data: Vec<i32> =
+    util::read_buffer(&device, &queue, &non_atomic_result_data_buffer).await;
 
   let atomic_result_data: Vec<i32> =
-    get_gpu_buffer(&device, &queue, &atomic_result_data_buffer, buffer_size)
-      .await;
+    util::read_buffer(&device, &queue, &atomic_result_data_buffer).await;
 
   // アトミックロックを使用した場合は正しい結果が得られる
   // アトミックロックを使用しない場合は誤った結果が出る可能性がある（まれに正しい結果が得られることもあるが、多くの場合は失敗する）
@@ -168,68 +155,3 @@ pub async fn run() -> Result<(), Box<dyn Error>> {
 
   Ok(())
 }
-
-async fn get_gpu_buffer<T>(
-  device: &wgpu::Device,
-  queue: &wgpu::Queue,
-  src_buffer: &wgpu::Buffer,
-  buffer_size: u64,
-) -> Vec<T>
-where
-  T: FromBytes<Bytes = [u8; 4]>,
-{
-  let tmp_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-    label: Some("tmp_buffer"),
-    size: buffer_size,
-    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-    mapped_at_creation: false,
-  });
-
-  let mut command_encoder =
-    device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-      label: Some("command_encoder for tmp"),
-    });
-
-  //
-  // Encode commands for copying buffer to buffer
-  //
-
-  command_encoder.copy_buffer_to_buffer(
-    &src_buffer,
-    0,
-    &tmp_buffer,
-    0,
-    buffer_size,
-  );
-
-  //
-  // Submit GPU commands
-  //
-
-  queue.submit(iter::once(command_encoder.finish()));
-
-  //
-  // Read buffer
-  //
-
-  let buffer_slice = tmp_buffer.slice(..);
-
-  let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
-  buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-    tx.send(result).unwrap();
-  });
-  device.poll(wgpu::Maintain::Wait);
-  rx.receive().await.unwrap().unwrap();
-
-  let data_view = buffer_slice.get_mapped_range();
-
-  let data = data_view
-    .chunks_exact(buffer_size as usize)
-    .map(|b| FromBytes::from_ne_bytes(&b.try_into().unwrap()))
-    .collect::<Vec<T>>();
-
-  drop(data_view);
-  tmp_buffer.unmap();
-
-  data
-}
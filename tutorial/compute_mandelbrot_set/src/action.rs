@@ -0,0 +1,29 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  IncreaseMaxIter,
+  DecreaseMaxIter,
+  TogglePrecision,
+  ResetView,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::IncreaseMaxIter => "Increase max iterations",
+      Action::DecreaseMaxIter => "Decrease max iterations",
+      Action::TogglePrecision => "Toggle single / double-float precision",
+      Action::ResetView => "Reset view",
+    }
+  }
+}
+
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new()
+    .bind(KeyCode::KeyO, Action::IncreaseMaxIter)
+    .bind(KeyCode::KeyU, Action::DecreaseMaxIter)
+    .bind(KeyCode::KeyP, Action::TogglePrecision)
+    .bind(KeyCode::KeyR, Action::ResetView)
+}
@@ -0,0 +1,60 @@
+// df64_* は wgsim::shader::DF64_WGSL から先頭に連結される
+
+struct Params {
+  // x.hi, x.lo, y.hi, y.lo
+  center: vec4f,
+  // 1ピクセルあたりの複素平面上の幅
+  scale: vec2f,
+  resolution: vec2f,
+  max_iter: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4f {
+  // 画面全体を覆う三角形
+  var pos = array<vec2f, 3>(
+    vec2f(-1.0, -1.0),
+    vec2f( 3.0, -1.0),
+    vec2f(-1.0,  3.0),
+  );
+  
+  return vec4f(pos[i], 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) frag_coord: vec4f) -> @location(0) vec4f {
+  //
+  // Map the pixel to the complex plane (y axis up)
+  //
+  
+  // ピクセル中心の半整数なので、f32 でも誤差は出ない
+  let offset = (frag_coord.xy - params.resolution * 0.5) * vec2f(1.0, -1.0);
+  let cx = df64_add(params.center.xy, df64_mul_f32(params.scale, offset.x));
+  let cy = df64_add(params.center.zw, df64_mul_f32(params.scale, offset.y));
+  
+  //
+  // Mandelbrot set iteration
+  //
+  
+  var zx = vec2f(0.0);
+  var zy = vec2f(0.0);
+  
+  for (var i = 0u; i < params.max_iter; i = i + 1u) {
+    // Mandelbrot formula: z = z^2 + c
+    let x2 = df64_mul(zx, zx);
+    let y2 = df64_mul(zy, zy);
+    // 2倍は指数が変わるだけなので、hi と lo をそのまま2倍してよい
+    zy = df64_add(2.0 * df64_mul(zx, zy), cy);
+    zx = df64_add(df64_sub(x2, y2), cx);
+    
+    // 脱出判定は単精度で十分
+    if (zx.x * zx.x + zy.x * zy.x > 4.0) {
+      let t = f32(i) / f32(params.max_iter);
+      return vec4f(t, t, t, 1.0);
+    }
+  }
+  
+  return vec4f(0.0, 0.0, 0.0, 1.0);
+}
@@ -0,0 +1,357 @@
+use std::error::Error;
+
+use bytemuck::{Pod, Zeroable};
+use wgsim::app::App;
+use wgsim::ctx::{DrawingContext, Size};
+use wgsim::input::KeyBindings;
+use wgsim::ppl::RenderPipelineBuilder;
+use wgsim::render::{Render, RenderTarget};
+use wgsim::shader::{self, Precision};
+use wgsim::util;
+use winit::dpi::PhysicalPosition;
+use winit::event::{MouseButton, WindowEvent};
+
+use crate::action::{self, Action};
+
+const INITIAL_CENTER: [f64; 2] = [-0.5, 0.];
+// 初期状態で画面の高さに収める複素平面上の幅
+const INITIAL_HEIGHT: f64 = 2.5;
+const INITIAL_MAX_ITER: u32 = 100;
+
+const MIN_MAX_ITER: u32 = 50;
+const MAX_MAX_ITER: u32 = 5000;
+const MAX_ITER_STEP: u32 = 50;
+
+// スクロール1行あたりの拡大率
+const ZOOM_PER_LINE: f64 = 1.2;
+// 1ピクセルの幅が中心座標のULPのこの倍数を下回ったら警告する
+const PRECISION_MARGIN: f64 = 4.;
+
+pub fn run_explorer() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = Initial {
+    precision: Precision::Single,
+    key_bindings: action::default_key_bindings(),
+  };
+  initial.key_bindings.print_help();
+  log::info!("Scroll to zoom toward the cursor, drag to pan");
+
+  let mut app: App<State> = App::new("compute_mandelbrot_set:explore", initial);
+  app.run()?;
+
+  Ok(())
+}
+
+struct Initial {
+  precision: Precision,
+  key_bindings: KeyBindings<Action>,
+}
+
+/// center と scale は double-float の [hi, lo] で渡す
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Params {
+  center: [f32; 4],
+  scale: [f32; 2],
+  resolution: [f32; 2],
+  max_iter: u32,
+  _padding: [u32; 3],
+}
+
+fn create_df64_shader_source() -> String {
+  format!(
+    "{}{}",
+    shader::DF64_WGSL,
+    include_str!("./explorer-df64.wgsl")
+  )
+}
+
+struct State {
+  pipeline: wgpu::RenderPipeline,
+  df64_pipeline: wgpu::RenderPipeline,
+  bind_group: wgpu::BindGroup,
+  params_buffer: wgpu::Buffer,
+
+  center: [f64; 2],
+  /// 1ピクセル（描画解像度）あたりの複素平面上の幅
+  scale: f64,
+  max_iter: u32,
+  precision: Precision,
+
+  render_size: Size,
+  surface_size: Size,
+  cursor: Option<PhysicalPosition<f64>>,
+  precision_warned: bool,
+
+  key_bindings: KeyBindings<Action>,
+}
+
+impl State {
+  fn reset_view(&mut self) {
+    self.center = INITIAL_CENTER;
+    self.scale = INITIAL_HEIGHT / self.render_size.height.max(1) as f64;
+    self.max_iter = INITIAL_MAX_ITER;
+  }
+
+  /// 描画解像度のピクセルに換算したカーソル位置（画面中心が原点、y は上向き）
+  fn cursor_offset(&self) -> Option<[f64; 2]> {
+    let cursor = self.cursor?;
+    let ratio = self.render_ratio();
+
+    Some([
+      (cursor.x - self.surface_size.width as f64 * 0.5) * ratio,
+      (self.surface_size.height as f64 * 0.5 - cursor.y) * ratio,
+    ])
+  }
+
+  /// サーフェスの1ピクセルに対する描画解像度のピクセル数
+  fn render_ratio(&self) -> f64 {
+    self.render_size.width as f64 / self.surface_size.width.max(1) as f64
+  }
+
+  /// 今の精度では隣り合うピクセルが同じ座標に丸められる倍率かどうか
+  fn check_precision(&mut self) {
+    let magnitude = self.center[0].abs().max(self.center[1].abs()).max(1.);
+    let lost =
+      self.scale < magnitude * self.precision.epsilon() * PRECISION_MARGIN;
+
+    if lost && !self.precision_warned {
+      match self.precision {
+        Precision::Single => log::warn!(
+          "scale {:e} is below f32 precision at this center, the image will get blocky (press P for double-float)",
+          self.scale
+        ),
+        Precision::Double => log::warn!(
+          "scale {:e} is below double-float precision at this center, the image will get blocky",
+          self.scale
+        ),
+      }
+    }
+    // 縮小して戻ったら、再び拡大したときにもう一度警告する
+    self.precision_warned = lost;
+  }
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    let shader =
+      ctx.device.create_shader_module(wgpu::include_wgsl!("./explorer.wgsl"));
+    let df64_shader =
+      ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Mandelbrot Df64 Shader"),
+        source: wgpu::ShaderSource::Wgsl(create_df64_shader_source().into()),
+      });
+
+    //
+    // uniform
+    //
+
+    let params_buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("Params Buffer"),
+      size: std::mem::size_of::<Params>() as wgpu::BufferAddress,
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+
+    //
+    // bind group
+    //
+
+    let bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[wgpu::BindingType::Buffer {
+        ty: wgpu::BufferBindingType::Uniform,
+        has_dynamic_offset: false,
+        min_binding_size: None,
+      }],
+      &[wgpu::ShaderStages::FRAGMENT],
+    );
+    let bind_group = util::create_bind_group(
+      &ctx.device,
+      &bind_group_layout,
+      &[params_buffer.as_entire_binding()],
+    );
+
+    //
+    // pipeline
+    //
+
+    let pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Mandelbrot Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+      });
+    let pipeline = RenderPipelineBuilder::new(ctx)
+      .vs_shader(&shader, "vs_main")
+      .fs_shader(&shader, "fs_main")
+      .pipeline_layout(&pipeline_layout)
+      .label("Mandelbrot Pipeline")
+      .build();
+    let df64_pipeline = RenderPipelineBuilder::new(ctx)
+      .vs_shader(&df64_shader, "vs_main")
+      .fs_shader(&df64_shader, "fs_main")
+      .pipeline_layout(&pipeline_layout)
+      .label("Mandelbrot Df64 Pipeline")
+      .build();
+
+    let mut state = Self {
+      pipeline,
+      df64_pipeline,
+      bind_group,
+      params_buffer,
+
+      center: INITIAL_CENTER,
+      scale: 0.,
+      max_iter: INITIAL_MAX_ITER,
+      precision: initial.precision,
+
+      render_size: *ctx.size(),
+      surface_size: *ctx.surface_size(),
+      cursor: None,
+      precision_warned: false,
+
+      key_bindings: initial.key_bindings.clone(),
+    };
+    state.reset_view();
+
+    state
+  }
+
+  fn resize(&mut self, ctx: &mut DrawingContext, size: Size) {
+    if size.width > 0 && size.height > 0 {
+      ctx.resize(size);
+
+      // 見えている範囲の高さが変わらないよう、ピクセルあたりの幅を合わせる
+      let new_render_size = *ctx.size();
+      self.scale *=
+        self.render_size.height as f64 / new_render_size.height.max(1) as f64;
+      self.render_size = new_render_size;
+      self.surface_size = *ctx.surface_size();
+    }
+  }
+
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    match event {
+      WindowEvent::CursorMoved { position, .. } => {
+        self.cursor = Some(*position);
+        return false;
+      }
+      WindowEvent::CursorLeft { .. } => {
+        self.cursor = None;
+        return false;
+      }
+      _ => {}
+    }
+
+    let action = match self.key_bindings.process_event(event) {
+      Some(action) => action,
+      None => return false,
+    };
+
+    match action {
+      Action::IncreaseMaxIter => {
+        self.max_iter = MAX_MAX_ITER.min(self.max_iter + MAX_ITER_STEP);
+        log::info!("max iterations: {}", self.max_iter);
+      }
+      Action::DecreaseMaxIter => {
+        self.max_iter = MIN_MAX_ITER.max(self.max_iter - MAX_ITER_STEP);
+        log::info!("max iterations: {}", self.max_iter);
+      }
+      Action::TogglePrecision => {
+        self.precision = self.precision.toggled();
+        log::info!("precision: {:?}", self.precision);
+        self.check_precision();
+      }
+      Action::ResetView => {
+        self.reset_view();
+        log::info!("view reset");
+      }
+    }
+
+    true
+  }
+
+  fn on_scroll(&mut self, delta: f32) {
+    let new_scale = self.scale / ZOOM_PER_LINE.powf(delta as f64);
+
+    // カーソルの下の点が動かないように中心をずらす
+    if let Some([x, y]) = self.cursor_offset() {
+      self.center[0] += x * (self.scale - new_scale);
+      self.center[1] += y * (self.scale - new_scale);
+    }
+    self.scale = new_scale;
+
+    self.check_precision();
+  }
+
+  fn on_mouse_drag(&mut self, dx: f32, dy: f32, button: MouseButton) {
+    if button != MouseButton::Left {
+      return;
+    }
+
+    // ドラッグはサーフェスのピクセル単位なので、描画解像度に換算する
+    let ratio = self.render_ratio();
+    self.center[0] -= dx as f64 * ratio * self.scale;
+    self.center[1] += dy as f64 * ratio * self.scale;
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, _dt: std::time::Duration) {
+    let size = ctx.size();
+    let [x_hi, x_lo] = shader::split_f64(self.center[0]);
+    let [y_hi, y_lo] = shader::split_f64(self.center[1]);
+    let params = Params {
+      center: [x_hi, x_lo, y_hi, y_lo],
+      scale: shader::split_f64(self.scale),
+      resolution: [size.width as f32, size.height as f32],
+      max_iter: self.max_iter,
+      _padding: [0; 3],
+    };
+
+    ctx.queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: RenderTarget,
+    _sample_count: u32,
+  ) -> Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+    let (view, frame) = match target {
+      RenderTarget::Surface(surface) => {
+        let frame = surface.get_current_texture()?;
+        let view =
+          frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, Some(frame))
+      }
+      RenderTarget::Texture(texture) => {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (view, None)
+      }
+    };
+
+    let color_attachment = util::create_color_attachment(&view);
+
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render Pass"),
+        color_attachments: &[Some(color_attachment)],
+        ..Default::default()
+      });
+
+    let pipeline = match self.precision {
+      Precision::Single => &self.pipeline,
+      Precision::Double => &self.df64_pipeline,
+    };
+    render_pass.set_pipeline(pipeline);
+    render_pass.set_bind_group(0, &self.bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+
+    drop(render_pass);
+
+    Ok(frame)
+  }
+}
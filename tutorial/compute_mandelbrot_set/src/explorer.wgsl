@@ -0,0 +1,52 @@
+// center と scale は double-float（hi, lo）で渡される
+// 単精度では hi だけを使う
+struct Params {
+  // x.hi, x.lo, y.hi, y.lo
+  center: vec4f,
+  // 1ピクセルあたりの複素平面上の幅
+  scale: vec2f,
+  resolution: vec2f,
+  max_iter: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4f {
+  // 画面全体を覆う三角形
+  var pos = array<vec2f, 3>(
+    vec2f(-1.0, -1.0),
+    vec2f( 3.0, -1.0),
+    vec2f(-1.0,  3.0),
+  );
+  
+  return vec4f(pos[i], 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) frag_coord: vec4f) -> @location(0) vec4f {
+  //
+  // Map the pixel to the complex plane (y axis up)
+  //
+  
+  let offset = (frag_coord.xy - params.resolution * 0.5) * vec2f(1.0, -1.0);
+  let c = params.center.xz + offset * params.scale.x;
+  
+  //
+  // Mandelbrot set iteration
+  //
+  
+  var z = vec2f(0.0);
+  
+  for (var i = 0u; i < params.max_iter; i = i + 1u) {
+    z = vec2f(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y) + c;
+    
+    if (dot(z, z) > 4.0) {
+      // Outside the Mandelbrot set, color based on iteration count
+      let t = f32(i) / f32(params.max_iter);
+      return vec4f(t, t, t, 1.0);
+    }
+  }
+  
+  return vec4f(0.0, 0.0, 0.0, 1.0);
+}
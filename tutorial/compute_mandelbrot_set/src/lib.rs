@@ -1,7 +1,12 @@
+mod action;
+mod explorer;
+
 use std::{error::Error, path::Path};
 
 use wgsim::compute::pixel::ComputePixel;
 
+pub use explorer::run_explorer;
+
 pub async fn run() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
@@ -14,12 +19,39 @@ pub async fn run() -> Result<(), Box<dyn Error>> {
     wgpu::TextureFormat::Rgba8Unorm,
     IMG_SIZE,
   )
-  .await?;
+  .await?
+  .with_timestamps();
 
   let buf = pixel.compute(8, 8).await?;
 
+  if let Some(elapsed_ms) = pixel.elapsed_ms() {
+    println!("compute pass: {:.3} ms", elapsed_ms);
+  }
+
   pixel.export_png(Path::new(EXPORT_PATH), &buf)?;
   pixel.clean_up(buf);
 
   Ok(())
 }
+
+pub async fn run_hdr() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  const IMG_SIZE: u32 = 512;
+  const EXPORT_PATH: &str = "export/compute_mandelbrot_set.exr";
+
+  let pixel = ComputePixel::new(
+    wgpu::include_wgsl!("./compute-hdr.wgsl"),
+    "cs_main",
+    wgpu::TextureFormat::Rgba32Float,
+    IMG_SIZE,
+  )
+  .await?;
+
+  let buf = pixel.compute(8, 8).await?;
+
+  pixel.export_exr(Path::new(EXPORT_PATH), &buf)?;
+  pixel.clean_up(buf);
+
+  Ok(())
+}
@@ -1,6 +1,7 @@
 use std::{error::Error, iter};
 
 use wgpu_helper::context as helper_util;
+use wgsim::util;
 
 pub async fn run() -> Result<(), Box<dyn Error>> {
   env_logger::init();
@@ -109,7 +110,13 @@ pub async fn run() -> Result<(), Box<dyn Error>> {
   drop(compute_pass);
 
   //
-  // copy buffer to GPU
+  // execute commands
+  //
+
+  queue.submit(iter::once(encoder.finish()));
+
+  //
+  // read buffer
   //
 
   // 計算が完了した後、メモリに直接アクセスすることはできない
@@ -121,64 +128,11 @@ pub async fn run() -> Result<(), Box<dyn Error>> {
   // つまり、コンピュートシェーダーで使用するSTORAGEフラグとMAP_READフラグを同時に設定することはできない
   // この制約を回避するため、データをRust側に戻す際には、一時的なステージング用のメモリブロックを使用する必要がある
 
-  let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-    label: Some("Storage Buffer for staging"),
-    size: buffer_size,
-    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-    mapped_at_creation: false,
-  });
-
-  // コンピュートシェーダーの結果を一時的なステージングバッファにコピーする
-  encoder.copy_buffer_to_buffer(
-    &store_buffer,
-    0,
-    &staging_buffer,
-    0,
-    buffer_size,
-  );
-
-  //
-  // execute commands
-  //
-
-  queue.submit(iter::once(encoder.finish()));
-
-  //
-  // map and read buffer
-  //
-
-  // データがステージングメモリに移動したら、map_asyncを使ってGPUメモリをロックし、GPUからCPUにコピーできるようにする
+  // util::read_buffer は、ステージングバッファへのコピーと map_async による読み出しをまとめて行う
   // ref: https://sotrh.github.io/learn-wgpu/news/0.13/
+  let data: Vec<f32> = util::read_buffer(&device, &queue, &store_buffer).await;
 
-  let buffer_slice = staging_buffer.slice(..);
-
-  let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
-  buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-    tx.send(result).unwrap();
-  });
-  device.poll(wgpu::Maintain::Wait);
-  rx.receive().await.unwrap().unwrap();
-
-  // コピーが完了したら、そのデータをデバッグ用のコンソールに出力する
-
-  let data = buffer_slice.get_mapped_range();
-
-  println!(
-    "Value after computation: {:?}",
-    data
-      // 指定したサイズ（ここでは4バイト）ごとにスライスを分割
-      // もしdataの長さが4の倍数でない場合、chunks_exactは余ったバイトを無視する
-      .chunks_exact(buffer_size as usize)
-      // neは「ネイティブエンディアン」の略で、実行環境のエンディアン（バイト順序）に依存して変換を行う
-      // 引数は[u8; 4]型を要求するため、try_intoを使って変換する
-      .map(|b| f32::from_ne_bytes(b.try_into().unwrap()))
-      .collect::<Vec<f32>>()
-  );
-
-  drop(data);
-
-  // マッピングを解除してメモリを解放
-  staging_buffer.unmap();
+  println!("Value after computation: {:?}", data);
 
   Ok(())
 }
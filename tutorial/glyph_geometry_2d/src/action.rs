@@ -0,0 +1,20 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  NextFont,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::NextFont => "Switch font",
+    }
+  }
+}
+
+// 文字を入力できるように、スペースではなくTabで切り替える
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new().bind(KeyCode::Tab, Action::NextFont)
+}
@@ -2,6 +2,7 @@ use std::{collections::HashMap, fs};
 
 use enum_rotate::EnumRotate;
 use meshtext::{IndexedMeshText, MeshGenerator, TextSection};
+use ttf_parser::Face;
 
 #[derive(Clone, Copy, EnumRotate)]
 pub enum FontSelection {
@@ -29,45 +30,134 @@ pub struct TextVertices2d {
   pub indices_len: u32,
 }
 
+#[derive(Clone, Copy)]
+pub struct TextLayout {
+  /// フォントの行の高さ（ascender - descender + line gap）に掛ける倍率
+  pub line_spacing: f32,
+  /// 指定すると、この幅（ピクセル）を超える行をスペースで折り返す
+  pub max_width: Option<f32>,
+}
+
+impl Default for TextLayout {
+  fn default() -> Self {
+    Self {
+      line_spacing: 1.0,
+      max_width: None,
+    }
+  }
+}
+
 pub fn get_text_vertices_2d(
   font_selection: FontSelection,
   text: &str,
   pos: [f32; 2],
-  scale: f32,
-  aspect: f32,
+  font_size: f32,
+  layout: TextLayout,
 ) -> TextVertices2d {
   let font_data = fs::read(font_file_map(font_selection).unwrap()).unwrap();
-  let font_data_static = Box::leak(font_data.into_boxed_slice());
+  let font_data_static: &'static [u8] = Box::leak(font_data.into_boxed_slice());
+
+  let face = Face::parse(font_data_static, 0).expect("failed to parse font.");
+  let em = face.units_per_em() as f32;
+  let units_per_line =
+    face.ascender() as f32 - face.descender() as f32 + face.line_gap() as f32;
+  let line_height = units_per_line / em * font_size * layout.line_spacing;
 
   let mut generator = MeshGenerator::new(font_data_static);
-  let transform = [
-    scale,
-    0.0,
-    0.0,
-    0.0,
-    scale * aspect,
-    0.0,
-    pos[0],
-    pos[1],
-    1.0,
-  ];
-  let data: IndexedMeshText = generator
-    .generate_section_2d(text, Some(&transform))
-    .expect("failed to generate glyph.");
 
   let mut vertex_data: Vec<u8> = Vec::new();
-  for vert in data.vertices.iter() {
-    vertex_data.extend_from_slice(vert.to_le_bytes().as_slice());
-  }
-
   let mut index_data: Vec<u8> = Vec::new();
-  for ind in data.indices.iter() {
-    index_data.extend_from_slice(ind.to_le_bytes().as_slice());
+  let mut vertex_count = 0;
+  let mut indices_len = 0;
+
+  let lines = text.split('\n').flat_map(|paragraph| {
+    wrap_line(&face, paragraph, font_size, layout.max_width)
+  });
+
+  for (i, line) in lines.enumerate() {
+    // 空行はメッシュを作れないので、ペンを進めるだけにする
+    if line.trim().is_empty() {
+      continue;
+    }
+
+    // グリフは上向きが+Yなので、下向きが+Yのピクセル座標に合わせて反転する
+    let baseline_y = pos[1] + line_height * i as f32;
+    let transform = [
+      font_size, 0.0, 0.0, 0.0, -font_size, 0.0, pos[0], baseline_y, 1.0,
+    ];
+    let data: IndexedMeshText = generator
+      .generate_section_2d(&line, Some(&transform))
+      .expect("failed to generate glyph.");
+
+    for vert in data.vertices.iter() {
+      vertex_data.extend_from_slice(vert.to_le_bytes().as_slice());
+    }
+
+    // 前の行までの頂点の後ろに続くので、インデックスをずらす
+    for ind in data.indices.iter() {
+      index_data
+        .extend_from_slice((ind + vertex_count).to_le_bytes().as_slice());
+    }
+
+    vertex_count += data.vertices.len() as u32 / 2;
+    indices_len += data.indices.len() as u32;
   }
 
   TextVertices2d {
     vertices: vertex_data,
     indices: index_data,
-    indices_len: data.indices.len() as u32,
+    indices_len,
   }
 }
+
+/// `max_width` を超えないように、スペースの位置で行を分ける
+/// （1単語で超える場合は、その単語だけの行にする）
+fn wrap_line(
+  face: &Face,
+  line: &str,
+  font_size: f32,
+  max_width: Option<f32>,
+) -> Vec<String> {
+  let Some(max_width) = max_width else {
+    return vec![line.to_string()];
+  };
+
+  let space_width = text_width(face, " ", font_size);
+
+  let mut lines = Vec::new();
+  let mut current = String::new();
+  let mut current_width = 0.0;
+
+  for word in line.split(' ') {
+    let word_width = text_width(face, word, font_size);
+
+    if !current.is_empty()
+      && current_width + space_width + word_width > max_width
+    {
+      lines.push(std::mem::take(&mut current));
+      current_width = 0.0;
+    }
+
+    if !current.is_empty() {
+      current.push(' ');
+      current_width += space_width;
+    }
+    current.push_str(word);
+    current_width += word_width;
+  }
+  lines.push(current);
+
+  lines
+}
+
+fn text_width(face: &Face, text: &str, font_size: f32) -> f32 {
+  let em = face.units_per_em() as f32;
+  let advance: u32 = text
+    .chars()
+    .filter_map(|c| face.glyph_index(c))
+    .filter_map(|id| face.glyph_hor_advance(id))
+    .map(u32::from)
+    .sum();
+
+  advance as f32 / em * font_size
+}
@@ -1,26 +1,32 @@
+mod action;
 mod font_data;
 
 use std::error::Error;
 
+use action::Action;
 use bytemuck::cast_slice;
 use enum_rotate::EnumRotate;
-use font_data::FontSelection;
+use font_data::{FontSelection, TextLayout};
 use wgpu::util::DeviceExt;
 use wgsim::app::App;
 use wgsim::ctx::{DrawingContext, Size};
+use wgsim::input::KeyBindings;
+use wgsim::matrix;
 use wgsim::ppl::RenderPipelineBuilder;
 use wgsim::render::{Render, RenderTarget};
 use wgsim::util;
 use winit::event::{ElementState, KeyEvent, WindowEvent};
-use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::keyboard::{Key, NamedKey};
 
 fn setup() -> Initial<'static> {
   Initial {
-    text: "Hello, World!",
+    text: "Hello,\nWorld!",
     font_selection: FontSelection::Lusitana,
-    text_position: [0.0, 0.0],
-    color: [1.0, 1.0, 1.0, 1.0],
-    scale: 0.45,
+    text_position: [40.0, 200.0],
+    color: [1.0, 1.0, 1.0, 0.8],
+    font_size: 120.0,
+    layout: TextLayout::default(),
+    key_bindings: action::default_key_bindings(),
   }
 }
 
@@ -28,6 +34,7 @@ pub fn run() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
   let initial = setup();
+  initial.key_bindings.print_help();
 
   let mut app: App<State> = App::new("glyph-geometry-2d", initial).with_msaa();
   app.run()?;
@@ -38,9 +45,12 @@ pub fn run() -> Result<(), Box<dyn Error>> {
 struct Initial<'a> {
   font_selection: FontSelection,
   text: &'a str,
+  /// 左上を原点とするピクセル座標（ベースラインの左端）
   text_position: [f32; 2],
   color: [f32; 4],
-  scale: f32,
+  font_size: f32,
+  layout: TextLayout,
+  key_bindings: KeyBindings<Action>,
 }
 
 struct State {
@@ -50,15 +60,22 @@ struct State {
   index_buffer: wgpu::Buffer,
   index_count: u32,
 
+  vert_bind_group: wgpu::BindGroup,
   frag_bind_group: wgpu::BindGroup,
 
+  projection_uniform_buffer: wgpu::Buffer,
+
   msaa_texture_view: wgpu::TextureView,
 
   data_changed: bool,
+  projection_changed: bool,
   font_selection: FontSelection,
   text: String,
   text_position: [f32; 2],
-  scale: f32,
+  font_size: f32,
+  layout: TextLayout,
+
+  key_bindings: KeyBindings<Action>,
 }
 
 impl<'a> Render<'a> for State {
@@ -76,6 +93,15 @@ impl<'a> Render<'a> for State {
     // uniform
     //
 
+    let Size { width, height } = *ctx.size();
+    let projection_mat = matrix::create_ortho_2d(width as f32, height as f32);
+    let projection_uniform_buffer =
+      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("projection uniform buffer"),
+        contents: cast_slice(projection_mat.as_ref() as &[f32; 16]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+
     let color_uniform_buffer =
       ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("color uniform buffer"),
@@ -83,6 +109,22 @@ impl<'a> Render<'a> for State {
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
       });
 
+    //
+    // uniform bind group for vertex shader
+    //
+
+    let vert_bind_group_layout = util::create_bind_group_layout_for_buffer(
+      &ctx.device,
+      &[wgpu::BufferBindingType::Uniform],
+      &[wgpu::ShaderStages::VERTEX],
+    );
+
+    let vert_bind_group = util::create_bind_group(
+      &ctx.device,
+      &vert_bind_group_layout,
+      &[projection_uniform_buffer.as_entire_binding()],
+    );
+
     //
     // uniform bind group for fragment shader
     //
@@ -112,7 +154,7 @@ impl<'a> Render<'a> for State {
     let pipeline_layout =
       ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("Render Pipeline Layout"),
-        bind_group_layouts: &[&frag_bind_group_layout],
+        bind_group_layouts: &[&vert_bind_group_layout, &frag_bind_group_layout],
         push_constant_ranges: &[],
       });
 
@@ -120,7 +162,9 @@ impl<'a> Render<'a> for State {
       .vs_shader(&shader, "vs_main")
       .fs_shader(&shader, "fs_main")
       .pipeline_layout(&pipeline_layout)
-      .vertex_buffer_layout(&vertex_buffer_layout);
+      .vertex_buffer_layout(&vertex_buffer_layout)
+      // 半透明の文字色で、重なったグリフも背景と合成されるようにする
+      .blend(wgpu::BlendState::ALPHA_BLENDING);
 
     let pipeline = pipeline_builder.build();
 
@@ -138,8 +182,8 @@ impl<'a> Render<'a> for State {
       initial.font_selection,
       initial.text,
       initial.text_position,
-      initial.scale,
-      ctx.aspect_ratio(),
+      initial.font_size,
+      initial.layout,
     );
 
     let vertex_buffer =
@@ -163,15 +207,22 @@ impl<'a> Render<'a> for State {
       index_buffer,
       index_count: geometry.indices_len,
 
+      vert_bind_group,
       frag_bind_group,
 
+      projection_uniform_buffer,
+
       msaa_texture_view,
 
       data_changed: false,
+      projection_changed: false,
       text: initial.text.to_string(),
       text_position: initial.text_position,
       font_selection: initial.font_selection,
-      scale: initial.scale,
+      font_size: initial.font_size,
+      layout: initial.layout,
+
+      key_bindings: initial.key_bindings.clone(),
     }
   }
 
@@ -183,33 +234,39 @@ impl<'a> Render<'a> for State {
         self.msaa_texture_view = util::create_msaa_texture_view(&ctx);
       }
 
-      self.data_changed = true;
+      // 頂点はピクセル単位なので、作り直すのは投影行列だけでよい
+      self.projection_changed = true;
     }
   }
 
   fn process_event(&mut self, event: &WindowEvent) -> bool {
-    match event {
-      WindowEvent::KeyboardInput {
-        event:
-          KeyEvent {
-            physical_key,
-            state: ElementState::Pressed,
-            ..
-          },
-        ..
-      } => match physical_key {
-        PhysicalKey::Code(KeyCode::Space) => {
-          self.font_selection = self.font_selection.next();
-          self.data_changed = true;
-          true
-        }
-        _ => false,
-      },
-      _ => false,
+    let action = match self.key_bindings.process_event(event) {
+      Some(action) => action,
+      None => return self.edit_text(event),
+    };
+
+    match action {
+      Action::NextFont => {
+        self.font_selection = self.font_selection.next();
+        self.data_changed = true;
+      }
     }
+
+    true
   }
 
   fn update(&mut self, ctx: &DrawingContext, _dt: std::time::Duration) {
+    if self.projection_changed {
+      let Size { width, height } = *ctx.size();
+      let projection_mat = matrix::create_ortho_2d(width as f32, height as f32);
+      ctx.queue.write_buffer(
+        &self.projection_uniform_buffer,
+        0,
+        cast_slice(projection_mat.as_ref() as &[f32; 16]),
+      );
+      self.projection_changed = false;
+    }
+
     if !self.data_changed {
       return;
     }
@@ -218,8 +275,8 @@ impl<'a> Render<'a> for State {
       self.font_selection,
       &self.text,
       self.text_position,
-      self.scale,
-      ctx.aspect_ratio(),
+      self.font_size,
+      self.layout,
     );
 
     self.vertex_buffer.destroy();
@@ -276,11 +333,18 @@ impl<'a> Render<'a> for State {
         ..Default::default()
       });
 
+    // 空文字や空白だけのときは、空のバッファをスライスできないので描かない
+    if self.index_count == 0 {
+      drop(render_pass);
+      return Ok(frame);
+    }
+
     render_pass.set_pipeline(&self.pipeline);
     render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
     render_pass
       .set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-    render_pass.set_bind_group(0, &self.frag_bind_group, &[]);
+    render_pass.set_bind_group(0, &self.vert_bind_group, &[]);
+    render_pass.set_bind_group(1, &self.frag_bind_group, &[]);
     render_pass.draw_indexed(0..self.index_count, 0, 0..1);
 
     drop(render_pass);
@@ -288,3 +352,51 @@ impl<'a> Render<'a> for State {
     Ok(frame)
   }
 }
+
+impl State {
+  /// 入力された文字を末尾に追加し、Backspaceで末尾を削除する
+  fn edit_text(&mut self, event: &WindowEvent) -> bool {
+    let WindowEvent::KeyboardInput {
+      event:
+        KeyEvent {
+          state: ElementState::Pressed,
+          logical_key,
+          text,
+          ..
+        },
+      ..
+    } = event
+    else {
+      return false;
+    };
+
+    match logical_key {
+      Key::Named(NamedKey::Backspace) => {
+        if self.text.pop().is_some() {
+          self.data_changed = true;
+        }
+        return true;
+      }
+      Key::Named(NamedKey::Enter) => {
+        self.text.push('\n');
+        self.data_changed = true;
+        return true;
+      }
+      _ => {}
+    }
+
+    // Enter以外の制御文字（Tab、Escなど）は入力しない
+    let typed = match text {
+      Some(text) => text.chars().filter(|c| !c.is_control()).collect(),
+      None => String::new(),
+    };
+    if typed.is_empty() {
+      return false;
+    }
+
+    self.text.push_str(&typed);
+    self.data_changed = true;
+
+    true
+  }
+}
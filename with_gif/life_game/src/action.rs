@@ -0,0 +1,35 @@
+use wgsim::input::{self, KeyBindings};
+use winit::keyboard::KeyCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  GrowGrid,
+  ShrinkGrid,
+  NextRule,
+  TogglePause,
+  Step,
+  Reseed,
+}
+
+impl input::Action for Action {
+  fn help(&self) -> &'static str {
+    match self {
+      Action::GrowGrid => "Double the grid size (reseeds the cells)",
+      Action::ShrinkGrid => "Halve the grid size (reseeds the cells)",
+      Action::NextRule => "Cycle the rule (Conway, HighLife, ...)",
+      Action::TogglePause => "Pause / resume the simulation",
+      Action::Step => "Pause and advance one generation",
+      Action::Reseed => "Reseed the grid with random cells",
+    }
+  }
+}
+
+pub fn default_key_bindings() -> KeyBindings<Action> {
+  KeyBindings::new()
+    .bind(KeyCode::ArrowUp, Action::GrowGrid)
+    .bind(KeyCode::ArrowDown, Action::ShrinkGrid)
+    .bind(KeyCode::KeyR, Action::NextRule)
+    .bind(KeyCode::Space, Action::TogglePause)
+    .bind(KeyCode::Period, Action::Step)
+    .bind(KeyCode::KeyS, Action::Reseed)
+}
@@ -1,35 +1,63 @@
+mod action;
+mod rule;
 mod vertex;
 
 use std::error::Error;
 use std::time;
 
-use rand::Rng;
+use action::Action;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rule::RULES;
 use vertex::{Vertex, VERTICES};
 use wgpu::util::DeviceExt;
-use wgpu_helper::context as helper_util;
-use wgpu_helper::{
-  context::WgpuContext,
-  framework::with_gif::{App, Gif, Render, RenderTarget},
-};
-
-// グリッドの縦方向と横方向にそれぞれいくつのセルが存在するか
-// 整数値で十分だが、シェーダー側でのキャストが面倒なので最初から浮動小数点値で定義
-const GRID_SIZE: f32 = 32.0;
+use wgsim::app::App;
+use wgsim::ctx::{DrawingContext, Size};
+use wgsim::export::Gif;
+use wgsim::input::KeyBindings;
+use wgsim::render::{Render, RenderTarget};
+use wgsim::util::{self, PingPong, PingPongBinding};
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, MouseButton, WindowEvent};
+
+// グリッドの縦方向と横方向にそれぞれいくつのセルが存在するか（起動時の値）
+// ↑↓キーで MIN_GRID_SIZE から MAX_GRID_SIZE まで2倍ずつ変えられる
+const GRID_SIZE: u32 = 32;
+const MIN_GRID_SIZE: u32 = 8;
+const MAX_GRID_SIZE: u32 = 512;
 
 // simulation.wgslの@workgroup_sizeと一致させる必要がある
-const WORKGROUP_SIZE: f32 = 8.0;
+const WORKGROUP_SIZE: u32 = 8;
 
-fn setup() -> Initial {
-  Initial {}
+// GIFの書き出しでは毎回同じ初期状態から始める
+const GIF_SEED: u64 = 4;
+
+const BACKGROUND: wgpu::Color = wgpu::Color {
+  r: 0.0,
+  g: 0.0,
+  b: 0.2,
+  a: 1.0,
+};
+
+fn setup(seed: Option<u64>) -> Initial {
+  Initial {
+    grid_size: GRID_SIZE,
+    rule_index: 0,
+    running: true,
+    seed,
+    key_bindings: action::default_key_bindings(),
+  }
 }
 
 pub fn run() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
-  let initial = setup();
+  let initial = setup(None);
+  initial.key_bindings.print_help();
 
   let mut app: App<State> = App::new("with_gif/life_game", initial)
     .with_window_size(512, 512)
+    .with_clear_color(BACKGROUND)
     .with_update_interval(time::Duration::from_millis(150));
   app.run()?;
 
@@ -39,15 +67,28 @@ pub fn run() -> Result<(), Box<dyn Error>> {
 pub async fn export_gif() -> Result<(), Box<dyn Error>> {
   env_logger::init();
 
-  let initial = setup();
+  let initial = setup(Some(GIF_SEED));
 
-  let mut gif = Gif::<State>::new(512, initial, false).await;
-  gif.export("export/with_gif-lige_game-3.gif", 30, 10).await?;
+  // 白い背景で書き出す
+  let mut gif = Gif::<State>::new(512, initial, false)
+    .await?
+    .with_clear_color(wgpu::Color::WHITE)
+    .await;
+  gif.export("export/with_gif-lige_game-4.gif", 30, 10).await?;
 
   Ok(())
 }
 
-struct Initial {}
+struct Initial {
+  pub grid_size: u32,
+  /// `rule::RULES` の何番目のルールで始めるか
+  pub rule_index: usize,
+  /// falseなら一時停止した状態で始める（. キーで1世代ずつ進める）
+  pub running: bool,
+  /// セルの初期配置に使う乱数のシード（Noneなら実行のたびに変わる）
+  pub seed: Option<u64>,
+  pub key_bindings: KeyBindings<Action>,
+}
 
 struct State {
   //
@@ -62,18 +103,51 @@ struct State {
   vertex_buffer: wgpu::Buffer,
   num_vertices: u32,
   num_instances: u32,
+  clear_color: wgpu::Color,
 
   //
   // for Ping-Pong patter
   //
-  ping_pong_bind_groups: Vec<wgpu::BindGroup>,
-  step: usize,
+  cell_states: PingPong<wgpu::Buffer>,
+
+  //
+  // グリッドサイズを変えるときに、ストレージバッファとバインドグループを作り直すためのもの
+  //
+  bind_group_layout: wgpu::BindGroupLayout,
+  grid_uniform_buffer: wgpu::Buffer,
+  rule_uniform_buffer: wgpu::Buffer,
+
+  //
+  // simulation settings
+  //
+  grid_size: u32,
+  rule_index: usize,
+  grid_resized: bool,
+  rule_changed: bool,
+  running: bool,
+  step_requested: bool,
+  reseed_requested: bool,
+  // コンピュートパスを実行した回数（＝進めた世代数）
+  generation: u64,
+  // グリッドの作り直しとリセットでも同じ乱数列を使い続ける
+  rng: StdRng,
+
+  //
+  // input
+  //
+  key_bindings: KeyBindings<Action>,
+  // カーソル位置をセルに変換するためのウィンドウサイズ（物理ピクセル）
+  window_size: Size,
+  cursor: Option<PhysicalPosition<f64>>,
+  painting: bool,
+  // 次の update で現在の入力バッファに書き込むセルのインデックス
+  painted_cells: Vec<u32>,
 }
 
 impl<'a> Render<'a> for State {
   type Initial = Initial;
 
-  async fn new(ctx: &WgpuContext<'a>, _initial: &Self::Initial) -> Self {
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
     //
     // shader
     //
@@ -97,81 +171,62 @@ impl<'a> Render<'a> for State {
 
     let num_vertices = VERTICES.len() as u32;
 
-    //
-    // instance variables
-    //
-
-    let grid_size = GRID_SIZE as u32;
-    let num_instances = grid_size * grid_size;
-
     //
     // uniform buffer
     //
 
+    // 整数値で十分だが、シェーダー側でのキャストが面倒なので浮動小数点値で渡す
+    let grid_size = initial.grid_size as f32;
     let grid_uniform_buffer =
       ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some("Grid uniforms"),
-        contents: bytemuck::cast_slice(&[GRID_SIZE, GRID_SIZE]),
+        contents: bytemuck::cast_slice(&[grid_size, grid_size]),
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
       });
 
-    //
-    // storage buffer
-    //
-
-    let mut rng = rand::thread_rng();
-    let cell_state: Vec<u32> = (0..grid_size * grid_size)
-      .map(|_| if rng.gen::<f32>() > 0.6 { 1 } else { 0 })
-      .collect();
-
-    let cell_state_storage_buffer_1 =
+    let rule = RULES[initial.rule_index];
+    let rule_uniform_buffer =
       ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Cell State 1"),
-        contents: bytemuck::cast_slice(cell_state.as_slice()),
-        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-      });
-    let cell_state_storage_buffer_2 =
-      ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Cell State 2"),
-        contents: bytemuck::cast_slice(cell_state.as_slice()),
-        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        label: Some("Rule uniforms"),
+        contents: bytemuck::cast_slice(&[rule.birth, rule.survive]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
       });
 
     //
     // bind group layout
     //
 
-    let bind_group_layout = helper_util::create_bind_group_layout(
+    let bind_group_layout = util::create_bind_group_layout_for_buffer(
       &ctx.device,
       &[
         wgpu::BufferBindingType::Uniform,
         wgpu::BufferBindingType::Storage { read_only: true },
         wgpu::BufferBindingType::Storage { read_only: false },
+        wgpu::BufferBindingType::Uniform,
       ],
       &[
         wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
         wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::COMPUTE,
         wgpu::ShaderStages::COMPUTE,
+        wgpu::ShaderStages::COMPUTE,
       ],
     );
 
-    let bind_group_1 = helper_util::create_bind_group(
-      &ctx.device,
-      &bind_group_layout,
-      &[
-        grid_uniform_buffer.as_entire_binding(),
-        cell_state_storage_buffer_1.as_entire_binding(), // input (1)
-        cell_state_storage_buffer_2.as_entire_binding(), // output (2)
-      ],
-    );
-    let bind_group_2 = helper_util::create_bind_group(
+    //
+    // storage buffer
+    //
+
+    let mut rng = match initial.seed {
+      Some(seed) => StdRng::seed_from_u64(seed),
+      None => StdRng::from_entropy(),
+    };
+    let cell_state = generate_cell_state(&mut rng, initial.grid_size);
+    let cell_states = create_cell_states(
       &ctx.device,
       &bind_group_layout,
-      &[
-        grid_uniform_buffer.as_entire_binding(),
-        cell_state_storage_buffer_2.as_entire_binding(), // input (2)
-        cell_state_storage_buffer_1.as_entire_binding(), // output (1)
-      ],
+      &grid_uniform_buffer,
+      &rule_uniform_buffer,
+      &cell_state,
     );
 
     //
@@ -199,7 +254,7 @@ impl<'a> Render<'a> for State {
           module: &render_shader,
           entry_point: "fs_main",
           targets: &[Some(wgpu::ColorTargetState {
-            format: ctx.format,
+            format: ctx.format(),
             blend: Some(wgpu::BlendState::REPLACE),
             write_mask: wgpu::ColorWrites::ALL,
           })],
@@ -231,45 +286,214 @@ impl<'a> Render<'a> for State {
       simulation_pipeline,
       vertex_buffer,
       num_vertices,
-      num_instances,
-      ping_pong_bind_groups: vec![bind_group_1, bind_group_2],
-      step: 0,
+      num_instances: initial.grid_size * initial.grid_size,
+      clear_color: ctx.clear_color,
+      cell_states,
+      bind_group_layout,
+      grid_uniform_buffer,
+      rule_uniform_buffer,
+      grid_size: initial.grid_size,
+      rule_index: initial.rule_index,
+      grid_resized: false,
+      rule_changed: false,
+      running: initial.running,
+      step_requested: false,
+      reseed_requested: false,
+      generation: 0,
+      rng,
+      key_bindings: initial.key_bindings.clone(),
+      window_size: *ctx.surface_size(),
+      cursor: None,
+      painting: false,
+      painted_cells: vec![],
     }
   }
 
+  fn resize(&mut self, ctx: &mut DrawingContext, size: Size) {
+    if size.width > 0 && size.height > 0 {
+      ctx.resize(size);
+      self.window_size = size;
+    }
+  }
+
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    // 左ボタンを押している間、カーソルの下のセルをアクティブにする
+    match event {
+      WindowEvent::CursorMoved { position, .. } => {
+        self.cursor = Some(*position);
+        if self.painting {
+          self.paint_at_cursor();
+          return true;
+        }
+      }
+      WindowEvent::CursorLeft { .. } => {
+        self.cursor = None;
+      }
+      // フォーカスを失ってから離されたボタンは届かない
+      WindowEvent::Focused(false) => {
+        self.painting = false;
+      }
+      WindowEvent::MouseInput {
+        state,
+        button: MouseButton::Left,
+        ..
+      } => {
+        self.painting = *state == ElementState::Pressed;
+        if self.painting {
+          self.paint_at_cursor();
+        }
+        return true;
+      }
+      _ => {}
+    }
+
+    let action = match self.key_bindings.process_event(event) {
+      Some(action) => action,
+      None => return false,
+    };
+
+    match action {
+      Action::GrowGrid => {
+        if self.grid_size < MAX_GRID_SIZE {
+          self.grid_size *= 2;
+          self.grid_resized = true;
+        }
+      }
+      Action::ShrinkGrid => {
+        if self.grid_size > MIN_GRID_SIZE {
+          self.grid_size /= 2;
+          self.grid_resized = true;
+        }
+      }
+      Action::NextRule => {
+        self.rule_index = (self.rule_index + 1) % RULES.len();
+        self.rule_changed = true;
+      }
+      Action::TogglePause => {
+        self.running = !self.running;
+        log::info!("{}", if self.running { "running" } else { "paused" });
+      }
+      Action::Step => {
+        self.running = false;
+        self.step_requested = true;
+      }
+      Action::Reseed => {
+        self.reseed_requested = true;
+      }
+    }
+
+    true
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, _dt: time::Duration) {
+    if self.grid_resized {
+      let grid_size = self.grid_size as f32;
+      ctx.queue.write_buffer(
+        &self.grid_uniform_buffer,
+        0,
+        bytemuck::cast_slice(&[grid_size, grid_size]),
+      );
+
+      // セルの数が変わるので、ストレージバッファを確保し直し、
+      // それを参照するPing-Pong用のバインドグループも作り直す
+      let cell_state = generate_cell_state(&mut self.rng, self.grid_size);
+      self.cell_states = create_cell_states(
+        &ctx.device,
+        &self.bind_group_layout,
+        &self.grid_uniform_buffer,
+        &self.rule_uniform_buffer,
+        &cell_state,
+      );
+      self.num_instances = self.grid_size * self.grid_size;
+      // 古いグリッドでのインデックスなので捨てる
+      self.painted_cells.clear();
+
+      log::info!("grid size: {0} x {0}", self.grid_size);
+      self.grid_resized = false;
+      // 作り直したバッファは新しい配置で埋まっている
+      self.reseed_requested = false;
+    }
+
+    if self.reseed_requested {
+      // サイズは変わらないので、バッファはそのままで両方を書き換える
+      let cell_state = generate_cell_state(&mut self.rng, self.grid_size);
+      let buffers = [
+        self.cell_states.current_resource(),
+        self.cell_states.next_resource(),
+      ];
+      for buffer in buffers {
+        ctx.queue.write_buffer(buffer, 0, bytemuck::cast_slice(&cell_state));
+      }
+      self.painted_cells.clear();
+
+      log::info!("reseeded");
+      self.reseed_requested = false;
+    }
+
+    if self.rule_changed {
+      let rule = RULES[self.rule_index];
+      ctx.queue.write_buffer(
+        &self.rule_uniform_buffer,
+        0,
+        bytemuck::cast_slice(&[rule.birth, rule.survive]),
+      );
+
+      log::info!("rule: {}", rule.name);
+      self.rule_changed = false;
+    }
+
+    // 次のコンピュートパスが読む（＝今表示している）バッファに書き込む
+    // どちらのバッファが入力かはPing-Pongが進むたびに入れ替わるので、current_resourceで選ぶ
+    for &i in &self.painted_cells {
+      let offset =
+        (std::mem::size_of::<u32>() * i as usize) as wgpu::BufferAddress;
+      ctx.queue.write_buffer(
+        self.cell_states.current_resource(),
+        offset,
+        bytemuck::cast_slice(&[1u32]),
+      );
+    }
+    self.painted_cells.clear();
+  }
+
   fn draw(
     &mut self,
     encoder: &mut wgpu::CommandEncoder,
     target: RenderTarget,
     _sample_count: u32,
-  ) -> anyhow::Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+  ) -> Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
     //
     // computing process
     //
 
-    let mut compute_pass =
-      encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-        label: Some("Compute Pass"),
-        timestamp_writes: None,
-      });
-
-    compute_pass.set_pipeline(&self.simulation_pipeline);
-    compute_pass.set_bind_group(
-      0,
-      &self.ping_pong_bind_groups[self.step % 2],
-      &[],
-    );
-
-    let workgroup_count = (GRID_SIZE / WORKGROUP_SIZE).ceil() as u32;
-    compute_pass.dispatch_workgroups(workgroup_count, workgroup_count, 1);
-
-    drop(compute_pass);
-
-    //
-    // swap: コンピューティングパイプラインの出力バッファをレンダリングパイプラインの入力バッファにする
-    //
-
-    self.step += 1;
+    // 一時停止中は、. キーで要求されたときだけ1世代進める
+    // （進めないときは、入力バッファをそのまま描く）
+    if self.running || self.step_requested {
+      let mut compute_pass =
+        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+          label: Some("Compute Pass"),
+          timestamp_writes: None,
+        });
+
+      compute_pass.set_pipeline(&self.simulation_pipeline);
+      compute_pass.set_bind_group(0, self.cell_states.current(), &[]);
+
+      let workgroup_count = self.grid_size.div_ceil(WORKGROUP_SIZE);
+      compute_pass.dispatch_workgroups(workgroup_count, workgroup_count, 1);
+
+      drop(compute_pass);
+
+      //
+      // swap: コンピューティングパイプラインの出力バッファをレンダリングパイプラインの入力バッファにする
+      //
+      // 同じエンコーダ内のパスは記録順に実行されるので、レンダーパスは
+      // コンピュートパスの書き込みが終わった後のバッファを読む（util::ensure_ordering 参照）
+      //
+
+      self.cell_states.advance();
+      self.generation += 1;
+      self.step_requested = false;
+    }
 
     //
     // get render target
@@ -287,19 +511,8 @@ impl<'a> Render<'a> for State {
       }
     };
 
-    let color_attach = wgpu::RenderPassColorAttachment {
-      view: &view,
-      resolve_target: None,
-      ops: wgpu::Operations {
-        load: wgpu::LoadOp::Clear(wgpu::Color {
-          r: 0.0,
-          g: 0.0,
-          b: 0.2,
-          a: 1.0,
-        }),
-        store: wgpu::StoreOp::Store,
-      },
-    };
+    let color_attach =
+      util::create_color_attachment_with(&view, self.clear_color);
 
     //
     // rendering process
@@ -313,11 +526,7 @@ impl<'a> Render<'a> for State {
       });
 
     render_pass.set_pipeline(&self.render_pipeline);
-    render_pass.set_bind_group(
-      0,
-      &self.ping_pong_bind_groups[self.step % 2],
-      &[],
-    );
+    render_pass.set_bind_group(0, self.cell_states.current(), &[]);
     render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
     render_pass.draw(0..self.num_vertices, 0..self.num_instances);
 
@@ -330,3 +539,121 @@ impl<'a> Render<'a> for State {
     Ok(frame)
   }
 }
+
+impl State {
+  /// Queues the cell under the cursor to be set alive in `update`.
+  fn paint_at_cursor(&mut self) {
+    let Some(cursor) = self.cursor else {
+      return;
+    };
+    let Size { width, height } = self.window_size;
+    let grid_size = self.grid_size as f64;
+
+    // 左下が(0, 0)のセルなので、ウィンドウ座標（左上が原点、下向きが+y）のyは反転する
+    let x = (cursor.x / width as f64 * grid_size).floor();
+    let y = ((1. - cursor.y / height as f64) * grid_size).floor();
+    if x < 0. || y < 0. || x >= grid_size || y >= grid_size {
+      return;
+    }
+
+    self.painted_cells.push(y as u32 * self.grid_size + x as u32);
+  }
+}
+
+/// `grid_size * grid_size` cells, about 40% of them alive.
+fn generate_cell_state(rng: &mut StdRng, grid_size: u32) -> Vec<u32> {
+  (0..grid_size * grid_size)
+    .map(|_| if rng.gen::<f32>() > 0.6 { 1 } else { 0 })
+    .collect()
+}
+
+/// Two cell state buffers both filled with `cell_state` and the bind groups
+/// alternating between them.
+fn create_cell_states(
+  device: &wgpu::Device,
+  bind_group_layout: &wgpu::BindGroupLayout,
+  grid_uniform_buffer: &wgpu::Buffer,
+  rule_uniform_buffer: &wgpu::Buffer,
+  cell_state: &[u32],
+) -> PingPong<wgpu::Buffer> {
+  let cell_state_storage_buffer_1 =
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Cell State 1"),
+      contents: bytemuck::cast_slice(cell_state),
+      usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    });
+  let cell_state_storage_buffer_2 =
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Cell State 2"),
+      contents: bytemuck::cast_slice(cell_state),
+      usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    });
+
+  // 1 -> 2, 2 -> 1 の順に入力と出力を入れ替えるバインドグループを作る
+  PingPong::new(
+    device,
+    bind_group_layout,
+    vec![cell_state_storage_buffer_1, cell_state_storage_buffer_2],
+    &[
+      PingPongBinding::Shared(grid_uniform_buffer.as_entire_binding()),
+      PingPongBinding::Current, // input
+      PingPongBinding::Next,    // output
+      PingPongBinding::Shared(rule_uniform_buffer.as_entire_binding()),
+    ],
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use wgsim::render::TestHarness;
+
+  use super::*;
+
+  #[test]
+  fn each_drawn_frame_advances_one_generation() {
+    let mut harness: TestHarness<State> =
+      TestHarness::new(&setup(Some(GIF_SEED)), 64, 64)
+        .expect("no GPU adapter for the headless harness");
+    let dt = time::Duration::from_millis(16);
+
+    for _ in 0..5 {
+      harness.step(dt);
+      harness.draw_to_vec().unwrap();
+    }
+    assert_eq!(harness.renderer().generation, 5);
+
+    // 一時停止中は描いても進まず、1世代ずつ送ったときだけ進む
+    harness.renderer_mut().running = false;
+    harness.step(dt);
+    harness.draw_to_vec().unwrap();
+    assert_eq!(harness.renderer().generation, 5);
+
+    harness.renderer_mut().step_requested = true;
+    harness.step(dt);
+    harness.draw_to_vec().unwrap();
+    assert_eq!(harness.renderer().generation, 6);
+  }
+  fn render_generations(frames: usize) -> Vec<Vec<u8>> {
+    let mut harness: TestHarness<State> =
+      TestHarness::new(&setup(Some(GIF_SEED)), 64, 64)
+        .expect("no GPU adapter for the headless harness");
+    let dt = time::Duration::from_millis(16);
+
+    (0..frames)
+      .map(|_| {
+        harness.step(dt);
+        harness.draw_to_vec().unwrap()
+      })
+      .collect()
+  }
+
+  #[test]
+  fn same_seed_renders_identical_frames() {
+    let first = render_generations(8);
+    let second = render_generations(8);
+
+    assert_eq!(first, second);
+    // 世代が進んで盤面が変わっていることも確かめる（全フレーム同じなら無意味）
+    assert_ne!(first[0], first[7]);
+  }
+}
@@ -0,0 +1,47 @@
+/// A life-like rule: a dead cell is born with `n` live neighbors if bit `n`
+/// of `birth` is set, and a live cell survives if bit `n` of `survive` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+  pub name: &'static str,
+  pub birth: u32,
+  pub survive: u32,
+}
+
+const fn mask(counts: &[u32]) -> u32 {
+  let mut mask = 0;
+  let mut i = 0;
+  while i < counts.len() {
+    mask |= 1 << counts[i];
+    i += 1;
+  }
+  mask
+}
+
+// キーで順に切り替えるルール（先頭が既定のライフゲーム）
+pub const RULES: [Rule; 5] = [
+  Rule {
+    name: "Conway (B3/S23)",
+    birth: mask(&[3]),
+    survive: mask(&[2, 3]),
+  },
+  Rule {
+    name: "HighLife (B36/S23)",
+    birth: mask(&[3, 6]),
+    survive: mask(&[2, 3]),
+  },
+  Rule {
+    name: "Seeds (B2/S)",
+    birth: mask(&[2]),
+    survive: mask(&[]),
+  },
+  Rule {
+    name: "Day & Night (B3678/S34678)",
+    birth: mask(&[3, 6, 7, 8]),
+    survive: mask(&[3, 4, 6, 7, 8]),
+  },
+  Rule {
+    name: "Life without Death (B3/S012345678)",
+    birth: mask(&[3]),
+    survive: mask(&[0, 1, 2, 3, 4, 5, 6, 7, 8]),
+  },
+];
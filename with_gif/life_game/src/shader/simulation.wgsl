@@ -21,6 +21,17 @@
 @group(0) @binding(1) var<storage> cell_state_in: array<u32>; // 読み取り専用
 @group(0) @binding(2) var<storage, read_write> cell_state_out: array<u32>; // バッファへの読み書きが可能
 
+// 誕生と生存の条件
+// - 隣接セルがn個のとき、birthのビットnが立っていれば非アクティブなセルがアクティブになる
+// - 隣接セルがn個のとき、surviveのビットnが立っていればアクティブなセルがアクティブなままになる
+// ルールをユニフォームで渡すので、シェーダーを書き換えずにHighLifeなどに切り替えられる
+struct Rule {
+  birth: u32,
+  survive: u32,
+}
+
+@group(0) @binding(3) var<uniform> rule: Rule;
+
 struct ComputeInput {
   // シェーダー呼び出しのグリッドの位置を伝える符号なし整数の3次元ベクトル
   // - このシェーダーは、グリッド内の各セルに対して一度実行する
@@ -67,6 +78,12 @@ fn cell_active(x: u32, y: u32) -> u32 {
 fn cp_main(in: ComputeInput) {
   let cell = in.cell;
   
+  // グリッドサイズがワークグループのサイズの倍数でないときにはみ出す呼び出しは何もしない
+  // （ラップアラウンドで別のセルに書き込んでしまわないように）
+  if (cell.x >= u32(grid.x) || cell.y >= u32(grid.y)) {
+    return;
+  }
+  
   // 特定のセルについて、アクティブな隣接セルの数を把握する必要がある
   // cell_active関数は、セルがアクティブであれば1を返す
   // 8つすべての隣接セルに対してcell_activeを呼び出し、戻り値を合計すれば、アクティブな隣接セルの数がわかる
@@ -82,20 +99,8 @@ fn cp_main(in: ComputeInput) {
   
   let i = cell_index(cell.xy);
   
-  // Conway's game of life rules:
-  // - 隣接セルが2つ未満のセルは、非アクティブとする
-  // - 隣接セルが2つまたは 3 つで、自身がアクティブなセルは、アクティブなままとする
-  // - 隣接セルが3つで、自身が非アクティブなセルは、アクティブとする
-  // - 隣接セルが4つ以上のセルは、非アクティブとする
-  switch active_neighbors {
-    case 2u: {
-      cell_state_out[i] = cell_state_in[i];
-    }
-    case 3u: {
-      cell_state_out[i] = 1u;
-    }
-    default: {
-      cell_state_out[i] = 0u;
-    }
-  }
+  // 自身の状態に応じて、誕生か生存のどちらかの条件を調べる
+  // Conway's game of life（B3/S23）なら birth = 1 << 3、survive = (1 << 2) | (1 << 3) となる
+  let mask = select(rule.birth, rule.survive, cell_state_in[i] == 1u);
+  cell_state_out[i] = (mask >> active_neighbors) & 1u;
 }